@@ -3,14 +3,60 @@ use async_trait::async_trait;
 pub use nexus::{
     nexus_bdev::{
         nexus_create,
+        nexus_create_with_missing,
         nexus_lookup,
         Nexus,
         NexusState,
         NexusStatus,
         VerboseError,
     },
-    nexus_child::{lookup_child_from_bdev, ChildState, Reason},
+    nexus_bdev_backup::ChildBackup,
+    nexus_bdev_read::{NexusReader, ReadFreshness},
+    nexus_bdev_rebuild::{RebuildCompletion, RebuildProgress},
+    nexus_bdev_self_test::{
+        ChildSelfTest,
+        SelfTestFailure,
+        SelfTestReport,
+        SELF_TEST_MAX_LATENCY,
+    },
+    nexus_bdev_snapshot::{
+        ChildSnapshotInfo,
+        ChildSnapshotReadiness,
+        SnapshotBlocker,
+        SnapshotClock,
+        SnapshotInfo,
+        SnapshotReadiness,
+        SystemClock,
+    },
+    nexus_bdev_verify::{
+        ConsistencyReport,
+        DivergentRange,
+        SnapshotAgreement,
+        SnapshotConsistencyReport,
+        VerifyOptions,
+    },
+    nexus_channel_dump::{ChannelChild, ChannelDump},
+    nexus_channel_warm::WarmedChannel,
+    nexus_child::{lookup_child_from_bdev, ChildLocality, ChildState, Reason},
+    nexus_child_history::{ChildHistory, ChildHistoryEvent},
+    nexus_child_io_stats::ChildIoStats,
+    nexus_child_latency::LatencyPercentiles,
+    nexus_child_limit::{BackgroundLimit, BackgroundStats},
+    nexus_child_record::ChildRecord,
     nexus_child_status_config,
+    nexus_event::{self, NexusEvent},
+    nexus_failure_trace::{FailureTrace, FailureTraceEntry},
+    nexus_health::{
+        ChildHealth,
+        HealthLevel,
+        NexusHealth,
+        NexusHealthDetail,
+        Readiness,
+    },
+    nexus_io::{child_lba, NioCtx},
+    nexus_io_inflight::InFlightIo,
+    nexus_io_limits::IoLimits,
+    nexus_io_trace::{TraceEvent, TracePoint, IO_TRACE_MAX_DURATION},
     nexus_label::{GptEntry, GptHeader},
     nexus_metadata_content::{
         NexusConfig,
@@ -18,7 +64,73 @@ pub use nexus::{
         NexusConfigVersion2,
         NexusConfigVersion3,
     },
+    nexus_metrics::{
+        NexusMetricsSnapshot,
+        NvmeErrorCount,
+        SubmitErrorCount,
+        UnhandledIoCount,
+        WriteStats,
+    },
+    nexus_observer::{IoObserver, MetricsObserver},
+    nexus_policy::{
+        AdminPassthru,
+        AllFailedPolicy,
+        AutoRecovery,
+        CompletionBatching,
+        DestroyRetry,
+        FailureCondition,
+        FailureStatusPolicy,
+        FaultPolicy,
+        FlushFailurePolicy,
+        IoAccounting,
+        IoTimeout,
+        NexusPolicy,
+        NoFaultPolicy,
+        NoMemoryBackoff,
+        NoMemoryPolicy,
+        OnlineVerify,
+        OpenRetry,
+        OutstandingLimit,
+        PausePolicy,
+        ReadPolicy,
+        UnhandledAction,
+        UnhandledPolicy,
+        UnsupportedAction,
+        UnsupportedPolicy,
+        WriteAck,
+        WriteCache,
+        WriteOrdering,
+    },
+    nexus_read_checksum::{read_checksum, READ_CHECKSUM_MD_SIZE},
+    nexus_read_weight::{ReadWeight, READ_WEIGHT_DEFAULT},
+    nexus_status_snapshot::{ChildStatusSnapshot, NexusStatusSnapshot},
+    nexus_throughput::Throughput,
+    nexus_verifier::ReadVerifier,
+    nexus_write_cache::COALESCE_MAX_WRITES,
+    nexus_writer_set::WriterSet,
+};
+
+#[cfg(feature = "io-recorder")]
+pub use nexus::nexus_io_recorder::{
+    ChildOutcome,
+    IoRecord,
+    Selection,
+    IO_RECORDER_DEPTH,
+};
+
+#[cfg(feature = "fault-injection")]
+pub use nexus::nexus_channel::inject_reconfigure_failure;
+#[cfg(feature = "fault-injection")]
+pub use nexus::nexus_child::{inject_destroy_failure, inject_destroy_failures};
+#[cfg(feature = "fault-injection")]
+pub use nexus::nexus_failure_trace::{
+    clear_failure_trace,
+    replay_failure_trace,
 };
+#[cfg(feature = "fault-injection")]
+pub use nexus::nexus_io::{inject_child_blocks, inject_no_memory};
+#[cfg(feature = "fault-injection")]
+pub use nexus::nexus_io_timeout::inject_child_hang;
 
 pub trait BdevCreateDestroy: CreateDestroy + GetName + std::fmt::Debug {}
 
@@ -3,12 +3,15 @@
 use spdk_sys::spdk_bdev_module;
 
 use crate::{
-    bdev::nexus::{nexus_bdev::Nexus, nexus_fn_table::NexusFnTable},
+    bdev::nexus::{
+        nexus_bdev::{nexus_lookup, Nexus},
+        nexus_fn_table::NexusFnTable,
+    },
     core::{Bdev, Share},
     jsonrpc::{jsonrpc_register, Code, JsonRpcError, Result},
 };
 use futures::{future::Future, FutureExt};
-use std::pin::Pin;
+use std::{path::Path, pin::Pin, time::Duration};
 
 /// Allocate C string and return pointer to it.
 /// NOTE: The resulting string must be freed explicitly after use!
@@ -18,22 +21,64 @@ macro_rules! c_str {
     };
 }
 
+mod nexus_admission;
+pub mod nexus_auto_recovery;
 pub mod nexus_bdev;
+pub mod nexus_bdev_backup;
 pub mod nexus_bdev_children;
+pub mod nexus_bdev_io;
+pub mod nexus_bdev_read;
 pub mod nexus_bdev_rebuild;
+pub mod nexus_bdev_repair;
+pub mod nexus_bdev_self_test;
 pub mod nexus_bdev_snapshot;
-mod nexus_channel;
+pub mod nexus_bdev_sync;
+pub mod nexus_bdev_verify;
+mod nexus_bounce;
+pub(crate) mod nexus_channel;
+pub mod nexus_channel_dump;
+pub mod nexus_channel_warm;
 pub(crate) mod nexus_child;
+pub mod nexus_child_history;
+pub mod nexus_child_io_stats;
+pub mod nexus_child_latency;
+pub mod nexus_child_limit;
+pub mod nexus_child_record;
 pub mod nexus_child_status_config;
+mod nexus_completion_batch;
 mod nexus_config;
+pub mod nexus_event;
+pub mod nexus_failure_trace;
 pub mod nexus_fn_table;
+pub mod nexus_health;
 pub mod nexus_io;
+pub mod nexus_io_inflight;
+pub mod nexus_io_limits;
+#[cfg(feature = "io-recorder")]
+pub mod nexus_io_recorder;
+pub mod nexus_io_timeout;
+pub mod nexus_io_trace;
 pub mod nexus_label;
+pub mod nexus_maintenance;
 pub mod nexus_metadata;
 pub mod nexus_metadata_content;
+pub mod nexus_metrics;
+pub mod nexus_missing;
 pub mod nexus_module;
 pub mod nexus_nbd;
+mod nexus_no_memory;
+pub mod nexus_observer;
+pub mod nexus_policy;
+mod nexus_quorum_write;
+pub mod nexus_read_checksum;
+mod nexus_read_sampling;
+pub mod nexus_read_weight;
 pub mod nexus_share;
+pub mod nexus_status_snapshot;
+pub mod nexus_throughput;
+pub mod nexus_verifier;
+pub mod nexus_write_cache;
+pub mod nexus_writer_set;
 
 #[derive(Deserialize)]
 struct NexusShareArgs {
@@ -48,6 +93,19 @@ struct NexusShareReply {
     uri: String,
 }
 
+#[derive(Deserialize)]
+struct NexusIoTraceArgs {
+    name: String,
+    /// file the events are written to, one JSON object per line
+    path: String,
+    duration_secs: u64,
+}
+
+#[derive(Deserialize)]
+struct NexusIoTraceStopArgs {
+    name: String,
+}
+
 /// public function which simply calls register module
 pub fn register_module() {
     nexus_module::register_module();
@@ -108,6 +166,43 @@ pub fn register_module() {
             Box::pin(f.boxed_local())
         },
     );
+
+    jsonrpc_register(
+        "nexus_io_trace",
+        |args: NexusIoTraceArgs| -> Pin<Box<dyn Future<Output = Result<()>>>> {
+            let f = async move {
+                let nexus = nexus_lookup(&args.name).ok_or(JsonRpcError {
+                    code: Code::NotFound,
+                    message: "nexus not found".to_string(),
+                })?;
+                nexus
+                    .start_io_trace_to_file(
+                        Path::new(&args.path),
+                        Duration::from_secs(args.duration_secs),
+                    )
+                    .map_err(|e| JsonRpcError {
+                        code: Code::InvalidParams,
+                        message: e.to_string(),
+                    })
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
+
+    jsonrpc_register(
+        "nexus_io_trace_stop",
+        |args: NexusIoTraceStopArgs| -> Pin<Box<dyn Future<Output = Result<bool>>>> {
+            let f = async move {
+                nexus_lookup(&args.name)
+                    .map(|nexus| nexus.stop_io_trace())
+                    .ok_or(JsonRpcError {
+                        code: Code::NotFound,
+                        message: "nexus not found".to_string(),
+                    })
+            };
+            Box::pin(f.boxed_local())
+        },
+    );
 }
 
 /// get a reference to the module
@@ -0,0 +1,94 @@
+//! Admission of IO within the outstanding IO limit of a nexus. The limit is
+//! split into a share per channel, such that an IO is admitted against the
+//! IO admitted on its own channel only and the IO path does not contend on a
+//! counter shared by all cores. Every channel publishes its demand, the IO
+//! admitted and queued on it, and rebalances its share in proportion to the
+//! demand of all channels every `IO_TIMEOUT_SCAN`, from the poller of the
+//! channel. As a channel without any IO admitted always admits one, and the
+//! shares are only rebalanced occasionally, the limit may be exceeded by a
+//! few IOs until the next rebalance.
+//!
+//! The IO admitted and the demand are counted per core, each core updating a
+//! counter of its own on a cache line of its own. Without a limit no IO goes
+//! through admission at all.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crossbeam::utils::CachePadded;
+
+use crate::core::Cores;
+
+/// number of counters the IO is spread over, cores beyond it share a counter
+const CORE_SLOTS: usize = 64;
+
+/// the IO admitted and the demand of the channels of a core
+#[derive(Debug, Default)]
+struct CoreSlot {
+    admitted: AtomicU64,
+    demand: AtomicU64,
+}
+
+#[derive(Debug)]
+pub(crate) struct Admission {
+    cores: Box<[CachePadded<CoreSlot>]>,
+}
+
+impl Default for Admission {
+    fn default() -> Self {
+        Self {
+            cores: (0 .. CORE_SLOTS)
+                .map(|_| CachePadded::new(CoreSlot::default()))
+                .collect(),
+        }
+    }
+}
+
+impl Admission {
+    /// the counters of the current core
+    fn slot(&self) -> &CoreSlot {
+        &self.cores[Cores::current() as usize % CORE_SLOTS]
+    }
+
+    /// account an IO admitted on the current core
+    pub(crate) fn admitted(&self) {
+        self.slot().admitted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account an admitted IO completed on the current core
+    pub(crate) fn released(&self) {
+        self.slot().admitted.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// number of IOs admitted on all cores
+    pub(crate) fn outstanding(&self) -> u64 {
+        self.cores
+            .iter()
+            .map(|c| c.admitted.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// replace the demand a channel of the current core published before
+    /// with its current demand
+    pub(crate) fn publish(&self, previous: u64, demand: u64) {
+        let slot = self.slot();
+        slot.demand.fetch_add(demand, Ordering::Relaxed);
+        slot.demand.fetch_sub(previous, Ordering::Relaxed);
+    }
+
+    /// the share of the limit of a channel with the given demand, in
+    /// proportion to the demand of all channels. The limit is split evenly
+    /// over the cores while there is no demand.
+    pub(crate) fn share(&self, max_ios: u64, demand: u64) -> usize {
+        let total = self
+            .cores
+            .iter()
+            .map(|c| c.demand.load(Ordering::Relaxed))
+            .sum::<u64>();
+        let share = if total == 0 {
+            max_ios / u64::from(Cores::count().id().max(1))
+        } else {
+            max_ios * demand / total
+        };
+        share.max(1) as usize
+    }
+}
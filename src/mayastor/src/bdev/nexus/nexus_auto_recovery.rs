@@ -0,0 +1,116 @@
+//! Automatic recovery of the children of a nexus which were retired for IO
+//! errors, such that the nexus heals itself after a transient outage of the
+//! backend of a child without the operator stepping in. While the recovery
+//! policy of the nexus is set, a task on the master reactor probes the retired
+//! children at the interval of the policy by bringing them back online, which
+//! recreates and reopens the child, checks its identity and capacity and
+//! starts its rebuild. A child which is not reachable yet stays closed until
+//! the next probe.
+//!
+//! Only children whose last event is a fault on IO errors, or on failing to
+//! open, are recovered: children faulted or taken offline by the operator,
+//! and those which returned corrupted data, are left alone. No child is
+//! recovered while the nexus is in maintenance.
+
+use std::sync::atomic::Ordering;
+
+use crate::{
+    bdev::{
+        nexus::{
+            nexus_bdev::Nexus,
+            nexus_bdev_verify::throttle,
+            nexus_child::{ChildState, NexusChild, Reason},
+            nexus_child_history::ChildHistoryEvent,
+            nexus_event::{self, NexusEvent},
+        },
+        nexus_lookup,
+        VerboseError,
+    },
+    core::Reactors,
+};
+
+impl Nexus {
+    /// start the task recovering the retired children, unless it runs
+    pub(crate) fn start_auto_recovery(&self) {
+        if self.auto_recovery_running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        Reactors::master().send_future(Self::auto_recovery(self.name.clone()));
+    }
+
+    /// probe the retired children of the nexus at the interval of its
+    /// recovery policy, until the policy is cleared or the nexus is destroyed
+    async fn auto_recovery(name: String) {
+        while let Some(recovery) =
+            nexus_lookup(&name).and_then(|n| n.policy.auto_recovery)
+        {
+            throttle(recovery.interval).await;
+            match nexus_lookup(&name) {
+                Some(nexus) if nexus.policy.auto_recovery.is_some() => {
+                    nexus.recover_children().await
+                }
+                _ => break,
+            }
+        }
+        debug!("{}: auto recovery stopped", name);
+        if let Some(nexus) = nexus_lookup(&name) {
+            nexus.auto_recovery_running.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// bring the retired children which are reachable again back online
+    async fn recover_children(&mut self) {
+        if self.in_maintenance() {
+            return;
+        }
+        let retired = self
+            .children
+            .iter()
+            .filter(|c| c.recoverable())
+            .map(|c| c.name.clone())
+            .collect::<Vec<_>>();
+
+        for child in retired {
+            match self.online_child(&child).await {
+                Ok(status) => {
+                    info!(
+                        "{}: recovering child {}, nexus is {:?}",
+                        self.name, child, status
+                    );
+                    nexus_event::emit(NexusEvent::ChildRecovering {
+                        nexus: self.name.clone(),
+                        child,
+                    });
+                }
+                Err(error) => debug!(
+                    "{}: child {} is not recoverable yet: {}",
+                    self.name,
+                    child,
+                    error.verbose()
+                ),
+            }
+        }
+    }
+}
+
+impl NexusChild {
+    /// the child was retired for IO errors or IO timeouts, or as it could not
+    /// be opened, and has not been brought back since
+    fn recoverable(&self) -> bool {
+        self.state() == ChildState::Closed
+            && self.bdev.is_none()
+            && matches!(
+                self.history().events.back(),
+                Some(ChildHistoryEvent::Faulted {
+                    reason: Reason::IoError,
+                    ..
+                }) | Some(ChildHistoryEvent::Faulted {
+                    reason: Reason::IoTimeout,
+                    ..
+                }) | Some(ChildHistoryEvent::Faulted {
+                    reason: Reason::CantOpen,
+                    ..
+                })
+            )
+    }
+}
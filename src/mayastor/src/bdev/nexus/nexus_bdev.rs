@@ -5,10 +5,18 @@
 //! application needs synchronous mirroring may be required.
 
 use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeSet, HashMap},
     env,
     fmt::{Display, Formatter},
     os::raw::c_void,
     ptr::NonNull,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
 use futures::{channel::oneshot, future::join_all};
@@ -22,6 +30,7 @@ use spdk_sys::{
     spdk_bdev,
     spdk_bdev_register,
     spdk_bdev_unregister,
+    spdk_for_each_channel,
     spdk_io_device_register,
     spdk_io_device_unregister,
 };
@@ -31,17 +40,73 @@ use crate::{
         nexus,
         nexus::{
             instances,
-            nexus_channel::{DrEvent, NexusChannel, ReconfigureCtx},
+            nexus_admission::Admission,
+            nexus_bdev_read::ReadFreshness,
+            nexus_bdev_rebuild::RebuildTracker,
+            nexus_bdev_snapshot::{SnapshotClock, SystemClock},
+            nexus_channel::{
+                ChildIoFlags,
+                DrEvent,
+                NexusChannel,
+                NexusChannelInner,
+                ReconfigureCtx,
+                TraverseCtx,
+            },
+            nexus_channel_warm::WarmChannels,
             nexus_child::{ChildError, ChildState, NexusChild},
+            nexus_child_limit::BackgroundLimit,
+            nexus_event::{self, NexusEvent},
+            nexus_io_trace::IoTrace,
             nexus_label::LabelError,
+            nexus_maintenance::MaintenanceState,
+            nexus_metrics::{NexusMetrics, NexusMetricsSnapshot, WriteStats},
+            nexus_missing::MissingWrites,
             nexus_nbd::{NbdDisk, NbdError},
+            nexus_observer::IoObserver,
+            nexus_policy::{
+                AdminPassthru,
+                AllFailedPolicy,
+                AutoRecovery,
+                CompletionBatching,
+                DestroyRetry,
+                FailureCondition,
+                FaultPolicy,
+                FlushFailurePolicy,
+                IoAccounting,
+                IoTimeout,
+                NexusPolicy,
+                NoFaultPolicy,
+                NoMemoryPolicy,
+                OnlineVerify,
+                OpenRetry,
+                OutstandingLimit,
+                PausePolicy,
+                ReadPolicy,
+                UnhandledAction,
+                UnhandledPolicy,
+                UnsupportedPolicy,
+                WriteAck,
+                WriteCache,
+                WriteOrdering,
+            },
+            nexus_read_checksum::READ_CHECKSUM_MD_SIZE,
+            nexus_verifier::ReadVerifier,
         },
     },
-    core::{Bdev, CoreError, IoType, Protocol, Reactor, Share},
+    core::{
+        Bdev,
+        CoreError,
+        DmaError,
+        IoType,
+        NvmeStatusCode,
+        Protocol,
+        Reactor,
+        Share,
+    },
     ffihelper::errno_result_from_i32,
     nexus_uri::{bdev_destroy, NexusBdevError},
     rebuild::RebuildError,
-    subsys::{NvmfError, NvmfSubsystem},
+    subsys::{Config, NvmfError, NvmfSubsystem},
 };
 
 /// Obtain the full error chain
@@ -131,6 +196,19 @@ pub enum Error {
         name
     ))]
     ChildGeometry { child: String, name: String },
+    #[snafu(display(
+        "Child {} of nexus {} is too small: {} blocks, {} blocks required",
+        child,
+        name,
+        child_blocks,
+        required_blocks
+    ))]
+    ChildTooSmall {
+        child: String,
+        name: String,
+        child_blocks: u64,
+        required_blocks: u64,
+    },
     #[snafu(display("Child {} of nexus {} cannot be found", child, name))]
     ChildMissing { child: String, name: String },
     #[snafu(display("Child {} of nexus {} has no error store", child, name))]
@@ -234,12 +312,240 @@ pub enum Error {
         name: String,
         state: String,
     },
-    #[snafu(display("Failed to get BdevHandle for snapshot operation"))]
-    FailedGetHandle,
+    #[snafu(display(
+        "Failed to get a handle to nexus {} with status {:?} and children {:?} for snapshot operation",
+        name,
+        status,
+        children
+    ))]
+    FailedGetHandle {
+        source: CoreError,
+        name: String,
+        status: NexusStatus,
+        children: Vec<(String, ChildState)>,
+    },
     #[snafu(display("Failed to create snapshot on nexus {}", name))]
     FailedCreateSnapshot { name: String, source: CoreError },
     #[snafu(display("NVMf subsystem error: {}", e))]
     SubsysNvmfError { e: String },
+    #[snafu(display(
+        "Children of nexus {} changed during the operation, gave up after {} attempts",
+        name,
+        attempts
+    ))]
+    TopologyChanged { name: String, attempts: u32 },
+    #[snafu(display(
+        "No child of nexus {} can serve reads with freshness {:?}",
+        name,
+        freshness
+    ))]
+    NoReadableChild {
+        name: String,
+        freshness: ReadFreshness,
+    },
+    #[snafu(display("Failed to read from child {} of nexus {}", child, name))]
+    ChildRead {
+        source: CoreError,
+        child: String,
+        name: String,
+    },
+    #[snafu(display("Failed to write to child {} of nexus {}", child, name))]
+    ChildWrite {
+        source: CoreError,
+        child: String,
+        name: String,
+    },
+    #[snafu(display(
+        "Data to repair child {} of nexus {} with matches no other child",
+        child,
+        name
+    ))]
+    RepairDiverges { child: String, name: String },
+    #[snafu(display("Failed to lock the range of nexus {} to repair", name))]
+    RepairRangeLock { source: Errno, name: String },
+    #[snafu(display("Operation failed on child {} of nexus {}", child, name))]
+    WriterOperation {
+        source: CoreError,
+        child: String,
+        name: String,
+    },
+    #[snafu(display(
+        "Child {} of nexus {} is not open but {}",
+        child,
+        name,
+        state
+    ))]
+    ChildNotOpen {
+        child: String,
+        name: String,
+        state: ChildState,
+    },
+    #[snafu(display(
+        "Child {} of nexus {} is not a local replica and cannot be backed up",
+        child,
+        name
+    ))]
+    BackupUnsupported { child: String, name: String },
+    #[snafu(display("Failed to back up child {} of nexus {}", child, name))]
+    ChildBackup {
+        source: CoreError,
+        child: String,
+        name: String,
+    },
+    #[snafu(display("Failed to snapshot child {} of nexus {}", child, name))]
+    ChildSnapshot {
+        source: crate::lvs::Error,
+        child: String,
+        name: String,
+    },
+    #[snafu(display(
+        "Child {} of nexus {} kept receiving writes, no consistent backup after {} attempts",
+        child,
+        name,
+        attempts
+    ))]
+    BackupInconsistent {
+        child: String,
+        name: String,
+        attempts: u32,
+    },
+    #[snafu(display(
+        "Nexus {} has fewer than two healthy children to compare",
+        name
+    ))]
+    NotEnoughChildren { name: String },
+    #[snafu(display(
+        "Snapshot {} of nexus {} was not found on any child",
+        snapshot,
+        name
+    ))]
+    SnapshotNotFound { snapshot: String, name: String },
+    #[snafu(display(
+        "Snapshot {} of nexus {} was deleted from some children only, children {:?} still hold it",
+        snapshot,
+        name,
+        children
+    ))]
+    SnapshotPartiallyDeleted {
+        snapshot: String,
+        name: String,
+        children: Vec<String>,
+    },
+    #[snafu(display("Failed to allocate buffers to verify nexus {}", name))]
+    VerifyAlloc { source: DmaError, name: String },
+    #[snafu(display(
+        "Alignment {} is not a power of two multiple of the block size of nexus {}",
+        alignment,
+        name
+    ))]
+    InvalidAlignment { alignment: u32, name: String },
+    #[snafu(display(
+        "Failed to sync children {:?} of nexus {}",
+        children,
+        name
+    ))]
+    SyncFailed { name: String, children: Vec<String> },
+    #[snafu(display("Failed to open a handle to nexus {}", name))]
+    IoHandle { source: CoreError, name: String },
+    #[snafu(display(
+        "Failed to warm the IO channels of nexus {} on {} threads",
+        name,
+        failed
+    ))]
+    WarmChannels { failed: usize, name: String },
+    #[snafu(display(
+        "Failed to dispatch IO at offset {} of nexus {}",
+        offset,
+        name
+    ))]
+    IoDispatch {
+        source: Errno,
+        offset: u64,
+        name: String,
+    },
+    #[snafu(display(
+        "Child {} of nexus {} belongs to {}, expected {}",
+        child,
+        name,
+        uuid,
+        expected
+    ))]
+    ForeignChild {
+        child: String,
+        name: String,
+        uuid: String,
+        expected: String,
+    },
+    #[snafu(display(
+        "Write cache {:?} is not supported by nexus {}",
+        cache,
+        name
+    ))]
+    WriteCacheUnsupported { cache: WriteCache, name: String },
+    #[snafu(display("Write quorum of nexus {} must not be zero", name))]
+    InvalidWriteQuorum { name: String },
+    #[snafu(display("Minimum readers of nexus {} must not be zero", name))]
+    InvalidMinReaders { name: String },
+    #[snafu(display(
+        "Failed to reconfigure the IO channels of nexus {}: {}",
+        name,
+        Errno::from_i32(-*status)
+    ))]
+    ReconfigureFailed { status: i32, name: String },
+    #[snafu(display(
+        "IO trace duration {:?} of nexus {} is out of range",
+        duration,
+        name
+    ))]
+    InvalidIoTraceDuration { duration: Duration, name: String },
+    #[snafu(display("An IO trace of nexus {} is already active", name))]
+    IoTraceActive { name: String },
+    #[snafu(display(
+        "Failed to create IO trace file {} of nexus {}: {}",
+        path,
+        name,
+        source
+    ))]
+    IoTraceFile {
+        source: std::io::Error,
+        path: String,
+        name: String,
+    },
+    #[snafu(display(
+        "Background IO limit {:?} of child {} of nexus {} admits no IO",
+        limit,
+        child,
+        name
+    ))]
+    InvalidBackgroundLimit {
+        limit: BackgroundLimit,
+        child: String,
+        name: String,
+    },
+    #[snafu(display(
+        "Outstanding IO limit {:?} of nexus {} admits no IO",
+        limit,
+        name
+    ))]
+    InvalidOutstandingLimit {
+        limit: OutstandingLimit,
+        name: String,
+    },
+    #[snafu(display(
+        "Action {:?} is not implemented for IO type {:?} by nexus {}",
+        action,
+        io_type,
+        name
+    ))]
+    UnhandledActionUnsupported {
+        io_type: IoType,
+        action: UnhandledAction,
+        name: String,
+    },
+    #[snafu(display("No IO {:#x} in flight on nexus {}", id, name))]
+    IoNotFound { id: u64, name: String },
+    #[snafu(display("Aborting all IO of nexus {} must be forced", name))]
+    AbortNotForced { name: String },
 }
 
 impl From<NvmfError> for Error {
@@ -280,6 +586,9 @@ impl From<Error> for tonic::Status {
             Error::ChildGeometry {
                 ..
             } => Status::invalid_argument(e.to_string()),
+            Error::ChildTooSmall {
+                ..
+            } => Status::invalid_argument(e.to_string()),
             Error::OpenChild {
                 ..
             } => Status::invalid_argument(e.to_string()),
@@ -289,6 +598,66 @@ impl From<Error> for tonic::Status {
             Error::ChildNotFound {
                 ..
             } => Status::not_found(e.to_string()),
+            Error::SnapshotNotFound {
+                ..
+            } => Status::not_found(e.to_string()),
+            Error::SnapshotPartiallyDeleted {
+                ..
+            } => Status::data_loss(e.to_string()),
+            Error::TopologyChanged {
+                ..
+            } => Status::aborted(e.to_string()),
+            Error::NoReadableChild {
+                ..
+            } => Status::unavailable(e.to_string()),
+            Error::FailedGetHandle {
+                ..
+            } => Status::unavailable(e.to_string()),
+            Error::InvalidAlignment {
+                ..
+            } => Status::invalid_argument(e.to_string()),
+            Error::IoNotFound {
+                ..
+            } => Status::not_found(e.to_string()),
+            Error::ForeignChild {
+                ..
+            } => Status::failed_precondition(e.to_string()),
+            Error::WriteCacheUnsupported {
+                ..
+            } => Status::invalid_argument(e.to_string()),
+            Error::InvalidOutstandingLimit {
+                ..
+            } => Status::invalid_argument(e.to_string()),
+            Error::InvalidBackgroundLimit {
+                ..
+            } => Status::invalid_argument(e.to_string()),
+            Error::InvalidWriteQuorum {
+                ..
+            } => Status::invalid_argument(e.to_string()),
+            Error::InvalidMinReaders {
+                ..
+            } => Status::invalid_argument(e.to_string()),
+            Error::ReconfigureFailed {
+                ..
+            } => Status::internal(e.to_string()),
+            Error::InvalidIoTraceDuration {
+                ..
+            } => Status::invalid_argument(e.to_string()),
+            Error::IoTraceActive {
+                ..
+            } => Status::failed_precondition(e.to_string()),
+            Error::IoTraceFile {
+                ..
+            } => Status::invalid_argument(e.to_string()),
+            Error::UnhandledActionUnsupported {
+                ..
+            } => Status::invalid_argument(e.to_string()),
+            Error::AbortNotForced {
+                ..
+            } => Status::failed_precondition(e.to_string()),
+            Error::RepairDiverges {
+                ..
+            } => Status::failed_precondition(e.to_string()),
             e => Status::new(Code::Internal, e.to_string()),
         }
     }
@@ -327,6 +696,52 @@ pub struct Nexus {
     pub(crate) share_handle: Option<String>,
     /// enum containing the protocol-specific target used to publish the nexus
     pub nexus_target: Option<NexusTarget>,
+    /// metrics of the nexus
+    pub(crate) metrics: Arc<NexusMetrics>,
+    /// the IO admitted within the outstanding IO limit on every core
+    pub(crate) admission: Admission,
+    /// observers of the IO path
+    pub(crate) io_observers: Vec<Arc<dyn IoObserver>>,
+    /// verifier of the data of successful child reads
+    pub(crate) read_verifier: Option<Arc<dyn ReadVerifier>>,
+    /// policies of the nexus
+    pub(crate) policy: NexusPolicy,
+    /// number of outstanding pause requests, IO is held while non zero
+    paused: AtomicU32,
+    /// topology epoch, incremented on every reconfiguration
+    pub(crate) epoch: AtomicU64,
+    /// background work held off while the nexus is in maintenance
+    pub(crate) maintenance: Option<MaintenanceState>,
+    /// serializes the snapshots of the nexus and of its children
+    pub(crate) snapshot_lock: futures::lock::Mutex<()>,
+    /// the clock the times of the snapshots of the nexus are taken from
+    pub(crate) snapshot_clock: Arc<dyn SnapshotClock>,
+    /// time of the last snapshot of the nexus which was attempted, 0 if none
+    pub(crate) last_snapshot_time: AtomicU64,
+    /// IO is being retried on the last healthy child rather than faulting it
+    pub(crate) last_child_held: AtomicBool,
+    /// the last reconfiguration of the IO channels failed, hence some cores
+    /// may still use the children from before it
+    reconfigure_failed: AtomicBool,
+    /// the faulted children which could not be retired as the reconfiguration
+    /// of the IO channels failed, by child name
+    pub(crate) stalled_children: std::sync::Mutex<BTreeSet<String>>,
+    /// verbose trace of the IO path, when active
+    pub(crate) io_trace: IoTrace,
+    /// the IO path accounts every IO, see `instrumented()`
+    instrumented: AtomicBool,
+    /// the segments written while a child assembled as a placeholder is not
+    /// rebuilt yet
+    pub(crate) missing_writes: Option<Arc<MissingWrites>>,
+    /// the IO channels held warm, see `nexus_channel_warm`
+    pub(crate) warm_channels: std::sync::Mutex<WarmChannels>,
+    /// the task recovering the retired children runs
+    pub(crate) auto_recovery_running: AtomicBool,
+    /// the progress of the rebuilds of the children, by child name
+    pub(crate) rebuild_trackers: HashMap<String, RebuildTracker>,
+    /// the descriptors of the readers opened with `ReadFreshness::InSync`,
+    /// by address
+    pub(crate) in_sync_descs: Vec<usize>,
 }
 
 unsafe impl core::marker::Sync for Nexus {}
@@ -416,9 +831,34 @@ impl Nexus {
             share_handle: None,
             size,
             nexus_target: None,
+            metrics: Arc::new(NexusMetrics::default()),
+            admission: Admission::default(),
+            io_observers: Vec::new(),
+            read_verifier: None,
+            policy: NexusPolicy {
+                retire_core: Config::get().nexus_opts.retire_core,
+                ..Default::default()
+            },
+            paused: AtomicU32::new(0),
+            epoch: AtomicU64::new(0),
+            maintenance: None,
+            snapshot_lock: futures::lock::Mutex::new(()),
+            snapshot_clock: Arc::new(SystemClock),
+            last_snapshot_time: AtomicU64::new(0),
+            last_child_held: AtomicBool::new(false),
+            reconfigure_failed: AtomicBool::new(false),
+            stalled_children: Default::default(),
+            io_trace: IoTrace::default(),
+            instrumented: AtomicBool::new(false),
+            missing_writes: None,
+            warm_channels: std::sync::Mutex::new(WarmChannels::default()),
+            auto_recovery_running: AtomicBool::new(false),
+            rebuild_trackers: HashMap::new(),
+            in_sync_descs: Vec::new(),
         });
 
         n.bdev.set_uuid(uuid.map(String::from));
+        n.update_instrumented();
 
         if let Some(child_bdevs) = child_bdevs {
             n.register_children(child_bdevs);
@@ -440,13 +880,493 @@ impl Nexus {
         *self.state.lock().unwrap() = state;
         state
     }
+    /// returns a snapshot of the metrics of the nexus
+    pub fn metrics(&self) -> NexusMetricsSnapshot {
+        let mut metrics = self.metrics.snapshot();
+        metrics.outstanding_ios = self.admission.outstanding();
+        metrics.max_outstanding_ios =
+            self.policy.max_outstanding.map_or(0, |l| l.max_ios);
+        metrics
+    }
+
+    /// the bytes written to the nexus and to its children, summed over the IO
+    /// channels of all cores
+    pub async fn write_stats(&self) -> WriteStats {
+        let written = Rc::new(Cell::new(self.metrics.written()));
+        let w = Rc::clone(&written);
+        self.traverse_io_channels(move |channel| {
+            let mut sum = w.get();
+            sum += channel.written;
+            w.set(sum);
+        })
+        .await;
+        written.get().into()
+    }
+
+    /// register an observer of the IO path of the nexus. Observers should be
+    /// registered before IO is submitted to the nexus.
+    pub fn register_io_observer(&mut self, observer: Arc<dyn IoObserver>) {
+        self.io_observers.push(observer);
+        self.update_instrumented();
+    }
+
+    /// Whether the IO path of the nexus accounts every IO: lists it as in
+    /// flight, traces it, checks its alignment, counts it in the throughput
+    /// and the write amplification, admits it within the outstanding IO
+    /// limit and passes it to the observers. The flag is read once per IO
+    /// when it is submitted. It is only cleared when nothing consumes any of
+    /// this: the nexus has no observer, no active IO trace, no alignment, no
+    /// outstanding IO limit, no IO timeout, no read sampling and no IO
+    /// accounting.
+    pub fn instrumented(&self) -> bool {
+        self.instrumented.load(Ordering::Relaxed)
+    }
+
+    /// recompute whether the nexus is instrumented, after a change of the
+    /// policies or consumers it depends on
+    pub(crate) fn update_instrumented(&self) {
+        let policy = &self.policy;
+        let instrumented = !self.io_observers.is_empty()
+            || self.io_trace.active()
+            || policy.alignment.is_some()
+            || policy.max_outstanding.is_some()
+            || policy.io_timeout.0 != Duration::default()
+            || policy.read_sample_interval != 0
+            || policy.io_accounting.0;
+        self.instrumented.store(instrumented, Ordering::Relaxed);
+    }
+
+    /// set the verifier of the data of successful child reads, see
+    /// `ReadVerifier`
+    pub fn set_read_verifier(
+        &mut self,
+        verifier: Option<Arc<dyn ReadVerifier>>,
+    ) {
+        self.read_verifier = verifier;
+    }
+
+    /// set the clock the times of the snapshots of the nexus are taken from,
+    /// see `SnapshotClock`
+    pub fn set_snapshot_clock(&mut self, clock: Arc<dyn SnapshotClock>) {
+        self.snapshot_clock = clock;
+    }
+
+    /// returns the policies of the nexus
+    pub fn policy(&self) -> &NexusPolicy {
+        &self.policy
+    }
+
+    /// set the handling of IO submitted while the nexus is paused
+    pub fn set_pause_policy(&mut self, policy: PausePolicy) {
+        info!("{}: pause policy set to {:?}", self.name, policy);
+        self.policy.pause = policy;
+    }
+
+    /// set the handling of IO which failed on all children
+    pub fn set_all_failed_policy(&mut self, policy: AllFailedPolicy) {
+        info!("{}: all failed policy set to {:?}", self.name, policy);
+        self.policy.all_failed = policy;
+    }
+
+    /// set the selection of the children reads are served by
+    pub fn set_read_policy(&mut self, policy: ReadPolicy) {
+        info!("{}: read policy set to {:?}", self.name, policy);
+        self.policy.read = policy;
+    }
+
+    /// set the child which serves the reads of the nexus with the preferred
+    /// read policy, None to rotate between all children
+    pub async fn set_preferred_reader(
+        &mut self,
+        child: Option<&str>,
+    ) -> Result<(), Error> {
+        let bdev = match child {
+            Some(child) => {
+                let child =
+                    self.children.iter().find(|c| c.name == child).ok_or_else(
+                        || Error::ChildNotFound {
+                            child: child.to_owned(),
+                            name: self.name.clone(),
+                        },
+                    )?;
+                child.bdev.as_ref().map(|b| b.name())
+            }
+            None => None,
+        };
+        info!("{}: preferred reader set to {:?}", self.name, child);
+        self.policy.preferred_reader = child.map(String::from);
+
+        self.traverse_io_channels(move |channel| {
+            channel.set_preferred_reader(bdev.as_deref())
+        })
+        .await;
+        Ok(())
+    }
+
+    /// Set the actions for IO of the types the nexus does not handle itself.
+    /// Every action must be implemented for its IO type. The IO types are
+    /// advertised as supported by the nexus accordingly.
+    pub fn set_unhandled_policy(
+        &mut self,
+        policy: UnhandledPolicy,
+    ) -> Result<(), Error> {
+        if let Some((io_type, action)) = policy
+            .actions()
+            .into_iter()
+            .find(|(io_type, action)| !action.implemented(*io_type))
+        {
+            return Err(Error::UnhandledActionUnsupported {
+                io_type,
+                action,
+                name: self.name.clone(),
+            });
+        }
+        info!("{}: unhandled IO policy set to {:?}", self.name, policy);
+        self.policy.unhandled = policy;
+        Ok(())
+    }
+
+    /// set the handling of IO which a child fails as unsupported
+    pub fn set_unsupported_policy(&mut self, policy: UnsupportedPolicy) {
+        info!("{}: unsupported IO policy set to {:?}", self.name, policy);
+        self.policy.unsupported = policy;
+    }
+
+    /// set the number of failed reads and writes which retire a child
+    pub fn set_fault_policy(&mut self, policy: FaultPolicy) {
+        info!("{}: fault policy set to {:?}", self.name, policy);
+        self.policy.fault = policy;
+    }
+
+    /// set the statuses with which a child may fail an IO without being
+    /// retired
+    pub fn set_no_fault_policy(&mut self, policy: NoFaultPolicy) {
+        info!("{}: no fault policy set to {:?}", self.name, policy);
+        self.policy.no_fault = policy;
+    }
+
+    /// set the ordering of writes submitted on the same core. Writes which
+    /// are already waiting for their predecessors are submitted in order when
+    /// ordering is disabled.
+    pub fn set_write_ordering(&mut self, ordering: WriteOrdering) {
+        info!("{}: write ordering set to {:?}", self.name, ordering);
+        self.policy.write_ordering = ordering;
+    }
+
+    /// Set the caching of writes before they are submitted to the children.
+    /// Writes held when the cache is disabled are submitted as usual. Write
+    /// back caching is not supported yet.
+    pub fn set_write_cache(&mut self, cache: WriteCache) -> Result<(), Error> {
+        if let WriteCache::WriteBack {
+            ..
+        } = cache
+        {
+            return Err(Error::WriteCacheUnsupported {
+                cache,
+                name: self.name.clone(),
+            });
+        }
+        info!("{}: write cache set to {:?}", self.name, cache);
+        self.policy.write_cache = cache;
+        Ok(())
+    }
+
+    /// set the processing of the completions of child IOs. Completions which
+    /// are batched already are processed as usual when batching is disabled.
+    pub fn set_completion_batching(&mut self, batching: CompletionBatching) {
+        info!("{}: completion batching set to {:?}", self.name, batching);
+        self.policy.completion_batching = batching;
+    }
+
+    /// Set the limit of the IO outstanding on the nexus, removing it when
+    /// None. IO queued at the limit is submitted as outstanding IO completes,
+    /// also once the limit has been raised or removed. A limit must admit at
+    /// least one IO.
+    pub fn set_max_outstanding(
+        &mut self,
+        limit: Option<OutstandingLimit>,
+    ) -> Result<(), Error> {
+        if let Some(l) = limit {
+            if l.max_ios == 0 {
+                return Err(Error::InvalidOutstandingLimit {
+                    limit: l,
+                    name: self.name.clone(),
+                });
+            }
+        }
+        info!("{}: outstanding IO limit set to {:?}", self.name, limit);
+        self.policy.max_outstanding = limit;
+        self.update_instrumented();
+        Ok(())
+    }
+
+    /// set the interval at which reads are sampled and compared with the data
+    /// of another child, zero disables the sampling
+    pub fn set_read_sample_interval(&mut self, interval: u32) {
+        info!("{}: read sample interval set to {}", self.name, interval);
+        self.policy.read_sample_interval = interval;
+        self.update_instrumented();
+    }
+
+    /// set the retries of the open of a child being added to the nexus
+    pub fn set_open_retry(&mut self, retry: OpenRetry) {
+        info!("{}: child open retry set to {:?}", self.name, retry);
+        self.policy.open_retry = retry;
+    }
+
+    /// set the time after which IO waiting on a child which does not respond
+    /// fails and the child is retired, zero to never time IO out
+    pub fn set_io_timeout(&mut self, timeout: Duration) {
+        info!("{}: IO timeout set to {:?}", self.name, timeout);
+        self.policy.io_timeout = IoTimeout(timeout);
+        self.update_instrumented();
+    }
+
+    /// set whether the IO is accounted in the throughput and the write
+    /// amplification of the nexus, see `instrumented()`
+    pub fn set_io_accounting(&mut self, enabled: bool) {
+        info!("{}: IO accounting enabled: {}", self.name, enabled);
+        self.policy.io_accounting = IoAccounting(enabled);
+        self.update_instrumented();
+    }
+
+    /// set the retries of the destroy of a child which is retired
+    pub fn set_destroy_retry(&mut self, retry: DestroyRetry) {
+        info!("{}: child destroy retry set to {:?}", self.name, retry);
+        self.policy.destroy_retry = retry;
+    }
+
+    /// set the NVMe status with which IO failing on the given condition is
+    /// completed, None to complete it with the status a child failed it with
+    pub fn set_failure_status(
+        &mut self,
+        condition: FailureCondition,
+        status: Option<NvmeStatusCode>,
+    ) {
+        info!(
+            "{}: status of IO failing on {:?} set to {:?}",
+            self.name, condition, status
+        );
+        self.policy.failure_status.set(condition, status);
+    }
+
+    /// set the verification of a child brought back online, None to always
+    /// rebuild it
+    pub fn set_online_verify(&mut self, verify: Option<OnlineVerify>) {
+        info!("{}: online verification set to {:?}", self.name, verify);
+        self.policy.online_verify = verify;
+    }
+
+    /// set the recovery of the children retired for IO errors, None to leave
+    /// them to the operator. The retired children are probed in the
+    /// background while it is set.
+    pub fn set_auto_recovery(&mut self, recovery: Option<AutoRecovery>) {
+        info!("{}: auto recovery set to {:?}", self.name, recovery);
+        self.policy.auto_recovery = recovery;
+        if recovery.is_some() {
+            self.start_auto_recovery();
+        }
+    }
+
+    /// set the number of children which must be able to serve reads for the
+    /// nexus to serve them, None to serve reads from any healthy child; a
+    /// minimum of zero is invalid
+    pub fn set_min_readers(&mut self, min: Option<usize>) -> Result<(), Error> {
+        if min == Some(0) {
+            return Err(Error::InvalidMinReaders {
+                name: self.name.clone(),
+            });
+        }
+        info!("{}: minimum readers set to {:?}", self.name, min);
+        self.policy.min_readers = min;
+        Ok(())
+    }
+
+    /// set the completion of IO which the children ran out of memory for
+    pub fn set_no_memory_policy(&mut self, policy: NoMemoryPolicy) {
+        info!("{}: no memory policy set to {:?}", self.name, policy);
+        self.policy.no_memory = policy;
+    }
+
+    /// set the outcome of a flush which failed on some of the children
+    pub fn set_flush_failure_policy(&mut self, policy: FlushFailurePolicy) {
+        info!("{}: flush failure policy set to {:?}", self.name, policy);
+        self.policy.flush_failure = policy;
+    }
+
+    /// set the number of times a failed reconfiguration is retried
+    pub fn set_reconfigure_retries(&mut self, retries: u32) {
+        info!("{}: reconfigure retries set to {:?}", self.name, retries);
+        self.policy.reconfigure_retries = retries;
+    }
+
+    /// set the number of open children the nexus needs to be ready for
+    /// writes, None for one; a quorum of zero is invalid
+    pub fn set_write_quorum(
+        &mut self,
+        quorum: Option<usize>,
+    ) -> Result<(), Error> {
+        if quorum == Some(0) {
+            return Err(Error::InvalidWriteQuorum {
+                name: self.name.clone(),
+            });
+        }
+        info!("{}: write quorum set to {:?}", self.name, quorum);
+        self.policy.write_quorum = quorum;
+        Ok(())
+    }
+
+    /// set when writes are acknowledged, a quorum of zero is invalid
+    pub fn set_write_ack(&mut self, ack: WriteAck) -> Result<(), Error> {
+        if ack == WriteAck::Quorum(0) {
+            return Err(Error::InvalidWriteQuorum {
+                name: self.name.clone(),
+            });
+        }
+        info!("{}: write acknowledgement set to {:?}", self.name, ack);
+        self.policy.write_ack = ack;
+        Ok(())
+    }
+
+    /// set the passthrough of NVMe admin commands to a child of the nexus,
+    /// admin commands fail when none is set
+    pub fn set_admin_passthru(
+        &mut self,
+        passthru: Option<AdminPassthru>,
+    ) -> Result<(), Error> {
+        if let Some(passthru) = &passthru {
+            if !self.children.iter().any(|c| c.name == passthru.child) {
+                return Err(Error::ChildNotFound {
+                    child: passthru.child.clone(),
+                    name: self.name.clone(),
+                });
+            }
+        }
+        info!("{}: admin passthru set to {:?}", self.name, passthru);
+        self.policy.admin_passthru = passthru;
+        Ok(())
+    }
+
+    /// Format the nexus with separate metadata in which reads return the
+    /// checksums of their data, see `nexus_read_checksum`. The format is
+    /// picked up by consumers when they open the nexus, hence it cannot be
+    /// changed while the nexus is shared.
+    pub fn set_read_checksum(&mut self, enabled: bool) -> Result<(), Error> {
+        if self.share_handle.is_some() {
+            return Err(Error::AlreadyShared {
+                name: self.name.clone(),
+            });
+        }
+        unsafe {
+            let bdev = &mut *self.bdev.as_ptr();
+            bdev.md_len = if enabled { READ_CHECKSUM_MD_SIZE } else { 0 };
+            bdev.md_interleave = false;
+        }
+        info!("{}: read checksums enabled: {}", self.name, enabled);
+        Ok(())
+    }
+
+    /// whether reads can return the checksums of their data
+    pub fn read_checksum(&self) -> bool {
+        unsafe { (*self.bdev.as_ptr()).md_len != 0 }
+    }
+
+    /// set the time for which IO which failed on the last healthy child is
+    /// retried before the child is faulted
+    pub fn set_last_child_grace(&mut self, grace: Duration) {
+        info!("{}: last child grace set to {:?}", self.name, grace);
+        self.policy.last_child_grace = grace;
+    }
+
+    /// Set the alignment in bytes required of IO submitted to the nexus,
+    /// resetting it to the block size when None. The alignment must be a
+    /// power of two and a multiple of the block size.
+    pub fn set_alignment(
+        &mut self,
+        alignment: Option<u32>,
+    ) -> Result<(), Error> {
+        if let Some(a) = alignment {
+            let block_len = self.bdev.block_len();
+            if !a.is_power_of_two() || block_len == 0 || a % block_len != 0 {
+                return Err(Error::InvalidAlignment {
+                    alignment: a,
+                    name: self.name.clone(),
+                });
+            }
+        }
+        info!("{}: IO alignment set to {:?}", self.name, alignment);
+        self.policy.alignment = alignment;
+        self.update_instrumented();
+        Ok(())
+    }
+
     /// returns the size in bytes of the nexus instance
     pub fn size(&self) -> u64 {
         u64::from(self.bdev.block_len()) * self.bdev.num_blocks()
     }
 
-    /// reconfigure the child event handler
+    /// reconfigure the child event handler, a failure is handled as by
+    /// `try_reconfigure()`
     pub(crate) async fn reconfigure(&self, event: DrEvent) {
+        let _ = self.try_reconfigure(event).await;
+    }
+
+    /// Reconfigure the IO channels of all cores after a change of the
+    /// children, retrying as set by the policy of the nexus. When every
+    /// attempt fails the nexus is marked as needing attention and an event is
+    /// raised; the mark is cleared by the next reconfiguration which succeeds.
+    /// The read weights of the children which are not pinned are reset.
+    pub(crate) async fn try_reconfigure(
+        &self,
+        event: DrEvent,
+    ) -> Result<(), Error> {
+        self.reset_read_weights();
+        let mut status = 0;
+        for attempt in 0 ..= self.policy.reconfigure_retries {
+            if attempt > 0 {
+                warn!(
+                    "{}: retrying reconfiguration event {:?}, attempt {}",
+                    self.name, event, attempt
+                );
+            }
+            status = self.reconfigure_once(&event).await;
+            if status == 0 {
+                break;
+            }
+            self.metrics.reconfigure_failed();
+        }
+
+        self.update_faulted();
+        self.update_io_limits();
+
+        if status == 0 {
+            if self.reconfigure_failed.swap(false, Ordering::SeqCst) {
+                info!("{}: IO channels reconfigured after failure", self.name);
+            }
+            return Ok(());
+        }
+
+        error!(
+            "{}: failed to reconfigure IO channels for {:?}: {}, nexus needs attention",
+            self.name,
+            event,
+            Errno::from_i32(-status)
+        );
+        self.reconfigure_failed.store(true, Ordering::SeqCst);
+        nexus_event::emit(NexusEvent::ReconfigureFailed {
+            nexus: self.name.clone(),
+            event: format!("{:?}", event),
+            status,
+        });
+        Err(Error::ReconfigureFailed {
+            status,
+            name: self.name.clone(),
+        })
+    }
+
+    /// reconfigure the IO channels once, returns the status of the traversal
+    /// of the channels
+    async fn reconfigure_once(&self, event: &DrEvent) -> i32 {
         let (s, r) = oneshot::channel::<i32>();
 
         info!(
@@ -454,12 +1374,14 @@ impl Nexus {
             self.name, event
         );
 
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+
         let ctx = Box::new(ReconfigureCtx::new(
             s,
             NonNull::new(self.as_ptr()).unwrap(),
         ));
 
-        NexusChannel::reconfigure(self.as_ptr(), ctx, &event);
+        NexusChannel::reconfigure(self.as_ptr(), ctx, event);
 
         let result = r.await.expect("reconfigure sender already dropped");
 
@@ -467,6 +1389,162 @@ impl Nexus {
             "{}: Dynamic reconfiguration event: {:?} completed {:?}",
             self.name, event, result
         );
+
+        result
+    }
+
+    /// The last reconfiguration of the IO channels failed even after its
+    /// retries, some cores may still submit IO to children which have been
+    /// faulted since. A rebalance or a later change of the children which
+    /// reconfigures the channels successfully clears it.
+    pub fn needs_attention(&self) -> bool {
+        self.reconfigure_failed.load(Ordering::SeqCst)
+    }
+
+    /// The faulted children which could not be retired as the IO channels
+    /// could not be reconfigured. They are kept faulted rather than
+    /// destroyed until they are removed from the nexus.
+    pub fn stalled_retires(&self) -> Vec<String> {
+        self.stalled_children
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// record that the faulted child could not be retired as the IO channels
+    /// could not be reconfigured
+    pub(crate) fn retire_stalled(&self, child: &NexusChild) {
+        self.stalled_children
+            .lock()
+            .unwrap()
+            .insert(child.name.clone());
+        nexus_event::emit(NexusEvent::RetireStalled {
+            nexus: self.name.clone(),
+            child: child.name.clone(),
+        });
+    }
+
+    /// Re-derive the IO channel of every core from the children of the nexus,
+    /// which restores a consistent view of the children on all cores and
+    /// spreads the reads of the cores across the readers. Unlike a
+    /// reconfiguration this is not triggered by a change of the children but
+    /// corrects channels which went stale, and it also resets the IO flags of
+    /// the children. Returns the number of channels which were corrected.
+    pub async fn rebalance(&self) -> usize {
+        info!("{}: rebalancing IO channels", self.name);
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+        self.children
+            .iter()
+            .for_each(|c| c.set_io_flags(ChildIoFlags::default()));
+
+        let corrected = Rc::new(Cell::new(0));
+        let c = Rc::clone(&corrected);
+        let status = self
+            .traverse_io_channels(move |channel| {
+                if channel.rebalance() {
+                    c.set(c.get() + 1);
+                }
+            })
+            .await;
+        if status == 0 {
+            self.reconfigure_failed.store(false, Ordering::SeqCst);
+        }
+
+        info!(
+            "{}: rebalanced IO channels, {} corrected",
+            self.name,
+            corrected.get()
+        );
+        self.update_faulted();
+        corrected.get()
+    }
+
+    /// Update the faulted gauge after the children of the nexus have been
+    /// reconfigured. When the nexus becomes faulted a critical event is raised
+    /// which carries the fault reason of the last surviving child, i.e. the
+    /// child that most recently transitioned out of the open state.
+    fn update_faulted(&self) {
+        let state = *self.state.lock().unwrap();
+        let faulted =
+            state == NexusState::Open && self.status() == NexusStatus::Faulted;
+
+        // a nexus which is being closed is not considered to be faulted
+        if self.metrics.set_faulted(faulted) == faulted
+            || state != NexusState::Open
+        {
+            return;
+        }
+
+        if faulted {
+            let child = self
+                .children
+                .iter()
+                .filter(|c| matches!(c.state(), ChildState::Faulted(_)))
+                .find(|c| c.prev_state.load() == ChildState::Open)
+                .or_else(|| {
+                    self.children
+                        .iter()
+                        .find(|c| matches!(c.state(), ChildState::Faulted(_)))
+                });
+            let reason = match child.map(|c| c.state()) {
+                Some(ChildState::Faulted(reason)) => Some(reason),
+                _ => None,
+            };
+
+            error!(
+                "{}: nexus is faulted, last child {:?} reason {:?}",
+                self.name,
+                child.map(|c| c.name.as_str()),
+                reason
+            );
+            nexus_event::emit(NexusEvent::Faulted {
+                nexus: self.name.clone(),
+                child: child.map(|c| c.name.clone()),
+                reason,
+            });
+        } else {
+            info!("{}: nexus recovered from the faulted state", self.name);
+            nexus_event::emit(NexusEvent::Recovered {
+                nexus: self.name.clone(),
+            });
+        }
+    }
+
+    /// run the given closure against the IO channel of every core, one core
+    /// at the time, and wait until all cores have been visited
+    pub(crate) async fn traverse_io_channels<F>(&self, f: F) -> i32
+    where
+        F: FnMut(&mut NexusChannelInner) + 'static,
+    {
+        let (s, r) = oneshot::channel::<i32>();
+        let ctx = Box::new(TraverseCtx::new(Box::new(f), s));
+
+        unsafe {
+            spdk_for_each_channel(
+                self.as_ptr(),
+                Some(NexusChannel::traverse_io_channels),
+                Box::into_raw(ctx).cast(),
+                Some(NexusChannel::traverse_completed),
+            );
+        }
+
+        r.await.expect("traverse sender already dropped")
+    }
+
+    /// wait until the writes submitted on every core so far have completed,
+    /// such that a flush covers the writes of all cores. A channel destroyed
+    /// meanwhile has no writes left to wait for.
+    pub(crate) async fn wait_writes(&self) {
+        let receivers = Rc::new(RefCell::new(Vec::new()));
+        let r = Rc::clone(&receivers);
+        self.traverse_io_channels(move |channel| {
+            r.borrow_mut().extend(channel.barrier.wait_writes());
+        })
+        .await;
+
+        join_all(receivers.replace(Vec::new())).await;
     }
 
     /// Opens the Nexus instance for IO
@@ -475,6 +1553,7 @@ impl Nexus {
 
         self.try_open_children().await?;
         self.sync_labels().await?;
+        self.track_missing_writes();
         self.register().await
     }
 
@@ -567,6 +1646,9 @@ impl Nexus {
             self.cancel_child_rebuild_jobs(&child.name).await;
         }
 
+        // the channels held warm hold handles to the children
+        self.cool_channels().await;
+
         for child in self.children.iter_mut() {
             info!("Destroying child bdev {}", child.name);
             if let Err(e) = child.close().await {
@@ -601,8 +1683,18 @@ impl Nexus {
         }
     }
 
-    /// resume IO to the bdev
-    pub(crate) async fn resume(&self) -> Result<(), Error> {
+    /// resume IO to the bdev. IO which has been queued while the nexus was
+    /// paused is submitted once the last pause request has been resumed.
+    pub async fn resume(&self) -> Result<(), Error> {
+        if self.paused.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.traverse_io_channels(|inner| {
+                std::mem::take(&mut inner.paused_ios)
+                    .into_iter()
+                    .for_each(|io| io.submit());
+            })
+            .await;
+        }
+
         if let Some(Protocol::Nvmf) = self.shared() {
             if let Some(subsystem) = NvmfSubsystem::nqn_lookup(&self.name) {
                 subsystem.resume().await.unwrap();
@@ -613,8 +1705,12 @@ impl Nexus {
     }
 
     /// suspend any incoming IO to the bdev pausing the controller allows us to
-    /// handle internal events and which is a protocol feature.
-    pub(crate) async fn pause(&self) -> Result<(), Error> {
+    /// handle internal events and which is a protocol feature. IO which is
+    /// submitted by other frontends while paused is handled according to the
+    /// pause policy of the nexus.
+    pub async fn pause(&self) -> Result<(), Error> {
+        self.paused.fetch_add(1, Ordering::SeqCst);
+
         if let Some(Protocol::Nvmf) = self.shared() {
             if let Some(subsystem) = NvmfSubsystem::nqn_lookup(&self.name) {
                 subsystem.pause().await.unwrap();
@@ -642,6 +1738,11 @@ impl Nexus {
         })
     }
 
+    /// returns true if IO to the nexus is currently held
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed) != 0
+    }
+
     /// set ANA state of the NVMe subsystem
     pub async fn set_ana_state(
         &self,
@@ -783,6 +1884,21 @@ pub async fn nexus_create(
     size: u64,
     uuid: Option<&str>,
     children: &[String],
+) -> Result<(), Error> {
+    nexus_create_with_missing(name, size, uuid, children, &[]).await
+}
+
+/// Create a new nexus as `nexus_create`, with placeholders for the children
+/// which are known to be part of the nexus but are not reachable yet. The
+/// placeholders are missing children, which are rebuilt from the segments
+/// written in the meantime once they are onlined. At least one of the
+/// children must be reachable.
+pub async fn nexus_create_with_missing(
+    name: &str,
+    size: u64,
+    uuid: Option<&str>,
+    children: &[String],
+    missing: &[String],
 ) -> Result<(), Error> {
     // global variable defined in the nexus module
     let nexus_list = instances();
@@ -824,6 +1940,10 @@ pub async fn nexus_create(
         }
     }
 
+    for child in missing {
+        ni.register_missing(child);
+    }
+
     match ni.open().await {
         Err(Error::NexusIncomplete {
             ..
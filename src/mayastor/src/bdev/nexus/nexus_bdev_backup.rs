@@ -0,0 +1,171 @@
+//! Implements the backup of a single child of a nexus. The child is flushed
+//! and snapshotted on its own, without pausing the nexus or touching its other
+//! children, and the snapshot is exported read-only for a backup reader. This
+//! allows for continuous backups from a designated replica with minimal impact
+//! on the frontend IO.
+//!
+//! As the nexus is not paused, writes may land on the child between the flush
+//! and the snapshot. The write counter of the child is compared before and
+//! after, and the backup is retaken when it moved, such that the snapshot is
+//! always consistent with the flush point.
+
+use std::{
+    convert::TryFrom,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::{nexus_lookup, Error, Nexus},
+        nexus_child::ChildState,
+    },
+    core::{Bdev, BdevHandle, CoreError},
+    lvs::Lvol,
+};
+
+/// number of times a backup is attempted before giving up when the child
+/// keeps receiving writes
+pub const BACKUP_ATTEMPTS: u32 = 3;
+
+/// A snapshot of a single child, exported read-only
+#[derive(Debug)]
+pub struct ChildBackup {
+    /// URI of the child which was backed up
+    pub child: String,
+    /// name of the snapshot
+    pub snapshot: String,
+    /// read-only handle to the snapshot for the backup reader
+    pub reader: BdevHandle,
+}
+
+impl Nexus {
+    /// Flush and snapshot a single child, returning a read-only handle to the
+    /// snapshot. Only local replicas can be backed up. The snapshot is taken
+    /// on the snapshot reactor of the nexus, under its snapshot lease.
+    pub async fn backup_child(&self, uri: &str) -> Result<ChildBackup, Error> {
+        let child =
+            self.children
+                .iter()
+                .find(|c| c.name == uri)
+                .ok_or_else(|| Error::ChildNotFound {
+                    child: uri.to_string(),
+                    name: self.name.clone(),
+                })?;
+
+        if child.state() != ChildState::Open {
+            return Err(Error::ChildNotOpen {
+                child: uri.to_string(),
+                name: self.name.clone(),
+                state: child.state(),
+            });
+        }
+
+        let snapshot = {
+            let (name, uri) = (self.name.clone(), uri.to_string());
+            self.on_snapshot_reactor(async move {
+                match nexus_lookup(&name) {
+                    Some(nexus) => nexus.backup_snapshot(&uri).await,
+                    None => Err(Error::NexusNotFound {
+                        name: name.clone(),
+                    }),
+                }
+            })
+            .await?
+        };
+
+        // the reader is opened on the reactor of the caller, which it is
+        // bound to
+        let reader = BdevHandle::open_with_bdev(&snapshot.as_bdev(), false)
+            .map_err(|source| Error::ChildBackup {
+                source,
+                child: uri.to_string(),
+                name: self.name.clone(),
+            })?;
+        info!(
+            "{}: backed up child {} to snapshot {}",
+            self.name,
+            uri,
+            snapshot.name()
+        );
+        Ok(ChildBackup {
+            child: uri.to_string(),
+            snapshot: snapshot.name(),
+            reader,
+        })
+    }
+
+    /// Take a snapshot of the child which is consistent with a flush of the
+    /// child, under the snapshot lease of the nexus. Runs on the snapshot
+    /// reactor of the nexus.
+    async fn backup_snapshot(&self, uri: &str) -> Result<Lvol, Error> {
+        let child =
+            self.children
+                .iter()
+                .find(|c| c.name == uri)
+                .ok_or_else(|| Error::ChildNotFound {
+                    child: uri.to_string(),
+                    name: self.name.clone(),
+                })?;
+        let backup = |source: CoreError| Error::ChildBackup {
+            source,
+            child: uri.to_string(),
+            name: self.name.clone(),
+        };
+        let hdl = child.handle().map_err(backup)?;
+        let bdev = hdl.get_bdev();
+        let lvol = Lvol::try_from(bdev.clone()).map_err(|_| {
+            Error::BackupUnsupported {
+                child: uri.to_string(),
+                name: self.name.clone(),
+            }
+        })?;
+
+        let _lease = self.snapshot_lease().await;
+        for attempt in 1 ..= BACKUP_ATTEMPTS {
+            hdl.flush().await.map_err(backup)?;
+            let flushed = Self::write_ops(&bdev).await;
+
+            let snapshot_name = Lvol::format_snapshot_name(
+                &lvol.name(),
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            );
+            let snapshot =
+                lvol.snapshot(&snapshot_name).await.map_err(|source| {
+                    Error::ChildSnapshot {
+                        source,
+                        child: uri.to_string(),
+                        name: self.name.clone(),
+                    }
+                })?;
+
+            if flushed.is_some() && Self::write_ops(&bdev).await == flushed {
+                return Ok(snapshot);
+            }
+
+            warn!(
+                "{}: child {} was written to during backup attempt {}, retrying",
+                self.name, uri, attempt
+            );
+            if let Err(e) = snapshot.destroy().await {
+                error!(
+                    "{}: failed to destroy inconsistent snapshot {}: {}",
+                    self.name, snapshot_name, e
+                );
+            }
+        }
+
+        Err(Error::BackupInconsistent {
+            child: uri.to_string(),
+            name: self.name.clone(),
+            attempts: BACKUP_ATTEMPTS,
+        })
+    }
+
+    /// number of writes completed by the bdev
+    async fn write_ops(bdev: &Bdev) -> Option<u64> {
+        bdev.stats().await.ok().map(|s| s.num_write_ops)
+    }
+}
@@ -20,11 +20,25 @@
 //! child requires rebuild first. If the rebuild flag is set then the rebuild
 //! is also started otherwise it has to be started through `start_rebuild`.
 //!
+//! With an online verification policy set, `online_child` reopens a child
+//! without a rebuild when it missed no writes while it was offline and a
+//! sample of its data matches that of an open child.
+//!
+//! Both `add_child` and `online_child` reject a device which carries the label
+//! of another nexus, as the uri may resolve to a different device than before
+//! (e.g. after disks were reordered on a reboot).
+//!
+//! Children may have their data partition at different offsets, when they
+//! were created with reserved regions of different sizes. IO is addressed per
+//! child, and `add_child` rejects a child with a larger reserved region which
+//! cannot be rebuilt as no healthy child shares its data offset.
+//!
 //! When reconfiguring the nexus, we traverse all our children, create new IO
 //! channels for all children that are in the open state.
 
 use futures::future::join_all;
 use snafu::ResultExt;
+use uuid::Uuid;
 
 use crate::{
     bdev::{
@@ -37,9 +51,13 @@ use crate::{
                 NexusStatus,
                 OpenChild,
             },
-            nexus_channel::DrEvent,
-            nexus_child::{ChildState, NexusChild},
+            nexus_bdev_verify::throttle,
+            nexus_channel::{ChildIoFlags, DrEvent},
+            nexus_child::{ChildLocality, ChildState, NexusChild},
             nexus_child_status_config::ChildStatusConfig,
+            nexus_event::{self, NexusEvent},
+            nexus_label::GptGuid,
+            nexus_policy::OnlineVerify,
         },
         Reason,
         VerboseError,
@@ -99,10 +117,30 @@ impl Nexus {
         uri: &str,
         norebuild: bool,
     ) -> Result<NexusStatus, Error> {
-        let status = self.add_child_only(uri).await?;
+        self.add_child_with(uri, norebuild, None).await
+    }
+
+    /// add a new child to an existing nexus, as `add_child`, with the given
+    /// locality rather than the one derived from the bdev of the child
+    pub async fn add_child_with_locality(
+        &mut self,
+        uri: &str,
+        norebuild: bool,
+        locality: ChildLocality,
+    ) -> Result<NexusStatus, Error> {
+        self.add_child_with(uri, norebuild, Some(locality)).await
+    }
+
+    async fn add_child_with(
+        &mut self,
+        uri: &str,
+        norebuild: bool,
+        locality: Option<ChildLocality>,
+    ) -> Result<NexusStatus, Error> {
+        let status = self.add_child_only(uri, locality).await?;
 
         if !norebuild {
-            if let Err(e) = self.start_rebuild(&uri).await {
+            if let Err(e) = self.start_auto_rebuild(&uri).await {
                 // todo: CAS-253 retry starting the rebuild again when ready
                 error!(
                     "Child added but rebuild failed to start: {}",
@@ -126,30 +164,131 @@ impl Nexus {
     async fn add_child_only(
         &mut self,
         uri: &str,
+        locality: Option<ChildLocality>,
     ) -> Result<NexusStatus, Error> {
+        let mut child = self.open_new_child(uri, locality).await?;
+
+        let valid = match self.validate_child_identity(&child).await {
+            Ok(_) => self.validate_child_geometry(&child).await,
+            Err(error) => Err(error),
+        };
+        if let Err(error) = valid {
+            if let Err(err) = child.close().await {
+                error!("Failed to close rejected child: {}", err.verbose());
+            }
+            if let Err(err) = bdev_destroy(uri).await {
+                error!("Failed to destroy rejected child: {}", err);
+            }
+            return Err(error);
+        }
+
+        // we have created the bdev, and created a nexusChild struct. To
+        // make use of the device itself the
+        // data and metadata must be validated. The child
+        // will be added and marked as faulted, once the rebuild has
+        // completed the device can transition to online
+        info!("{}: child opened successfully {}", self.name, uri);
+
+        // it can never take part in the IO path
+        // of the nexus until it's rebuilt from a healthy child.
+        child.fault(Reason::OutOfSync).await;
+        if ChildStatusConfig::add(&child).is_err() {
+            error!("Failed to add child status information");
+        }
+
+        self.children.push(child);
+        self.child_count += 1;
+        self.update_io_limits();
+
+        if let Err(e) = self.sync_labels().await {
+            error!("Failed to sync labels {:?}", e);
+            // todo: how to signal this?
+        }
+
+        Ok(self.status())
+    }
+
+    /// Create the bdev of a child being added and open it. When either fails
+    /// it is retried as the open retry policy of the nexus allows, as a
+    /// remote backend may need a moment to become reachable; a child which
+    /// does not fit the nexus is refused right away.
+    async fn open_new_child(
+        &self,
+        uri: &str,
+        locality: Option<ChildLocality>,
+    ) -> Result<NexusChild, Error> {
+        let retry = self.policy.open_retry;
+        let mut retries = 0;
+        loop {
+            match self.try_open_new_child(uri, locality).await {
+                Ok(mut child) => {
+                    child.open_retries = retries;
+                    return Ok(child);
+                }
+                Err(
+                    error @ Error::CreateChild {
+                        ..
+                    },
+                )
+                | Err(
+                    error @ Error::OpenChild {
+                        ..
+                    },
+                ) if retries < retry.retries => {
+                    let delay = retry.backoff * 2u32.saturating_pow(retries);
+                    warn!(
+                        "{}: failed to open child {}, retrying in {:?}: {}",
+                        self.name,
+                        uri,
+                        delay,
+                        error.verbose()
+                    );
+                    retries += 1;
+                    self.metrics.child_open_retried();
+                    throttle(delay).await;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// create the bdev of a child being added and open it
+    async fn try_open_new_child(
+        &self,
+        uri: &str,
+        locality: Option<ChildLocality>,
+    ) -> Result<NexusChild, Error> {
         let name = bdev_create(&uri).await.context(CreateChild {
             name: self.name.clone(),
         })?;
 
         let child_bdev = match Bdev::lookup_by_name(&name) {
             Some(child) => {
-                if child.block_len() != self.bdev.block_len()
-                    || self.min_num_blocks() > child.num_blocks()
-                {
+                let result = self
+                    .validate_child_capacity(&name, &child)
+                    .and_then(|_| {
+                        if child.block_len() != self.bdev.block_len()
+                            || self.min_num_blocks() > child.num_blocks()
+                        {
+                            Err(Error::ChildGeometry {
+                                child: name.clone(),
+                                name: self.name.clone(),
+                            })
+                        } else {
+                            Ok(())
+                        }
+                    });
+
+                if let Err(error) = result {
                     if let Err(err) = bdev_destroy(uri).await {
                         error!(
                             "Failed to destroy child bdev with wrong geometry: {}",
                             err
                         );
                     }
-
-                    return Err(Error::ChildGeometry {
-                        child: name,
-                        name: self.name.clone(),
-                    });
-                } else {
-                    child
+                    return Err(error);
                 }
+                child
             }
             None => {
                 return Err(Error::ChildMissing {
@@ -164,32 +303,9 @@ impl Nexus {
             self.name.clone(),
             Some(child_bdev),
         );
+        child.locality = locality;
         match child.open(self.size) {
-            Ok(name) => {
-                // we have created the bdev, and created a nexusChild struct. To
-                // make use of the device itself the
-                // data and metadata must be validated. The child
-                // will be added and marked as faulted, once the rebuild has
-                // completed the device can transition to online
-                info!("{}: child opened successfully {}", self.name, name);
-
-                // it can never take part in the IO path
-                // of the nexus until it's rebuilt from a healthy child.
-                child.fault(Reason::OutOfSync).await;
-                if ChildStatusConfig::add(&child).is_err() {
-                    error!("Failed to add child status information");
-                }
-
-                self.children.push(child);
-                self.child_count += 1;
-
-                if let Err(e) = self.sync_labels().await {
-                    error!("Failed to sync labels {:?}", e);
-                    // todo: how to signal this?
-                }
-
-                Ok(self.status())
-            }
+            Ok(_) => Ok(child),
             Err(e) => {
                 if let Err(err) = bdev_destroy(uri).await {
                     error!(
@@ -233,6 +349,8 @@ impl Nexus {
 
         self.children.remove(idx);
         self.child_count -= 1;
+        self.stalled_children.lock().unwrap().remove(uri);
+        self.release_missing_writes();
 
         // Update child status to remove this child
         NexusChild::save_state_change();
@@ -322,6 +440,43 @@ impl Nexus {
         result
     }
 
+    /// Enable or disable reads and writes to a child on all IO channels. This
+    /// allows a child to be drained from reads while it is still kept in sync
+    /// by writes (or vice versa) without faulting it. The flags are kept by
+    /// the child, hence they also apply to the channels created or refreshed
+    /// later, until they are set again or the nexus is rebalanced.
+    pub async fn set_child_io_flags(
+        &self,
+        name: &str,
+        read: bool,
+        write: bool,
+    ) -> Result<(), Error> {
+        let child = match self.children.iter().find(|c| c.name == name) {
+            Some(child) if child.bdev.is_some() => child,
+            _ => {
+                return Err(Error::ChildNotFound {
+                    name: self.name.clone(),
+                    child: name.to_owned(),
+                })
+            }
+        };
+
+        let flags = ChildIoFlags {
+            read,
+            write,
+        };
+
+        info!(
+            "{}: setting IO flags {:?} of child {}",
+            self.name, flags, name
+        );
+
+        child.set_io_flags(flags);
+        self.traverse_io_channels(|channel| channel.refresh()).await;
+
+        Ok(())
+    }
+
     /// online a child and reconfigure the IO channels. The child is already
     /// registered, but simply not opened. This can be required in case where
     /// a child is misbehaving.
@@ -336,14 +491,184 @@ impl Nexus {
                 child: name.to_owned(),
                 name: self.name.clone(),
             })?;
-            self.start_rebuild(name).await.map(|_| {})?;
-            Ok(self.status())
         } else {
-            Err(Error::ChildNotFound {
+            return Err(Error::ChildNotFound {
                 name: self.name.clone(),
                 child: name.to_owned(),
-            })
+            });
+        }
+
+        // the uri may resolve to a different device than before
+        let child = self.children.iter().find(|c| c.name == name).unwrap();
+        if let Err(error) = self.validate_child_identity(child).await {
+            let child = self.get_child_by_name(name)?;
+            if let Err(e) = child.close().await {
+                error!(
+                    "{}: failed to close foreign child {}: {}",
+                    self.name,
+                    name,
+                    e.verbose()
+                );
+            }
+            return Err(error);
+        }
+
+        // the child may have been reprovisioned with a smaller capacity
+        // while it was offline
+        let bdev = self.get_child_by_name(name)?.bdev.clone().unwrap();
+        if let Err(error) = self.validate_child_capacity(name, &bdev) {
+            let child = self.get_child_by_name(name)?;
+            if let Err(e) = child.close().await {
+                error!(
+                    "{}: failed to close undersized child {}: {}",
+                    self.name,
+                    name,
+                    e.verbose()
+                );
+            }
+            return Err(error);
+        }
+
+        if self.get_child_by_name(name)?.placeholder {
+            self.adopt_placeholder(name).await;
+        } else if let Some(verify) = self.policy.online_verify {
+            let rebuild_skipped = self.reopen_verified(name, verify).await;
+            nexus_event::emit(NexusEvent::OnlineVerified {
+                nexus: self.name.clone(),
+                child: name.to_owned(),
+                rebuild_skipped,
+            });
+            if rebuild_skipped {
+                return Ok(self.status());
+            }
         }
+
+        self.start_rebuild(name).await.map(|_| {})?;
+        Ok(self.status())
+    }
+
+    /// Reopen a child which is being onlined without a rebuild, if it missed
+    /// no writes while it was offline and its data matches that of an open
+    /// child in the sampled segments. The nexus is paused while the child is
+    /// reopened, such that no write slips in between the last check and the
+    /// reconfiguration of the IO channels. Returns true if the child was
+    /// reopened.
+    async fn reopen_verified(
+        &mut self,
+        name: &str,
+        verify: OnlineVerify,
+    ) -> bool {
+        let missed_writes = |nexus: &Nexus| {
+            nexus
+                .children
+                .iter()
+                .find(|c| c.name == name)
+                .map_or(true, |c| {
+                    c.write_errors() != 0
+                        || c.closed_generation()
+                            != Some(nexus.metrics.write_generation())
+                })
+        };
+        if missed_writes(self) {
+            info!("{}: child {} missed writes while offline", self.name, name);
+            return false;
+        }
+
+        match self.verify_online_samples(name, verify).await {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(error) => {
+                warn!(
+                    "{}: failed to verify child {}: {}",
+                    self.name,
+                    name,
+                    error.verbose()
+                );
+                return false;
+            }
+        }
+
+        if let Err(error) = self.pause().await {
+            warn!("{}: failed to pause: {}", self.name, error.verbose());
+            return false;
+        }
+        let reopen = !missed_writes(self);
+        if reopen {
+            if let Ok(child) = self.get_child_by_name(name) {
+                child.set_state(ChildState::Open);
+                NexusChild::save_state_change();
+            }
+            self.reconfigure(DrEvent::ChildRebuild).await;
+            info!("{}: child {} reopened without a rebuild", self.name, name);
+        }
+        if let Err(error) = self.resume().await {
+            error!("{}: failed to resume: {}", self.name, error.verbose());
+        }
+        reopen
+    }
+
+    /// Verify that the child belongs to this nexus when it carries a nexus
+    /// label, i.e. that the disk GUID of its label is the UUID of the nexus.
+    /// A child without a label is new to the nexus and accepted.
+    async fn validate_child_identity(
+        &self,
+        child: &NexusChild,
+    ) -> Result<(), Error> {
+        let expected =
+            GptGuid::from(Uuid::from_bytes(self.bdev.uuid().as_bytes()));
+        match child.probe_label().await {
+            Ok(label) if label.primary.guid != expected => {
+                error!(
+                    "{}: child {} carries the label of {}",
+                    self.name, child.name, label.primary.guid
+                );
+                Err(Error::ForeignChild {
+                    child: child.name.clone(),
+                    name: self.name.clone(),
+                    uuid: label.primary.guid.to_string(),
+                    expected: expected.to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Verify that the data partition in the label of the child, if any, can
+    /// be accommodated. A label with a larger reserved region than that of
+    /// the nexus is kept, so its data partition must hold the nexus and a
+    /// healthy child must share its data offset to rebuild it from. Any other
+    /// label is replaced by one with the geometry of the nexus.
+    async fn validate_child_geometry(
+        &self,
+        child: &NexusChild,
+    ) -> Result<(), Error> {
+        let label = match child.probe_label().await {
+            Ok(label) => label,
+            Err(_) => return Ok(()),
+        };
+        let (offset, blocks) =
+            match (label.data_offset(), label.data_block_count()) {
+                (Ok(offset), Ok(blocks)) => (offset, blocks),
+                _ => return Ok(()),
+            };
+        if offset <= self.data_ent_offset {
+            return Ok(());
+        }
+
+        let source = self.children.iter().any(|c| {
+            c.state() == ChildState::Open && self.child_data_offset(c) == offset
+        });
+        if blocks < self.bdev.num_blocks() || !source {
+            error!(
+                "{}: child {} has {} data blocks at offset {}, which cannot be accommodated",
+                self.name, child.name, blocks, offset
+            );
+            return Err(Error::ChildGeometry {
+                child: child.name.clone(),
+                name: self.name.clone(),
+            });
+        }
+        Ok(())
     }
 
     /// Close each child that belongs to this nexus.
@@ -370,23 +695,23 @@ impl Nexus {
             })
     }
 
-    /// try to open all the child devices
+    /// try to open all the child devices, other than the missing ones
     pub(crate) async fn try_open_children(&mut self) -> Result<(), Error> {
-        if self.children.is_empty()
-            || self.children.iter().any(|c| c.bdev.is_none())
-        {
+        let present = || {
+            self.children
+                .iter()
+                .filter(|c| c.state() != ChildState::Missing)
+        };
+        if present().next().is_none() || present().any(|c| c.bdev.is_none()) {
             return Err(Error::NexusIncomplete {
                 name: self.name.clone(),
             });
         }
 
-        let blk_size = self.children[0].bdev.as_ref().unwrap().block_len();
+        let blk_size =
+            present().next().unwrap().bdev.as_ref().unwrap().block_len();
 
-        if self
-            .children
-            .iter()
-            .any(|b| b.bdev.as_ref().unwrap().block_len() != blk_size)
-        {
+        if present().any(|b| b.bdev.as_ref().unwrap().block_len() != blk_size) {
             return Err(Error::MixedBlockSizes {
                 name: self.name.clone(),
             });
@@ -399,6 +724,7 @@ impl Nexus {
         let (open, error): (Vec<_>, Vec<_>) = self
             .children
             .iter_mut()
+            .filter(|c| c.state() != ChildState::Missing)
             .map(|c| c.open(size))
             .partition(Result::is_ok);
 
@@ -431,6 +757,7 @@ impl Nexus {
 
         self.children
             .iter()
+            .filter(|c| c.state() != ChildState::Missing)
             .map(|c| c.bdev.as_ref().unwrap().alignment())
             .collect::<Vec<_>>()
             .iter()
@@ -446,6 +773,7 @@ impl Nexus {
                 }
             })
             .for_each(drop);
+        self.update_io_limits();
         Ok(())
     }
 
@@ -469,6 +797,39 @@ impl Nexus {
         blockcnt
     }
 
+    /// The number of blocks a child must have to hold the data partition of
+    /// the nexus. Before the nexus is opened its geometry is not known yet and
+    /// any child is large enough.
+    pub(crate) fn required_child_blocks(&self) -> u64 {
+        self.data_ent_offset + self.bdev.num_blocks()
+    }
+
+    /// Validate that the child bdev is large enough for the current geometry
+    /// of the nexus, such that IO cannot run past the end of the child.
+    pub(crate) fn validate_child_capacity(
+        &self,
+        child: &str,
+        bdev: &Bdev,
+    ) -> Result<(), Error> {
+        let required_blocks = self.required_child_blocks();
+        if bdev.num_blocks() < required_blocks {
+            error!(
+                "{}: child {} too small, {} blocks required, {} available",
+                self.name,
+                child,
+                required_blocks,
+                bdev.num_blocks()
+            );
+            return Err(Error::ChildTooSmall {
+                child: child.to_owned(),
+                name: self.name.clone(),
+                child_blocks: bdev.num_blocks(),
+                required_blocks,
+            });
+        }
+        Ok(())
+    }
+
     /// lookup a child by its name
     pub fn child_lookup(&self, name: &str) -> Option<&NexusChild> {
         self.children
@@ -477,6 +838,12 @@ impl Nexus {
             .find(|c| c.bdev.as_ref().unwrap().name() == name)
     }
 
+    /// offset of the data partition of the child in blocks, which is the
+    /// data offset of the nexus for a child without a validated label
+    pub(crate) fn child_data_offset(&self, child: &NexusChild) -> u64 {
+        child.data_offset.unwrap_or(self.data_ent_offset)
+    }
+
     pub fn get_child_by_name(
         &mut self,
         name: &str,
@@ -0,0 +1,195 @@
+//! Implements reads and writes through the IO path of a nexus for internal
+//! tooling, such as scrubbing and verification, which wants to issue IO and
+//! await its completion. The IO is submitted to the nexus bdev like any other
+//! IO, so it is subject to the same child selection, retire and repair logic.
+//! The completion is delivered through a oneshot channel that is only set up
+//! for this path; frontend IO is not affected.
+
+use futures::channel::oneshot;
+use libc::c_void;
+use nix::errno::Errno;
+
+use spdk_sys::{
+    spdk_bdev_compare_and_write_blocks,
+    spdk_bdev_compare_blocks,
+    spdk_bdev_free_io,
+    spdk_bdev_io,
+    spdk_bdev_read,
+    spdk_bdev_write,
+};
+
+use crate::{
+    bdev::nexus::nexus_bdev::{Error, Nexus},
+    core::{BdevHandle, Bio, DmaBuf, IoStatus},
+    ffihelper::cb_arg,
+};
+
+impl Nexus {
+    /// completion callback which sends back the status of the nexus IO
+    extern "C" fn io_status_cb(
+        io: *mut spdk_bdev_io,
+        _success: bool,
+        arg: *mut c_void,
+    ) {
+        let sender = unsafe {
+            Box::from_raw(arg as *const _ as *mut oneshot::Sender<IoStatus>)
+        };
+        let status = Bio::from(io).status();
+
+        unsafe {
+            spdk_bdev_free_io(io);
+        }
+
+        sender.send(status).expect("io completion error");
+    }
+
+    /// open a handle to the nexus bdev for the async IO helpers
+    pub(crate) fn io_handle(
+        &self,
+        read_write: bool,
+    ) -> Result<BdevHandle, Error> {
+        BdevHandle::open_with_bdev(&self.bdev, read_write).map_err(|source| {
+            Error::IoHandle {
+                source,
+                name: self.name.clone(),
+            }
+        })
+    }
+
+    /// read from the given byte offset of the nexus into the buffer, returning
+    /// the status the nexus completed the IO with
+    pub async fn read_at(
+        &self,
+        offset: u64,
+        buffer: &mut DmaBuf,
+    ) -> Result<IoStatus, Error> {
+        let hdl = self.io_handle(false)?;
+        let (desc, chan) = hdl.io_tuple();
+        let (s, r) = oneshot::channel::<IoStatus>();
+        let errno = unsafe {
+            spdk_bdev_read(
+                desc,
+                chan,
+                **buffer,
+                offset,
+                buffer.len() as u64,
+                Some(Self::io_status_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(Error::IoDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset,
+                name: self.name.clone(),
+            });
+        }
+
+        Ok(r.await.expect("Failed awaiting nexus read IO"))
+    }
+
+    /// write the buffer to the given byte offset of the nexus, returning the
+    /// status the nexus completed the IO with
+    pub async fn write_at(
+        &self,
+        offset: u64,
+        buffer: &DmaBuf,
+    ) -> Result<IoStatus, Error> {
+        let hdl = self.io_handle(true)?;
+        let (desc, chan) = hdl.io_tuple();
+        let (s, r) = oneshot::channel::<IoStatus>();
+        let errno = unsafe {
+            spdk_bdev_write(
+                desc,
+                chan,
+                **buffer,
+                offset,
+                buffer.len() as u64,
+                Some(Self::io_status_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(Error::IoDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset,
+                name: self.name.clone(),
+            });
+        }
+
+        Ok(r.await.expect("Failed awaiting nexus write IO"))
+    }
+
+    /// compare the data at the given byte offset of the nexus with the buffer,
+    /// returning the status the nexus completed the IO with
+    pub async fn compare_at(
+        &self,
+        offset: u64,
+        buffer: &DmaBuf,
+    ) -> Result<IoStatus, Error> {
+        let hdl = self.io_handle(false)?;
+        let (desc, chan) = hdl.io_tuple();
+        let block_len = self.bdev.block_len() as u64;
+        let (s, r) = oneshot::channel::<IoStatus>();
+        let errno = unsafe {
+            spdk_bdev_compare_blocks(
+                desc,
+                chan,
+                **buffer,
+                offset / block_len,
+                buffer.len() / block_len,
+                Some(Self::io_status_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(Error::IoDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset,
+                name: self.name.clone(),
+            });
+        }
+
+        Ok(r.await.expect("Failed awaiting nexus compare IO"))
+    }
+
+    /// compare the data at the given byte offset of the nexus with the first
+    /// buffer and write the second one there if they match, returning the
+    /// status the nexus completed the IO with
+    pub async fn compare_and_write_at(
+        &self,
+        offset: u64,
+        compare: &DmaBuf,
+        write: &DmaBuf,
+    ) -> Result<IoStatus, Error> {
+        let hdl = self.io_handle(true)?;
+        let (desc, chan) = hdl.io_tuple();
+        let block_len = self.bdev.block_len() as u64;
+        let (s, r) = oneshot::channel::<IoStatus>();
+        let errno = unsafe {
+            spdk_bdev_compare_and_write_blocks(
+                desc,
+                chan,
+                **compare,
+                **write,
+                offset / block_len,
+                compare.len() / block_len,
+                Some(Self::io_status_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(Error::IoDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset,
+                name: self.name.clone(),
+            });
+        }
+
+        Ok(r.await.expect("Failed awaiting nexus compare-and-write IO"))
+    }
+}
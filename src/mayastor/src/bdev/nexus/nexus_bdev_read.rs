@@ -0,0 +1,191 @@
+//! Implements reads with a freshness hint on a nexus. A reader is a handle
+//! to the nexus bdev whose reads go through the IO path of the nexus like any
+//! other IO, except that a reader opened with `ReadFreshness::InSync` only
+//! has its reads served by children which are open, receive all writes and
+//! are not being rebuilt. It is meant for tooling, such as consistency
+//! checkers, which must not read stale data.
+
+use futures::channel::oneshot;
+use libc::c_void;
+use nix::errno::Errno;
+
+use spdk_sys::{spdk_bdev_free_io, spdk_bdev_io, spdk_bdev_read};
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::{nexus_lookup, Error, Nexus},
+        nexus_child::ChildState,
+    },
+    core::{BdevHandle, Bio, CoreError, DmaBuf, IoStatus, NvmeStatusCode},
+    ffihelper::cb_arg,
+};
+
+/// Freshness required from the child a read is served by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadFreshness {
+    /// any child which serves reads, as for regular nexus reads
+    Any,
+    /// only a child which is fully in sync, i.e. open, receiving all writes
+    /// and not being rebuilt. The read fails rather than being served by
+    /// another child.
+    InSync,
+}
+
+/// A handle to a nexus whose reads are served by children of the given
+/// freshness. The freshness is a property of the descriptor of the handle,
+/// which the nexus looks up for every read submitted through it.
+#[derive(Debug)]
+pub struct NexusReader {
+    hdl: BdevHandle,
+    nexus: String,
+    freshness: ReadFreshness,
+}
+
+impl NexusReader {
+    /// completion callback which sends back the status of the read and the
+    /// NVMe status it failed with, if any
+    extern "C" fn read_cb(
+        io: *mut spdk_bdev_io,
+        _success: bool,
+        arg: *mut c_void,
+    ) {
+        let sender = unsafe {
+            Box::from_raw(
+                arg as *const _
+                    as *mut oneshot::Sender<(IoStatus, NvmeStatusCode)>,
+            )
+        };
+        let bio = Bio::from(io);
+        let status = (bio.status(), NvmeStatusCode::from(&bio));
+
+        unsafe {
+            spdk_bdev_free_io(io);
+        }
+
+        sender.send(status).expect("io completion error");
+    }
+
+    /// the freshness the reads of the reader require
+    pub fn freshness(&self) -> ReadFreshness {
+        self.freshness
+    }
+
+    /// read from the given byte offset of the nexus into the buffer. Returns
+    /// `Error::NoReadableChild` when no child of the required freshness is
+    /// available, the status the nexus completed the read with otherwise.
+    pub async fn read_at(
+        &self,
+        offset: u64,
+        buffer: &mut DmaBuf,
+    ) -> Result<IoStatus, Error> {
+        let (desc, chan) = self.hdl.io_tuple();
+        let (s, r) = oneshot::channel::<(IoStatus, NvmeStatusCode)>();
+        let errno = unsafe {
+            spdk_bdev_read(
+                desc,
+                chan,
+                **buffer,
+                offset,
+                buffer.len() as u64,
+                Some(Self::read_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(Error::IoDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset,
+                name: self.nexus.clone(),
+            });
+        }
+
+        match r.await.expect("Failed awaiting nexus read IO") {
+            (IoStatus::NvmeError, status)
+                if self.freshness == ReadFreshness::InSync
+                    && status == NvmeStatusCode::NAMESPACE_NOT_READY =>
+            {
+                Err(Error::NoReadableChild {
+                    name: self.nexus.clone(),
+                    freshness: self.freshness,
+                })
+            }
+            (status, _) => Ok(status),
+        }
+    }
+}
+
+impl Drop for NexusReader {
+    fn drop(&mut self) {
+        if self.freshness != ReadFreshness::InSync {
+            return;
+        }
+        if let Some(nexus) = nexus_lookup(&self.nexus) {
+            let desc = self.hdl.io_tuple().0 as usize;
+            nexus.in_sync_descs.retain(|d| *d != desc);
+        }
+    }
+}
+
+impl Nexus {
+    /// open a reader on the nexus whose reads are served by children of the
+    /// given freshness only
+    pub fn open_reader(
+        &mut self,
+        freshness: ReadFreshness,
+    ) -> Result<NexusReader, Error> {
+        let hdl = self.io_handle(false)?;
+        if freshness == ReadFreshness::InSync {
+            self.in_sync_descs.push(hdl.io_tuple().0 as usize);
+        }
+        Ok(NexusReader {
+            hdl,
+            nexus: self.name.clone(),
+            freshness,
+        })
+    }
+
+    /// Read `len` bytes from the given byte offset of the nexus from the
+    /// child with the given URI only, bypassing the selection of the child
+    /// and the retries of regular nexus reads, such that the data of the
+    /// children can be compared. The child must be open.
+    pub async fn read_from_child(
+        &self,
+        uri: &str,
+        offset: u64,
+        len: u64,
+    ) -> Result<DmaBuf, Error> {
+        let child =
+            self.children
+                .iter()
+                .find(|c| c.name == uri)
+                .ok_or_else(|| Error::ChildNotFound {
+                    child: uri.to_string(),
+                    name: self.name.clone(),
+                })?;
+
+        if child.state() != ChildState::Open {
+            return Err(Error::ChildNotOpen {
+                child: uri.to_string(),
+                name: self.name.clone(),
+                state: child.state(),
+            });
+        }
+
+        let read = |source: CoreError| Error::ChildRead {
+            source,
+            child: uri.to_string(),
+            name: self.name.clone(),
+        };
+        let hdl = child.handle().map_err(read)?;
+        let mut buffer =
+            hdl.dma_malloc(len).map_err(|source| Error::VerifyAlloc {
+                source,
+                name: self.name.clone(),
+            })?;
+        let offset = offset
+            + self.child_data_offset(child) * self.bdev.block_len() as u64;
+        hdl.read_at(offset, &mut buffer).await.map_err(read)?;
+        Ok(buffer)
+    }
+}
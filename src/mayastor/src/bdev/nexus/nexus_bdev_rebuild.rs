@@ -1,4 +1,14 @@
-use futures::channel::oneshot::Receiver;
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use futures::{channel::oneshot::Receiver, StreamExt};
+use serde::Serialize;
 use snafu::ResultExt;
 
 use rpc::mayastor::{
@@ -21,6 +31,7 @@ use crate::{
             },
             nexus_channel::DrEvent,
             nexus_child::{ChildState, NexusChild, Reason},
+            nexus_event::{self, NexusEvent},
         },
         VerboseError,
     },
@@ -34,7 +45,114 @@ use crate::{
     },
 };
 
+/// The outcome of the rebuild of a child, raised as an event once the rebuild
+/// job has finished
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RebuildCompletion {
+    pub child: String,
+    /// true if the child has been rebuilt and is in sync, false if the
+    /// rebuild failed or was stopped
+    pub success: bool,
+    /// number of bytes copied to the child
+    pub bytes: u64,
+    /// time the rebuild took
+    pub elapsed: Duration,
+    /// topology epoch of the nexus once the outcome has been applied, from
+    /// which on a rebuilt child receives all writes
+    pub epoch: u64,
+}
+
+/// The progress of the rebuild of a child
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RebuildProgress {
+    pub child: String,
+    /// number of blocks recovered, including those which a differential
+    /// rebuild found in sync already
+    pub blocks_copied: u64,
+    /// number of blocks to recover
+    pub blocks_total: u64,
+    /// progress of the rebuild in percent
+    pub percent: u64,
+    /// blocks recovered per second since the rebuild started
+    pub blocks_per_sec: u64,
+    /// estimated time left at that rate, unknown until blocks are recovered
+    pub remaining: Option<Duration>,
+}
+
+/// Follows the progress of a rebuild job from the count of recovered blocks
+/// which the job publishes, hence without going through the job itself.
+#[derive(Debug)]
+pub(crate) struct RebuildTracker {
+    recovered: Arc<AtomicU64>,
+    total: u64,
+    start: Instant,
+}
+
+impl RebuildTracker {
+    fn new(job: &RebuildJob, total: u64) -> Self {
+        Self {
+            recovered: job.recovered(),
+            total,
+            start: Instant::now(),
+        }
+    }
+
+    fn progress(&self, child: &str) -> RebuildProgress {
+        let copied = self.recovered.load(Ordering::Relaxed);
+        let elapsed = self.start.elapsed();
+        let blocks_per_sec = match elapsed.as_micros() as u64 {
+            0 => 0,
+            us => copied * 1_000_000 / us,
+        };
+        let remaining = if copied == 0 {
+            None
+        } else {
+            Some(elapsed.mul_f64((self.total - copied) as f64 / copied as f64))
+        };
+        RebuildProgress {
+            child: child.to_string(),
+            blocks_copied: copied,
+            blocks_total: self.total,
+            percent: copied * 100 / std::cmp::max(self.total, 1),
+            blocks_per_sec,
+            remaining,
+        }
+    }
+}
+
 impl Nexus {
+    /// The progress of the rebuild of the given child, if it is rebuilt
+    pub fn rebuild_progress(&self, child: &str) -> Option<RebuildProgress> {
+        self.rebuild_trackers.get(child).map(|t| t.progress(child))
+    }
+
+    /// A future which resolves once the rebuild of the given child finishes,
+    /// whether the rebuild has been started yet or not. The future must be
+    /// obtained before the rebuild can finish, as an outcome which has
+    /// already been raised is not seen.
+    pub fn rebuild_completion(
+        &self,
+        child: &str,
+    ) -> impl Future<Output = Option<RebuildCompletion>> {
+        let mut events = nexus_event::subscribe();
+        let nexus = self.name.clone();
+        let child = child.to_string();
+        async move {
+            while let Some(event) = events.next().await {
+                match event {
+                    NexusEvent::RebuildCompleted {
+                        nexus: n,
+                        completion,
+                    } if n == nexus && completion.child == child => {
+                        return Some(completion)
+                    }
+                    _ => {}
+                }
+            }
+            None
+        }
+    }
+
     /// Starts a rebuild job and returns a receiver channel
     /// which can be used to await the rebuild completion
     pub async fn start_rebuild(
@@ -43,11 +161,17 @@ impl Nexus {
     ) -> Result<Receiver<RebuildState>, Error> {
         trace!("{}: start rebuild request for {}", self.name, name);
 
-        let src_child_name = match self
-            .children
-            .iter()
-            .find(|c| c.state() == ChildState::Open && c.name != name)
-        {
+        // the rebuild copies the blocks at the same offset, hence the source
+        // must have its data partition at the same offset as the destination
+        let data_offset = match self.children.iter().find(|c| c.name == name) {
+            Some(c) => self.child_data_offset(c),
+            None => self.data_ent_offset,
+        };
+        let src_child_name = match self.children.iter().find(|c| {
+            c.state() == ChildState::Open
+                && c.name != name
+                && self.child_data_offset(c) == data_offset
+        }) {
             Some(child) => Ok(child.name.clone()),
             None => Err(Error::NoRebuildSource {
                 name: self.name.clone(),
@@ -72,20 +196,45 @@ impl Nexus {
                 }),
             }?;
 
-        let job = RebuildJob::create(
-            &self.name,
-            &src_child_name,
-            &dst_child_name,
-            std::ops::Range::<u64> {
-                start: self.data_ent_offset,
-                end: self.bdev.num_blocks() + self.data_ent_offset,
-            },
-            |nexus, job| {
-                Reactors::current().send_future(async move {
-                    Nexus::notify_rebuild(nexus, job).await;
-                });
-            },
-        )
+        let range = std::ops::Range::<u64> {
+            start: data_offset,
+            end: self.bdev.num_blocks() + data_offset,
+        };
+        let range_len = range.end - range.start;
+        let notify_fn: fn(String, String) = |nexus, job| {
+            Reactors::current().send_future(async move {
+                Nexus::notify_rebuild(nexus, job).await;
+            });
+        };
+        // a child assembled as a placeholder only lacks the segments written
+        // while it was missing
+        let placeholder = self
+            .children
+            .iter()
+            .any(|c| c.name == dst_child_name && c.placeholder);
+        let job = match self.missing_writes.clone() {
+            Some(written) if placeholder => {
+                info!(
+                    "{}: rebuilding the segments written while {} was missing",
+                    self.name, dst_child_name
+                );
+                RebuildJob::create_differential(
+                    &self.name,
+                    &src_child_name,
+                    &dst_child_name,
+                    range,
+                    written,
+                    notify_fn,
+                )
+            }
+            _ => RebuildJob::create(
+                &self.name,
+                &src_child_name,
+                &dst_child_name,
+                range,
+                notify_fn,
+            ),
+        }
         .context(CreateRebuildError {
             child: name.to_owned(),
             name: self.name.clone(),
@@ -97,14 +246,24 @@ impl Nexus {
         // sequentially, from start to the end.
         // This means any Write frontend IO to a range which has already been
         // rebuilt would then need to be rebuilt again.
-        // Ensuring that the dst child receives all frontend Write IO keeps all
-        // rebuilt ranges in sync with the other children.
+        // Ensuring that the dst child receives frontend Write IO below the
+        // rebuild cursor keeps all rebuilt ranges in sync with the other
+        // children. Writes at or beyond the cursor are skipped, as that range
+        // is copied by the rebuild job afterwards.
         self.reconfigure(DrEvent::ChildRebuild).await;
 
-        job.as_client().start().context(RebuildOperationError {
-            job: name.to_owned(),
-            name: self.name.clone(),
-        })
+        let complete =
+            job.as_client().start().context(RebuildOperationError {
+                job: name.to_owned(),
+                name: self.name.clone(),
+            })?;
+        // the job notifies its completion from a future of its own, hence
+        // the tracker is in place before the rebuild can finish
+        self.rebuild_trackers.insert(
+            dst_child_name.clone(),
+            RebuildTracker::new(job, range_len),
+        );
+        Ok(complete)
     }
 
     /// Terminates a rebuild in the background
@@ -217,7 +376,7 @@ impl Nexus {
     /// todo: how to proceed if no healthy child is found?
     pub async fn start_rebuild_jobs(&mut self, child_names: Vec<String>) {
         for name in child_names {
-            if let Err(e) = self.start_rebuild(&name).await {
+            if let Err(e) = self.start_auto_rebuild(&name).await {
                 error!("Failed to start rebuild: {}", e.verbose());
             }
         }
@@ -257,15 +416,21 @@ impl Nexus {
         job: &RebuildJob,
     ) -> Result<(), Error> {
         let recovering_child = self.get_child_by_name(&job.destination)?;
+        let stats = job.stats();
+        let bytes =
+            (stats.blocks_recovered - stats.blocks_skipped) * stats.block_size;
 
         match job.state() {
             RebuildState::Completed => {
+                recovering_child.record_rebuild(job.elapsed(), bytes);
                 recovering_child.set_state(ChildState::Open);
+                recovering_child.placeholder = false;
                 NexusChild::save_state_change();
                 info!(
                     "Child {} has been rebuilt successfully",
                     recovering_child.name
                 );
+                self.release_missing_writes();
             }
             RebuildState::Stopped => {
                 info!(
@@ -302,6 +467,16 @@ impl Nexus {
         }
 
         self.reconfigure(DrEvent::ChildRebuild).await;
+        nexus_event::emit(NexusEvent::RebuildCompleted {
+            nexus: self.name.clone(),
+            completion: RebuildCompletion {
+                child: job.destination.clone(),
+                success: job.state() == RebuildState::Completed,
+                bytes,
+                elapsed: job.elapsed(),
+                epoch: self.epoch(),
+            },
+        });
         Ok(())
     }
 
@@ -316,6 +491,7 @@ impl Nexus {
             return Ok(());
         }
 
+        self.rebuild_trackers.remove(&job);
         let complete_err = self.on_rebuild_complete_job(&j).await;
         let remove_err = RebuildJob::remove(&job)
             .context(RemoveRebuildJob {
@@ -0,0 +1,131 @@
+//! Implements writes to a single child of a nexus, the primitive with which
+//! repair tooling corrects a region on which a child diverges from the other
+//! children, e.g. as found by `verify_consistency`. These writes bypass the IO
+//! path of the nexus bdev, which writes to all children.
+//!
+//! To keep the primitive from diverging a mirror, the data written must equal
+//! the data another open child holds in the region. The region is locked on
+//! the nexus while the children are compared and written, such that frontend
+//! writes cannot change it in between.
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::{Error, Nexus},
+        nexus_child::ChildState,
+    },
+    core::{Bdev, CoreError, DmaBuf, RangeContext},
+};
+
+impl Nexus {
+    /// Write the data to the given byte offset of the nexus on the child with
+    /// the given URI only, to repair a region on which it diverges from the
+    /// other children. Returns `Error::RepairDiverges` when no other open
+    /// child holds the same data in the region.
+    pub async fn write_to_child(
+        &self,
+        uri: &str,
+        offset: u64,
+        data: &DmaBuf,
+    ) -> Result<(), Error> {
+        let child =
+            self.children
+                .iter()
+                .find(|c| c.name == uri)
+                .ok_or_else(|| Error::ChildNotFound {
+                    child: uri.to_string(),
+                    name: self.name.clone(),
+                })?;
+
+        if child.state() != ChildState::Open {
+            return Err(Error::ChildNotOpen {
+                child: uri.to_string(),
+                name: self.name.clone(),
+                state: child.state(),
+            });
+        }
+
+        let desc = Bdev::open_by_name(&self.name, false).map_err(|_| {
+            Error::NexusNotFound {
+                name: self.name.clone(),
+            }
+        })?;
+        let channel = desc.get_channel().ok_or(Error::NexusNotFound {
+            name: self.name.clone(),
+        })?;
+        let block_len = self.bdev.block_len() as u64;
+        let mut ctx = RangeContext::new(
+            offset / block_len,
+            (data.len() + block_len - 1) / block_len,
+        );
+        desc.lock_lba_range(&mut ctx, &channel)
+            .await
+            .map_err(|source| Error::RepairRangeLock {
+                source,
+                name: self.name.clone(),
+            })?;
+
+        let result = self.write_matching(uri, offset, data).await;
+
+        desc.unlock_lba_range(&mut ctx, &channel)
+            .await
+            .map_err(|source| Error::RepairRangeLock {
+                source,
+                name: self.name.clone(),
+            })?;
+        result
+    }
+
+    /// write the data to the child if another open child holds it already
+    async fn write_matching(
+        &self,
+        uri: &str,
+        offset: u64,
+        data: &DmaBuf,
+    ) -> Result<(), Error> {
+        let mut matched = false;
+        for peer in self
+            .children
+            .iter()
+            .filter(|c| c.name != uri && c.state() == ChildState::Open)
+        {
+            let peer_data =
+                self.read_from_child(&peer.name, offset, data.len()).await?;
+            if peer_data.as_slice() == data.as_slice() {
+                matched = true;
+                break;
+            }
+        }
+        if !matched {
+            return Err(Error::RepairDiverges {
+                child: uri.to_string(),
+                name: self.name.clone(),
+            });
+        }
+
+        let child =
+            self.children
+                .iter()
+                .find(|c| c.name == uri)
+                .ok_or_else(|| Error::ChildNotFound {
+                    child: uri.to_string(),
+                    name: self.name.clone(),
+                })?;
+        let write = |source: CoreError| Error::ChildWrite {
+            source,
+            child: uri.to_string(),
+            name: self.name.clone(),
+        };
+        let hdl = child.handle().map_err(write)?;
+        let offset = offset
+            + self.child_data_offset(child) * self.bdev.block_len() as u64;
+        hdl.write_at(offset, data).await.map_err(write)?;
+        info!(
+            "{}: repaired {} bytes at offset {} of child {}",
+            self.name,
+            data.len(),
+            offset,
+            uri
+        );
+        Ok(())
+    }
+}
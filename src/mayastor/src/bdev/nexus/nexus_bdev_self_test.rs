@@ -0,0 +1,163 @@
+//! Implements a self test of the children of a nexus, used when commissioning
+//! a nexus or after maintenance. A pattern is written to every child, read
+//! back, verified and flushed, such that a child which is reachable but
+//! returns bad data or is far too slow is found before it serves real IO.
+//!
+//! The test is confined to the first block of the metadata partition of the
+//! child, which is not used by the metadata layout, so the test never touches
+//! user data or metadata and can be run on a live nexus.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::bdev::nexus::{
+    nexus_bdev::Nexus,
+    nexus_child::{ChildState, NexusChild},
+};
+
+/// a child which takes longer than this to complete the test fails it
+pub const SELF_TEST_MAX_LATENCY: Duration = Duration::from_secs(1);
+
+/// Reason why a child failed the self test
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum SelfTestFailure {
+    /// the child is not open
+    NotOpen(ChildState),
+    /// the reserved test region of the child could not be located
+    NoRegion(String),
+    /// a write, read or flush of the test region failed
+    Io(String),
+    /// the data read back differs from the data written
+    Mismatch,
+    /// the test took longer than `SELF_TEST_MAX_LATENCY`
+    Slow(Duration),
+}
+
+/// Outcome of the self test of a single child
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChildSelfTest {
+    /// name of the child
+    pub child: String,
+    /// time taken to write, read back and flush the test region
+    pub latency: Duration,
+    /// the reason the child failed the test, if any
+    pub failure: Option<SelfTestFailure>,
+}
+
+impl ChildSelfTest {
+    /// true if the child passed the test
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Outcome of the self test of a nexus
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SelfTestReport {
+    pub children: Vec<ChildSelfTest>,
+}
+
+impl SelfTestReport {
+    /// true if all children passed the test
+    pub fn passed(&self) -> bool {
+        self.children.iter().all(|c| c.passed())
+    }
+}
+
+impl Nexus {
+    /// test every child by writing, reading back, verifying and flushing a
+    /// pattern in its reserved test region
+    pub async fn self_test(&self) -> SelfTestReport {
+        // vary the pattern between runs, so that data left behind by an
+        // earlier run cannot pass the verification
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos() as u8;
+
+        let mut children = Vec::new();
+        for child in &self.children {
+            let start = Instant::now();
+            let failure = Self::self_test_child(child, seed).await.err();
+            let latency = start.elapsed();
+            let failure = match failure {
+                None if latency > SELF_TEST_MAX_LATENCY => {
+                    Some(SelfTestFailure::Slow(latency))
+                }
+                failure => failure,
+            };
+            if let Some(failure) = &failure {
+                warn!(
+                    "{}: child {} failed the self test: {:?}",
+                    self.name, child.name, failure
+                );
+            }
+            children.push(ChildSelfTest {
+                child: child.name.clone(),
+                latency,
+                failure,
+            });
+        }
+
+        SelfTestReport {
+            children,
+        }
+    }
+
+    /// run the self test on a single child
+    async fn self_test_child(
+        child: &NexusChild,
+        seed: u8,
+    ) -> Result<(), SelfTestFailure> {
+        if child.state() != ChildState::Open {
+            return Err(SelfTestFailure::NotOpen(child.state()));
+        }
+
+        let label = child
+            .probe_label()
+            .await
+            .map_err(|e| SelfTestFailure::NoRegion(e.to_string()))?;
+        let partition = label
+            .partitions
+            .get(0)
+            .filter(|p| p.ent_name.name == "MayaMeta")
+            .ok_or_else(|| {
+                SelfTestFailure::NoRegion("no metadata partition".into())
+            })?;
+
+        let hdl = child
+            .handle()
+            .map_err(|e| SelfTestFailure::Io(e.to_string()))?;
+        let block_len = u64::from(hdl.get_bdev().block_len());
+        let offset = partition.ent_start * block_len;
+
+        let mut written = hdl
+            .dma_malloc(block_len)
+            .map_err(|e| SelfTestFailure::Io(e.to_string()))?;
+        let mut read = hdl
+            .dma_malloc(block_len)
+            .map_err(|e| SelfTestFailure::Io(e.to_string()))?;
+        written
+            .as_mut_slice()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, b)| *b = i as u8 ^ seed);
+        read.fill(!seed);
+
+        hdl.write_at(offset, &written)
+            .await
+            .map_err(|e| SelfTestFailure::Io(e.to_string()))?;
+        hdl.read_at(offset, &mut read)
+            .await
+            .map_err(|e| SelfTestFailure::Io(e.to_string()))?;
+        if read.as_slice() != written.as_slice() {
+            return Err(SelfTestFailure::Mismatch);
+        }
+        hdl.flush()
+            .await
+            .map_err(|e| SelfTestFailure::Io(e.to_string()))?;
+
+        Ok(())
+    }
+}
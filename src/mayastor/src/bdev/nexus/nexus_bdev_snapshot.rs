@@ -1,28 +1,552 @@
 //! Implements snapshot operations on a nexus.
+//!
+//! Snapshots pause, flush and resume the nexus or a child, which is too heavy
+//! to run on a core which serves frontend IO. The snapshot operations of a
+//! nexus, i.e. `create_snapshot`, `delete_snapshot` and the snapshot step of
+//! `backup_child`, therefore run on its snapshot reactor: the reactor of the
+//! core set with `Nexus::set_snapshot_core`, the master reactor by default. The
+//! caller awaits the result on its own reactor. Children which failed IO are
+//! retired on the reactor of the core set with `Nexus::set_retire_core`, all
+//! other administrative operations run on the reactor of the caller.
 
-use rpc::mayastor::CreateSnapshotReply;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt,
+    future::Future,
+    sync::atomic::Ordering,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use futures::{channel::oneshot, lock::MutexGuard};
+use nix::errno::Errno;
+use rpc::mayastor::{CreateSnapshotReply, DestroySnapshotReply};
+use serde::Serialize;
 
 use crate::{
-    bdev::nexus::nexus_bdev::{Error, Nexus},
-    core::BdevHandle,
-    lvs::Lvol,
+    bdev::nexus::{
+        nexus_bdev::{nexus_lookup, Error, Nexus},
+        nexus_child::{ChildState, NexusChild},
+    },
+    core::{BdevHandle, CoreError, Cores, IoType, Reactor, Reactors},
+    lvs::{Lvol, Lvs},
 };
 
+/// Reason why a child would block a snapshot of the nexus
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum SnapshotBlocker {
+    /// the child is not open
+    NotOpen(ChildState),
+    /// a handle to the child could not be opened
+    NoHandle(String),
+    /// the child is neither a local replica nor accepts NVMe admin commands
+    Unsupported,
+    /// the pool of the local replica does not have enough free space to keep
+    /// the replica fully provisioned after the snapshot
+    NoSpace { available: u64, required: u64 },
+}
+
+/// Readiness of a single child for a snapshot of the nexus
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChildSnapshotReadiness {
+    /// name of the child
+    pub child: String,
+    /// the reason this child would block the snapshot, if any
+    pub blocker: Option<SnapshotBlocker>,
+}
+
+impl ChildSnapshotReadiness {
+    /// true if the child does not block the snapshot
+    pub fn is_ready(&self) -> bool {
+        self.blocker.is_none()
+    }
+}
+
+/// Result of a snapshot dry-run of the nexus
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotReadiness {
+    /// a handle to the nexus, through which the snapshot is taken, could be
+    /// opened
+    pub nexus_handle: bool,
+    /// readiness of every child of the nexus
+    pub children: Vec<ChildSnapshotReadiness>,
+}
+
+impl SnapshotReadiness {
+    /// true if the snapshot is expected to succeed
+    pub fn is_ready(&self) -> bool {
+        self.nexus_handle && self.children.iter().all(|c| c.is_ready())
+    }
+}
+
+/// A snapshot of a child taken as part of a snapshot of the nexus
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChildSnapshotInfo {
+    /// name of the child
+    pub child: String,
+    /// name of the bdev of the snapshot of the child
+    pub bdev: String,
+}
+
+/// A snapshot of the nexus, as found on its children
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotInfo {
+    /// name of the snapshot, as returned by `create_snapshot`
+    pub name: String,
+    /// time the snapshot was taken, in seconds since the unix epoch
+    pub time: u64,
+    /// the children which hold the snapshot
+    pub children: Vec<ChildSnapshotInfo>,
+    /// the children which do not hold the snapshot, or whose snapshots could
+    /// not be listed
+    pub missing: Vec<String>,
+    /// the snapshot is held by all children of the nexus
+    pub complete: bool,
+}
+
+/// The source of the times the snapshots of a nexus are taken at, which their
+/// names are derived from
+pub trait SnapshotClock: Send + Sync {
+    /// the current time in seconds since the unix epoch
+    fn now(&self) -> u64;
+}
+
+impl fmt::Debug for dyn SnapshotClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SnapshotClock")
+    }
+}
+
+/// The system clock, the snapshot clock of a nexus by default
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl SnapshotClock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+}
+
+/// Exclusive right to take snapshots of a nexus and its children, released
+/// when dropped
+pub type SnapshotLease<'a> = MutexGuard<'a, ()>;
+
 impl Nexus {
-    /// Create a snapshot on all children
+    /// Acquire the snapshot lease of the nexus, waiting for the snapshot in
+    /// progress to finish if there is one. All snapshots of the nexus and of
+    /// its children are taken under the lease, such that their pauses and
+    /// flushes cannot interleave.
+    pub async fn snapshot_lease(&self) -> SnapshotLease<'_> {
+        self.snapshot_lock.lock().await
+    }
+
+    /// acquire the snapshot lease without waiting, returns None when a
+    /// snapshot is in progress
+    pub fn try_snapshot_lease(&self) -> Option<SnapshotLease<'_>> {
+        self.snapshot_lock.try_lock()
+    }
+
+    /// set the core on whose reactor the snapshot operations of the nexus
+    /// run, the master reactor when None
+    pub fn set_snapshot_core(&mut self, core: Option<u32>) {
+        info!("{}: snapshot core set to {:?}", self.name, core);
+        self.policy.snapshot_core = core;
+    }
+
+    /// The reactor on which the snapshot operations of the nexus run, which
+    /// is the master reactor unless a core has been configured.
+    pub fn snapshot_reactor(&self) -> &'static Reactor {
+        match self.policy.snapshot_core {
+            Some(core) => Reactors::get_by_core(core).unwrap_or_else(|| {
+                warn!(
+                    "{}: no reactor on snapshot core {}, using master",
+                    self.name, core
+                );
+                Reactors::master()
+            }),
+            None => Reactors::master(),
+        }
+    }
+
+    /// Set the core on whose reactor the children of the nexus which failed
+    /// IO are retired, the master reactor when `None`. A retire already
+    /// dispatched completes on the reactor it was dispatched to.
+    pub fn set_retire_core(&mut self, core: Option<u32>) {
+        info!("{}: retire core set to {:?}", self.name, core);
+        self.policy.retire_core = core;
+    }
+
+    /// The reactor on which the children of the nexus are retired, which is
+    /// the master reactor unless a core has been configured.
+    pub fn retire_reactor(&self) -> &'static Reactor {
+        match self.policy.retire_core {
+            Some(core) => Reactors::get_by_core(core).unwrap_or_else(|| {
+                warn!(
+                    "{}: no reactor on retire core {}, using master",
+                    self.name, core
+                );
+                Reactors::master()
+            }),
+            None => Reactors::master(),
+        }
+    }
+
+    /// run a snapshot operation on the snapshot reactor of the nexus and
+    /// await its result, the operation runs inline when already on it
+    pub(crate) async fn on_snapshot_reactor<T: 'static>(
+        &self,
+        op: impl Future<Output = Result<T, Error>> + 'static,
+    ) -> Result<T, Error> {
+        let reactor = self.snapshot_reactor();
+        if reactor.core() == Cores::current() {
+            return op.await;
+        }
+
+        let (s, r) = oneshot::channel();
+        reactor.send_future(async move {
+            let _ = s.send(op.await);
+        });
+        r.await.expect("Failed awaiting snapshot operation")
+    }
+
+    /// Create a snapshot on all children. The snapshot is retaken when the
+    /// children of the nexus change while it is being taken, so that it is
+    /// never applied to a part of the children only. Concurrent snapshots
+    /// are serialized. The snapshot is taken on the snapshot reactor of the
+    /// nexus.
     pub async fn create_snapshot(&self) -> Result<CreateSnapshotReply, Error> {
-        if let Ok(h) = BdevHandle::open_with_bdev(&self.bdev, false) {
-            match h.create_snapshot().await {
-                Ok(t) => Ok(CreateSnapshotReply {
-                    name: Lvol::format_snapshot_name(&self.bdev.name(), t),
+        let name = self.name.clone();
+        self.on_snapshot_reactor(async move {
+            match nexus_lookup(&name) {
+                Some(nexus) => nexus.snapshot_children().await,
+                None => Err(Error::NexusNotFound {
+                    name: name.clone(),
+                }),
+            }
+        })
+        .await
+    }
+
+    /// snapshot all children under the snapshot lease
+    async fn snapshot_children(&self) -> Result<CreateSnapshotReply, Error> {
+        let _lease = self.snapshot_lease().await;
+        let (_, t) = self
+            .with_writer_set(|_| async move {
+                let h = self.snapshot_handle()?;
+                let t = self.next_snapshot_time();
+                self.last_snapshot_time.store(t, Ordering::Relaxed);
+                h.create_snapshot_at(t).await.map_err(|e| {
+                    Error::FailedCreateSnapshot {
+                        name: self.bdev.name(),
+                        source: e,
+                    }
+                })?;
+                Ok(t)
+            })
+            .await?;
+        self.metrics.snapshot_taken();
+
+        Ok(CreateSnapshotReply {
+            name: Lvol::format_snapshot_name(&self.bdev.name(), t),
+        })
+    }
+
+    /// The time the next snapshot of the nexus is taken at, which its name is
+    /// derived from. The time is that of the snapshot clock of the nexus,
+    /// unless a snapshot already exists at that time, or a snapshot was
+    /// attempted at that time or later: the time is then advanced past them,
+    /// such that no two snapshots of the nexus get the same name. A snapshot
+    /// which failed counts as attempted, as it may have been taken on some of
+    /// the children.
+    pub fn next_snapshot_time(&self) -> u64 {
+        let now = self.snapshot_clock.now();
+        let mut time = now;
+        let last = self.last_snapshot_time.load(Ordering::Relaxed);
+        if last != 0 {
+            time = time.max(last + 1);
+        }
+        let taken = self
+            .child_snapshot_infos()
+            .into_iter()
+            .map(|s| s.time)
+            .collect::<BTreeSet<_>>();
+        while taken.contains(&time) {
+            time += 1;
+        }
+        if time != now {
+            debug!(
+                "{}: next snapshot advanced to time {} to keep its name unique",
+                self.name, time
+            );
+        }
+        time
+    }
+
+    /// The time of a snapshot of the nexus, as encoded in its name by
+    /// `create_snapshot`. A name which does not belong to a snapshot of this
+    /// nexus is not found.
+    pub(crate) fn snapshot_time(&self, snapshot: &str) -> Result<u64, Error> {
+        snapshot
+            .strip_prefix(&format!("{}-snap-", self.bdev.name()))
+            .and_then(|t| t.parse::<u64>().ok())
+            .ok_or_else(|| Error::SnapshotNotFound {
+                snapshot: snapshot.to_string(),
+                name: self.name.clone(),
+            })
+    }
+
+    /// the snapshot the child took as part of the snapshot of the nexus taken
+    /// at the given time, if the child is a local replica which holds it
+    pub(crate) fn child_snapshot(
+        child: &NexusChild,
+        time: u64,
+    ) -> Option<Lvol> {
+        let lvol = Self::child_lvol(child)?;
+        let name = Lvol::format_snapshot_name(&lvol.name(), time);
+        Lvs::lookup(&lvol.pool())?
+            .lvols()?
+            .find(|l| l.name() == name)
+    }
+
+    /// the replica of the child, if the child is a local replica
+    fn child_lvol(child: &NexusChild) -> Option<Lvol> {
+        child
+            .handle()
+            .ok()
+            .and_then(|hdl| Lvol::try_from(hdl.get_bdev()).ok())
+    }
+
+    /// the snapshots the child took as part of snapshots of the nexus, by
+    /// the time they were taken, None if the snapshots of the child cannot
+    /// be listed as it is not a local replica
+    fn child_snapshots(child: &NexusChild) -> Option<BTreeMap<u64, String>> {
+        let lvol = Self::child_lvol(child)?;
+        let prefix = format!("{}-snap-", lvol.name());
+        let snapshots = Lvs::lookup(&lvol.pool())?
+            .lvols()?
+            .filter(|l| l.is_snapshot())
+            .filter_map(|l| {
+                let name = l.name();
+                let time = name.strip_prefix(&prefix)?.parse::<u64>().ok()?;
+                Some((time, name))
+            })
+            .collect();
+        Some(snapshots)
+    }
+
+    /// List the snapshots of the nexus found on its children, oldest first.
+    /// A snapshot which is missing on some of the children, e.g. as a child
+    /// was rebuilt or added after it was taken, is not complete. Only the
+    /// snapshots of local replicas can be listed, the other children are
+    /// reported as missing every snapshot. The snapshots are listed on the
+    /// snapshot reactor of the nexus.
+    pub async fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>, Error> {
+        let name = self.name.clone();
+        self.on_snapshot_reactor(async move {
+            match nexus_lookup(&name) {
+                Some(nexus) => Ok(nexus.child_snapshot_infos()),
+                None => Err(Error::NexusNotFound {
+                    name: name.clone(),
                 }),
-                Err(e) => Err(Error::FailedCreateSnapshot {
-                    name: self.bdev.name(),
-                    source: e,
+            }
+        })
+        .await
+    }
+
+    /// reconcile the snapshots of all children by the time they were taken
+    fn child_snapshot_infos(&self) -> Vec<SnapshotInfo> {
+        let listed = self
+            .children
+            .iter()
+            .map(|c| (c.name.clone(), Self::child_snapshots(c)))
+            .collect::<Vec<_>>();
+
+        let mut snapshots = BTreeMap::<u64, Vec<ChildSnapshotInfo>>::new();
+        for (child, child_snapshots) in &listed {
+            for (time, bdev) in child_snapshots.iter().flatten() {
+                snapshots.entry(*time).or_default().push(ChildSnapshotInfo {
+                    child: child.clone(),
+                    bdev: bdev.clone(),
+                });
+            }
+        }
+
+        snapshots
+            .into_iter()
+            .map(|(time, children)| {
+                let missing = listed
+                    .iter()
+                    .filter(|(child, _)| {
+                        !children.iter().any(|c| &c.child == child)
+                    })
+                    .map(|(child, _)| child.clone())
+                    .collect::<Vec<_>>();
+                SnapshotInfo {
+                    name: Lvol::format_snapshot_name(&self.bdev.name(), time),
+                    time,
+                    complete: missing.is_empty(),
+                    children,
+                    missing,
+                }
+            })
+            .collect()
+    }
+
+    /// Delete a snapshot taken by `create_snapshot` from all children which
+    /// hold it. Deleting a snapshot which no child holds fails as not found,
+    /// such that a repeated delete is told apart from a failed one. When the
+    /// snapshot could be deleted from some children only, the children which
+    /// still hold it are reported. The snapshot is deleted on the snapshot
+    /// reactor of the nexus.
+    pub async fn delete_snapshot(
+        &self,
+        snapshot: &str,
+    ) -> Result<DestroySnapshotReply, Error> {
+        let name = self.name.clone();
+        let snapshot = snapshot.to_string();
+        self.on_snapshot_reactor(async move {
+            match nexus_lookup(&name) {
+                Some(nexus) => nexus.delete_child_snapshots(&snapshot).await,
+                None => Err(Error::NexusNotFound {
+                    name: name.clone(),
                 }),
             }
-        } else {
-            Err(Error::FailedGetHandle)
+        })
+        .await
+    }
+
+    /// delete the snapshot from all children under the snapshot lease
+    async fn delete_child_snapshots(
+        &self,
+        snapshot: &str,
+    ) -> Result<DestroySnapshotReply, Error> {
+        let time = self.snapshot_time(snapshot)?;
+        let _lease = self.snapshot_lease().await;
+
+        let snapshots = self
+            .children
+            .iter()
+            .filter_map(|c| {
+                Self::child_snapshot(c, time).map(|s| (c.name.clone(), s))
+            })
+            .collect::<Vec<_>>();
+        if snapshots.is_empty() {
+            return Err(Error::SnapshotNotFound {
+                snapshot: snapshot.to_string(),
+                name: self.name.clone(),
+            });
+        }
+
+        let mut failed = Vec::new();
+        for (child, lvol) in snapshots {
+            if let Err(e) = lvol.destroy().await {
+                error!(
+                    "{}: failed to delete snapshot {} of child {}: {}",
+                    self.name, snapshot, child, e
+                );
+                failed.push(child);
+            }
+        }
+        if !failed.is_empty() {
+            return Err(Error::SnapshotPartiallyDeleted {
+                snapshot: snapshot.to_string(),
+                name: self.name.clone(),
+                children: failed,
+            });
+        }
+
+        info!("{}: deleted snapshot {}", self.name, snapshot);
+        Ok(DestroySnapshotReply {
+            name: snapshot.to_string(),
+        })
+    }
+
+    /// Open a handle to the nexus through which the snapshot is taken. The
+    /// open is retried once when it failed for a reason which is likely to be
+    /// transient, i.e. the bdev was busy or resources were short.
+    fn snapshot_handle(&self) -> Result<BdevHandle, Error> {
+        let is_transient = |e: &CoreError| match e {
+            CoreError::OpenBdev {
+                source,
+            } => matches!(source, Errno::EBUSY | Errno::EAGAIN | Errno::ENOMEM),
+            CoreError::GetIoChannel {
+                ..
+            } => true,
+            _ => false,
+        };
+
+        BdevHandle::open_with_bdev(&self.bdev, false)
+            .or_else(|e| {
+                if is_transient(&e) {
+                    warn!(
+                        "{}: failed to open a handle for snapshot, retrying: {}",
+                        self.name, e
+                    );
+                    BdevHandle::open_with_bdev(&self.bdev, false)
+                } else {
+                    Err(e)
+                }
+            })
+            .map_err(|source| Error::FailedGetHandle {
+                source,
+                name: self.name.clone(),
+                status: self.status(),
+                children: self
+                    .children
+                    .iter()
+                    .map(|c| (c.name.clone(), c.state()))
+                    .collect(),
+            })
+    }
+
+    /// Validate the prerequisites of `create_snapshot` without taking the
+    /// snapshot, returning the readiness of every child
+    pub fn can_snapshot(&self) -> SnapshotReadiness {
+        SnapshotReadiness {
+            nexus_handle: BdevHandle::open_with_bdev(&self.bdev, false).is_ok(),
+            children: self
+                .children
+                .iter()
+                .map(|c| ChildSnapshotReadiness {
+                    child: c.name.clone(),
+                    blocker: Self::snapshot_blocker(c),
+                })
+                .collect(),
+        }
+    }
+
+    /// the reason the child would block a snapshot, if any
+    fn snapshot_blocker(child: &NexusChild) -> Option<SnapshotBlocker> {
+        if child.state() != ChildState::Open {
+            return Some(SnapshotBlocker::NotOpen(child.state()));
+        }
+
+        let hdl = match child.handle() {
+            Ok(hdl) => hdl,
+            Err(e) => return Some(SnapshotBlocker::NoHandle(e.to_string())),
+        };
+
+        let bdev = hdl.get_bdev();
+        match Lvol::try_from(bdev.clone()) {
+            Ok(lvol) if !lvol.is_thin() => {
+                let available = Lvs::lookup(&lvol.pool())
+                    .map(|lvs| lvs.available())
+                    .unwrap_or_default();
+                if available < lvol.size() {
+                    Some(SnapshotBlocker::NoSpace {
+                        available,
+                        required: lvol.size(),
+                    })
+                } else {
+                    None
+                }
+            }
+            Ok(_) => None,
+            Err(_) if bdev.io_type_supported(IoType::NvmeAdmin) => None,
+            Err(_) => Some(SnapshotBlocker::Unsupported),
         }
     }
 }
@@ -0,0 +1,59 @@
+//! Implements the administrative sync of a nexus. Unlike a flush submitted to
+//! the nexus bdev, which is an IO completed with a single status, a sync
+//! flushes every open child directly and reports the outcome per child, such
+//! that backups and shutdown sequences can tell which replicas hold all the
+//! writes acknowledged by the nexus.
+
+use futures::future::join_all;
+
+use crate::bdev::nexus::{
+    nexus_bdev::{Error, Nexus},
+    nexus_child::ChildState,
+};
+
+impl Nexus {
+    /// Flush all open children and await the completion of the flushes, such
+    /// that the writes acknowledged by the nexus before the call are durable
+    /// on every child. IO may continue while the sync is in progress. Returns
+    /// the children which were flushed, or an error listing those which
+    /// failed to flush.
+    pub async fn sync(&self) -> Result<Vec<String>, Error> {
+        let handles = self
+            .children
+            .iter()
+            .filter(|c| c.state() == ChildState::Open)
+            .map(|c| (c.name.clone(), c.handle()))
+            .collect::<Vec<_>>();
+
+        let results =
+            join_all(handles.into_iter().map(|(child, hdl)| async move {
+                let result = match hdl {
+                    Ok(hdl) => hdl.flush().await.map(|_| ()),
+                    Err(e) => Err(e),
+                };
+                (child, result)
+            }))
+            .await;
+
+        let mut synced = Vec::new();
+        let mut failed = Vec::new();
+        for (child, result) in results {
+            match result {
+                Ok(()) => synced.push(child),
+                Err(e) => {
+                    error!(?e, "{}: failed to sync child {}", self.name, child);
+                    failed.push(child);
+                }
+            }
+        }
+
+        if failed.is_empty() {
+            Ok(synced)
+        } else {
+            Err(Error::SyncFailed {
+                name: self.name.clone(),
+                children: failed,
+            })
+        }
+    }
+}
@@ -0,0 +1,480 @@
+//! Implements a read-only consistency check across the children of a nexus.
+//! The address space of the nexus is read segment by segment from all healthy
+//! children and the data is compared. Divergent segments are reported along
+//! with the children which disagree with the majority, but nothing is
+//! repaired.
+//!
+//! The check can be throttled with a delay between segments, limited to a
+//! part of the nexus and resumed from where a previous run stopped. At most
+//! `max_ranges` divergent ranges are kept in the report, such that memory
+//! stays bounded for large volumes; the total number of divergent bytes is
+//! always accounted. The reads from a child are subject to its background IO
+//! limit, see `nexus_child_limit`.
+//!
+//! The snapshots of the children taken by a snapshot of the nexus are compared
+//! the same way, by `verify_snapshot_consistency`. Only the snapshots of local
+//! replicas can be read, those of the other children are skipped.
+//!
+//! A child brought back online is compared with an open child on a sample of
+//! segments, by `verify_online_samples`, to tell whether it must be rebuilt.
+
+use std::time::Duration;
+
+use futures::channel::oneshot;
+use serde::Serialize;
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::{Error, Nexus},
+        nexus_child::ChildState,
+        nexus_event::{self, NexusEvent},
+        nexus_policy::OnlineVerify,
+    },
+    core::{poller, BdevHandle},
+};
+
+/// default size in bytes of the segments the nexus is verified in
+pub const VERIFY_SEGMENT_SIZE: u64 = 64 * 1024;
+
+/// default number of divergent ranges kept in the report
+pub const VERIFY_MAX_RANGES: usize = 1024;
+
+/// Options of a consistency check
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyOptions {
+    /// byte offset to start at, e.g. the end of a previous report
+    pub offset: u64,
+    /// number of bytes to verify, up to the end of the nexus if not set
+    pub length: Option<u64>,
+    /// size in bytes of the segments which are read and compared at once
+    pub segment_size: u64,
+    /// delay between segments to limit the impact on frontend IO
+    pub segment_delay: Option<Duration>,
+    /// number of divergent ranges kept in the report
+    pub max_ranges: usize,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            length: None,
+            segment_size: VERIFY_SEGMENT_SIZE,
+            segment_delay: None,
+            max_ranges: VERIFY_MAX_RANGES,
+        }
+    }
+}
+
+/// A range of the nexus on which the children diverge
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DivergentRange {
+    /// byte offset of the range
+    pub offset: u64,
+    /// length of the range in bytes
+    pub length: u64,
+    /// children whose data differs from the majority of the children
+    pub children: Vec<String>,
+}
+
+/// Result of a consistency check
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ConsistencyReport {
+    /// byte offset the check started at
+    pub start: u64,
+    /// byte offset the check stopped at, from which it can be resumed
+    pub end: u64,
+    /// children which have been compared
+    pub children: Vec<String>,
+    /// number of bytes on which the children diverge
+    pub divergent_bytes: u64,
+    /// the divergent ranges, adjacent ranges with the same children merged
+    pub ranges: Vec<DivergentRange>,
+    /// more divergent ranges were found than kept in the report
+    pub truncated: bool,
+}
+
+impl ConsistencyReport {
+    /// true if no divergence was found
+    pub fn is_consistent(&self) -> bool {
+        self.divergent_bytes == 0
+    }
+
+    /// account a divergent segment
+    fn diverged(
+        &mut self,
+        offset: u64,
+        length: u64,
+        children: Vec<String>,
+        max_ranges: usize,
+    ) {
+        self.divergent_bytes += length;
+        if let Some(last) = self.ranges.last_mut() {
+            if last.offset + last.length == offset && last.children == children
+            {
+                last.length += length;
+                return;
+            }
+        }
+        if self.ranges.len() < max_ranges {
+            self.ranges.push(DivergentRange {
+                offset,
+                length,
+                children,
+            });
+        } else {
+            self.truncated = true;
+        }
+    }
+}
+
+/// Agreement of the snapshot of a single child with the snapshots of the
+/// other children
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotAgreement {
+    /// name of the child
+    pub child: String,
+    /// name of the snapshot of the child
+    pub snapshot: String,
+    /// number of bytes on which the snapshot differs from the majority
+    pub divergent_bytes: u64,
+}
+
+impl SnapshotAgreement {
+    /// true if the snapshot agrees with the majority everywhere
+    pub fn agrees(&self) -> bool {
+        self.divergent_bytes == 0
+    }
+}
+
+/// Result of a consistency check of a snapshot of the nexus
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotConsistencyReport {
+    /// name of the snapshot of the nexus
+    pub snapshot: String,
+    /// the snapshots of the children which have been compared
+    pub children: Vec<SnapshotAgreement>,
+    /// children whose snapshot could not be read, i.e. which are not local
+    /// replicas or have no snapshot of that name
+    pub skipped: Vec<String>,
+    /// the divergent ranges, in bytes of the nexus
+    pub report: ConsistencyReport,
+}
+
+impl SnapshotConsistencyReport {
+    /// true if no divergence was found
+    pub fn is_consistent(&self) -> bool {
+        self.report.is_consistent()
+    }
+
+    /// the first range on which the snapshots diverge, if any
+    pub fn first_divergence(&self) -> Option<&DivergentRange> {
+        self.report.ranges.first()
+    }
+}
+
+/// a readable copy of the data of a child: the name of the child, a handle
+/// and the byte offset of the data of the nexus
+type Source = (String, BdevHandle, u64);
+
+/// wait for the given duration without blocking the reactor
+pub(crate) async fn throttle(delay: Duration) {
+    let (s, r) = oneshot::channel::<()>();
+    let mut s = Some(s);
+    let poller = poller::Builder::new()
+        .with_interval(delay.as_micros() as u64)
+        .with_poll_fn(move || {
+            if let Some(s) = s.take() {
+                let _ = s.send(());
+            }
+            0
+        })
+        .build();
+    let _ = r.await;
+    poller.stop();
+}
+
+/// the children whose data differs from the majority; on a tie the group of
+/// the first child is taken as the majority
+fn dissenters(names: &[String], data: &[&[u8]]) -> Vec<String> {
+    let mut majority = 0;
+    let mut votes = 0;
+    for (i, d) in data.iter().enumerate() {
+        let n = data.iter().filter(|o| *o == d).count();
+        if n > votes {
+            majority = i;
+            votes = n;
+        }
+    }
+    names
+        .iter()
+        .zip(data)
+        .filter(|(_, d)| **d != data[majority])
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+impl Nexus {
+    /// Read the nexus from all healthy children and report the ranges on
+    /// which they diverge, without repairing them
+    pub async fn verify_consistency(
+        &self,
+        opts: VerifyOptions,
+    ) -> Result<ConsistencyReport, Error> {
+        let block_len = self.bdev.block_len() as u64;
+        let mut handles = Vec::new();
+        for child in self.children.iter() {
+            if child.state() != ChildState::Open {
+                continue;
+            }
+            let hdl = child.handle().map_err(|source| Error::ChildRead {
+                source,
+                child: child.name.clone(),
+                name: self.name.clone(),
+            })?;
+            let base = self.child_data_offset(child) * block_len;
+            handles.push((child.name.clone(), hdl, base));
+        }
+        if handles.len() < 2 {
+            return Err(Error::NotEnoughChildren {
+                name: self.name.clone(),
+            });
+        }
+
+        let (report, _) = self.compare(&handles, &opts).await?;
+        if !report.is_consistent() {
+            warn!(
+                "{}: children diverge on {} bytes between {} and {}",
+                self.name, report.divergent_bytes, report.start, report.end
+            );
+        }
+        Ok(report)
+    }
+
+    /// Read the given snapshot of the nexus from the snapshots of all healthy
+    /// children and report the ranges on which they diverge. The snapshots
+    /// are read like the children by `verify_consistency`, with the same
+    /// options. A divergent snapshot raises a `SnapshotDivergent` event.
+    pub async fn verify_snapshot_consistency(
+        &self,
+        snapshot: &str,
+        opts: VerifyOptions,
+    ) -> Result<SnapshotConsistencyReport, Error> {
+        let time = self.snapshot_time(snapshot)?;
+
+        let block_len = self.bdev.block_len() as u64;
+        let mut handles = Vec::new();
+        let mut snapshots = Vec::new();
+        let mut skipped = Vec::new();
+        for child in self.children.iter() {
+            if child.state() != ChildState::Open {
+                continue;
+            }
+            let snap = match Self::child_snapshot(child, time) {
+                Some(snap) => snap,
+                None => {
+                    skipped.push(child.name.clone());
+                    continue;
+                }
+            };
+            let hdl = BdevHandle::open_with_bdev(&snap.as_bdev(), false)
+                .map_err(|source| Error::ChildRead {
+                    source,
+                    child: child.name.clone(),
+                    name: self.name.clone(),
+                })?;
+            let base = self.child_data_offset(child) * block_len;
+            handles.push((child.name.clone(), hdl, base));
+            snapshots.push(snap.name());
+        }
+        if handles.is_empty() {
+            return Err(Error::SnapshotNotFound {
+                snapshot: snapshot.to_string(),
+                name: self.name.clone(),
+            });
+        }
+        if handles.len() < 2 {
+            return Err(Error::NotEnoughChildren {
+                name: self.name.clone(),
+            });
+        }
+
+        let (report, dissent) = self.compare(&handles, &opts).await?;
+        if !report.is_consistent() {
+            warn!(
+                "{}: snapshots {} diverge on {} bytes between {} and {}",
+                self.name,
+                snapshot,
+                report.divergent_bytes,
+                report.start,
+                report.end
+            );
+            nexus_event::emit(NexusEvent::SnapshotDivergent {
+                nexus: self.name.clone(),
+                snapshot: snapshot.to_string(),
+                divergent_bytes: report.divergent_bytes,
+            });
+        }
+
+        Ok(SnapshotConsistencyReport {
+            snapshot: snapshot.to_string(),
+            children: handles
+                .iter()
+                .zip(snapshots)
+                .zip(dissent)
+                .map(|(((child, _, _), snapshot), divergent_bytes)| {
+                    SnapshotAgreement {
+                        child: child.clone(),
+                        snapshot,
+                        divergent_bytes,
+                    }
+                })
+                .collect(),
+            skipped,
+            report,
+        })
+    }
+
+    /// Compare the data of a child which is being onlined with that of an
+    /// open child on segments spread evenly over the nexus, returns true if
+    /// they match in all of them
+    pub(crate) async fn verify_online_samples(
+        &self,
+        name: &str,
+        verify: OnlineVerify,
+    ) -> Result<bool, Error> {
+        let block_len = self.bdev.block_len() as u64;
+        let source = self
+            .children
+            .iter()
+            .find(|c| c.state() == ChildState::Open && c.name != name)
+            .ok_or_else(|| Error::NotEnoughChildren {
+                name: self.name.clone(),
+            })?;
+        let child =
+            self.children
+                .iter()
+                .find(|c| c.name == name)
+                .ok_or_else(|| Error::ChildNotFound {
+                    child: name.to_owned(),
+                    name: self.name.clone(),
+                })?;
+
+        let mut handles = Vec::new();
+        for c in &[source, child] {
+            let hdl = c.handle().map_err(|source| Error::ChildRead {
+                source,
+                child: c.name.clone(),
+                name: self.name.clone(),
+            })?;
+            let base = self.child_data_offset(c) * block_len;
+            handles.push((c.name.clone(), hdl, base));
+        }
+
+        let samples = std::cmp::max(verify.samples, 1) as u64;
+        let stride = self.bdev.size_in_bytes() / samples;
+        for i in 0 .. samples {
+            let opts = VerifyOptions {
+                offset: i * stride,
+                length: Some(verify.segment_size),
+                segment_size: verify.segment_size,
+                ..Default::default()
+            };
+            let (report, _) = self.compare(&handles, &opts).await?;
+            if !report.is_consistent() {
+                info!(
+                    "{}: child {} diverges from {} at offset {}",
+                    self.name, name, source.name, report.start
+                );
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Read the given sources segment by segment and compare them, returning
+    /// the report and the number of bytes on which each source differs from
+    /// the majority
+    async fn compare(
+        &self,
+        handles: &[Source],
+        opts: &VerifyOptions,
+    ) -> Result<(ConsistencyReport, Vec<u64>), Error> {
+        let block_len = self.bdev.block_len() as u64;
+        let size = self.bdev.size_in_bytes();
+        let segment_size =
+            std::cmp::max(block_len, opts.segment_size / block_len * block_len);
+        let start = std::cmp::min(opts.offset / block_len * block_len, size);
+        let end = match opts.length {
+            Some(length) => std::cmp::min(start + length, size),
+            None => size,
+        };
+
+        let names = handles
+            .iter()
+            .map(|(n, _, _)| n.clone())
+            .collect::<Vec<_>>();
+        let mut report = ConsistencyReport {
+            start,
+            end: start,
+            children: names.clone(),
+            ..Default::default()
+        };
+
+        let alloc = |length: u64| {
+            handles
+                .iter()
+                .map(|(_, hdl, _)| hdl.dma_malloc(length))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|source| Error::VerifyAlloc {
+                    source,
+                    name: self.name.clone(),
+                })
+        };
+        let mut bufs = alloc(segment_size)?;
+        let mut dissent = vec![0; handles.len()];
+
+        let mut offset = start;
+        while offset < end {
+            let length = std::cmp::min(segment_size, end - offset);
+            // the last segment may be shorter
+            if length != segment_size {
+                bufs = alloc(length)?;
+            }
+            for ((name, hdl, base), buf) in handles.iter().zip(bufs.iter_mut())
+            {
+                self.background_io(name, length).await;
+                hdl.read_at(base + offset, buf).await.map_err(|source| {
+                    Error::ChildRead {
+                        source,
+                        child: name.clone(),
+                        name: self.name.clone(),
+                    }
+                })?;
+            }
+
+            let data = bufs.iter().map(|b| b.as_slice()).collect::<Vec<_>>();
+            if data.iter().any(|d| *d != data[0]) {
+                let dissenters = dissenters(&names, &data);
+                for (bytes, name) in dissent.iter_mut().zip(names.iter()) {
+                    if dissenters.contains(name) {
+                        *bytes += length;
+                    }
+                }
+                report.diverged(offset, length, dissenters, opts.max_ranges);
+                self.metrics.divergent_bytes(length);
+            }
+
+            offset += length;
+            report.end = offset;
+
+            if let Some(delay) = opts.segment_delay {
+                if offset < end {
+                    throttle(delay).await;
+                }
+            }
+        }
+
+        Ok((report, dissent))
+    }
+}
@@ -0,0 +1,145 @@
+//! Bounce buffers for IO with more iovecs than a child accepts. A child may
+//! advertise the most iovecs of a single IO, and an IO of the nexus which is
+//! scattered over more iovecs is submitted to such a child through a
+//! contiguous buffer instead: the data of a write is copied into the buffer
+//! before it is submitted, the data of a read is copied out of it once the
+//! child has completed it. The children which accept the iovecs of the IO are
+//! submitted the IO as is.
+//!
+//! The buffers are taken from a pool per channel, such that no buffer is
+//! allocated per IO. A buffer is kept by the IO until the last child IO
+//! submitted from it has completed. When all buffers of the pool are in use
+//! the IO is completed with NoMemory and resubmitted later.
+
+use std::{collections::HashMap, ffi::c_void};
+
+use spdk_sys::iovec;
+
+use crate::core::DmaBuf;
+
+/// most bounce buffers of a channel
+pub(crate) const BOUNCE_POOL_SIZE: usize = 16;
+
+/// the buffer of an IO and the number of child IOs submitted from it which
+/// have not completed yet
+#[derive(Debug)]
+struct Bounce {
+    slot: usize,
+    users: u32,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct BouncePool {
+    bufs: Vec<DmaBuf>,
+    /// the buffers which no IO keeps
+    free: Vec<usize>,
+    /// the buffers kept by IOs, by the address of their bdev IO
+    taken: HashMap<usize, Bounce>,
+}
+
+impl BouncePool {
+    /// The buffer of the given IO of at least `len` bytes, taken from the
+    /// pool if the IO has none yet. Returns the buffer and whether it has
+    /// just been taken, or None when the pool is exhausted.
+    pub(crate) fn get(
+        &mut self,
+        io: usize,
+        len: u64,
+        alignment: u64,
+    ) -> Option<(&mut DmaBuf, bool)> {
+        if let Some(bounce) = self.taken.get(&io) {
+            return Some((&mut self.bufs[bounce.slot], false));
+        }
+
+        let fits = self.free.iter().position(|s| self.bufs[*s].len() >= len);
+        let slot = match fits {
+            Some(i) => self.free.swap_remove(i),
+            None => {
+                let buf = DmaBuf::new(len, alignment).ok()?;
+                match self.free.pop() {
+                    // a buffer too small for the IO is replaced
+                    Some(slot) => {
+                        self.bufs[slot] = buf;
+                        slot
+                    }
+                    None if self.bufs.len() < BOUNCE_POOL_SIZE => {
+                        self.bufs.push(buf);
+                        self.bufs.len() - 1
+                    }
+                    None => return None,
+                }
+            }
+        };
+        self.taken.insert(
+            io,
+            Bounce {
+                slot,
+                users: 0,
+            },
+        );
+        Some((&mut self.bufs[slot], true))
+    }
+
+    /// account a child IO submitted from the buffer of the IO
+    pub(crate) fn submitted(&mut self, io: usize) {
+        if let Some(bounce) = self.taken.get_mut(&io) {
+            bounce.users += 1;
+        }
+    }
+
+    /// The buffer of the IO if the child IO with the given data was submitted
+    /// from it. The buffer is returned to the pool once the last such child
+    /// IO has completed, its data stays valid until it is taken again.
+    pub(crate) fn completed(
+        &mut self,
+        io: usize,
+        data: *mut c_void,
+    ) -> Option<&DmaBuf> {
+        let bounce = self.taken.get_mut(&io)?;
+        if *self.bufs[bounce.slot] != data {
+            return None;
+        }
+        bounce.users -= 1;
+        let slot = bounce.slot;
+        if bounce.users == 0 {
+            self.taken.remove(&io);
+            self.free.push(slot);
+        }
+        Some(&self.bufs[slot])
+    }
+
+    /// return the buffer of the IO to the pool if no child IO was submitted
+    /// from it
+    pub(crate) fn settle(&mut self, io: usize) {
+        if matches!(self.taken.get(&io), Some(b) if b.users == 0) {
+            let bounce = self.taken.remove(&io).unwrap();
+            self.free.push(bounce.slot);
+        }
+    }
+}
+
+/// copy the data held by the iovecs into the buffer
+pub(crate) fn copy_from_iovs(iovs: &[iovec], buf: &mut [u8]) {
+    let mut offset = 0;
+    for iov in iovs {
+        let len = std::cmp::min(iov.iov_len as usize, buf.len() - offset);
+        let data = unsafe {
+            std::slice::from_raw_parts(iov.iov_base as *const u8, len)
+        };
+        buf[offset .. offset + len].copy_from_slice(data);
+        offset += len;
+    }
+}
+
+/// copy the data of the buffer into the iovecs
+pub(crate) fn copy_to_iovs(buf: &[u8], iovs: &[iovec]) {
+    let mut offset = 0;
+    for iov in iovs {
+        let len = std::cmp::min(iov.iov_len as usize, buf.len() - offset);
+        let data = unsafe {
+            std::slice::from_raw_parts_mut(iov.iov_base as *mut u8, len)
+        };
+        data.copy_from_slice(&buf[offset .. offset + len]);
+        offset += len;
+    }
+}
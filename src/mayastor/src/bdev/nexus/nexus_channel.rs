@@ -1,6 +1,12 @@
 //!
 //! IO is driven by means of so called channels.
-use std::{ffi::c_void, ptr::NonNull};
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    ffi::c_void,
+    ptr::NonNull,
+    sync::{atomic::AtomicU64, Arc},
+    time::{Duration, Instant},
+};
 
 use futures::channel::oneshot;
 
@@ -15,10 +21,33 @@ use spdk_sys::{
 };
 
 use crate::{
-    bdev::{nexus::nexus_child::ChildState, Nexus, Reason},
-    core::{BdevHandle, Mthread},
+    bdev::{
+        nexus::{
+            nexus_bounce::BouncePool,
+            nexus_child::{ChildLocality, ChildState, NexusChild},
+            nexus_child_io_stats::ChannelIoStats,
+            nexus_completion_batch::CompletionBatch,
+            nexus_io::{FusedLock, NexusBio},
+            nexus_io_inflight::IoList,
+            nexus_metrics::ChannelWrites,
+            nexus_no_memory::NoMemoryQueue,
+            nexus_policy::ReadPolicy,
+            nexus_read_weight::READ_WEIGHT_DEFAULT,
+            nexus_throughput::ChannelTraffic,
+            nexus_write_cache::WriteCoalescer,
+        },
+        Nexus,
+        Reason,
+    },
+    core::{poller::Poller, Bdev, BdevHandle, Cores, Mthread},
+    rebuild::RebuildJob,
 };
 
+#[cfg(feature = "io-recorder")]
+use crate::bdev::nexus::nexus_io_recorder::{IoRecorder, Selection};
+#[cfg(feature = "fault-injection")]
+use crate::core::{Bio, IoStatus, NvmeStatusCode};
+
 /// io channel, per core
 #[repr(C)]
 #[derive(Debug)]
@@ -30,11 +59,230 @@ pub(crate) struct NexusChannel {
 #[derive(Debug)]
 pub(crate) struct NexusChannelInner {
     pub(crate) writers: Vec<BdevHandle>,
+    /// children which serve reads, the local ones first
     pub(crate) readers: Vec<BdevHandle>,
+    /// number of local children at the front of the readers
+    pub(crate) local_readers: usize,
+    /// write-only children that are being rebuilt, along with the rebuild
+    /// cursor of their job
+    pub(crate) rebuilding: Vec<(BdevHandle, Arc<AtomicU64>)>,
     pub(crate) previous: usize,
+    /// IO submitted while the nexus is paused
+    pub(crate) paused_ios: VecDeque<NexusBio>,
+    /// IO queued at the outstanding IO limit of the nexus
+    pub(crate) throttled_ios: VecDeque<NexusBio>,
+    /// number of IOs admitted on this channel which have not completed yet
+    pub(crate) admitted_ios: usize,
+    /// the share of the outstanding IO limit of the nexus admitted on this
+    /// channel, see `nexus_admission`
+    admit_share: usize,
+    /// the limit the share was computed for
+    admit_limit: u64,
+    /// the demand this channel published last
+    admit_demand: u64,
+    /// number of reads completed on this channel since the last one which
+    /// was sampled
+    pub(crate) reads_since_sample: u32,
+    /// IO submitted on this channel which has not completed yet
+    pub(crate) outstanding: IoList,
+    /// the child IOs submitted on this channel which have not completed yet,
+    /// by the address of the bdev of the child
+    child_ios: HashMap<usize, ChildIos>,
+    /// offset of the data partition of every child in the channel, by the
+    /// address of the bdev of the child
+    data_offsets: HashMap<usize, u64>,
+    /// read weight of every reader whose weight is not the default, by the
+    /// address of the bdev of the child
+    read_weights: HashMap<usize, u32>,
+    /// credit of every reader in the weighted selection of readers, by the
+    /// address of the bdev of the child
+    read_credits: HashMap<usize, i64>,
+    /// address of the bdev of the reader which serves the reads with the
+    /// preferred read policy
+    preferred: Option<usize>,
+    /// ordering of writes and flushes submitted on this channel
+    pub(crate) barrier: FlushBarrier,
+    /// the run of writes held by the write cache
+    pub(crate) coalescer: WriteCoalescer,
+    /// the successful child IOs whose completion is batched
+    pub(crate) completions: CompletionBatch,
+    /// the buffers of IO submitted to children which do not accept its
+    /// iovecs
+    pub(crate) bounce: BouncePool,
+    /// the children on which the data of a compare decided by a quorum
+    /// differed, by the address of the bdev IO of the compare
+    pub(crate) diverged: HashMap<usize, Vec<Bdev>>,
+    /// the ranges locked by the compare-and-writes whose write is in flight,
+    /// by the address of the bdev IO of the compare-and-write
+    pub(crate) fused_locks: HashMap<usize, FusedLock>,
+    /// number of writes acknowledged by a quorum which are still in flight
+    /// on every child lagging behind it, by the address of the bdev of the
+    /// child
+    lagging: HashMap<usize, u32>,
+    /// offset and number of blocks of the writes acknowledged by a quorum
+    /// which are still in flight on some child
+    late_writes: Vec<(u64, u64)>,
+    /// the writes held back as they overlap a write acknowledged by a quorum
+    /// which is still in flight
+    pub(crate) held_writes: VecDeque<NexusBio>,
+    /// the bytes written to the nexus and its children on this channel
+    pub(crate) written: ChannelWrites,
+    /// the reads and writes submitted on this channel since the poller of
+    /// the channel last added them to the throughput of the nexus
+    pub(crate) traffic: ChannelTraffic,
+    /// the reads and writes completed by every child on this channel
+    pub(crate) io_stats: ChannelIoStats,
+    /// the last IOs completed on this channel
+    #[cfg(feature = "io-recorder")]
+    pub(crate) recorder: IoRecorder,
+    /// statuses replayed for the child IOs in flight, by child IO
+    #[cfg(feature = "fault-injection")]
+    pub(crate) replayed: HashMap<usize, (IoStatus, NvmeStatusCode)>,
+    /// the child IOs whose completion is held back as they were made to
+    /// hang, along with their IO
+    #[cfg(feature = "fault-injection")]
+    pub(crate) hung_ios: Vec<(NexusBio, Bio)>,
+    /// expires the IO of the channel, see `nexus_io_timeout`
+    pub(crate) timeout_poller: Option<Poller<'static>>,
+    /// time of the last scan for expired IO, which the IO submitted on the
+    /// channel is stamped with unless it is timed precisely
+    pub(crate) clock: Instant,
+    /// the IO requeued as the children ran out of memory, see
+    /// `nexus_no_memory`
+    pub(crate) no_memory: NoMemoryQueue,
     device: *mut c_void,
 }
 
+/// the child IOs outstanding on a child
+#[derive(Debug)]
+struct ChildIos {
+    outstanding: u32,
+    /// the last time the child completed an IO, or was submitted one while
+    /// it had none outstanding
+    progress: Instant,
+}
+
+/// Tracks the writes in flight on a channel, such that a flush acts as a
+/// barrier: a flush is only submitted to the children once all writes (writes,
+/// write zeroes and unmaps) which were submitted before it have completed on
+/// all children, and is completed once the children have flushed. Writes
+/// submitted after the flush are not held back by it. The writes submitted on
+/// other cores are waited for by `Nexus::wait_writes` before the flush gets to
+/// the barrier of its own channel.
+///
+/// When writes are ordered, every write is a barrier as well: it is held
+/// until all writes submitted before it have completed, and holds back the
+/// writes submitted after it.
+#[derive(Debug, Default)]
+pub(crate) struct FlushBarrier {
+    /// sequence number of the last write submitted
+    seq: u64,
+    /// sequence numbers of the writes in flight
+    in_flight: BTreeSet<u64>,
+    /// flushes waiting for the writes up to their sequence number
+    flushes: VecDeque<(u64, NexusBio)>,
+    /// ordered writes waiting for the writes before their sequence number
+    writes: VecDeque<(u64, NexusBio)>,
+    /// flushes of other cores waiting for the writes up to their sequence
+    /// number
+    waiters: Vec<(u64, oneshot::Sender<()>)>,
+}
+
+impl FlushBarrier {
+    /// account a write submitted and return its sequence number
+    pub(crate) fn write_submitted(&mut self) -> u64 {
+        self.seq += 1;
+        self.in_flight.insert(self.seq);
+        self.seq
+    }
+
+    /// account a write completed
+    pub(crate) fn write_completed(&mut self, seq: u64) {
+        self.in_flight.remove(&seq);
+    }
+
+    /// returns the flush if all writes before it have completed, otherwise
+    /// it waits for them
+    pub(crate) fn flush(&mut self, io: NexusBio) -> Option<NexusBio> {
+        if self.flushes.is_empty() && !self.is_blocked(self.seq) {
+            Some(io)
+        } else {
+            self.flushes.push_back((self.seq, io));
+            None
+        }
+    }
+
+    /// returns the ordered write with the given sequence number if all
+    /// writes before it have completed, otherwise it waits for them
+    pub(crate) fn order(&mut self, seq: u64, io: NexusBio) -> Option<NexusBio> {
+        if self.is_blocked(seq - 1) {
+            self.writes.push_back((seq, io));
+            None
+        } else {
+            Some(io)
+        }
+    }
+
+    /// returns the next ordered write of which all writes before it have
+    /// completed
+    pub(crate) fn next_write(&mut self) -> Option<NexusBio> {
+        match self.writes.front() {
+            Some((seq, _)) if !self.is_blocked(*seq - 1) => {
+                self.writes.pop_front().map(|(_, io)| io)
+            }
+            _ => None,
+        }
+    }
+
+    /// returns the next flush of which all writes before it have completed
+    pub(crate) fn next_flush(&mut self) -> Option<NexusBio> {
+        match self.flushes.front() {
+            Some((seq, _)) if !self.is_blocked(*seq) => {
+                self.flushes.pop_front().map(|(_, io)| io)
+            }
+            _ => None,
+        }
+    }
+
+    /// returns a receiver which completes once the writes submitted so far
+    /// have completed, or none when no write is in flight
+    pub(crate) fn wait_writes(&mut self) -> Option<oneshot::Receiver<()>> {
+        if !self.is_blocked(self.seq) {
+            return None;
+        }
+        let (s, r) = oneshot::channel();
+        self.waiters.push((self.seq, s));
+        Some(r)
+    }
+
+    /// complete the waiters of which all writes have completed
+    pub(crate) fn release_waiters(&mut self) {
+        let mut i = 0;
+        while i < self.waiters.len() {
+            if self.is_blocked(self.waiters[i].0) {
+                i += 1;
+            } else {
+                let (_, s) = self.waiters.swap_remove(i);
+                let _ = s.send(());
+            }
+        }
+    }
+
+    /// true if a write with a sequence number up to seq is in flight
+    fn is_blocked(&self, seq: u64) -> bool {
+        self.in_flight.range(..= seq).next().is_some()
+    }
+
+    /// remove all flushes and writes which are waiting
+    pub(crate) fn drain(&mut self) -> Vec<NexusBio> {
+        self.flushes
+            .drain(..)
+            .chain(self.writes.drain(..))
+            .map(|(_, io)| io)
+            .collect()
+    }
+}
+
 #[derive(Debug)]
 /// reconfigure context holding among others
 /// the completion channel.
@@ -56,6 +304,46 @@ impl ReconfigureCtx {
     }
 }
 
+/// context used to run a closure against the inner channel of every core
+pub(crate) struct TraverseCtx {
+    /// closure invoked on each core
+    f: Box<dyn FnMut(&mut NexusChannelInner)>,
+    /// channel to send completion on.
+    sender: oneshot::Sender<i32>,
+}
+
+impl TraverseCtx {
+    pub(crate) fn new(
+        f: Box<dyn FnMut(&mut NexusChannelInner)>,
+        sender: oneshot::Sender<i32>,
+    ) -> Self {
+        Self {
+            f,
+            sender,
+        }
+    }
+}
+
+/// IO flags of a child, used to drain a child from the read or write path
+/// without faulting it. The flags are kept by the child and applied by every
+/// channel whenever it is created or refreshed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ChildIoFlags {
+    /// the child serves reads
+    pub read: bool,
+    /// the child receives writes
+    pub write: bool,
+}
+
+impl Default for ChildIoFlags {
+    fn default() -> Self {
+        Self {
+            read: true,
+            write: true,
+        }
+    }
+}
+
 #[derive(Debug)]
 /// Dynamic Reconfiguration Events occur when a child is added or removed
 pub enum DrEvent {
@@ -78,10 +366,46 @@ impl NexusChannelInner {
     /// threads more often depending on what core we are on etc, we might be
     /// "awaiting' while the thread is already trying to submit IO.
     pub(crate) fn child_select(&mut self) -> Option<usize> {
-        if self.readers.is_empty() {
+        let selected = self.child_select_policy();
+        if self.lagging.is_empty() {
+            return selected;
+        }
+        // a reader which lags behind a quorum does not hold the data of the
+        // writes still in flight on it, the next one which does not is
+        // selected instead
+        let count = self.readers.len();
+        let i = selected?;
+        (0 .. count)
+            .map(|n| (i + n) % count)
+            .find(|i| !self.is_lagging(&self.readers[*i].get_bdev()))
+    }
+
+    /// select a reader according to the read policy of the nexus
+    fn child_select_policy(&mut self) -> Option<usize> {
+        // with the prefer local read policy, only the local readers are
+        // rotated between unless there are none
+        let nexus = unsafe { Nexus::from_raw(self.device) };
+        if nexus.policy.read == ReadPolicy::LeastOutstanding {
+            return self.child_select_least_outstanding();
+        }
+        // with the preferred read policy, the preferred reader serves all
+        // reads unless its read weight is zero
+        if nexus.policy.read == ReadPolicy::Preferred {
+            if let Some(i) = self.preferred_reader() {
+                return Some(i);
+            }
+        }
+        let count = self.candidates(nexus.policy.read);
+        if !self.read_weights.is_empty() {
+            if let Some(i) = self.child_select_weighted(count) {
+                return Some(i);
+            }
+        }
+
+        if count == 0 {
             None
         } else {
-            if self.previous < self.readers.len() - 1 {
+            if self.previous < count - 1 {
                 self.previous += 1;
             } else {
                 self.previous = 0;
@@ -90,6 +414,364 @@ impl NexusChannelInner {
         }
     }
 
+    /// number of readers at the front of the readers the given policy
+    /// selects from
+    fn candidates(&self, policy: ReadPolicy) -> usize {
+        if policy == ReadPolicy::PreferLocal && self.local_readers != 0 {
+            self.local_readers
+        } else {
+            self.readers.len()
+        }
+    }
+
+    /// describe why the reader at the given index was selected by
+    /// `child_select`, for the IO recorder
+    #[cfg(feature = "io-recorder")]
+    pub(crate) fn selection(&self, i: usize) -> Selection {
+        let nexus = unsafe { Nexus::from_raw(self.device) };
+        Selection {
+            policy: nexus.policy.read,
+            position: i,
+            candidates: self.candidates(nexus.policy.read),
+            local: i < self.local_readers,
+            outstanding: self
+                .readers
+                .iter()
+                .map(|h| {
+                    let bdev = h.get_bdev();
+                    let child = nexus
+                        .child_lookup(&bdev.name())
+                        .map_or_else(|| bdev.name(), |c| c.name.clone());
+                    (child, self.child_ios_outstanding(&bdev))
+                })
+                .collect(),
+        }
+    }
+
+    /// the read weight of the reader at the given index
+    fn read_weight(&self, i: usize) -> u32 {
+        self.read_weights
+            .get(&(self.readers[i].get_bdev().as_ptr() as usize))
+            .copied()
+            .unwrap_or(READ_WEIGHT_DEFAULT)
+    }
+
+    /// Select one of the first `count` readers in proportion to their read
+    /// weight, by smooth weighted round-robin. All readers are selected from
+    /// when the first ones all have a weight of zero, returns None when all
+    /// readers do.
+    fn child_select_weighted(&mut self, count: usize) -> Option<usize> {
+        let mut count = count;
+        if (0 .. count).all(|i| self.read_weight(i) == 0) {
+            count = self.readers.len();
+        }
+        let total = (0 .. count)
+            .map(|i| i64::from(self.read_weight(i)))
+            .sum::<i64>();
+        if total == 0 {
+            return None;
+        }
+
+        let mut selected: Option<(usize, i64)> = None;
+        for i in 0 .. count {
+            let weight = i64::from(self.read_weight(i));
+            let credit = self
+                .read_credits
+                .entry(self.readers[i].get_bdev().as_ptr() as usize)
+                .or_default();
+            *credit += weight;
+            if selected.map_or(true, |(_, c)| *credit > c) {
+                selected = Some((i, *credit));
+            }
+        }
+        let (i, _) = selected?;
+        if let Some(credit) = self
+            .read_credits
+            .get_mut(&(self.readers[i].get_bdev().as_ptr() as usize))
+        {
+            *credit -= total;
+        }
+        self.previous = i;
+        Some(i)
+    }
+
+    /// the index of the preferred reader, if it serves reads
+    fn preferred_reader(&self) -> Option<usize> {
+        let preferred = self.preferred?;
+        self.readers
+            .iter()
+            .position(|h| h.get_bdev().as_ptr() as usize == preferred)
+            .filter(|i| self.read_weight(*i) != 0)
+    }
+
+    /// set the reader of the given child as the preferred one
+    pub(crate) fn set_preferred_reader(&mut self, bdev: Option<&str>) {
+        self.preferred = bdev.and_then(|bdev| {
+            self.readers
+                .iter()
+                .find(|h| h.get_bdev().name() == bdev)
+                .map(|h| h.get_bdev().as_ptr() as usize)
+        });
+    }
+
+    /// set the read weight of the reader of the given child
+    pub(crate) fn set_read_weight(&mut self, bdev: &str, weight: u32) {
+        let key =
+            match self.readers.iter().find(|h| h.get_bdev().name() == bdev) {
+                Some(hdl) => hdl.get_bdev().as_ptr() as usize,
+                None => return,
+            };
+        if weight == READ_WEIGHT_DEFAULT {
+            self.read_weights.remove(&key);
+        } else {
+            self.read_weights.insert(key, weight);
+        }
+        self.read_credits.clear();
+    }
+
+    /// select the reader with the fewest child IOs outstanding, starting the
+    /// search after the previously selected reader. Readers with a read
+    /// weight of zero are skipped unless all readers have one.
+    fn child_select_least_outstanding(&mut self) -> Option<usize> {
+        let count = self.readers.len();
+        if count == 0 {
+            return None;
+        }
+        let weighted = !self.read_weights.is_empty()
+            && (0 .. count).any(|i| self.read_weight(i) != 0);
+        let start = (self.previous + 1) % count;
+        let i = (0 .. count)
+            .map(|k| (start + k) % count)
+            .filter(|i| !weighted || self.read_weight(*i) != 0)
+            .min_by_key(|i| {
+                self.child_ios_outstanding(&self.readers[*i].get_bdev())
+            })?;
+        self.previous = i;
+        Some(i)
+    }
+
+    /// account a child IO submitted to the given child
+    pub(crate) fn child_io_submitted(&mut self, bdev: &Bdev) {
+        let ios =
+            self.child_ios
+                .entry(bdev.as_ptr() as usize)
+                .or_insert_with(|| ChildIos {
+                    outstanding: 0,
+                    progress: Instant::now(),
+                });
+        if ios.outstanding == 0 {
+            ios.progress = Instant::now();
+        }
+        ios.outstanding += 1;
+    }
+
+    /// account a child IO completed by the given child
+    pub(crate) fn child_io_completed(&mut self, bdev: &Bdev) {
+        self.child_ios_completed(bdev, 1);
+    }
+
+    /// account a number of child IOs completed by the given child
+    pub(crate) fn child_ios_completed(&mut self, bdev: &Bdev, count: u32) {
+        if let Some(ios) = self.child_ios.get_mut(&(bdev.as_ptr() as usize)) {
+            ios.outstanding = ios.outstanding.saturating_sub(count);
+            ios.progress = Instant::now();
+        }
+    }
+
+    /// number of child IOs outstanding on the given child
+    pub(crate) fn child_ios_outstanding(&self, bdev: &Bdev) -> u32 {
+        self.child_ios
+            .get(&(bdev.as_ptr() as usize))
+            .map_or(0, |ios| ios.outstanding)
+    }
+
+    /// the given child has had IO outstanding on this channel without
+    /// completing any of it for at least the given time
+    pub(crate) fn child_hung(&self, bdev: &Bdev, timeout: Duration) -> bool {
+        self.child_ios
+            .get(&(bdev.as_ptr() as usize))
+            .map_or(false, |ios| {
+                ios.outstanding > 0 && ios.progress.elapsed() >= timeout
+            })
+    }
+
+    /// the time after which the IO of the nexus expires, zero when it
+    /// never does
+    pub(crate) fn io_timeout(&self) -> Duration {
+        unsafe { Nexus::from_raw(self.device) }.policy.io_timeout.0
+    }
+
+    /// whether an IO may be admitted on this channel within the given
+    /// outstanding IO limit, which it always may when the channel has no IO
+    /// admitted
+    pub(crate) fn may_admit(&mut self, max_ios: u64) -> bool {
+        if self.admit_limit != max_ios {
+            self.rebalance_admission();
+        }
+        self.admitted_ios == 0 || self.admitted_ios < self.admit_share
+    }
+
+    /// publish the demand of this channel and recompute its share of the
+    /// outstanding IO limit of the nexus, if it has one
+    pub(crate) fn rebalance_admission(&mut self) {
+        let nexus = unsafe { Nexus::from_raw(self.device) };
+        let max_ios = match nexus.policy.max_outstanding {
+            Some(limit) => limit.max_ios,
+            None if self.admit_demand == 0 => return,
+            None => 0,
+        };
+        let demand = (self.admitted_ios + self.throttled_ios.len()) as u64;
+        nexus.admission.publish(self.admit_demand, demand);
+        self.admit_demand = demand;
+        self.admit_limit = max_ios;
+        self.admit_share = nexus.admission.share(max_ios, demand);
+    }
+
+    /// add the reads and writes submitted on this channel to the throughput
+    /// of the nexus, as of the last scan of the channel for expired IO
+    pub(crate) fn add_traffic(&mut self) {
+        let nexus = unsafe { Nexus::from_raw(self.device) };
+        nexus.metrics.channel_traffic(&mut self.traffic, self.clock);
+    }
+
+    /// record the offset of the data partition of a child
+    fn add_data_offset(&mut self, child: &NexusChild) {
+        if let (Some(bdev), Some(offset)) = (&child.bdev, child.data_offset) {
+            self.data_offsets.insert(bdev.as_ptr() as usize, offset);
+        }
+    }
+
+    /// offset of the data partition of the given child, which is the data
+    /// offset of the nexus unless the label of the child says otherwise
+    pub(crate) fn data_offset(&self, bdev: &Bdev) -> u64 {
+        match self.data_offsets.get(&(bdev.as_ptr() as usize)) {
+            Some(offset) => *offset,
+            None => unsafe { Nexus::from_raw(self.device) }.data_ent_offset,
+        }
+    }
+
+    /// add a reader with the read weight of its child, keeping the local
+    /// readers in front of the remote ones
+    fn add_reader(&mut self, hdl: BdevHandle, child: &NexusChild) {
+        let weight = child.read_weight.weight();
+        if weight != READ_WEIGHT_DEFAULT {
+            self.read_weights
+                .insert(hdl.get_bdev().as_ptr() as usize, weight);
+        }
+        let nexus = unsafe { Nexus::from_raw(self.device) };
+        if nexus.policy.preferred_reader.as_ref() == Some(&child.name) {
+            self.preferred = Some(hdl.get_bdev().as_ptr() as usize);
+        }
+        if child.locality() == ChildLocality::Local {
+            self.readers.insert(self.local_readers, hdl);
+            self.local_readers += 1;
+        } else {
+            self.readers.push(hdl);
+        }
+    }
+
+    /// select a reader which is in sync, rotating between the readers which
+    /// are, regardless of the read policy of the nexus
+    pub(crate) fn child_select_in_sync(&mut self) -> Option<usize> {
+        let count = self.readers.len();
+        let i = (1 ..= count)
+            .map(|n| (self.previous + n) % count)
+            .find(|i| self.in_sync(&self.readers[*i].get_bdev()))?;
+        self.previous = i;
+        Some(i)
+    }
+
+    /// true if the child is in sync: it is open, receives all writes
+    /// submitted to the nexus and is not being rebuilt
+    pub(crate) fn in_sync(&self, bdev: &Bdev) -> bool {
+        let nexus = unsafe { Nexus::from_raw(self.device) };
+        let is = |h: &BdevHandle| h.get_bdev().as_ptr() == bdev.as_ptr();
+        self.writers.iter().any(is)
+            && !self.is_lagging(bdev)
+            && !self.rebuilding.iter().any(|(h, _)| is(h))
+            && nexus.children.iter().any(|c| {
+                c.state() == ChildState::Open
+                    && c.bdev.as_ref().map(|b| b.as_ptr())
+                        == Some(bdev.as_ptr())
+            })
+    }
+
+    /// true if the child has writes acknowledged by a quorum in flight
+    fn is_lagging(&self, bdev: &Bdev) -> bool {
+        self.lagging.contains_key(&(bdev.as_ptr() as usize))
+    }
+
+    /// account a write which was acknowledged by a quorum while still in
+    /// flight on the given children
+    pub(crate) fn write_lagging(
+        &mut self,
+        children: &[Bdev],
+        offset: u64,
+        num_blocks: u64,
+    ) {
+        for bdev in children {
+            *self.lagging.entry(bdev.as_ptr() as usize).or_default() += 1;
+        }
+        self.late_writes.push((offset, num_blocks));
+    }
+
+    /// account the completion of a write acknowledged by a quorum on a child
+    /// which lagged behind it
+    pub(crate) fn lagging_completed(&mut self, bdev: &Bdev) {
+        let key = bdev.as_ptr() as usize;
+        if let Some(n) = self.lagging.get_mut(&key) {
+            *n -= 1;
+            if *n == 0 {
+                self.lagging.remove(&key);
+            }
+        }
+    }
+
+    /// account the completion of a write acknowledged by a quorum on all
+    /// children
+    pub(crate) fn late_write_completed(
+        &mut self,
+        offset: u64,
+        num_blocks: u64,
+    ) {
+        if let Some(i) = self
+            .late_writes
+            .iter()
+            .position(|w| *w == (offset, num_blocks))
+        {
+            self.late_writes.swap_remove(i);
+        }
+    }
+
+    /// true if the given range of blocks overlaps a write acknowledged by a
+    /// quorum which is still in flight
+    pub(crate) fn overlaps_late_write(
+        &self,
+        offset: u64,
+        num_blocks: u64,
+    ) -> bool {
+        self.late_writes
+            .iter()
+            .any(|(o, n)| offset < o + n && *o < offset + num_blocks)
+    }
+
+    /// true if every reader and writer of the channel is an open child of
+    /// the nexus, and the channel has readers and writers when the nexus has
+    /// open children
+    pub(crate) fn is_populated(&self) -> bool {
+        let nexus = unsafe { Nexus::from_raw(self.device) };
+        let open = |hdl: &BdevHandle| {
+            nexus
+                .child_lookup(&hdl.get_bdev().name())
+                .map_or(false, |c| c.state() == ChildState::Open)
+        };
+        let any_open =
+            nexus.children.iter().any(|c| c.state() == ChildState::Open);
+        self.readers.iter().chain(self.writers.iter()).all(open)
+            && (!any_open
+                || (!self.readers.is_empty() && !self.writers.is_empty()))
+    }
+
     /// refreshing our channels simply means that we either have a child going
     /// online or offline. We don't know which child has gone, or was added, so
     /// we simply put back all the channels, and reopen the bdevs that are in
@@ -115,17 +797,102 @@ impl NexusChannelInner {
         // channel
         self.writers.clear();
         self.readers.clear();
+        self.local_readers = 0;
+        self.rebuilding.clear();
+        self.data_offsets.clear();
+        self.read_weights.clear();
+        self.read_credits.clear();
+        self.preferred = None;
         self.previous = 0;
 
-        // iterate over all our children which are in the open state
+        self.add_children();
+
+        // the statistics of the children move along with their index, those
+        // of the children the channel no longer holds a handle to are
+        // dropped, a child starts afresh when it is reopened
+        let held = self
+            .writers
+            .iter()
+            .chain(self.rebuilding.iter().map(|(hdl, _)| hdl))
+            .map(|hdl| hdl.get_bdev().as_ptr() as usize)
+            .collect::<Vec<_>>();
+        let bdevs = nexus
+            .children
+            .iter()
+            .map(|c| {
+                c.bdev
+                    .as_ref()
+                    .map(|b| b.as_ptr() as usize)
+                    .filter(|b| held.contains(b))
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+        self.io_stats.remap(&bdevs);
+
+        trace!(
+            "{}: New number of IO channels write:{} read:{} rebuild:{} out of {} children",
+            nexus.name,
+            self.writers.len(),
+            self.readers.len(),
+            self.rebuilding.len(),
+            nexus.children.len()
+        );
+
+        //trace!("{:?}", nexus.children);
+    }
+
+    /// re-derive the readers and writers of this channel from the children
+    /// of the nexus, as `refresh` does, and start the rotation between the
+    /// readers at a different child on every core such that the cores do not
+    /// all read from the same child. Returns true if the channel did not
+    /// reflect the children of the nexus.
+    pub(crate) fn rebalance(&mut self) -> bool {
+        let before = self.view();
+        self.refresh();
+        if !self.readers.is_empty() {
+            self.previous = Cores::current() as usize % self.readers.len();
+        }
+        before != self.view()
+    }
+
+    /// the bdev names of the readers, writers and rebuilding children
+    fn view(&self) -> [BTreeSet<String>; 3] {
+        fn names<'a>(
+            hdls: impl Iterator<Item = &'a BdevHandle>,
+        ) -> BTreeSet<String> {
+            hdls.map(|h| h.get_bdev().name()).collect()
+        }
+        [
+            names(self.readers.iter()),
+            names(self.writers.iter()),
+            names(self.rebuilding.iter().map(|(h, _)| h)),
+        ]
+    }
+
+    /// Add the children of the nexus to this channel: the open children to
+    /// the writers and readers, then the children being rebuilt, which only
+    /// receive writes for the region that has already been rebuilt. The IO
+    /// flags of a child are applied: a child that is read disabled is not a
+    /// reader, and as a result is never returned by `child_select`. Likewise,
+    /// a write disabled child is not a writer and does not take part in
+    /// `submit_all`.
+    fn add_children(&mut self) {
+        let nexus = unsafe { Nexus::from_raw(self.device) };
+
         nexus
             .children
             .iter_mut()
             .filter(|c| c.state() == ChildState::Open)
             .for_each(|c| match (c.handle(), c.handle()) {
                 (Ok(w), Ok(r)) => {
-                    self.writers.push(w);
-                    self.readers.push(r);
+                    let flags = c.io_flags();
+                    self.add_data_offset(c);
+                    if flags.write {
+                        self.writers.push(w);
+                    }
+                    if flags.read {
+                        self.add_reader(r, c);
+                    }
                 }
                 _ => {
                     c.set_state(ChildState::Faulted(Reason::CantOpen));
@@ -133,31 +900,23 @@ impl NexusChannelInner {
                 }
             });
 
-        // then add write-only children
         if !self.readers.is_empty() {
             nexus
                 .children
                 .iter_mut()
-                .filter(|c| c.rebuilding())
+                .filter(|c| c.rebuilding() && c.io_flags().write)
                 .for_each(|c| {
-                    if let Ok(hdl) = c.handle() {
-                        self.writers.push(hdl);
+                    if let (Ok(hdl), Ok(job)) =
+                        (c.handle(), RebuildJob::lookup(&c.name))
+                    {
+                        self.add_data_offset(c);
+                        self.rebuilding.push((hdl, job.cursor()));
                     } else {
                         c.set_state(ChildState::Faulted(Reason::CantOpen));
                         error!("failed to create handle for {}", c);
                     }
                 });
         }
-
-        trace!(
-            "{}: New number of IO channels write:{} read:{} out of {} children",
-            nexus.name,
-            self.writers.len(),
-            self.readers.len(),
-            nexus.children.len()
-        );
-
-        //trace!("{:?}", nexus.children);
     }
 }
 
@@ -169,29 +928,53 @@ impl NexusChannel {
     ) -> i32 {
         let nexus = unsafe { Nexus::from_raw(device) };
         debug!("{}: Creating IO channels at {:p}", nexus.bdev.name(), ctx);
+        nexus.metrics.channel_created();
 
         let ch = NexusChannel::from_raw(ctx);
         let mut channels = Box::new(NexusChannelInner {
             writers: Vec::new(),
             readers: Vec::new(),
+            local_readers: 0,
+            rebuilding: Vec::new(),
             previous: 0,
+            paused_ios: VecDeque::new(),
+            throttled_ios: VecDeque::new(),
+            admitted_ios: 0,
+            admit_share: 0,
+            admit_limit: 0,
+            admit_demand: 0,
+            reads_since_sample: 0,
+            outstanding: IoList::default(),
+            child_ios: HashMap::new(),
+            data_offsets: HashMap::new(),
+            read_weights: HashMap::new(),
+            read_credits: HashMap::new(),
+            preferred: None,
+            barrier: FlushBarrier::default(),
+            coalescer: WriteCoalescer::default(),
+            completions: CompletionBatch::default(),
+            bounce: BouncePool::default(),
+            diverged: HashMap::new(),
+            fused_locks: HashMap::new(),
+            lagging: HashMap::new(),
+            late_writes: Vec::new(),
+            held_writes: VecDeque::new(),
+            written: ChannelWrites::default(),
+            traffic: ChannelTraffic::default(),
+            io_stats: ChannelIoStats::default(),
+            #[cfg(feature = "io-recorder")]
+            recorder: IoRecorder::default(),
+            #[cfg(feature = "fault-injection")]
+            replayed: HashMap::new(),
+            #[cfg(feature = "fault-injection")]
+            hung_ios: Vec::new(),
+            timeout_poller: None,
+            clock: Instant::now(),
+            no_memory: NoMemoryQueue::default(),
             device,
         });
-
-        nexus
-            .children
-            .iter_mut()
-            .filter(|c| c.state() == ChildState::Open)
-            .for_each(|c| match (c.handle(), c.handle()) {
-                (Ok(w), Ok(r)) => {
-                    channels.writers.push(w);
-                    channels.readers.push(r);
-                }
-                _ => {
-                    c.set_state(ChildState::Faulted(Reason::CantOpen));
-                    error!("Failed to get handle for {}, skipping bdev", c)
-                }
-            });
+        channels.start_io_timeout();
+        channels.add_children();
         ch.inner = Box::into_raw(channels);
         0
     }
@@ -201,8 +984,34 @@ impl NexusChannel {
         let nexus = unsafe { Nexus::from_raw(device) };
         debug!("{} Destroying IO channels", nexus.bdev.name());
         let inner = NexusChannel::from_raw(ctx).inner_mut();
+        inner.timeout_poller.take();
+        nexus.admission.publish(inner.admit_demand, 0);
+        nexus.metrics.channel_written(inner.written);
+        nexus
+            .metrics
+            .channel_traffic(&mut inner.traffic, Instant::now());
+        inner.no_memory.stop();
+        inner
+            .paused_ios
+            .drain(..)
+            .chain(inner.throttled_ios.drain(..))
+            .chain(inner.no_memory.drain())
+            .chain(inner.held_writes.drain(..))
+            .for_each(|io| io.fail_retriable());
+        inner
+            .barrier
+            .drain()
+            .into_iter()
+            .for_each(|io| io.fail_retriable());
+        if let Some((ios, bytes)) = inner.coalescer.take() {
+            nexus.metrics.cache_released(bytes, 0);
+            ios.into_iter().for_each(|io| io.fail_retriable());
+        }
+        NexusBio::complete_batch(inner.completions.take());
         inner.writers.clear();
         inner.readers.clear();
+        inner.local_readers = 0;
+        inner.rebuilding.clear();
     }
 
     /// function called when we receive a Dynamic Reconfigure event (DR)
@@ -253,10 +1062,55 @@ impl NexusChannel {
     pub extern "C" fn refresh_io_channels(ch_iter: *mut spdk_io_channel_iter) {
         let channel = unsafe { spdk_io_channel_iter_get_channel(ch_iter) };
         let inner = Self::inner_from_channel(channel);
+        #[cfg(feature = "fault-injection")]
+        {
+            let nexus = unsafe { Nexus::from_raw(inner.device) };
+            let mut failures = RECONFIGURE_FAILURES.lock().unwrap();
+            if let Some(count) = failures.get_mut(&nexus.name) {
+                *count -= 1;
+                if *count == 0 {
+                    failures.remove(&nexus.name);
+                }
+                // the channels of the remaining cores are left as they are
+                unsafe {
+                    spdk_for_each_channel_continue(
+                        ch_iter,
+                        -(nix::errno::Errno::EIO as i32),
+                    )
+                };
+                return;
+            }
+        }
         inner.refresh();
         unsafe { spdk_for_each_channel_continue(ch_iter, 0) };
     }
 
+    /// run the closure of the traverse context on the channel of the current
+    /// core and continue with the next one
+    pub extern "C" fn traverse_io_channels(ch_iter: *mut spdk_io_channel_iter) {
+        let channel = unsafe { spdk_io_channel_iter_get_channel(ch_iter) };
+        let inner = Self::inner_from_channel(channel);
+        let ctx = unsafe {
+            &mut *(spdk_io_channel_iter_get_ctx(ch_iter) as *mut TraverseCtx)
+        };
+        (ctx.f)(inner);
+        unsafe { spdk_for_each_channel_continue(ch_iter, 0) };
+    }
+
+    /// signal that the closure has been run on all cores
+    pub extern "C" fn traverse_completed(
+        ch_iter: *mut spdk_io_channel_iter,
+        status: i32,
+    ) {
+        let ctx: Box<TraverseCtx> = unsafe {
+            Box::from_raw(
+                spdk_io_channel_iter_get_ctx(ch_iter) as *mut TraverseCtx
+            )
+        };
+
+        ctx.sender.send(status).expect("traverse channel gone");
+    }
+
     /// Converts a raw pointer to a nexusChannel. Note that the memory is not
     /// allocated by us.
     pub(crate) fn from_raw<'a>(n: *mut c_void) -> &'a mut Self {
@@ -284,3 +1138,22 @@ impl NexusChannel {
         unsafe { &mut *self.inner }
     }
 }
+
+/// number of reconfigurations which are still to fail, by nexus
+#[cfg(feature = "fault-injection")]
+static RECONFIGURE_FAILURES: once_cell::sync::Lazy<
+    std::sync::Mutex<HashMap<String, u32>>,
+> = once_cell::sync::Lazy::new(Default::default);
+
+/// make the next `count` reconfigurations of the IO channels of the nexus
+/// with the given name fail with EIO, before the channel of any core has been
+/// refreshed
+#[cfg(feature = "fault-injection")]
+pub fn inject_reconfigure_failure(nexus: &str, count: u32) {
+    let mut failures = RECONFIGURE_FAILURES.lock().unwrap();
+    if count == 0 {
+        failures.remove(nexus);
+    } else {
+        failures.insert(nexus.to_string(), count);
+    }
+}
@@ -0,0 +1,74 @@
+//! Dumps the IO channels of a nexus for debugging. Every core has its own
+//! channel with the children it reads from and writes to; these are expected
+//! to be the same on all cores, a difference hints at a reconfiguration which
+//! did not reach all channels. The dump visits the channel of each core on
+//! its own thread, so it is safe to take while IO is flowing.
+
+use std::{cell::RefCell, rc::Rc};
+
+use serde::Serialize;
+
+use crate::{
+    bdev::nexus::{nexus_bdev::Nexus, nexus_child::ChildState},
+    core::{BdevHandle, Cores},
+};
+
+/// A child as seen by the IO channel of a core
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelChild {
+    /// name of the bdev the channel holds a handle to
+    pub bdev: String,
+    /// URI of the child with that bdev, if it is still a child of the nexus
+    pub child: Option<String>,
+    /// current state of that child
+    pub state: Option<ChildState>,
+    /// number of child IOs outstanding on the child on this core
+    pub outstanding: u32,
+}
+
+/// The children in the IO channel of a single core
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelDump {
+    pub core: u32,
+    pub readers: Vec<ChannelChild>,
+    pub writers: Vec<ChannelChild>,
+    /// write-only children that are being rebuilt
+    pub rebuilding: Vec<ChannelChild>,
+}
+
+impl Nexus {
+    /// the children in the IO channel of every core
+    pub async fn dump_channels(&self) -> Vec<ChannelDump> {
+        let dumps = Rc::new(RefCell::new(Vec::new()));
+        let d = Rc::clone(&dumps);
+        let nexus = self.name.clone();
+        self.traverse_io_channels(move |channel| {
+            let child = |hdl: &BdevHandle| {
+                let outstanding =
+                    channel.child_ios_outstanding(&hdl.get_bdev());
+                let bdev = hdl.get_bdev().name();
+                let child = crate::bdev::nexus_lookup(&nexus)
+                    .and_then(|n| n.child_lookup(&bdev));
+                ChannelChild {
+                    child: child.map(|c| c.name.clone()),
+                    state: child.map(|c| c.state()),
+                    bdev,
+                    outstanding,
+                }
+            };
+            d.borrow_mut().push(ChannelDump {
+                core: Cores::current(),
+                readers: channel.readers.iter().map(child).collect(),
+                writers: channel.writers.iter().map(child).collect(),
+                rebuilding: channel
+                    .rebuilding
+                    .iter()
+                    .map(|(hdl, _)| child(hdl))
+                    .collect(),
+            });
+        })
+        .await;
+
+        dumps.replace(Vec::new())
+    }
+}
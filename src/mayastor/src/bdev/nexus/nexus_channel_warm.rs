@@ -0,0 +1,194 @@
+//! Warms the IO channels of a nexus ahead of the IO. A channel is created
+//! lazily by the first IO submitted on a thread, and that IO pays for opening
+//! handles to all children, which may have to set up their own channels
+//! (e.g. connect the queue pairs of remote children) in turn. After a nexus
+//! has been created, or has failed over, that shows as a latency spike on the
+//! first IO on every core.
+//!
+//! `warm_channels` creates the channel of the nexus on every thread up front,
+//! with the same machinery as the first IO would, and validates that its
+//! readers and writers are the open children of the nexus, refreshing it
+//! otherwise. The channels are held until `cool_channels` is called or the
+//! nexus is destroyed, such that they are not torn down when the last other
+//! user on a thread puts them. A reconfiguration refreshes them in place like
+//! all other channels.
+
+use std::ffi::c_void;
+
+use futures::{channel::oneshot, future::join_all};
+use serde::Serialize;
+use snafu::ResultExt;
+use spdk_sys::{
+    spdk_bdev_desc,
+    spdk_bdev_get_io_channel,
+    spdk_for_each_thread,
+    spdk_io_channel,
+    spdk_io_channel_get_thread,
+    spdk_put_io_channel,
+    spdk_thread_send_msg,
+};
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::{Error, IoHandle, Nexus},
+        nexus_channel::NexusChannel,
+    },
+    core::{Cores, Descriptor, Mthread},
+};
+
+/// The IO channel of a nexus on a thread, as found when it was warmed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WarmedChannel {
+    /// name of the thread the channel belongs to
+    pub thread: String,
+    pub core: u32,
+    pub readers: usize,
+    pub writers: usize,
+    /// the channel did not match the open children and was refreshed
+    pub refreshed: bool,
+}
+
+/// the channels held warm and the descriptor they were taken with
+#[derive(Debug, Default)]
+pub(crate) struct WarmChannels {
+    desc: Option<Descriptor>,
+    /// addresses of the channels
+    channels: Vec<usize>,
+}
+
+/// context of warming the channel on every thread
+struct WarmCtx {
+    desc: *mut spdk_bdev_desc,
+    channels: Vec<usize>,
+    warmed: Vec<WarmedChannel>,
+    /// number of threads on which no channel could be created
+    failed: usize,
+    sender: oneshot::Sender<(Vec<usize>, Vec<WarmedChannel>, usize)>,
+}
+
+/// create and validate the channel of the nexus on the current thread
+extern "C" fn warm_thread(arg: *mut c_void) {
+    let ctx = unsafe { &mut *(arg as *mut WarmCtx) };
+    let ch = unsafe { spdk_bdev_get_io_channel(ctx.desc) };
+    if ch.is_null() {
+        ctx.failed += 1;
+        return;
+    }
+
+    let inner = NexusChannel::inner_from_channel(ch);
+    let refreshed = !inner.is_populated();
+    if refreshed {
+        inner.refresh();
+    }
+    ctx.warmed.push(WarmedChannel {
+        thread: Mthread::current()
+            .map(|t| t.name().to_string())
+            .unwrap_or_default(),
+        core: Cores::current(),
+        readers: inner.readers.len(),
+        writers: inner.writers.len(),
+        refreshed,
+    });
+    ctx.channels.push(ch as usize);
+}
+
+/// invoked once the channel has been warmed on all threads
+extern "C" fn warm_completed(arg: *mut c_void) {
+    let ctx = unsafe { Box::from_raw(arg as *mut WarmCtx) };
+    let WarmCtx {
+        channels,
+        warmed,
+        failed,
+        sender,
+        ..
+    } = *ctx;
+    let _ = sender.send((channels, warmed, failed));
+}
+
+/// put a channel on the thread it belongs to
+extern "C" fn put_channel(arg: *mut c_void) {
+    let (ch, sender) =
+        *unsafe { Box::from_raw(arg as *mut (usize, oneshot::Sender<()>)) };
+    unsafe { spdk_put_io_channel(ch as *mut spdk_io_channel) };
+    let _ = sender.send(());
+}
+
+/// put the channels on their threads and wait for it
+async fn put_channels(channels: Vec<usize>) {
+    let puts = channels.into_iter().map(|ch| {
+        let (s, r) = oneshot::channel::<()>();
+        unsafe {
+            spdk_thread_send_msg(
+                spdk_io_channel_get_thread(ch as *mut spdk_io_channel),
+                Some(put_channel),
+                Box::into_raw(Box::new((ch, s))).cast(),
+            );
+        }
+        r
+    });
+    join_all(puts).await;
+}
+
+impl Nexus {
+    /// Create the IO channel of the nexus on every thread and hold it, such
+    /// that the first IO on a thread finds it populated. Channels held from
+    /// a previous call are released first. Returns the channels as found.
+    pub async fn warm_channels(&self) -> Result<Vec<WarmedChannel>, Error> {
+        self.cool_channels().await;
+
+        let desc = self.bdev.open(false).context(IoHandle {
+            name: self.name.clone(),
+        })?;
+        let (s, r) = oneshot::channel();
+        let ctx = Box::new(WarmCtx {
+            desc: desc.as_ptr(),
+            channels: Vec::new(),
+            warmed: Vec::new(),
+            failed: 0,
+            sender: s,
+        });
+        unsafe {
+            spdk_for_each_thread(
+                Some(warm_thread),
+                Box::into_raw(ctx).cast(),
+                Some(warm_completed),
+            );
+        }
+        let (channels, warmed, failed) =
+            r.await.expect("warm channels sender gone");
+
+        if failed > 0 {
+            put_channels(channels).await;
+            return Err(Error::WarmChannels {
+                failed,
+                name: self.name.clone(),
+            });
+        }
+
+        info!(
+            "{}: warmed IO channels on {} threads, {} refreshed",
+            self.name,
+            warmed.len(),
+            warmed.iter().filter(|w| w.refreshed).count()
+        );
+        *self.warm_channels.lock().unwrap() = WarmChannels {
+            desc: Some(desc),
+            channels,
+        };
+        Ok(warmed)
+    }
+
+    /// release the IO channels held warm, a channel is destroyed once it is
+    /// not used on its thread anymore
+    pub async fn cool_channels(&self) {
+        let warm = std::mem::take(&mut *self.warm_channels.lock().unwrap());
+        if !warm.channels.is_empty() {
+            debug!(
+                "{}: releasing {} warm IO channels",
+                self.name,
+                warm.channels.len()
+            );
+        }
+        put_channels(warm.channels).await;
+    }
+}
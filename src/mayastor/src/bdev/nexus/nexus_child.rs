@@ -1,7 +1,12 @@
 use std::{
     convert::TryFrom,
     fmt::{Display, Formatter},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+        Mutex,
+    },
+    time::Duration,
 };
 
 use nix::errno::Errno;
@@ -12,14 +17,27 @@ use crate::{
     bdev::{
         nexus::{
             instances,
-            nexus_channel::DrEvent,
+            nexus_channel::{ChildIoFlags, DrEvent},
             nexus_child::ChildState::Faulted,
+            nexus_child_history::ChildHistory,
+            nexus_child_limit::{BackgroundLimiter, BackgroundStats},
+            nexus_child_record::{ChildRecord, TrackRecord},
             nexus_child_status_config::ChildStatusConfig,
+            nexus_event::{self, NexusEvent},
+            nexus_read_weight::ReadWeighting,
         },
         nexus_lookup,
         VerboseError,
     },
-    core::{Bdev, BdevHandle, CoreError, Descriptor, Reactor, Reactors},
+    core::{
+        Bdev,
+        BdevHandle,
+        CoreError,
+        Descriptor,
+        IoType,
+        Reactor,
+        Reactors,
+    },
     nexus_uri::{bdev_create, bdev_destroy, NexusBdevError},
     rebuild::{ClientOperations, RebuildJob},
 };
@@ -61,6 +79,15 @@ pub enum ChildError {
     },
 }
 
+/// Locality of a child relative to the nexus
+#[derive(Debug, Serialize, PartialEq, Deserialize, Eq, Copy, Clone)]
+pub enum ChildLocality {
+    /// the child is on the same node as the nexus
+    Local,
+    /// the child is on another node
+    Remote,
+}
+
 #[derive(Debug, Serialize, PartialEq, Deserialize, Eq, Copy, Clone)]
 pub enum Reason {
     /// no particular reason for the child to be in this state
@@ -76,6 +103,10 @@ pub enum Reason {
     IoError,
     /// the child has been explicitly faulted due to a rpc call
     Rpc,
+    /// the child returned data which failed verification
+    DataCorruption,
+    /// the child did not complete IO within the IO timeout of the nexus
+    IoTimeout,
 }
 
 impl Display for Reason {
@@ -91,6 +122,12 @@ impl Display for Reason {
             }
             Self::IoError => write!(f, "The child had too many I/O errors"),
             Self::Rpc => write!(f, "The child is faulted due to a rpc call"),
+            Self::DataCorruption => {
+                write!(f, "The child returned corrupted data")
+            }
+            Self::IoTimeout => {
+                write!(f, "The child did not complete I/O in time")
+            }
         }
     }
 }
@@ -109,6 +146,12 @@ pub enum ChildState {
     Closed,
     /// the child is faulted
     Faulted(Reason),
+    /// the child is known to be part of the nexus but was not reachable when
+    /// the nexus was assembled, it has no bdev and takes no part in the IO
+    Missing,
+    /// the child was retired but its bdev could not be destroyed, it takes
+    /// no part in the IO and needs to be removed by an operator
+    DestroyFailed,
 }
 
 impl Display for ChildState {
@@ -120,6 +163,8 @@ impl Display for ChildState {
             Self::Open => write!(f, "Child is open"),
             Self::Destroying => write!(f, "Child is being destroyed"),
             Self::Closed => write!(f, "Closed"),
+            Self::Missing => write!(f, "Child is missing"),
+            Self::DestroyFailed => write!(f, "Child failed to be destroyed"),
         }
     }
 }
@@ -144,6 +189,54 @@ pub struct NexusChild {
     pub prev_state: AtomicCell<ChildState>,
     #[serde(skip_serializing)]
     remove_channel: (mpsc::Sender<()>, mpsc::Receiver<()>),
+    /// history of the faults and rebuilds of the child
+    #[serde(skip_serializing)]
+    history: Mutex<ChildHistory>,
+    /// a retire of the child has been dispatched, and the child has not been
+    /// opened since
+    #[serde(skip_serializing)]
+    retiring: AtomicBool,
+    /// locality of the child as set when it was added, derived from its
+    /// bdev otherwise
+    #[serde(skip_serializing)]
+    pub(crate) locality: Option<ChildLocality>,
+    /// number of reads which failed on the child since it was last opened
+    #[serde(skip_serializing)]
+    read_errors: AtomicU64,
+    /// number of writes and other data modifying IOs which failed on the
+    /// child since it was last opened
+    #[serde(skip_serializing)]
+    write_errors: AtomicU64,
+    /// uptime of the child and the IO it completed since its last error
+    #[serde(skip_serializing)]
+    track_record: TrackRecord,
+    /// the limit of the background IO issued to the child and the IO issued
+    #[serde(skip_serializing)]
+    pub(crate) background: BackgroundLimiter,
+    /// the weight by which reads are spread over the children
+    #[serde(skip_serializing)]
+    pub(crate) read_weight: ReadWeighting,
+    /// number of retries it took to open the child when it was added
+    #[serde(skip_serializing)]
+    pub(crate) open_retries: u32,
+    /// offset of the data partition of the child in blocks, as found in its
+    /// label. Children created with reserved regions of different sizes have
+    /// their data at different offsets.
+    #[serde(skip_serializing)]
+    pub(crate) data_offset: Option<u64>,
+    /// the child was assembled as a placeholder and has not been rebuilt
+    /// since, so only the blocks written while it was missing need to be
+    /// copied to it
+    #[serde(skip_serializing)]
+    pub(crate) placeholder: bool,
+    /// the write generation of the nexus when the child was last closed, see
+    /// `Nexus::online_child`
+    #[serde(skip_serializing)]
+    closed_generation: AtomicCell<Option<u64>>,
+    /// whether the child serves reads and receives writes, applied by every
+    /// IO channel of the nexus
+    #[serde(skip_serializing)]
+    io_flags: AtomicCell<ChildIoFlags>,
 }
 
 impl Display for NexusChild {
@@ -168,6 +261,24 @@ impl NexusChild {
     pub(crate) fn set_state(&self, state: ChildState) {
         let prev_state = self.state.swap(state);
         self.prev_state.store(prev_state);
+        if state == ChildState::Open {
+            self.retiring.store(false, Ordering::SeqCst);
+            self.read_errors.store(0, Ordering::Relaxed);
+            self.write_errors.store(0, Ordering::Relaxed);
+        }
+        if state == ChildState::Open && prev_state != ChildState::Open {
+            self.track_record.opened();
+            self.closed_generation.store(None);
+        } else if state != ChildState::Open && prev_state == ChildState::Open {
+            self.track_record.closed();
+            self.closed_generation.store(
+                nexus_lookup(&self.parent)
+                    .map(|n| n.metrics.write_generation()),
+            );
+        }
+        if let ChildState::Faulted(_) = state {
+            self.track_record.io_failed();
+        }
         trace!(
             "{}: child {}: state change from {} to {}",
             self.parent,
@@ -175,6 +286,70 @@ impl NexusChild {
             prev_state.to_string(),
             state.to_string(),
         );
+        if state != prev_state {
+            if let Some(nexus) = nexus_lookup(&self.parent) {
+                nexus_event::emit(NexusEvent::ChildStateChanged {
+                    nexus: self.parent.clone(),
+                    child: self.name.clone(),
+                    previous: prev_state,
+                    state,
+                    health: nexus.health(),
+                });
+            }
+        }
+    }
+
+    /// mark the child as being retired, returns false if a retire has been
+    /// dispatched already since the child was last opened
+    pub(crate) fn start_retire(&self) -> bool {
+        self.retiring
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    /// account an IO of the given type which failed on the child, returns the
+    /// number of failures of that kind since the child was last opened
+    pub(crate) fn io_failed(&self, io_type: IoType) -> u64 {
+        let errors = if io_type == IoType::Read {
+            &self.read_errors
+        } else {
+            &self.write_errors
+        };
+        self.track_record.io_failed();
+        errors.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// the write generation of the nexus when the child was last closed, if
+    /// it has not been opened since
+    pub(crate) fn closed_generation(&self) -> Option<u64> {
+        self.closed_generation.load()
+    }
+
+    /// number of reads which failed since the child was last opened
+    pub fn read_errors(&self) -> u64 {
+        self.read_errors.load(Ordering::Relaxed)
+    }
+
+    /// number of writes and other data modifying IOs which failed since the
+    /// child was last opened
+    pub fn write_errors(&self) -> u64 {
+        self.write_errors.load(Ordering::Relaxed)
+    }
+
+    /// account an IO which completed successfully on the child
+    pub(crate) fn io_completed(&self) {
+        self.track_record.io_completed();
+    }
+
+    /// the time the child has been open and the IO it completed since its
+    /// last error
+    pub fn track_record(&self) -> ChildRecord {
+        self.track_record.record()
+    }
+
+    /// the background IO issued to the child and its limit
+    pub fn background(&self) -> BackgroundStats {
+        self.background.stats()
     }
 
     /// Open the child in RW mode and claim the device to be ours. If the child
@@ -261,6 +436,7 @@ impl NexusChild {
                 self.set_state(ChildState::Faulted(reason));
             }
             _ => {
+                self.record_fault(reason);
                 if let Err(e) = self.close().await {
                     error!(
                         "{}: child {} failed to close with error {}",
@@ -288,7 +464,7 @@ impl NexusChild {
         NexusChild::save_state_change();
     }
 
-    /// Online a previously offlined child.
+    /// Online a previously offlined or a missing child.
     /// The child is set out-of-sync so that it will be rebuilt.
     /// TODO: channels need to be updated when bdevs are opened
     pub(crate) async fn online(
@@ -297,8 +473,9 @@ impl NexusChild {
     ) -> Result<String, ChildError> {
         // Only online a child if it was previously set offline. Check for a
         // "Closed" state as that is what offlining a child will set it to.
+        // A missing child has no bdev yet either.
         match self.state.load() {
-            ChildState::Closed => {
+            ChildState::Closed | ChildState::Missing => {
                 // Re-create the bdev as it will have been previously destroyed.
                 let name =
                     bdev_create(&self.name).await.context(ChildBdevCreate {
@@ -327,6 +504,17 @@ impl NexusChild {
         self.state.load()
     }
 
+    /// the IO flags of the child
+    pub(crate) fn io_flags(&self) -> ChildIoFlags {
+        self.io_flags.load()
+    }
+
+    /// set the IO flags of the child, which the IO channels of the nexus
+    /// apply once they are refreshed
+    pub(crate) fn set_io_flags(&self, flags: ChildIoFlags) {
+        self.io_flags.store(flags);
+    }
+
     pub(crate) fn rebuilding(&self) -> bool {
         match RebuildJob::lookup(&self.name) {
             Ok(_) => self.state() == ChildState::Faulted(Reason::OutOfSync),
@@ -412,10 +600,15 @@ impl NexusChild {
             }
         }
 
-        // Remove the child from the I/O path. If we had an IO error the bdev,
-        // the channels were already reconfigured so we don't have to do
-        // that twice.
-        if state != ChildState::Faulted(Reason::IoError) {
+        // Remove the child from the I/O path. If the child was retired due to
+        // an IO error or corrupted data, the channels were already
+        // reconfigured so we don't have to do that twice.
+        if !matches!(
+            state,
+            ChildState::Faulted(Reason::IoError)
+                | ChildState::Faulted(Reason::DataCorruption)
+                | ChildState::Faulted(Reason::IoTimeout)
+        ) {
             let nexus_name = self.parent.clone();
             Reactor::block_on(async move {
                 match nexus_lookup(&nexus_name) {
@@ -459,14 +652,72 @@ impl NexusChild {
             state: AtomicCell::new(ChildState::Init),
             prev_state: AtomicCell::new(ChildState::Init),
             remove_channel: mpsc::channel(0),
+            history: Mutex::new(ChildHistory::default()),
+            retiring: AtomicBool::new(false),
+            locality: None,
+            read_errors: AtomicU64::new(0),
+            write_errors: AtomicU64::new(0),
+            track_record: TrackRecord::default(),
+            background: BackgroundLimiter::default(),
+            read_weight: ReadWeighting::default(),
+            open_retries: 0,
+            data_offset: None,
+            placeholder: false,
+            closed_generation: AtomicCell::new(None),
+            io_flags: AtomicCell::new(ChildIoFlags::default()),
         }
     }
 
+    /// create a placeholder for a child which is not reachable yet
+    pub(crate) fn missing(name: String, parent: String) -> Self {
+        Self {
+            state: AtomicCell::new(ChildState::Missing),
+            placeholder: true,
+            ..Self::new(name, parent, None)
+        }
+    }
+
+    /// offset of the data partition of the child in blocks, known once the
+    /// label of the child has been validated
+    pub fn data_offset(&self) -> Option<u64> {
+        self.data_offset
+    }
+
+    /// number of retries it took to open the child when it was added to the
+    /// nexus, see `OpenRetry`
+    pub fn open_retries(&self) -> u32 {
+        self.open_retries
+    }
+
+    /// returns a copy of the fault and rebuild history of the child
+    pub fn history(&self) -> ChildHistory {
+        self.history.lock().unwrap().clone()
+    }
+
+    /// record a fault of the child in its history
+    pub(crate) fn record_fault(&self, reason: Reason) {
+        self.history.lock().unwrap().record_fault(reason);
+    }
+
+    /// record a successful rebuild of the child in its history
+    pub(crate) fn record_rebuild(&self, duration: Duration, bytes: u64) {
+        self.history.lock().unwrap().record_rebuild(duration, bytes);
+    }
+
     /// destroy the child bdev
     pub(crate) async fn destroy(&self) -> Result<(), NexusBdevError> {
         trace!("destroying child {:?}", self);
         if self.bdev.is_some() {
             self.set_state(ChildState::Destroying);
+            #[cfg(feature = "fault-injection")]
+            {
+                if take_destroy_failure(&self.name) {
+                    return Err(NexusBdevError::DestroyBdev {
+                        source: Errno::EIO,
+                        name: self.name.clone(),
+                    });
+                }
+            }
             bdev_destroy(&self.name).await
         } else {
             warn!("Destroy child without bdev");
@@ -502,7 +753,7 @@ impl NexusChild {
     }
 
     /// Return the rebuild job which is rebuilding this child, if rebuilding
-    fn get_rebuild_job(&self) -> Option<&mut RebuildJob> {
+    pub(crate) fn get_rebuild_job(&self) -> Option<&mut RebuildJob> {
         let job = RebuildJob::lookup(&self.name).ok()?;
         assert_eq!(job.nexus, self.parent);
         Some(job)
@@ -527,6 +778,16 @@ impl NexusChild {
         }
     }
 
+    /// the locality of the child, as set when it was added or as derived
+    /// from its bdev when it was not
+    pub fn locality(&self) -> ChildLocality {
+        match self.locality {
+            Some(locality) => locality,
+            None if self.is_local() == Some(true) => ChildLocality::Local,
+            None => ChildLocality::Remote,
+        }
+    }
+
     pub fn handle(&self) -> Result<BdevHandle, CoreError> {
         if let Some(desc) = self.desc.as_ref() {
             BdevHandle::try_from(Arc::clone(desc))
@@ -539,6 +800,46 @@ impl NexusChild {
     }
 }
 
+/// number of destroys which are still to fail, by child name
+#[cfg(feature = "fault-injection")]
+static DESTROY_FAILURES: once_cell::sync::Lazy<
+    Mutex<std::collections::HashMap<String, u32>>,
+> = once_cell::sync::Lazy::new(Default::default);
+
+/// make the next destroy of the child with the given name fail with EIO,
+/// after the child has been marked as being destroyed
+#[cfg(feature = "fault-injection")]
+pub fn inject_destroy_failure(name: &str) {
+    inject_destroy_failures(name, 1);
+}
+
+/// make the next `count` destroys of the child with the given name fail
+#[cfg(feature = "fault-injection")]
+pub fn inject_destroy_failures(name: &str, count: u32) {
+    let mut injected = DESTROY_FAILURES.lock().unwrap();
+    if count == 0 {
+        injected.remove(name);
+    } else {
+        injected.insert(name.to_string(), count);
+    }
+}
+
+/// consume an injected destroy failure of the child, if any
+#[cfg(feature = "fault-injection")]
+fn take_destroy_failure(name: &str) -> bool {
+    let mut injected = DESTROY_FAILURES.lock().unwrap();
+    match injected.get_mut(name) {
+        Some(count) => {
+            *count -= 1;
+            if *count == 0 {
+                injected.remove(name);
+            }
+            true
+        }
+        None => false,
+    }
+}
+
 /// Looks up a child based on the underlying bdev name
 pub fn lookup_child_from_bdev(bdev_name: &str) -> Option<&mut NexusChild> {
     for nexus in instances() {
@@ -0,0 +1,73 @@
+//! History of the faults and rebuilds of a nexus child. A child which faults
+//! and is rebuilt over and over again is a candidate for permanent removal,
+//! which the history makes visible. Only the last CHILD_HISTORY_LEN events
+//! are kept, next to the totals.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+
+use crate::bdev::nexus::nexus_child::Reason;
+
+/// number of events kept in the history of a child
+pub const CHILD_HISTORY_LEN: usize = 16;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub enum ChildHistoryEvent {
+    /// the child was faulted
+    Faulted { reason: Reason, time: SystemTime },
+    /// the child was rebuilt successfully
+    Rebuilt {
+        duration: Duration,
+        bytes: u64,
+        time: SystemTime,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChildHistory {
+    /// number of times the child was faulted
+    pub faults: u64,
+    /// reason and time of the last fault
+    pub last_fault: Option<(Reason, SystemTime)>,
+    /// number of successful rebuilds of the child
+    pub rebuilds: u64,
+    /// duration and number of bytes copied of the last rebuild
+    pub last_rebuild: Option<(Duration, u64)>,
+    /// the most recent events, oldest first
+    pub events: VecDeque<ChildHistoryEvent>,
+}
+
+impl ChildHistory {
+    /// record a fault of the child
+    pub(crate) fn record_fault(&mut self, reason: Reason) {
+        let time = SystemTime::now();
+        self.faults += 1;
+        self.last_fault = Some((reason, time));
+        self.push(ChildHistoryEvent::Faulted {
+            reason,
+            time,
+        });
+    }
+
+    /// record a successful rebuild of the child
+    pub(crate) fn record_rebuild(&mut self, duration: Duration, bytes: u64) {
+        self.rebuilds += 1;
+        self.last_rebuild = Some((duration, bytes));
+        self.push(ChildHistoryEvent::Rebuilt {
+            duration,
+            bytes,
+            time: SystemTime::now(),
+        });
+    }
+
+    fn push(&mut self, event: ChildHistoryEvent) {
+        if self.events.len() == CHILD_HISTORY_LEN {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+}
@@ -0,0 +1,195 @@
+//! IO statistics of the children of a nexus: the number of reads and writes
+//! every child completed and their latency percentiles, such that the slow
+//! replica of a nexus can be told apart without attaching a profiler.
+//!
+//! The statistics are kept in the IO channel of every core and only that core
+//! updates them, so the completion path takes neither a lock nor an atomic.
+//! They are summed over the channels of all cores when queried, and they are
+//! the single source of the latency of the children: every other latency
+//! figure of a child is derived from them. The latency of a child IO is
+//! measured from the tick count at which it was submitted to the child.
+//!
+//! A channel keeps the statistics of a child at the index of the child in the
+//! nexus, along with the address of the bdev of the child such that the
+//! statistics of a child are never attributed to another one which took its
+//! index. They are moved along with the child when the children of the nexus
+//! change, and a channel keeps them for as long as it holds a handle to the
+//! child, so they cover the IO since the child was last opened.
+
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use serde::Serialize;
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::Nexus,
+        nexus_child_latency::{bucket_of, LatencyPercentiles},
+    },
+    core::{Bdev, IoType},
+};
+
+/// the IO completed by a child since it was last opened
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChildIoStats {
+    /// name of the child
+    pub child: String,
+    /// number of reads completed successfully by the child
+    pub reads: u64,
+    /// number of writes completed successfully by the child
+    pub writes: u64,
+    pub read_latency: LatencyPercentiles,
+    pub write_latency: LatencyPercentiles,
+    /// latency of all IO completed successfully by the child, which includes
+    /// the IOs other than reads and writes
+    pub latency: LatencyPercentiles,
+}
+
+/// the IOs of a child counted per bucket of their latency
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ChildCounts {
+    pub(crate) reads: Vec<u64>,
+    pub(crate) writes: Vec<u64>,
+    /// the IOs other than reads and writes, such as flushes and unmaps
+    pub(crate) other: Vec<u64>,
+}
+
+/// add the IOs counted per bucket to the total
+fn sum(total: &mut Vec<u64>, counts: &[u64]) {
+    if total.len() < counts.len() {
+        total.resize(counts.len(), 0);
+    }
+    total.iter_mut().zip(counts).for_each(|(t, c)| *t += c);
+}
+
+impl ChildCounts {
+    fn add(&mut self, other: &ChildCounts) {
+        sum(&mut self.reads, &other.reads);
+        sum(&mut self.writes, &other.writes);
+        sum(&mut self.other, &other.other);
+    }
+
+    /// the IOs of all types per bucket of their latency
+    pub(crate) fn all(&self) -> Vec<u64> {
+        let mut all = self.reads.clone();
+        sum(&mut all, &self.writes);
+        sum(&mut all, &self.other);
+        all
+    }
+}
+
+/// the IO statistics of the child at an index of the nexus
+#[derive(Debug, Default)]
+struct ChildSlot {
+    /// address of the bdev of the child, 0 if the channel holds no handle
+    /// to it
+    bdev: usize,
+    counts: ChildCounts,
+}
+
+/// the IO statistics of the children kept by the channel of a core, by the
+/// index of the child in the nexus
+#[derive(Debug, Default)]
+pub(crate) struct ChannelIoStats {
+    children: Vec<ChildSlot>,
+}
+
+impl ChannelIoStats {
+    /// account a child IO which completed successfully with the given
+    /// latency on the child at the given index
+    pub(crate) fn record(
+        &mut self,
+        child: usize,
+        bdev: &Bdev,
+        io_type: IoType,
+        latency: Duration,
+    ) {
+        if self.children.len() <= child {
+            self.children.resize_with(child + 1, ChildSlot::default);
+        }
+        let slot = &mut self.children[child];
+        let bdev = bdev.as_ptr() as usize;
+        if slot.bdev != bdev {
+            *slot = ChildSlot {
+                bdev,
+                ..Default::default()
+            };
+        }
+        let counts = match io_type {
+            IoType::Read => &mut slot.counts.reads,
+            IoType::Write => &mut slot.counts.writes,
+            _ => &mut slot.counts.other,
+        };
+        let i = bucket_of(latency);
+        if counts.len() <= i {
+            counts.resize(i + 1, 0);
+        }
+        counts[i] += 1;
+    }
+
+    /// move the statistics of every child to its index in the nexus, given
+    /// the address of the bdev of the child at every index or 0 if the
+    /// channel holds no handle to it, forgetting the statistics of the
+    /// children the channel holds no handle to anymore
+    pub(crate) fn remap(&mut self, bdevs: &[usize]) {
+        let mut previous = std::mem::take(&mut self.children);
+        self.children = bdevs
+            .iter()
+            .map(|bdev| {
+                previous
+                    .iter_mut()
+                    .find(|s| *bdev != 0 && s.bdev == *bdev)
+                    .map(std::mem::take)
+                    .unwrap_or_default()
+            })
+            .collect();
+    }
+}
+
+impl Nexus {
+    /// the IOs every child completed since it was last opened per bucket of
+    /// their latency, summed over all cores, along with the name of the child
+    pub(crate) async fn child_io_counts(&self) -> Vec<(String, ChildCounts)> {
+        let names = self
+            .children
+            .iter()
+            .map(|c| c.name.clone())
+            .collect::<Vec<_>>();
+        let bdevs = self
+            .children
+            .iter()
+            .map(|c| c.bdev.as_ref().map_or(0, |b| b.as_ptr() as usize))
+            .collect::<Vec<_>>();
+        let totals =
+            Rc::new(RefCell::new(vec![ChildCounts::default(); bdevs.len()]));
+        let t = Rc::clone(&totals);
+        self.traverse_io_channels(move |channel| {
+            let mut t = t.borrow_mut();
+            for ((total, bdev), slot) in
+                t.iter_mut().zip(&bdevs).zip(&channel.io_stats.children)
+            {
+                if *bdev != 0 && slot.bdev == *bdev {
+                    total.add(&slot.counts);
+                }
+            }
+        })
+        .await;
+        names.into_iter().zip(totals.replace(Vec::new())).collect()
+    }
+
+    /// the number of reads and writes every child completed since it was
+    /// last opened and their latency percentiles, summed over all cores
+    pub async fn child_io_stats(&self) -> Vec<ChildIoStats> {
+        self.child_io_counts()
+            .await
+            .into_iter()
+            .map(|(child, counts)| ChildIoStats {
+                child,
+                reads: counts.reads.iter().sum(),
+                writes: counts.writes.iter().sum(),
+                read_latency: LatencyPercentiles::from_counts(&counts.reads),
+                write_latency: LatencyPercentiles::from_counts(&counts.writes),
+                latency: LatencyPercentiles::from_counts(&counts.all()),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,82 @@
+//! Latency percentiles of a nexus child. The completion of every child IO is
+//! counted in a bucket of its latency, from which the percentiles are derived,
+//! such that a child with a good average but a bad tail stands out. The
+//! buckets are spaced logarithmically with four buckets per power of two of
+//! microseconds, so a percentile is exact to within 25%. The buckets are kept
+//! per core with the other IO statistics of the children, see
+//! `nexus_child_io_stats`, and summed over all cores when queried.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// number of buckets per power of two
+const SUB_BUCKETS: u64 = 4;
+
+/// number of buckets, which covers latencies up to about 30 seconds
+const BUCKETS: usize = 96;
+
+/// latency percentiles of the IO completed by a child
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencyPercentiles {
+    /// number of IOs the percentiles are taken over
+    pub samples: u64,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+/// index of the bucket of a latency
+pub(crate) fn bucket_of(latency: Duration) -> usize {
+    bucket(latency.as_micros() as u64).min(BUCKETS - 1)
+}
+
+impl LatencyPercentiles {
+    /// the percentiles of the IO counted per bucket of a histogram, each
+    /// reported as the upper bound of the bucket it falls in
+    pub(crate) fn from_counts(counts: &[u64]) -> Self {
+        let samples = counts.iter().sum::<u64>();
+        let percentile = |p: u64| {
+            if samples == 0 {
+                return Duration::default();
+            }
+            let rank = (samples * p + 99) / 100;
+            let mut seen = 0;
+            let i = counts
+                .iter()
+                .position(|c| {
+                    seen += c;
+                    seen >= rank
+                })
+                .unwrap_or(BUCKETS - 1);
+            Duration::from_micros(upper_bound(i))
+        };
+
+        Self {
+            samples,
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+        }
+    }
+}
+
+/// index of the bucket of a latency in microseconds
+fn bucket(us: u64) -> usize {
+    if us < SUB_BUCKETS {
+        return us as usize;
+    }
+    let power = 63 - u64::from(us.leading_zeros());
+    let sub = (us >> (power - 2)) & (SUB_BUCKETS - 1);
+    ((power - 1) * SUB_BUCKETS + sub) as usize
+}
+
+/// the latency in microseconds up to which IO falls in the bucket
+fn upper_bound(i: usize) -> u64 {
+    let i = i as u64;
+    if i < SUB_BUCKETS {
+        return i + 1;
+    }
+    let power = i / SUB_BUCKETS + 1;
+    (SUB_BUCKETS + i % SUB_BUCKETS + 1) << (power - 2)
+}
@@ -0,0 +1,171 @@
+//! Rate limit of the background IO issued to a nexus child, which is the IO
+//! of a rebuild copying to or from the child and of a consistency check
+//! reading from it. Frontend IO is never limited. Every background IO reserves
+//! the next slot of the child, the length of which follows from the size of
+//! the IO and the limit, and is issued once its slot has passed, so the
+//! background IO issued to a child within any interval never exceeds the
+//! limit, however many rebuild tasks issue it concurrently.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::bdev::nexus::{
+    nexus_bdev::{Error, Nexus},
+    nexus_bdev_verify::throttle,
+};
+
+/// interval over which the rate of background IO is measured
+const RATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// the rate at which background IO may be issued to a child, either bound
+/// may be left unset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct BackgroundLimit {
+    pub bytes_per_sec: Option<u64>,
+    pub iops: Option<u64>,
+}
+
+impl BackgroundLimit {
+    /// the time the given number of bytes takes up at the limit
+    fn cost(&self, bytes: u64) -> Duration {
+        let by_bytes = self
+            .bytes_per_sec
+            .map(|r| Duration::from_nanos(bytes * 1_000_000_000 / r))
+            .unwrap_or_default();
+        let by_ios = self
+            .iops
+            .map(|r| Duration::from_nanos(1_000_000_000 / r))
+            .unwrap_or_default();
+        std::cmp::max(by_bytes, by_ios)
+    }
+}
+
+/// the background IO issued to a child
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BackgroundStats {
+    pub limit: Option<BackgroundLimit>,
+    /// number of bytes issued since the child was added
+    pub bytes: u64,
+    /// number of IOs issued since the child was added
+    pub ios: u64,
+    /// bytes per second issued over the last completed interval of a second
+    pub bytes_per_sec: u64,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    stats: BackgroundStats,
+    /// end of the last slot reserved
+    next: Option<Instant>,
+    /// start of the interval the rate is being measured over
+    interval: Option<Instant>,
+    /// bytes issued since the start of the interval
+    interval_bytes: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct BackgroundLimiter {
+    state: Mutex<LimiterState>,
+}
+
+impl BackgroundLimiter {
+    pub(crate) fn set_limit(&self, limit: Option<BackgroundLimit>) {
+        let mut state = self.state.lock().unwrap();
+        state.stats.limit = limit;
+        state.next = None;
+    }
+
+    /// reserve the slot of a background IO of the given size, returns how
+    /// long it must wait before it is issued
+    pub(crate) fn reserve(&self, bytes: u64) -> Duration {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let limit = match state.stats.limit {
+            Some(limit) => limit,
+            None => return Duration::default(),
+        };
+        let next =
+            std::cmp::max(now, state.next.unwrap_or(now)) + limit.cost(bytes);
+        state.next = Some(next);
+        next - now
+    }
+
+    /// account a background IO of the given size which is being issued
+    pub(crate) fn issued(&self, bytes: u64) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+
+        state.stats.bytes += bytes;
+        state.stats.ios += 1;
+        let start = *state.interval.get_or_insert(now);
+        let elapsed = now - start;
+        if elapsed >= RATE_INTERVAL {
+            state.stats.bytes_per_sec = (state.interval_bytes as u128
+                * 1_000_000
+                / elapsed.as_micros())
+                as u64;
+            state.interval = Some(now);
+            state.interval_bytes = 0;
+        }
+        state.interval_bytes += bytes;
+    }
+
+    pub(crate) fn stats(&self) -> BackgroundStats {
+        self.state.lock().unwrap().stats.clone()
+    }
+}
+
+impl Nexus {
+    /// Limit the rate of the background IO issued to a child, or remove the
+    /// limit with None. A new limit applies to the background IO issued from
+    /// then on, a bound of zero is invalid.
+    pub fn set_child_background_limit(
+        &self,
+        name: &str,
+        limit: Option<BackgroundLimit>,
+    ) -> Result<(), Error> {
+        if let Some(l) = limit {
+            if l.bytes_per_sec == Some(0) || l.iops == Some(0) {
+                return Err(Error::InvalidBackgroundLimit {
+                    limit: l,
+                    child: name.to_owned(),
+                    name: self.name.clone(),
+                });
+            }
+        }
+        let child =
+            self.children
+                .iter()
+                .find(|c| c.name == name)
+                .ok_or_else(|| Error::ChildNotFound {
+                    child: name.to_owned(),
+                    name: self.name.clone(),
+                })?;
+
+        info!(
+            "{}: background IO limit of child {} set to {:?}",
+            self.name, name, limit
+        );
+        child.background.set_limit(limit);
+        Ok(())
+    }
+
+    /// wait until a background IO of the given size may be issued to the
+    /// child within its limit, and account it
+    pub(crate) async fn background_io(&self, name: &str, bytes: u64) {
+        let child = match self.children.iter().find(|c| c.name == name) {
+            Some(child) => child,
+            None => return,
+        };
+        let delay = child.background.reserve(bytes);
+        if delay > Duration::default() {
+            throttle(delay).await;
+        }
+        child.background.issued(bytes);
+        self.metrics.background_io(bytes);
+    }
+}
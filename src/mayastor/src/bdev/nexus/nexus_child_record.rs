@@ -0,0 +1,103 @@
+//! Track record of a nexus child: the time it has spent open and the number
+//! of IOs it completed since its last error, which tell a child that served
+//! IO without fault for a long time apart from one that just rejoined.
+//!
+//! The IOs are counted per core, each core incrementing a counter of its own
+//! on a cache line of its own, such that the completion path does not contend
+//! on a shared counter. The counters are summed when queried; an error records
+//! the sum at that moment, from which the IOs completed since are counted.
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use crossbeam::{atomic::AtomicCell, utils::CachePadded};
+use serde::Serialize;
+
+use crate::core::Cores;
+
+/// number of counters the completed IOs are spread over, cores beyond it
+/// share a counter
+const CORE_SLOTS: usize = 64;
+
+/// the track record of a child
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ChildRecord {
+    /// cumulative time the child has been open since it was added
+    pub uptime: Duration,
+    /// number of IOs completed successfully since the last IO which failed
+    /// on the child, or since the child was faulted
+    pub error_free_ios: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct TrackRecord {
+    /// IOs completed per core
+    completed: Box<[CachePadded<AtomicU64>]>,
+    /// sum of the IOs completed at the last error
+    last_error: AtomicU64,
+    /// time the child was open for before it was last opened
+    uptime: AtomicCell<Duration>,
+    /// time the child was last opened, if it is open
+    open_since: AtomicCell<Option<Instant>>,
+}
+
+impl Default for TrackRecord {
+    fn default() -> Self {
+        Self {
+            completed: (0 .. CORE_SLOTS)
+                .map(|_| CachePadded::new(AtomicU64::new(0)))
+                .collect(),
+            last_error: AtomicU64::new(0),
+            uptime: AtomicCell::new(Duration::default()),
+            open_since: AtomicCell::new(None),
+        }
+    }
+}
+
+impl TrackRecord {
+    /// account an IO completed successfully on the current core
+    pub(crate) fn io_completed(&self) {
+        self.completed[Cores::current() as usize % CORE_SLOTS]
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account an IO which failed, or a fault of the child
+    pub(crate) fn io_failed(&self) {
+        self.last_error.store(self.completed(), Ordering::Relaxed);
+    }
+
+    /// account the child being opened
+    pub(crate) fn opened(&self) {
+        self.open_since.store(Some(Instant::now()));
+    }
+
+    /// account the child leaving the open state
+    pub(crate) fn closed(&self) {
+        if let Some(since) = self.open_since.swap(None) {
+            self.uptime.store(self.uptime.load() + since.elapsed());
+        }
+    }
+
+    fn completed(&self) -> u64 {
+        self.completed
+            .iter()
+            .map(|c| c.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    pub(crate) fn record(&self) -> ChildRecord {
+        let open = self
+            .open_since
+            .load()
+            .map(|since| since.elapsed())
+            .unwrap_or_default();
+        ChildRecord {
+            uptime: self.uptime.load() + open,
+            error_free_ios: self
+                .completed()
+                .saturating_sub(self.last_error.load(Ordering::Relaxed)),
+        }
+    }
+}
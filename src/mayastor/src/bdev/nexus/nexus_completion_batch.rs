@@ -0,0 +1,35 @@
+//! Batched completion of child IOs, see `CompletionBatching`. Every channel
+//! collects the child IOs which complete successfully on its core, and
+//! processes them together once the batch is full, a child IO fails, or at
+//! the latest once the reactor has handled the completions polled along with
+//! them. The children are then accounted once per batch rather than once per
+//! child IO. Failed child IOs are never batched, and the batch collected
+//! before them is processed first, such that the IOs see their child IOs
+//! complete in the same order as without batching.
+
+use crate::{bdev::nexus::nexus_io::NexusBio, core::Bio};
+
+/// the successful child IOs collected on a channel, along with the IO they
+/// belong to
+#[derive(Debug, Default)]
+pub(crate) struct CompletionBatch {
+    completions: Vec<(NexusBio, Bio)>,
+}
+
+impl CompletionBatch {
+    /// add a child IO to the batch, returns true if it starts the batch
+    pub(crate) fn push(&mut self, io: NexusBio, child_io: Bio) -> bool {
+        self.completions.push((io, child_io));
+        self.completions.len() == 1
+    }
+
+    /// number of child IOs in the batch
+    pub(crate) fn len(&self) -> usize {
+        self.completions.len()
+    }
+
+    /// take the batch for processing
+    pub(crate) fn take(&mut self) -> Vec<(NexusBio, Bio)> {
+        std::mem::take(&mut self.completions)
+    }
+}
@@ -0,0 +1,398 @@
+//! Events raised by a nexus. Events can be observed locally by subscribing to
+//! the event stream and are forwarded to the control plane over the message
+//! bus when mayastor has been configured with a message bus endpoint.
+
+use std::sync::Mutex;
+
+use futures::channel::mpsc;
+use nix::errno::Errno;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use mbus_api::v0;
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev_rebuild::RebuildCompletion,
+        nexus_child::{ChildState, Reason},
+        nexus_health::NexusHealth,
+    },
+    core::IoType,
+    subsys::EventPublisher,
+};
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub enum NexusEvent {
+    /// the nexus has no healthy children left and cannot serve IO
+    Faulted {
+        nexus: String,
+        child: Option<String>,
+        reason: Option<Reason>,
+    },
+    /// the nexus has a healthy child again after having been faulted
+    Recovered { nexus: String },
+    /// the background work of the nexus has been suspended for maintenance
+    MaintenanceEntered { nexus: String },
+    /// the background work of the nexus has been resumed after maintenance
+    MaintenanceExited { nexus: String },
+    /// IO failed on the last healthy child, which is retried for the grace
+    /// period of the nexus before the child is faulted
+    LastChildProtected { nexus: String, child: String },
+    /// the rebuild of a child has finished, successfully or not
+    RebuildCompleted {
+        nexus: String,
+        completion: RebuildCompletion,
+    },
+    /// an IO was submitted to some of the children but its submission failed
+    /// on a child, which is retired unless the submission ran out of memory.
+    /// Until then the range of the IO may differ between the children.
+    PartialSubmit {
+        nexus: String,
+        child: String,
+        io_type: IoType,
+        /// offset of the IO in blocks, relative to the start of the nexus
+        offset: u64,
+        num_blocks: u64,
+        /// number of children the IO was submitted to
+        submitted: usize,
+        errno: i32,
+    },
+    /// an IO which is submitted to all children was failed as the nexus had
+    /// no writable child
+    NoWritableChildren { nexus: String, io_type: IoType },
+    /// the children returned different data for a sampled read. The
+    /// children which disagree with the majority are retired to be rebuilt
+    /// if there is a majority, otherwise all children which returned data
+    /// are listed and nothing is repaired.
+    ReadMismatch {
+        nexus: String,
+        /// offset of the read in blocks, relative to the start of the nexus
+        offset: u64,
+        num_blocks: u64,
+        children: Vec<String>,
+        repaired: bool,
+    },
+    /// the IO channels of the nexus could not be reconfigured after a change
+    /// of its children, some cores may still use the previous children. The
+    /// nexus needs attention until a later reconfiguration succeeds.
+    ReconfigureFailed {
+        nexus: String,
+        /// the reconfiguration event which failed
+        event: String,
+        status: i32,
+    },
+    /// the state of a child changed
+    ChildStateChanged {
+        nexus: String,
+        child: String,
+        previous: ChildState,
+        state: ChildState,
+        /// health of the nexus once the state has changed
+        health: NexusHealth,
+    },
+    /// the snapshots of the children taken by a snapshot of the nexus hold
+    /// different data
+    SnapshotDivergent {
+        nexus: String,
+        snapshot: String,
+        divergent_bytes: u64,
+    },
+    /// a child brought back online was verified, and either reopened or
+    /// rebuilt
+    OnlineVerified {
+        nexus: String,
+        child: String,
+        rebuild_skipped: bool,
+    },
+    /// a child retired for IO errors is reachable again and is being brought
+    /// back online by the automatic recovery of the nexus
+    ChildRecovering { nexus: String, child: String },
+    /// a child has been faulted for the given reason and is being retired,
+    /// raised once per fault
+    ChildFaulted {
+        nexus: String,
+        child: String,
+        reason: Reason,
+    },
+    /// a faulted child could not be retired as the IO channels could not be
+    /// reconfigured; it is kept faulted rather than destroyed, as some cores
+    /// may still use it, until it is removed from the nexus
+    RetireStalled { nexus: String, child: String },
+}
+
+impl NexusEvent {
+    /// name of the nexus the event relates to
+    pub fn nexus(&self) -> &str {
+        match self {
+            Self::Faulted {
+                nexus, ..
+            } => nexus,
+            Self::Recovered {
+                nexus,
+            } => nexus,
+            Self::MaintenanceEntered {
+                nexus,
+            } => nexus,
+            Self::MaintenanceExited {
+                nexus,
+            } => nexus,
+            Self::LastChildProtected {
+                nexus, ..
+            } => nexus,
+            Self::RebuildCompleted {
+                nexus, ..
+            } => nexus,
+            Self::PartialSubmit {
+                nexus, ..
+            } => nexus,
+            Self::NoWritableChildren {
+                nexus, ..
+            } => nexus,
+            Self::ReadMismatch {
+                nexus, ..
+            } => nexus,
+            Self::ReconfigureFailed {
+                nexus, ..
+            } => nexus,
+            Self::ChildStateChanged {
+                nexus, ..
+            } => nexus,
+            Self::SnapshotDivergent {
+                nexus, ..
+            } => nexus,
+            Self::OnlineVerified {
+                nexus, ..
+            } => nexus,
+            Self::ChildRecovering {
+                nexus, ..
+            } => nexus,
+            Self::ChildFaulted {
+                nexus, ..
+            } => nexus,
+            Self::RetireStalled {
+                nexus, ..
+            } => nexus,
+        }
+    }
+
+    fn severity(&self) -> v0::EventSeverity {
+        match self {
+            Self::Faulted {
+                ..
+            }
+            | Self::ReadMismatch {
+                ..
+            }
+            | Self::NoWritableChildren {
+                ..
+            }
+            | Self::ReconfigureFailed {
+                ..
+            }
+            | Self::RetireStalled {
+                ..
+            } => v0::EventSeverity::Critical,
+            Self::ChildStateChanged {
+                state: ChildState::DestroyFailed,
+                ..
+            } => v0::EventSeverity::Warning,
+            Self::Recovered {
+                ..
+            }
+            | Self::MaintenanceEntered {
+                ..
+            }
+            | Self::MaintenanceExited {
+                ..
+            }
+            | Self::ChildStateChanged {
+                ..
+            }
+            | Self::OnlineVerified {
+                ..
+            }
+            | Self::ChildRecovering {
+                ..
+            } => v0::EventSeverity::Info,
+            Self::LastChildProtected {
+                ..
+            }
+            | Self::SnapshotDivergent {
+                ..
+            }
+            | Self::ChildFaulted {
+                ..
+            } => v0::EventSeverity::Warning,
+            Self::RebuildCompleted {
+                completion, ..
+            } if completion.success => v0::EventSeverity::Info,
+            Self::RebuildCompleted {
+                ..
+            }
+            | Self::PartialSubmit {
+                ..
+            } => v0::EventSeverity::Warning,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Faulted {
+                ..
+            } => "NexusFaulted",
+            Self::Recovered {
+                ..
+            } => "NexusRecovered",
+            Self::MaintenanceEntered {
+                ..
+            } => "NexusMaintenanceEntered",
+            Self::MaintenanceExited {
+                ..
+            } => "NexusMaintenanceExited",
+            Self::LastChildProtected {
+                ..
+            } => "NexusLastChildProtected",
+            Self::RebuildCompleted {
+                ..
+            } => "NexusRebuildCompleted",
+            Self::PartialSubmit {
+                ..
+            } => "NexusPartialSubmit",
+            Self::NoWritableChildren {
+                ..
+            } => "NexusNoWritableChildren",
+            Self::ReadMismatch {
+                ..
+            } => "NexusReadMismatch",
+            Self::ReconfigureFailed {
+                ..
+            } => "NexusReconfigureFailed",
+            Self::ChildStateChanged {
+                ..
+            } => "NexusChildStateChanged",
+            Self::SnapshotDivergent {
+                ..
+            } => "NexusSnapshotDivergent",
+            Self::OnlineVerified {
+                ..
+            } => "NexusOnlineVerified",
+            Self::ChildRecovering {
+                ..
+            } => "NexusChildRecovering",
+            Self::ChildFaulted {
+                ..
+            } => "NexusChildFaulted",
+            Self::RetireStalled {
+                ..
+            } => "NexusRetireStalled",
+        }
+    }
+}
+
+impl From<&NexusEvent> for v0::NexusEvent {
+    fn from(event: &NexusEvent) -> Self {
+        let (child, reason) = match event {
+            NexusEvent::Faulted {
+                child,
+                reason,
+                ..
+            } => (
+                child.as_ref().map(|c| v0::ChildUri::from(c.as_str())),
+                reason.map(|r| r.to_string()).unwrap_or_default(),
+            ),
+            NexusEvent::LastChildProtected {
+                child, ..
+            } => (Some(v0::ChildUri::from(child.as_str())), String::new()),
+            NexusEvent::RebuildCompleted {
+                completion, ..
+            } => (
+                Some(v0::ChildUri::from(completion.child.as_str())),
+                if completion.success {
+                    String::new()
+                } else {
+                    Reason::RebuildFailed.to_string()
+                },
+            ),
+            NexusEvent::PartialSubmit {
+                child,
+                errno,
+                ..
+            } => (
+                Some(v0::ChildUri::from(child.as_str())),
+                Errno::from_i32(*errno).desc().to_string(),
+            ),
+            NexusEvent::ReadMismatch {
+                children, ..
+            } => (
+                children.first().map(|c| v0::ChildUri::from(c.as_str())),
+                Reason::DataCorruption.to_string(),
+            ),
+            NexusEvent::ReconfigureFailed {
+                status, ..
+            } => (None, Errno::from_i32(-*status).desc().to_string()),
+            NexusEvent::ChildStateChanged {
+                child,
+                state,
+                ..
+            } => (Some(v0::ChildUri::from(child.as_str())), state.to_string()),
+            NexusEvent::OnlineVerified {
+                child,
+                rebuild_skipped,
+                ..
+            } => (
+                Some(v0::ChildUri::from(child.as_str())),
+                if *rebuild_skipped {
+                    "rebuild skipped".to_string()
+                } else {
+                    "rebuild required".to_string()
+                },
+            ),
+            NexusEvent::ChildRecovering {
+                child, ..
+            } => (
+                Some(v0::ChildUri::from(child.as_str())),
+                "reachable again".to_string(),
+            ),
+            NexusEvent::ChildFaulted {
+                child,
+                reason,
+                ..
+            } => (Some(v0::ChildUri::from(child.as_str())), reason.to_string()),
+            NexusEvent::RetireStalled {
+                child, ..
+            } => (
+                Some(v0::ChildUri::from(child.as_str())),
+                "IO channels not reconfigured".to_string(),
+            ),
+            _ => (None, String::new()),
+        };
+        Self {
+            node: Default::default(),
+            nexus: event.nexus().to_string(),
+            severity: event.severity(),
+            kind: event.kind().to_string(),
+            child,
+            reason,
+        }
+    }
+}
+
+static SUBSCRIBERS: Lazy<Mutex<Vec<mpsc::UnboundedSender<NexusEvent>>>> =
+    Lazy::new(|| Mutex::new(Vec::new()));
+
+/// subscribe to the events raised by all nexus instances. The subscription
+/// ends when the receiver is dropped.
+pub fn subscribe() -> mpsc::UnboundedReceiver<NexusEvent> {
+    let (s, r) = mpsc::unbounded();
+    SUBSCRIBERS.lock().unwrap().push(s);
+    r
+}
+
+/// raise an event to all local subscribers and the control plane
+pub(crate) fn emit(event: NexusEvent) {
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .retain(|s| s.unbounded_send(event.clone()).is_ok());
+    EventPublisher::publish(v0::NexusEvent::from(&event));
+}
@@ -0,0 +1,144 @@
+//! Traces of the outcomes of the child IOs of a nexus, such that a failure
+//! seen on a node can be reproduced against a test nexus. A trace is taken
+//! from the flight recorder with `Nexus::failure_trace()`, which requires the
+//! `io-recorder` feature, and holds the status and latency of every child IO
+//! of the recorded IOs, in the order in which the IOs were submitted.
+//!
+//! A trace is replayed with `replay_failure_trace()`, which requires the
+//! `fault-injection` feature: the next child IOs of the same type to each
+//! child named in the trace complete with the recorded outcomes in turn,
+//! after the recorded latency, whatever the outcome of the IO on the child
+//! itself. The children of a trace are renamed to those of the test nexus
+//! with `FailureTrace::rename_child()`.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{IoStatus, IoType, NvmeStatusCode};
+
+#[cfg(feature = "io-recorder")]
+use crate::bdev::nexus::nexus_bdev::Nexus;
+
+/// the outcome of a child IO
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailureTraceEntry {
+    /// time between the submission of the first IO of the trace and that of
+    /// the IO the child IO belongs to
+    pub at: Duration,
+    /// name of the child
+    pub child: String,
+    pub io_type: IoType,
+    /// status the child IO completed with
+    pub status: IoStatus,
+    /// NVMe status the child IO completed with
+    pub nvme_status: NvmeStatusCode,
+    /// time between the submission and the completion of the child IO
+    pub latency: Duration,
+}
+
+/// The outcomes of the child IOs of a nexus, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FailureTrace {
+    pub entries: Vec<FailureTraceEntry>,
+}
+
+impl FailureTrace {
+    /// rename a child of the trace, to replay its outcomes on another child
+    pub fn rename_child(&mut self, from: &str, to: &str) {
+        self.entries
+            .iter_mut()
+            .filter(|e| e.child == from)
+            .for_each(|e| e.child = to.to_string());
+    }
+
+    /// the child IOs of the trace which did not complete successfully
+    pub fn failures(&self) -> impl Iterator<Item = &FailureTraceEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.status != IoStatus::Success)
+    }
+}
+
+#[cfg(feature = "io-recorder")]
+impl Nexus {
+    /// the outcomes of the child IOs of the IOs in the flight recorder of the
+    /// nexus
+    pub async fn failure_trace(&self) -> FailureTrace {
+        let mut history = self.io_history().await;
+        history.sort_by_key(|r| r.submitted);
+
+        let start = match history.first() {
+            Some(record) => record.submitted,
+            None => return FailureTrace::default(),
+        };
+        let entries = history
+            .into_iter()
+            .flat_map(|r| {
+                let at = r.submitted.duration_since(start);
+                let io_type = r.io_type;
+                r.outcomes.into_iter().map(move |o| FailureTraceEntry {
+                    at,
+                    child: o.child,
+                    io_type,
+                    status: o.status,
+                    nvme_status: o.nvme_status,
+                    latency: o.latency,
+                })
+            })
+            .collect();
+
+        FailureTrace {
+            entries,
+        }
+    }
+}
+
+/// outcomes still to be replayed, by child
+#[cfg(feature = "fault-injection")]
+static REPLAYED: once_cell::sync::Lazy<
+    std::sync::Mutex<
+        std::collections::HashMap<
+            String,
+            std::collections::VecDeque<FailureTraceEntry>,
+        >,
+    >,
+> = once_cell::sync::Lazy::new(Default::default);
+
+/// make the next child IOs to the children named in the trace complete with
+/// the outcomes of the trace, after those of traces replayed earlier
+#[cfg(feature = "fault-injection")]
+pub fn replay_failure_trace(trace: &FailureTrace) {
+    let mut replayed = REPLAYED.lock().unwrap();
+    for entry in &trace.entries {
+        replayed
+            .entry(entry.child.clone())
+            .or_default()
+            .push_back(entry.clone());
+    }
+}
+
+/// drop the outcomes of the given child which are still to be replayed
+#[cfg(feature = "fault-injection")]
+pub fn clear_failure_trace(child: &str) {
+    REPLAYED.lock().unwrap().remove(child);
+}
+
+/// consume the next outcome to replay for an IO of the given type to the
+/// given child, if any
+#[cfg(feature = "fault-injection")]
+pub(crate) fn take_replayed(
+    child: &str,
+    io_type: IoType,
+) -> Option<FailureTraceEntry> {
+    let mut replayed = REPLAYED.lock().unwrap();
+    let outcomes = replayed.get_mut(child)?;
+    let entry = outcomes
+        .iter()
+        .position(|e| e.io_type == io_type)
+        .and_then(|i| outcomes.remove(i));
+    if outcomes.is_empty() {
+        replayed.remove(child);
+    }
+    entry
+}
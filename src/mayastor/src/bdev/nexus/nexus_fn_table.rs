@@ -22,6 +22,7 @@ use crate::{
         instances,
         nexus_bdev::Nexus,
         nexus_io::{nexus_submit_io, NexusBio},
+        nexus_policy::UnhandledAction,
     },
     core::IoType,
 };
@@ -75,6 +76,9 @@ impl NexusFnTable {
             // we always assume the device supports read/write commands
             // allow NVMe Admin as it is needed for local replicas
             IoType::Read | IoType::Write | IoType::NvmeAdmin => true,
+            // the compare is evaluated by reading from a child, and the write
+            // is submitted as a write
+            IoType::CompareAndWrite => true,
             IoType::Flush
             | IoType::Reset
             | IoType::Unmap
@@ -89,14 +93,25 @@ impl NexusFnTable {
                 }
                 supported
             }
-            _ => {
-                debug!(
-                    "un matched IO type {:#?} not supported for {}",
-                    _io_type,
-                    nexus.bdev.name()
-                );
-                false
-            }
+            // other IO types are supported as the unhandled IO policy of the
+            // nexus determines
+            _ => match nexus.policy.unhandled.action(_io_type) {
+                UnhandledAction::Fail => {
+                    debug!(
+                        "un matched IO type {:#?} not supported for {}",
+                        _io_type,
+                        nexus.bdev.name()
+                    );
+                    false
+                }
+                UnhandledAction::Emulate => true,
+                // a child which does not support a compare has it emulated
+                // by the bdev layer, which reads the data from the child
+                UnhandledAction::Quorum => true,
+                UnhandledAction::SingleChild | UnhandledAction::Broadcast => {
+                    nexus.io_is_supported(_io_type)
+                }
+            },
         }
     }
 
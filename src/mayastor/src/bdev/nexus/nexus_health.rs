@@ -0,0 +1,227 @@
+//! Health of a nexus, finer grained than its status. The health is derived from
+//! the states of the children of the nexus. The readiness of a nexus tells
+//! whether it can serve IO at all, and is cheap enough to be probed often as
+//! it does no IO and takes no locks. Rather than probing the health, it can be
+//! awaited to reach a level, as every state change of a child raises an event
+//! carrying the health of the nexus.
+
+use std::{future::Future, time::Duration};
+
+use futures::{channel::oneshot, select, FutureExt, StreamExt};
+use serde::Serialize;
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::Nexus,
+        nexus_bdev_verify::throttle,
+        nexus_child::{ChildState, NexusChild},
+        nexus_event::{self, NexusEvent},
+    },
+    core::Reactors,
+};
+
+/// Level of redundancy of a nexus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum HealthLevel {
+    /// all children are open
+    Healthy,
+    /// some children are not open, but the data is still held by at least
+    /// two open children
+    Degraded,
+    /// at most one child is open, a further fault makes the nexus lose
+    /// access to the data
+    Critical,
+}
+
+/// Number of children of a nexus per state, and the derived health level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct NexusHealth {
+    /// number of open children
+    pub open: usize,
+    /// number of faulted children which are not being rebuilt
+    pub faulted: usize,
+    /// number of children which are being rebuilt
+    pub rebuilding: usize,
+    /// number of children which were missing when the nexus was assembled
+    /// and have not appeared since
+    pub missing: usize,
+    /// number of children in any other state, e.g. closed
+    pub other: usize,
+    /// the derived health level
+    pub level: HealthLevel,
+}
+
+/// Whether a nexus can serve IO
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Readiness {
+    /// reads and writes can be served
+    Ready,
+    /// reads can be served, but fewer children than the write quorum of the
+    /// nexus are open
+    ReadOnly,
+    /// no IO can be served, as no child is open or the nexus is paused
+    NotReady,
+}
+
+/// Health of a single child
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChildHealth {
+    /// name of the child
+    pub child: String,
+    /// state of the child
+    pub state: ChildState,
+    /// the child is being rebuilt
+    pub rebuilding: bool,
+}
+
+/// Health of a nexus including the health of every child
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NexusHealthDetail {
+    /// health of the nexus
+    pub health: NexusHealth,
+    /// readiness of the nexus
+    pub readiness: Readiness,
+    /// health of every child of the nexus
+    pub children: Vec<ChildHealth>,
+}
+
+impl NexusHealth {
+    fn new(children: &[NexusChild]) -> Self {
+        let states = children
+            .iter()
+            .map(|c| (c.state(), c.rebuilding()))
+            .collect::<Vec<_>>();
+        Self::tally(&states)
+    }
+
+    /// the health of a nexus whose children are in the given states, along
+    /// with whether they are being rebuilt
+    pub(crate) fn tally(states: &[(ChildState, bool)]) -> Self {
+        let mut health = Self {
+            open: 0,
+            faulted: 0,
+            rebuilding: 0,
+            missing: 0,
+            other: 0,
+            level: HealthLevel::Healthy,
+        };
+
+        for (state, rebuilding) in states {
+            match state {
+                ChildState::Open => health.open += 1,
+                ChildState::Faulted(_) if *rebuilding => health.rebuilding += 1,
+                ChildState::Faulted(_) => health.faulted += 1,
+                ChildState::Missing => health.missing += 1,
+                _ => health.other += 1,
+            }
+        }
+
+        health.level = if health.open > 0 && health.open == states.len() {
+            HealthLevel::Healthy
+        } else if health.open >= 2 {
+            HealthLevel::Degraded
+        } else {
+            HealthLevel::Critical
+        };
+
+        health
+    }
+}
+
+impl Nexus {
+    /// returns the number of children per state and the derived health level
+    pub fn health(&self) -> NexusHealth {
+        NexusHealth::new(&self.children)
+    }
+
+    /// Returns whether the nexus can serve IO: reads need an open child and
+    /// writes need as many open children as the write quorum of the nexus.
+    /// A paused nexus, for instance while it is being reconfigured, serves no
+    /// IO.
+    pub fn readiness(&self) -> Readiness {
+        let open = self
+            .children
+            .iter()
+            .filter(|c| c.state() == ChildState::Open)
+            .count();
+        self.readiness_with(open)
+    }
+
+    /// the readiness of the nexus with the given number of open children
+    pub(crate) fn readiness_with(&self, open: usize) -> Readiness {
+        if self.is_paused() {
+            return Readiness::NotReady;
+        }
+        if open == 0 {
+            Readiness::NotReady
+        } else if open < self.policy.write_quorum.unwrap_or(1) {
+            Readiness::ReadOnly
+        } else {
+            Readiness::Ready
+        }
+    }
+
+    /// true if the nexus can serve reads and writes
+    pub fn is_ready(&self) -> bool {
+        self.readiness() == Readiness::Ready
+    }
+
+    /// A future which resolves once the health level of the nexus is the
+    /// target level or a better one, or once the timeout fires. On timeout
+    /// the health the nexus was last seen with is returned as the error, so
+    /// the caller knows how close it got. The future is driven by the state
+    /// changes of the children and need not be awaited on a reactor.
+    pub fn wait_for_health(
+        &self,
+        target: HealthLevel,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<NexusHealth, NexusHealth>> {
+        let mut events = nexus_event::subscribe();
+        let mut health = self.health();
+        let nexus = self.name.clone();
+
+        let (sender, expired) = oneshot::channel::<()>();
+        if health.level > target {
+            Reactors::current().send_future(async move {
+                throttle(timeout).await;
+                let _ = sender.send(());
+            });
+        }
+
+        async move {
+            let mut expired = expired.fuse();
+            while health.level > target {
+                select! {
+                    event = events.next() => match event {
+                        Some(NexusEvent::ChildStateChanged {
+                            nexus: n,
+                            health: h,
+                            ..
+                        }) if n == nexus => health = h,
+                        Some(_) => {}
+                        None => return Err(health),
+                    },
+                    _ = expired => return Err(health),
+                }
+            }
+            Ok(health)
+        }
+    }
+
+    /// returns the health of the nexus along with the health of every child
+    pub fn health_detail(&self) -> NexusHealthDetail {
+        NexusHealthDetail {
+            health: self.health(),
+            readiness: self.readiness(),
+            children: self
+                .children
+                .iter()
+                .map(|c| ChildHealth {
+                    child: c.name.clone(),
+                    state: c.state(),
+                    rebuilding: c.rebuilding(),
+                })
+                .collect(),
+        }
+    }
+}
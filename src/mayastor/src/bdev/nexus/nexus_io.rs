@@ -2,31 +2,66 @@ use std::{
     fmt::Debug,
     ops::{Deref, DerefMut},
     ptr::NonNull,
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
 };
 
 use libc::c_void;
 use nix::errno::Errno;
 
 use spdk_sys::{
+    spdk_bdev_abort,
+    spdk_bdev_comparev_blocks,
+    spdk_bdev_flush_blocks,
+    spdk_bdev_free_io,
     spdk_bdev_io,
+    spdk_bdev_nvme_admin_passthru,
+    spdk_bdev_read_blocks,
     spdk_bdev_readv_blocks,
     spdk_bdev_reset,
     spdk_bdev_unmap_blocks,
+    spdk_bdev_write_blocks,
     spdk_bdev_write_zeroes_blocks,
     spdk_bdev_writev_blocks,
     spdk_io_channel,
+    SPDK_NVME_SCT_GENERIC,
+    SPDK_NVME_SC_ABORTED_BY_REQUEST,
+    SPDK_NVME_SC_INVALID_FIELD,
 };
 
 use crate::{
     bdev::{
         nexus::{
-            nexus_bdev::NEXUS_PRODUCT_ID,
+            nexus_bdev_verify::throttle,
+            nexus_bounce::{copy_from_iovs, copy_to_iovs},
             nexus_channel::{DrEvent, NexusChannel, NexusChannelInner},
+            nexus_child::NexusChild,
+            nexus_event::{self, NexusEvent},
+            nexus_io_inflight::{InFlightIo, IoLink},
+            nexus_io_timeout::IO_TIMEOUT_SCAN,
+            nexus_io_trace::{TraceEvent, TracePoint},
+            nexus_policy::{
+                AllFailedPolicy,
+                CompletionBatching,
+                FailureCondition,
+                FlushFailurePolicy,
+                NoMemoryPolicy,
+                PausePolicy,
+                UnhandledAction,
+                UnsupportedAction,
+                WriteAck,
+                WriteCache,
+                WriteOrdering,
+            },
+            nexus_quorum_write::QuorumWrite,
+            nexus_read_checksum::{fill_read_checksums, READ_CHECKSUM_MD_SIZE},
+            nexus_read_sampling::{compare_read, overlaps_write},
+            nexus_throughput::Traffic,
+            nexus_write_cache::{WriteRun, COALESCE_MAX_WRITES},
         },
         nexus_lookup,
         ChildState,
         Nexus,
-        NexusStatus,
         Reason,
     },
     core::{
@@ -34,15 +69,27 @@ use crate::{
         BdevHandle,
         Bio,
         Cores,
-        GenericStatusCode,
+        Descriptor,
+        DmaBuf,
+        IoChannel,
         IoStatus,
         IoType,
         Mthread,
+        NvmeStatusCode,
+        RangeContext,
         Reactors,
     },
     ffihelper::FfiResult,
 };
 
+#[cfg(feature = "io-recorder")]
+use crate::bdev::nexus::nexus_io_recorder::{ChildOutcome, IoRecord};
+#[cfg(feature = "fault-injection")]
+use crate::bdev::nexus::{
+    nexus_failure_trace::{take_replayed, FailureTraceEntry},
+    nexus_io_timeout::take_child_hang,
+};
+
 #[allow(unused_macros)]
 macro_rules! offset_of {
     ($container:ty, $field:ident) => {
@@ -57,6 +104,15 @@ macro_rules! container_of {
     }};
 }
 
+/// size in bytes of the segments in which a failed read is retried
+const READ_REPAIR_SEGMENT_SIZE: u64 = 4096;
+
+/// size in bytes of the writes a write zeroes is emulated with
+const WRITE_ZEROES_SEGMENT_SIZE: u64 = 128 * 1024;
+
+/// interval at which an IO which failed on the last healthy child is retried
+const LAST_CHILD_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
 #[repr(transparent)]
 #[derive(Debug, Clone)]
 pub(crate) struct NexusBio(Bio);
@@ -95,6 +151,72 @@ pub struct NioCtx {
     status: IoStatus,
     channel: NonNull<spdk_io_channel>,
     core: u32,
+    /// the IO has been resubmitted after it failed on all children
+    retried: bool,
+    /// the children a failed read has been tried on, by their index in the
+    /// children of the nexus, see `fail_over_read`
+    tried: u64,
+    /// the read is only served by children which are in sync, as it was
+    /// submitted through a reader opened with `ReadFreshness::InSync`
+    in_sync: bool,
+    /// sequence number of a write on its channel, 0 for other IO
+    seq: u64,
+    /// NVMe status of the last child IO which failed with one
+    nvme_status: NvmeStatusCode,
+    /// time the IO was submitted to the nexus, see `stamp_submitted`
+    submitted: Instant,
+    /// the nexus was instrumented when the IO was submitted, see
+    /// `Nexus::instrumented()`
+    instrumented: bool,
+    /// the IO was aborted on request
+    aborted: bool,
+    /// the IO has been admitted within the outstanding IO limit
+    admitted: bool,
+    /// number of children on which the data of a compare differs
+    miscompares: u8,
+    /// number of times the flush has been resubmitted to a child which
+    /// failed it
+    flush_retries: u8,
+    /// the flush failed on a child and must fail
+    flush_failed: bool,
+    /// the IO kept failing on the last healthy child for the grace period
+    timed_out: bool,
+    /// the IO waited on its child IOs for longer than the IO timeout
+    expired: bool,
+    /// the QuorumWrite the child IOs of a write complete through when it is
+    /// acknowledged once a quorum of the children completed it
+    quorum: Option<NonNull<QuorumWrite>>,
+    /// the links of the IO in the IO in flight on its channel
+    link: IoLink,
+    /// number of child IOs completed
+    #[cfg(feature = "io-recorder")]
+    children: u8,
+    /// the child which served the read
+    #[cfg(feature = "io-recorder")]
+    served: Option<Bdev>,
+}
+
+impl NioCtx {
+    /// number of child IOs still in flight
+    pub fn in_flight(&self) -> u8 {
+        self.in_flight
+    }
+
+    /// number of child IOs completed successfully
+    pub fn num_ok(&self) -> u8 {
+        self.num_ok
+    }
+
+    /// core on which the IO was submitted
+    pub fn core(&self) -> u32 {
+        self.core
+    }
+
+    /// NVMe status of the last child IO which failed with one, which the IO
+    /// is failed with when it fails
+    pub fn nvme_status(&self) -> NvmeStatusCode {
+        self.nvme_status
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -110,28 +232,58 @@ enum Disposition {
 }
 
 pub(crate) fn nexus_submit_io(mut io: NexusBio) {
-    if let Err(e) = match io.cmd() {
-        IoType::Read => io.readv(),
-        // these IOs are submitted to all the underlying children
-        IoType::Write | IoType::WriteZeros | IoType::Reset | IoType::Unmap => {
-            io.submit_all()
-        }
-        IoType::Flush => {
-            io.ok();
-            Ok(())
+    io.stamp_submitted();
+
+    // the accounting of the IO is skipped as a whole when nothing consumes
+    // it, see `Nexus::instrumented()`
+    let instrumented = io.nexus().instrumented();
+    io.ctx_as_mut().instrumented = instrumented;
+    if instrumented {
+        io.inner_channel().outstanding.push(&io);
+        io.notify_submit();
+        io.trace(|| TracePoint::Submit);
+
+        if !io.is_aligned() {
+            io.fail_misaligned();
+            return;
         }
-        IoType::NvmeAdmin => {
-            io.fail();
-            Err(Errno::EINVAL)
+
+        match io.cmd() {
+            IoType::Write => {
+                let bytes = io.num_blocks() * io.block_len();
+                io.inner_channel().written.logical += bytes;
+                io.inner_channel().traffic.account(Traffic::Write, bytes)
+            }
+            IoType::Read => io
+                .inner_channel()
+                .traffic
+                .account(Traffic::Read, io.num_blocks() * io.block_len()),
+            _ => {}
         }
+    }
+
+    let modifies = matches!(
+        io.cmd(),
+        IoType::Write
+            | IoType::WriteZeros
+            | IoType::Unmap
+            | IoType::CompareAndWrite
+    );
+    if modifies {
+        io.nexus().metrics.data_modified();
+    }
 
-        _ => {
-            trace!(?io, "not supported");
-            io.fail();
-            Err(Errno::EOPNOTSUPP)
+    // the segments written while a child is missing are rebuilt once it
+    // appears, they are marked before the IO is dispatched such that a
+    // rebuild which reaches the segment afterwards copies it
+    if let Some(writes) = io.nexus().missing_writes.as_ref() {
+        if modifies {
+            writes.mark(io.offset(), io.num_blocks());
         }
-    } {
-        error!(?e, ?io, "Error during IO submission");
+    }
+
+    if !instrumented || io.admit() {
+        io.dispatch();
     }
 }
 
@@ -150,6 +302,26 @@ impl NexusBio {
         ctx.status = IoStatus::Pending;
         ctx.in_flight = 0;
         ctx.num_ok = 0;
+        ctx.retried = false;
+        ctx.tried = 0;
+        ctx.in_sync = false;
+        ctx.seq = 0;
+        ctx.nvme_status = NvmeStatusCode::default();
+        ctx.aborted = false;
+        ctx.instrumented = false;
+        ctx.admitted = false;
+        ctx.miscompares = 0;
+        ctx.flush_retries = 0;
+        ctx.flush_failed = false;
+        ctx.timed_out = false;
+        ctx.expired = false;
+        ctx.quorum = None;
+        ctx.link = IoLink::default();
+        #[cfg(feature = "io-recorder")]
+        {
+            ctx.children = 0;
+            ctx.served = None;
+        }
         bio
     }
 
@@ -161,237 +333,2431 @@ impl NexusBio {
     ) {
         let mut nexus_io = NexusBio::from(nexus_io);
         let child_io = Bio::from(child_io);
+        #[cfg(feature = "fault-injection")]
+        if take_child_hang(&child_io.bdev().name()) {
+            let io = nexus_io.clone();
+            return nexus_io.inner_channel().hung_ios.push((io, child_io));
+        }
+        #[cfg(feature = "fault-injection")]
+        if let Some(entry) = take_replayed(
+            &nexus_io.child_name(&child_io.bdev()),
+            nexus_io.cmd(),
+        ) {
+            return nexus_io.replay(child_io, entry);
+        }
+        if let CompletionBatching::Batched {
+            max_ios,
+        } = nexus_io.nexus().policy.completion_batching
+        {
+            return nexus_io.complete_batched(child_io, success, max_ios);
+        }
         nexus_io.complete(child_io, success);
     }
 
-    #[inline(always)]
-    /// a mutable reference to the IO context
-    fn ctx_as_mut(&mut self) -> &mut NioCtx {
-        self.specific_as_mut::<NioCtx>()
+    /// complete a child IO with a replayed outcome rather than its own, once
+    /// the child IO has taken as long as the outcome
+    #[cfg(feature = "fault-injection")]
+    fn replay(mut self, child_io: Bio, entry: FailureTraceEntry) {
+        let success = entry.status == IoStatus::Success;
+        self.inner_channel().replayed.insert(
+            child_io.as_ptr() as usize,
+            (entry.status, entry.nvme_status),
+        );
+        let delay = entry.latency.checked_sub(child_io.elapsed());
+        Reactors::current().send_future(async move {
+            if let Some(delay) = delay {
+                throttle(delay).await;
+            }
+            self.complete(child_io, success);
+        });
     }
 
-    #[inline(always)]
-    /// immutable reference to the IO context
-    fn ctx(&self) -> &NioCtx {
-        self.specific::<NioCtx>()
+    /// the status a child IO completed with, which is the replayed one when
+    /// the outcome of the child IO was replayed
+    fn child_status(&mut self, child_io: &Bio) -> (IoStatus, NvmeStatusCode) {
+        #[cfg(feature = "fault-injection")]
+        if let Some(status) = self
+            .inner_channel()
+            .replayed
+            .remove(&(child_io.as_ptr() as usize))
+        {
+            return status;
+        }
+        (child_io.status(), child_io.nvme_status_code())
     }
 
-    /// Determine what to do with the IO if anything. In principle, it's the
-    /// same way any other IO is completed but, it wrapped by the
-    /// disposition. The general approach is if we return Disposition::
-    /// Retire(IoStatus::Success) it means that we retire the current child and
-    /// then, return mark the IO successful.
-    fn disposition(&mut self) -> Disposition {
-        let ctx = self.ctx_as_mut();
-        match ctx.status {
-            // all child IO's completed, complete the parent IO
-            IoStatus::Pending if ctx.in_flight == 0 => {
-                Disposition::Complete(IoStatus::Success)
-            }
-            // some child IO has completed, but not all
-            IoStatus::Pending if ctx.in_flight != 0 => {
-                Disposition::Flying(IoStatus::Success)
+    /// add a child IO which completed successfully to the completion batch
+    /// of the channel, processing the batch when it is full and otherwise
+    /// once the reactor gets to it after the completions polled along with
+    /// it. A failed child IO is processed right away, after the batch.
+    fn complete_batched(self, child_io: Bio, success: bool, max_ios: usize) {
+        let channel = self.ctx().channel;
+        let completions =
+            &mut NexusChannel::inner_from_channel(channel.as_ptr()).completions;
+        if !success {
+            Self::complete_batch(completions.take());
+            let mut io = self;
+            return io.complete(child_io, false);
+        }
+
+        let started = completions.push(self, child_io);
+        if completions.len() >= max_ios {
+            Self::complete_batch(completions.take());
+        } else if started {
+            Reactors::current().send_future(async move {
+                let inner = NexusChannel::inner_from_channel(channel.as_ptr());
+                Self::complete_batch(inner.completions.take());
+            });
+        }
+    }
+
+    /// process a batch of successful child IOs. The children are accounted
+    /// once for all of their child IOs in the batch, after which the child
+    /// IOs are completed in the order they were collected in.
+    pub(crate) fn complete_batch(completions: Vec<(NexusBio, Bio)>) {
+        let first = match completions.first() {
+            Some((io, _)) => io,
+            None => return,
+        };
+        let nexus = first.nexus();
+        let inner = first.inner_channel();
+
+        let mut bdevs: Vec<(Bdev, u32)> = Vec::new();
+        for (_, child_io) in &completions {
+            let bdev = child_io.bdev();
+            match bdevs.iter_mut().find(|(b, _)| b.as_ptr() == bdev.as_ptr()) {
+                Some((_, n)) => *n += 1,
+                None => bdevs.push((bdev, 1)),
             }
-            // Other IO are still inflight we encountered an error, retire this
-            // child
-            IoStatus::Failed if ctx.in_flight != 0 => {
-                Disposition::Retire(IoStatus::Pending)
+        }
+        for (bdev, n) in &bdevs {
+            inner.child_ios_completed(bdev, *n);
+            if let Some(i) = nexus.children.iter().position(|c| {
+                c.bdev
+                    .as_ref()
+                    .map_or(false, |b| b.as_ptr() == bdev.as_ptr())
+            }) {
+                completions
+                    .iter()
+                    .filter(|(_, c)| c.bdev().as_ptr() == bdev.as_ptr())
+                    .for_each(|(_, c)| {
+                        inner.io_stats.record(
+                            i,
+                            bdev,
+                            c.io_type(),
+                            c.elapsed(),
+                        );
+                        nexus.children[i].io_completed();
+                    });
             }
+        }
 
-            // this IO failed, but we have seen successfully IO's for the parent
-            // already retire it
-            IoStatus::Failed if ctx.num_ok != 0 && ctx.in_flight == 0 => {
-                Disposition::Retire(IoStatus::Success)
-            }
+        for (mut io, child_io) in completions {
+            io.complete_child(&child_io, true);
+            child_io.free();
+        }
+    }
 
-            // ALL io's have failed
-            IoStatus::Failed if ctx.num_ok == 0 && ctx.in_flight == 0 => {
-                Disposition::Complete(IoStatus::Failed)
-            }
-            // all IOs that where partially submitted completed, no bubble up
-            // the ENOMEM to the upper layer we do not care if the
-            // IO failed or complete, the whole IO must be resubmitted
-            IoStatus::NoMemory if ctx.in_flight == 0 => {
-                Disposition::Complete(IoStatus::NoMemory)
+    /// submit the IO to the children of the nexus
+    pub(crate) fn submit(mut self) {
+        // the QuorumWrite of an earlier submission is freed by now
+        self.ctx_as_mut().quorum = None;
+        if let Err(e) = match self.cmd() {
+            IoType::Read => self.readv(),
+            // these IOs are submitted to all the underlying children
+            IoType::Write | IoType::WriteZeros | IoType::Unmap => {
+                self.write_submitted();
+                if self.hold_overlapping() {
+                    return;
+                }
+                if self.nexus().policy.write_ordering == WriteOrdering::Barrier
+                {
+                    return self.submit_ordered();
+                }
+                if let WriteCache::WriteThrough {
+                    max_bytes,
+                } = self.nexus().policy.write_cache
+                {
+                    if self.cmd() == IoType::Write {
+                        return self.submit_coalesced(max_bytes);
+                    }
+                }
+                self.submit_all()
             }
-            _ => {
-                error!("{:?}", ctx);
-                Disposition::Complete(IoStatus::Failed)
+            IoType::Reset => self.submit_all(),
+            // the write of a compare-and-write is submitted once the compare
+            // has been evaluated
+            IoType::CompareAndWrite => {
+                self.write_submitted();
+                Reactors::current()
+                    .send_future(Self::submit_fused(self.clone()));
+                Ok(())
             }
+            // a flush is a barrier, it waits for the writes submitted before
+            // it on this channel
+            IoType::Flush => return self.submit_flush(),
+            IoType::NvmeAdmin => self.submit_admin(),
+
+            _ => self.submit_unhandled(),
+        } {
+            error!(?e, io = ?self, "Error during IO submission");
         }
     }
 
-    /// returns the type of command for this IO
-    #[inline(always)]
-    fn cmd(&self) -> IoType {
-        self.io_type()
+    /// account a write with the flush barrier of the channel. A write which
+    /// is resubmitted keeps its sequence number.
+    fn write_submitted(&mut self) {
+        if self.ctx().seq == 0 {
+            let seq = self.inner_channel().barrier.write_submitted();
+            self.ctx_as_mut().seq = seq;
+        }
     }
 
-    /// completion handler for the nexus when a child IO completes
-    pub fn complete(&mut self, child_io: Bio, success: bool) {
-        assert_eq!(self.ctx().core, Cores::current());
+    /// account the completion of a write with the flush barrier of the
+    /// channel, and submit the flushes and ordered writes which no longer
+    /// wait for any write
+    fn write_completed(&self) {
+        let seq = self.ctx().seq;
+        if seq != 0 {
+            Self::barrier_completed(self.inner_channel(), seq);
+        }
+    }
 
-        // decrement the counter of in flight IO
-        self.ctx_as_mut().in_flight -= 1;
+    /// account the completion of the write with the given sequence number
+    /// with the flush barrier of the channel
+    fn barrier_completed(inner: &mut NexusChannelInner, seq: u64) {
+        inner.barrier.write_completed(seq);
+        inner.barrier.release_waiters();
+        while let Some(io) = inner.barrier.next_flush() {
+            io.flush_children();
+        }
+        if let Some(io) = inner.barrier.next_write() {
+            io.submit_write_children();
+        }
+    }
 
-        // record the state of at least one of the IO's.
-        if !success {
-            self.ctx_as_mut().status = IoStatus::Failed;
+    /// account the completion on all children of a write which was
+    /// acknowledged by a quorum before, and resubmit the writes held back as
+    /// they overlapped it
+    pub(crate) fn late_write_completed(
+        channel: NonNull<spdk_io_channel>,
+        seq: u64,
+        offset: u64,
+        num_blocks: u64,
+    ) {
+        let inner = NexusChannel::inner_from_channel(channel.as_ptr());
+        inner.late_write_completed(offset, num_blocks);
+        if seq != 0 {
+            Self::barrier_completed(inner, seq);
+        }
+        let held = inner.held_writes.drain(..).collect::<Vec<_>>();
+        held.into_iter().for_each(|io| io.submit());
+    }
+
+    /// hold back a write which overlaps a write acknowledged by a quorum that
+    /// is still in flight on the channel, until that write has completed on
+    /// all children. Returns true if the write is held.
+    fn hold_overlapping(&self) -> bool {
+        let inner = self.inner_channel();
+        if inner.overlaps_late_write(self.offset(), self.num_blocks()) {
+            inner.held_writes.push_back(self.clone());
+            true
         } else {
-            self.ctx_as_mut().num_ok += 1;
+            false
         }
+    }
 
-        match self.disposition() {
-            // the happy path, all is good
-            Disposition::Complete(IoStatus::Success) => self.ok(),
-            // All of IO's have failed but all remaining in flights completed
-            // now as well depending on the error we can attempt to
-            // do a retry.
-            Disposition::Complete(IoStatus::Failed) => self.fail(),
+    /// submit an ordered write once all writes submitted before it have
+    /// completed
+    fn submit_ordered(self) {
+        let seq = self.ctx().seq;
+        let inner =
+            NexusChannel::inner_from_channel(self.ctx().channel.as_ptr());
+        if let Some(io) = inner.barrier.order(seq, self) {
+            io.submit_write_children();
+        }
+    }
 
-            // IOs were submitted before we bumped into ENOMEM. The IO has
-            // now completed, so we can finally report back to the
-            // callee that we encountered ENOMEM during submission
-            Disposition::Complete(IoStatus::NoMemory) => self.no_mem(),
+    /// submit an ordered write to the children
+    fn submit_write_children(mut self) {
+        if let Err(e) = self.submit_all() {
+            error!(?e, io = ?self, "Error during ordered write submission");
+        }
+    }
 
-            // We can mark the IO as success but before we do we need to retire
-            // this child. This typically would only match when the last IO
-            // has failed i.e [ok,ok,fail]
-            Disposition::Retire(IoStatus::Success) => {
-                assert_eq!(success, false);
-                error!(
-                    ?self,
-                    ?child_io,
-                    "{}:{}",
-                    Cores::current(),
-                    "last child IO failed completion"
-                );
-                self.try_retire(child_io.clone());
-                self.ok();
+    /// submit the flush once all writes submitted before it on any core have
+    /// completed, the writes held by the write cache are submitted first
+    fn submit_flush(self) {
+        let inner =
+            NexusChannel::inner_from_channel(self.ctx().channel.as_ptr());
+        if let Some((ios, bytes)) = inner.coalescer.take() {
+            Self::submit_run(ios, bytes);
+        }
+        Reactors::current().send_future(async move {
+            self.nexus().wait_writes().await;
+            let inner =
+                NexusChannel::inner_from_channel(self.ctx().channel.as_ptr());
+            if let Some(io) = inner.barrier.flush(self) {
+                io.flush_children();
             }
+        });
+    }
 
-            // IO still in flight (pending) fail this IO and continue by setting
-            // the parent status back to pending for example [ok,
-            // fail, pending]
-            Disposition::Retire(IoStatus::Pending) => {
-                assert_eq!(success, false);
-                error!(
-                    ?self,
-                    ?child_io,
-                    "{}:{}",
-                    Cores::current(),
-                    "some child IO completion failed"
-                );
+    /// submit the flush to the children which support it, completing it
+    /// right away when none of them do. It fails when there are no children
+    /// to flush at all.
+    fn flush_children(mut self) {
+        if self.write_targets().next().is_none()
+            || self
+                .write_targets()
+                .any(|h| h.get_bdev().io_type_supported(IoType::Flush))
+        {
+            if let Err(e) = self.submit_all() {
+                error!(?e, io = ?self, "Error during flush submission");
+            }
+        } else {
+            self.ok();
+        }
+    }
+
+    /// hold a write in the run of the write cache of the channel, submitting
+    /// the run when the write does not continue it or fills it up. The run
+    /// is submitted at the latest when the reactor gets to it after the IO
+    /// submitted along with the write.
+    fn submit_coalesced(self, max_bytes: u64) {
+        let bytes = self.num_blocks() * self.block_len();
+        let end = self.offset() + self.num_blocks();
+        let iovs = self.iov_count() as u32;
+        let channel = self.ctx().channel;
+        self.nexus().metrics.cache_held(bytes);
 
-                self.try_retire(child_io.clone());
-                // more IO is pending ensure we set the proper context state
-                self.ctx_as_mut().status = IoStatus::Pending;
+        // the run is cut before it has more iovecs than a write target
+        // accepts, as the run is not bounced
+        let max_iovs = self
+            .write_targets()
+            .map(|h| h.get_bdev().max_num_segments())
+            .filter(|max| *max != 0)
+            .min();
+        let coalescer = &mut self.inner_channel().coalescer;
+        if !coalescer.extends(self.offset(), bytes, iovs, max_bytes, max_iovs) {
+            if let Some((ios, run_bytes)) = coalescer.take() {
+                Self::submit_run(ios, run_bytes);
             }
-            // Disposition::Flying(_) => {
-            //     assert_eq!(self.ctx().status, IoStatus::Pending);
-            //     assert_ne!(self.ctx().in_flight, 0);
-            // },
-            _ => {}
         }
 
-        // always free the child IO. The status of the child IO has been set by
-        // the underlying device before invocation of the callback.
-        child_io.free();
+        let coalescer =
+            &mut NexusChannel::inner_from_channel(channel.as_ptr()).coalescer;
+        let started = coalescer.push(self, end, bytes, iovs);
+        if coalescer.bytes() >= max_bytes
+            || coalescer.len() >= COALESCE_MAX_WRITES
+            || max_iovs.map_or(false, |max| coalescer.iovs() >= max)
+        {
+            if let Some((ios, run_bytes)) = coalescer.take() {
+                Self::submit_run(ios, run_bytes);
+            }
+        } else if started {
+            Reactors::current().send_future(async move {
+                let inner = NexusChannel::inner_from_channel(channel.as_ptr());
+                if let Some((ios, run_bytes)) = inner.coalescer.take() {
+                    Self::submit_run(ios, run_bytes);
+                }
+            });
+        }
     }
 
-    /// reference to the inner channels. The inner channel contains the specific
-    /// per-core data structures.
-    #[allow(clippy::mut_from_ref)]
-    fn inner_channel(&self) -> &mut NexusChannelInner {
-        NexusChannel::inner_from_channel(self.ctx().channel.as_ptr())
-    }
+    /// Submit a run of contiguous writes to the children as a single write.
+    /// The writes are accounted and completed individually, as if each of
+    /// them had been submitted on its own by `submit_all`.
+    fn submit_run(mut ios: Vec<NexusBio>, bytes: u64) {
+        ios[0].nexus().metrics.cache_released(bytes, ios.len());
+        if ios.len() == 1 {
+            let mut io = ios.pop().unwrap();
+            if let Err(e) = io.submit_all() {
+                error!(?e, io = ?io, "Error during write submission");
+            }
+            return;
+        }
+
+        let first = &ios[0];
+        let num_blocks = ios.iter().map(|io| io.num_blocks()).sum::<u64>();
+        let targets = first
+            .write_targets()
+            .map(|h| {
+                (h.io_tuple(), h.get_bdev(), first.child_range(h, num_blocks))
+            })
+            .collect::<Vec<_>>();
+        let iovs = ios
+            .iter()
+            .flat_map(|io| unsafe {
+                std::slice::from_raw_parts(io.iovs(), io.iov_count() as usize)
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        let run = Box::into_raw(Box::new(WriteRun {
+            ios,
+            iovs,
+            pending: 0,
+        }));
+
+        let mut inflight = 0;
+        let mut failed = Vec::new();
+        for ((desc, chan), bdev, offset) in targets {
+            let rc = offset.and_then(|offset| {
+                unsafe {
+                    spdk_bdev_writev_blocks(
+                        desc,
+                        chan,
+                        (*run).iovs.as_mut_ptr(),
+                        (*run).iovs.len() as i32,
+                        offset,
+                        num_blocks,
+                        Some(Self::run_completion),
+                        run.cast(),
+                    )
+                }
+                .to_result(Errno::from_i32)
+            });
+            match rc {
+                Ok(_) => {
+                    let ios = &unsafe { &*run }.ios;
+                    ios[0].inner_channel().child_io_submitted(&bdev);
+                    ios.iter().for_each(|io| {
+                        io.trace(|| TracePoint::Dispatch {
+                            child: io.child_name(&bdev),
+                        })
+                    });
+                    inflight += 1
+                }
+                Err(se) => {
+                    let ios = &unsafe { &*run }.ios;
+                    ios[0].nexus().metrics.submit_failed(IoType::Write, se);
+                    failed.push((bdev, se))
+                }
+            }
+        }
+
+        let enomem = failed.iter().any(|(_, se)| *se == Errno::ENOMEM);
+        if inflight == 0 {
+            let run = unsafe { Box::from_raw(run) };
+            let empty = failed.is_empty();
+            run.ios.into_iter().for_each(|mut io| {
+                if enomem {
+                    io.no_mem()
+                } else if empty {
+                    io.fail_no_writers()
+                } else {
+                    io.fail()
+                }
+            });
+            return;
+        }
 
-    //TODO make const
-    fn data_ent_offset(&self) -> u64 {
-        let b = self.bdev();
-        assert_eq!(b.product_name(), NEXUS_PRODUCT_ID);
-        unsafe { Nexus::from_raw((*b.as_ptr()).ctxt) }.data_ent_offset
+        let run = unsafe { &mut *run };
+        run.pending = inflight;
+        run.ios[0].physical_written(bytes * inflight as u64);
+        for io in run.ios.iter_mut() {
+            let ctx = io.ctx_as_mut();
+            ctx.in_flight = inflight as u8;
+            if enomem {
+                ctx.status = IoStatus::NoMemory;
+            }
+        }
+        run.ios[0].partial_submit(
+            IoType::Write,
+            run.ios[0].offset(),
+            num_blocks,
+            inflight,
+            &failed,
+        );
+        if !enomem {
+            for (bdev, _) in failed {
+                run.ios[0].retire(bdev, Reason::IoError);
+            }
+        }
     }
 
-    /// helper routine to get a channel to read from
-    fn read_channel_at_index(&self, i: usize) -> &BdevHandle {
-        &self.inner_channel().readers[i]
+    /// invoked when the write of a run completes on a child, which completes
+    /// the child IO of every write of the run
+    unsafe extern "C" fn run_completion(
+        child_io: *mut spdk_bdev_io,
+        success: bool,
+        arg: *mut c_void,
+    ) {
+        let run = &mut *(arg as *mut WriteRun);
+        let child_io = Bio::from(child_io);
+        run.ios[0]
+            .inner_channel()
+            .child_io_completed(&child_io.bdev());
+        for io in run.ios.iter_mut() {
+            if success {
+                io.child_io_completed(&child_io);
+            }
+            io.complete_child(&child_io, success);
+        }
+        child_io.free();
+
+        run.pending -= 1;
+        if run.pending == 0 {
+            drop(Box::from_raw(arg as *mut WriteRun));
+        }
     }
 
-    /// submit a read operation to one of the children of this nexus
-    #[inline(always)]
-    fn submit_read(&self, hdl: &BdevHandle) -> Result<(), Errno> {
-        let (desc, chan) = hdl.io_tuple();
-        unsafe {
-            spdk_bdev_readv_blocks(
-                desc,
-                chan,
-                self.iovs(),
-                self.iov_count(),
-                self.offset() + self.data_ent_offset(),
-                self.num_blocks(),
-                Some(Self::child_completion),
-                self.as_ptr().cast(),
-            )
+    /// submit an admitted IO, or hold it while the nexus is paused
+    fn dispatch(self) {
+        if self.nexus().is_paused() {
+            self.submit_paused();
+        } else {
+            self.submit();
         }
-        .to_result(Errno::from_i32)
     }
 
-    /// submit read IO to some child
-    fn readv(&mut self) -> Result<(), Errno> {
-        if let Some(i) = self.inner_channel().child_select() {
-            let hdl = self.read_channel_at_index(i);
-            self.submit_read(hdl).map(|_| {
-                self.ctx_as_mut().in_flight += 1;
-            })
+    /// Admit the IO within the outstanding IO limit of the nexus. An IO which
+    /// is not admitted is queued behind the IO queued before it on the
+    /// channel, or failed with a retriable status when the queue is full.
+    /// Returns true if the IO has been admitted.
+    fn admit(&mut self) -> bool {
+        let nexus = self.nexus();
+        let limit = match nexus.policy.max_outstanding {
+            Some(limit) => limit,
+            None => return true,
+        };
+        let inner = self.inner_channel();
+        if inner.throttled_ios.is_empty() && inner.may_admit(limit.max_ios) {
+            inner.admitted_ios += 1;
+            nexus.admission.admitted();
+            self.ctx_as_mut().admitted = true;
+            return true;
+        }
+
+        nexus.metrics.io_throttled();
+        if inner.throttled_ios.len() < limit.queue_depth {
+            inner.throttled_ios.push_back(self.clone());
         } else {
-            self.fail();
-            Err(Errno::ENODEV)
+            self.fail_retriable();
         }
+        false
     }
 
-    #[inline(always)]
-    fn submit_write(&self, hdl: &BdevHandle) -> Result<(), Errno> {
-        let (desc, chan) = hdl.io_tuple();
-        unsafe {
-            spdk_bdev_writev_blocks(
-                desc,
-                chan,
-                self.iovs(),
-                self.iov_count(),
-                self.offset() + self.data_ent_offset(),
-                self.num_blocks(),
-                Some(Self::child_completion),
-                self.as_ptr().cast(),
-            )
+    /// release an admitted IO which completes, and admit the IO queued on the
+    /// channel for as long as the share of the outstanding IO limit of the
+    /// channel allows, all of it once the limit has been removed
+    fn release(&self) {
+        let nexus = self.nexus();
+        nexus.admission.released();
+        let inner = self.inner_channel();
+        inner.admitted_ios -= 1;
+
+        while !inner.throttled_ios.is_empty()
+            && nexus
+                .policy
+                .max_outstanding
+                .map_or(true, |l| inner.may_admit(l.max_ios))
+        {
+            let mut io = inner.throttled_ios.pop_front().unwrap();
+            inner.admitted_ios += 1;
+            nexus.admission.admitted();
+            io.ctx_as_mut().admitted = true;
+            io.dispatch();
         }
-        .to_result(Errno::from_i32)
     }
 
-    #[inline(always)]
-    fn submit_unmap(&self, hdl: &BdevHandle) -> Result<(), Errno> {
-        let (desc, chan) = hdl.io_tuple();
-        unsafe {
-            spdk_bdev_unmap_blocks(
-                desc,
-                chan,
-                self.offset() + self.data_ent_offset(),
-                self.num_blocks(),
-                Some(Self::child_completion),
-                self.as_ptr().cast(),
-            )
+    /// handle an IO submitted while the nexus is paused, according to the
+    /// pause policy of the nexus
+    fn submit_paused(self) {
+        let inner =
+            NexusChannel::inner_from_channel(self.ctx().channel.as_ptr());
+        match self.nexus().policy.pause {
+            PausePolicy::Queue(depth) if inner.paused_ios.len() < depth => {
+                inner.paused_ios.push_back(self)
+            }
+            _ => self.fail_retriable(),
         }
-        .to_result(Errno::from_i32)
     }
 
-    #[inline(always)]
+    /// true if the offset and length of the IO satisfy the alignment
+    /// required by the nexus. IO is always aligned to the block size, so only
+    /// a raised alignment needs to be checked.
+    fn is_aligned(&self) -> bool {
+        let alignment = match self.nexus().policy.alignment {
+            Some(alignment) => u64::from(alignment),
+            None => return true,
+        };
+        match self.cmd() {
+            IoType::Read
+            | IoType::Write
+            | IoType::WriteZeros
+            | IoType::Unmap => {
+                let block_len = self.block_len();
+                (self.offset() * block_len) % alignment == 0
+                    && (self.num_blocks() * block_len) % alignment == 0
+            }
+            _ => true,
+        }
+    }
+
+    /// fail an IO which is not aligned with invalid field in command, the NVMe
+    /// equivalent of EINVAL
+    fn fail_misaligned(&mut self) {
+        self.nexus().metrics.io_misaligned();
+        self.ctx_as_mut().nvme_status = NvmeStatusCode {
+            sct: SPDK_NVME_SCT_GENERIC as u8,
+            sc: SPDK_NVME_SC_INVALID_FIELD as u8,
+        };
+        self.fail();
+    }
+
+    /// fail an IO which is submitted to all children as the nexus has no
+    /// writable child left, with the status of the failure status policy
+    fn fail_no_writers(&mut self) {
+        let nexus = self.nexus();
+        nexus.metrics.no_writers();
+        nexus.metrics.submit_failed(self.cmd(), Errno::ENODEV);
+        warn!(
+            "{}: no writable children, failing {:?} of {} blocks at {}",
+            nexus.name,
+            self.cmd(),
+            self.num_blocks(),
+            self.offset()
+        );
+        nexus_event::emit(NexusEvent::NoWritableChildren {
+            nexus: nexus.name.clone(),
+            io_type: self.cmd(),
+        });
+        self.map_failure(FailureCondition::NoWritableChildren);
+        self.fail();
+    }
+
+    /// set the status the failure status policy of the nexus maps the
+    /// condition to, returns false if it maps the condition to none
+    fn map_failure(&mut self, condition: FailureCondition) -> bool {
+        match self.nexus().policy.failure_status.status(condition) {
+            Some(status) => {
+                self.ctx_as_mut().nvme_status = status;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// the nexus this IO was submitted to
+    #[inline(always)]
+    fn nexus(&self) -> &Nexus {
+        unsafe { Nexus::from_raw((*self.bdev().as_ptr()).ctxt) }
+    }
+
+    /// invoke the observers of the nexus on submission of the IO
+    #[inline(always)]
+    fn notify_submit(&self) {
+        let observers = &self.nexus().io_observers;
+        if !observers.is_empty() {
+            let io_type = self.cmd();
+            observers
+                .iter()
+                .for_each(|o| o.on_submit(self.ctx(), io_type));
+        }
+    }
+
+    /// Stamp the time the IO is submitted. The clock is only read for the IO
+    /// when its time is consumed precisely, i.e. by the observers, the IO
+    /// recorder, the latency of a flush or the grace period of the last
+    /// child. Otherwise the IO is stamped with the time of the last scan of
+    /// its channel, which is as precise as the IO timeout and the listing of
+    /// the IO in flight need.
+    #[inline(always)]
+    fn stamp_submitted(&mut self) {
+        let nexus = self.nexus();
+        let precise = cfg!(feature = "io-recorder")
+            || !nexus.io_observers.is_empty()
+            || self.cmd() == IoType::Flush
+            || nexus.policy.last_child_grace != Duration::default();
+        self.ctx_as_mut().submitted = if precise {
+            Instant::now()
+        } else {
+            self.inner_channel().clock
+        };
+    }
+
+    /// emit an event of the IO trace of the nexus, if one is active
+    #[inline(always)]
+    fn trace(&self, point: impl FnOnce() -> TracePoint) {
+        if !self.ctx().instrumented {
+            return;
+        }
+        let trace = &self.nexus().io_trace;
+        if trace.active() {
+            trace.emit(|time| TraceEvent {
+                time,
+                id: self.as_ptr() as u64,
+                core: self.ctx().core,
+                io_type: self.cmd(),
+                offset: self.offset(),
+                num_blocks: self.num_blocks(),
+                point: point(),
+            });
+        }
+    }
+
+    /// name of the child with the given bdev, or the name of the bdev if it
+    /// no longer belongs to a child
+    fn child_name(&self, bdev: &Bdev) -> String {
+        self.nexus()
+            .child_lookup(&bdev.name())
+            .map_or_else(|| bdev.name(), |child| child.name.clone())
+    }
+
+    /// invoke the observers of the nexus with the final status of the IO. This
+    /// must be done before the IO is completed as it may be reused after.
+    #[inline(always)]
+    fn notify_complete(&self, status: IoStatus) {
+        let instrumented = self.ctx().instrumented;
+        if instrumented {
+            self.inner_channel().outstanding.remove(self);
+        }
+        if self.ctx().admitted {
+            self.release();
+        }
+        if self.cmd() == IoType::Flush {
+            self.nexus()
+                .metrics
+                .flush_completed(self.ctx().submitted.elapsed());
+        }
+        if self.cmd() == IoType::CompareAndWrite {
+            if let Some(lock) = self
+                .inner_channel()
+                .fused_locks
+                .remove(&(self.as_ptr() as usize))
+            {
+                Reactors::current().send_future(lock.release());
+            }
+        }
+        #[cfg(feature = "io-recorder")]
+        self.record(status);
+        if !instrumented {
+            return;
+        }
+        self.trace(|| TracePoint::Complete {
+            status,
+            nvme_status: self.ctx().nvme_status,
+        });
+        let observers = &self.nexus().io_observers;
+        if !observers.is_empty() {
+            observers
+                .iter()
+                .for_each(|o| o.on_complete(self.ctx(), status));
+        }
+    }
+
+    /// account the bytes written to the children for the IO in the write
+    /// amplification of the nexus, when it is instrumented
+    #[inline(always)]
+    fn physical_written(&self, bytes: u64) {
+        if self.ctx().instrumented {
+            self.inner_channel().written.physical += bytes;
+        }
+    }
+
+    /// record the completion of the IO with the recorder of the channel
+    #[cfg(feature = "io-recorder")]
+    fn record(&self, status: IoStatus) {
+        let ctx = self.ctx();
+        let record = IoRecord {
+            core: ctx.core,
+            io_type: self.cmd(),
+            offset: self.offset(),
+            num_blocks: self.num_blocks(),
+            status,
+            submitted: ctx.submitted,
+            latency: ctx.submitted.elapsed(),
+            children: ctx.children,
+            served: ctx
+                .served
+                .as_ref()
+                .and_then(|bdev| self.nexus().child_lookup(&bdev.name()))
+                .map(|child| child.name.clone()),
+            selection: self
+                .inner_channel()
+                .recorder
+                .take_selection(self.as_ptr() as usize),
+            outcomes: self
+                .inner_channel()
+                .recorder
+                .take_outcomes(self.as_ptr() as usize),
+        };
+        self.inner_channel().recorder.record(record);
+    }
+
+    /// describe the IO while it is in flight
+    pub(crate) fn in_flight_io(&self) -> InFlightIo {
+        let ctx = self.ctx();
+        InFlightIo {
+            id: self.as_ptr() as u64,
+            core: ctx.core,
+            io_type: self.cmd(),
+            offset: self.offset(),
+            num_blocks: self.num_blocks(),
+            age: ctx.submitted.elapsed(),
+            children: ctx.in_flight,
+        }
+    }
+
+    /// abort the IO: an IO held while the nexus is paused or queued at the
+    /// outstanding IO limit is failed right away, otherwise the abort of its
+    /// child IOs is requested from the children, which complete them as
+    /// aborted if they have not completed yet. Returns the number of aborts
+    /// the children accepted.
+    pub(crate) fn abort(mut self) -> usize {
+        let inner = self.inner_channel();
+        let held = [&mut inner.paused_ios, &mut inner.throttled_ios]
+            .iter_mut()
+            .any(|queue| {
+                match queue.iter().position(|io| io.as_ptr() == self.as_ptr()) {
+                    Some(i) => queue.remove(i).is_some(),
+                    None => false,
+                }
+            })
+            || inner.no_memory.remove(&self);
+        if held {
+            let ctx = self.ctx_as_mut();
+            ctx.aborted = true;
+            ctx.nvme_status = NvmeStatusCode {
+                sct: SPDK_NVME_SCT_GENERIC as u8,
+                sc: SPDK_NVME_SC_ABORTED_BY_REQUEST as u8,
+            };
+            self.nexus().metrics.io_aborted();
+            self.fail();
+            return 1;
+        }
+
+        // the child IOs of a quorum write complete through its QuorumWrite
+        let bio_cb_arg: *mut c_void = match self.ctx().quorum {
+            Some(write) => write.as_ptr().cast(),
+            None => self.as_ptr().cast(),
+        };
+        let inner = self.inner_channel();
+        let accepted = inner
+            .writers
+            .iter()
+            .chain(inner.rebuilding.iter().map(|(h, _)| h))
+            .filter(|h| {
+                let (desc, chan) = h.io_tuple();
+                let rc = unsafe {
+                    spdk_bdev_abort(
+                        desc,
+                        chan,
+                        bio_cb_arg,
+                        Some(Self::abort_completion),
+                        std::ptr::null_mut(),
+                    )
+                };
+                if rc != 0 {
+                    debug!(
+                        "{}: abort not accepted by child {}: {}",
+                        self.nexus().name,
+                        h.get_bdev().name(),
+                        Errno::from_i32(rc.abs())
+                    );
+                }
+                rc == 0
+            })
+            .count();
+        // the IO may complete with the child IOs which were made to hang, so
+        // they are only released once the aborts have been requested
+        #[cfg(feature = "fault-injection")]
+        let released = self.release_hung_ios();
+        #[cfg(not(feature = "fault-injection"))]
+        let released = 0;
+        accepted + released
+    }
+
+    /// complete the child IOs of this IO which were made to hang as aborted,
+    /// as a device which responds to the abort would
+    #[cfg(feature = "fault-injection")]
+    fn release_hung_ios(&self) -> usize {
+        let inner = self.inner_channel();
+        let (released, kept): (Vec<_>, Vec<_>) = inner
+            .hung_ios
+            .drain(..)
+            .partition(|(io, _)| io.as_ptr() == self.as_ptr());
+        inner.hung_ios = kept;
+        let aborted = NvmeStatusCode {
+            sct: SPDK_NVME_SCT_GENERIC as u8,
+            sc: SPDK_NVME_SC_ABORTED_BY_REQUEST as u8,
+        };
+        released
+            .into_iter()
+            .map(|(mut io, child_io)| {
+                io.inner_channel().replayed.insert(
+                    child_io.as_ptr() as usize,
+                    (IoStatus::Aborted, aborted),
+                );
+                io.complete(child_io, false);
+            })
+            .count()
+    }
+
+    /// the IO has waited on its child IOs for at least the given time and
+    /// has not expired yet. An IO may be stamped up to a scan of its channel
+    /// before it was submitted, which the time waited allows for.
+    pub(crate) fn expired(&self, timeout: Duration) -> bool {
+        let ctx = self.ctx();
+        ctx.in_flight > 0
+            && !ctx.expired
+            && ctx.submitted.elapsed() >= timeout + IO_TIMEOUT_SCAN
+    }
+
+    /// expire the IO: the given children, which have not completed any IO
+    /// for the IO timeout, are retired and the child IOs of the IO are
+    /// aborted. The IO fails once they have completed.
+    pub(crate) fn expire(mut self, hung: &[Bdev]) {
+        assert_eq!(self.ctx().core, Cores::current());
+        warn!(
+            "{}: {:?} of {} blocks at {} timed out after {:?}, {} child IOs in flight",
+            self.nexus().name,
+            self.cmd(),
+            self.num_blocks(),
+            self.offset(),
+            self.ctx().submitted.elapsed(),
+            self.ctx().in_flight,
+        );
+        self.ctx_as_mut().expired = true;
+        self.nexus().metrics.io_timed_out();
+        hung.iter()
+            .for_each(|bdev| self.retire(bdev.clone(), Reason::IoTimeout));
+        self.abort();
+    }
+
+    /// fail an IO which expired once all its child IOs have completed, with
+    /// the status of the timeout condition
+    fn fail_expired(&mut self) {
+        self.map_failure(FailureCondition::Timeout);
+        self.fail();
+    }
+
+    /// invoked when the abort of the child IOs completes, the child IOs
+    /// themselves are completed through `child_completion`
+    extern "C" fn abort_completion(
+        io: *mut spdk_bdev_io,
+        _success: bool,
+        _arg: *mut c_void,
+    ) {
+        unsafe { spdk_bdev_free_io(io) };
+    }
+
+    /// mark the IO as successful
+    #[inline(always)]
+    fn ok(&self) {
+        if self.cmd() == IoType::Read && !self.md_buf().is_null() {
+            self.fill_read_checksums();
+        }
+        self.write_completed();
+        self.notify_complete(IoStatus::Success);
+        self.0.ok();
+    }
+
+    /// mark the IO as failed, with the NVMe status a child failed it with if
+    /// any, such that the initiator learns why the IO failed
+    #[inline(always)]
+    fn fail(&self) {
+        self.write_completed();
+        let status = self.ctx().nvme_status;
+        if status.is_success() {
+            self.notify_complete(IoStatus::Failed);
+            self.0.fail();
+        } else {
+            self.notify_complete(IoStatus::NvmeError);
+            self.0.fail_nvme(status);
+        }
+    }
+
+    /// fail an IO whose submission to the children failed with the given
+    /// error, the range of the IO not fitting a child (EINVAL) failing it
+    /// as out of range
+    fn fail_submission(&mut self, se: Errno) {
+        if se == Errno::EINVAL {
+            self.ctx_as_mut().nvme_status = NvmeStatusCode::LBA_OUT_OF_RANGE;
+        }
+        self.fail();
+    }
+
+    /// fail the IO with a status which makes the initiator retry it
+    pub(crate) fn fail_retriable(&self) {
+        self.write_completed();
+        self.notify_complete(IoStatus::NvmeError);
+        self.0.fail_retriable();
+    }
+
+    /// complete an IO which failed on all children with the status of the
+    /// failure status policy of the nexus, or according to its all failed
+    /// policy when it has none
+    fn fail_all(&mut self) {
+        let condition = if self.ctx().timed_out {
+            FailureCondition::Timeout
+        } else {
+            FailureCondition::AllChildrenFailed
+        };
+        if self.map_failure(condition) {
+            self.fail();
+        } else if self.nexus().policy.all_failed
+            == AllFailedPolicy::AllReplicasFailed
+        {
+            self.write_completed();
+            self.notify_complete(IoStatus::NvmeError);
+            self.0.fail_all_replicas();
+        } else {
+            self.fail();
+        }
+    }
+
+    /// resubmit an IO which failed on all children
+    fn retry(&mut self) {
+        let ctx = self.ctx_as_mut();
+        ctx.retried = true;
+        ctx.tried = 0;
+        ctx.status = IoStatus::Pending;
+        ctx.in_flight = 0;
+        ctx.num_ok = 0;
+        ctx.nvme_status = NvmeStatusCode::default();
+        ctx.miscompares = 0;
+        self.inner_channel()
+            .diverged
+            .remove(&(self.as_ptr() as usize));
+        self.clone().submit();
+    }
+
+    /// mark the IO as impossible to submit due to a memory constraint,
+    /// according to the no memory policy of the nexus
+    fn no_mem(&self) {
+        let nexus = self.nexus();
+        nexus.metrics.no_memory();
+        match nexus.policy.no_memory {
+            NoMemoryPolicy::Resubmit => self.complete_no_mem(),
+            NoMemoryPolicy::Delay(delay) => {
+                let io = self.clone();
+                Reactors::current().send_future(async move {
+                    throttle(delay).await;
+                    io.complete_no_mem();
+                });
+            }
+            NoMemoryPolicy::Busy => self.fail_retriable(),
+            NoMemoryPolicy::Backoff(backoff) => {
+                let requeued = matches!(
+                    self.cmd(),
+                    IoType::Read
+                        | IoType::Write
+                        | IoType::WriteZeros
+                        | IoType::Unmap
+                ) && self
+                    .inner_channel()
+                    .requeue_no_mem(self.clone(), backoff);
+                if requeued {
+                    nexus.metrics.no_memory_requeued();
+                } else {
+                    self.complete_no_mem();
+                }
+            }
+        }
+    }
+
+    /// resubmit an IO which was requeued as the children ran out of memory
+    pub(crate) fn resubmit_no_mem(mut self) {
+        let ctx = self.ctx_as_mut();
+        ctx.status = IoStatus::Pending;
+        ctx.in_flight = 0;
+        ctx.num_ok = 0;
+        ctx.nvme_status = NvmeStatusCode::default();
+        ctx.miscompares = 0;
+        self.inner_channel()
+            .diverged
+            .remove(&(self.as_ptr() as usize));
+        self.submit();
+    }
+
+    /// complete the IO with NoMemory, for SPDK to resubmit it
+    #[inline(always)]
+    fn complete_no_mem(&self) {
+        self.write_completed();
+        self.notify_complete(IoStatus::NoMemory);
+        self.0.no_mem();
+    }
+
+    #[inline(always)]
+    /// a mutable reference to the IO context
+    fn ctx_as_mut(&mut self) -> &mut NioCtx {
+        self.specific_as_mut::<NioCtx>()
+    }
+
+    #[inline(always)]
+    /// immutable reference to the IO context
+    fn ctx(&self) -> &NioCtx {
+        self.specific::<NioCtx>()
+    }
+
+    /// the links of the IO in the IO in flight on its channel
+    pub(crate) fn link(&self) -> &IoLink {
+        &self.ctx().link
+    }
+
+    /// the links of the IO in the IO in flight on its channel
+    pub(crate) fn link_mut(&mut self) -> &mut IoLink {
+        &mut self.ctx_as_mut().link
+    }
+
+    /// Determine what to do with the IO if anything. In principle, it's the
+    /// same way any other IO is completed but, it wrapped by the
+    /// disposition. The general approach is if we return Disposition::
+    /// Retire(IoStatus::Success) it means that we retire the current child and
+    /// then, return mark the IO successful.
+    fn disposition(&mut self) -> Disposition {
+        let ctx = self.ctx_as_mut();
+        match ctx.status {
+            // all child IO's completed, complete the parent IO
+            IoStatus::Pending if ctx.in_flight == 0 => {
+                Disposition::Complete(IoStatus::Success)
+            }
+            // some child IO has completed, but not all
+            IoStatus::Pending if ctx.in_flight != 0 => {
+                Disposition::Flying(IoStatus::Success)
+            }
+            // Other IO are still inflight we encountered an error, retire this
+            // child
+            IoStatus::Failed if ctx.in_flight != 0 => {
+                Disposition::Retire(IoStatus::Pending)
+            }
+
+            // this IO failed, but we have seen successfully IO's for the parent
+            // already retire it
+            IoStatus::Failed if ctx.num_ok != 0 && ctx.in_flight == 0 => {
+                Disposition::Retire(IoStatus::Success)
+            }
+
+            // ALL io's have failed
+            IoStatus::Failed if ctx.num_ok == 0 && ctx.in_flight == 0 => {
+                Disposition::Complete(IoStatus::Failed)
+            }
+            // all IOs that where partially submitted completed, no bubble up
+            // the ENOMEM to the upper layer we do not care if the
+            // IO failed or complete, the whole IO must be resubmitted
+            IoStatus::NoMemory if ctx.in_flight == 0 => {
+                Disposition::Complete(IoStatus::NoMemory)
+            }
+            // a child IO was aborted on request, which does not reflect on
+            // the health of the child; the IO completes as aborted once the
+            // other child IOs have completed
+            IoStatus::Aborted if ctx.in_flight != 0 => {
+                Disposition::Flying(IoStatus::Aborted)
+            }
+            IoStatus::Aborted => Disposition::Complete(IoStatus::Aborted),
+            _ => {
+                error!("{:?}", ctx);
+                Disposition::Complete(IoStatus::Failed)
+            }
+        }
+    }
+
+    /// returns the type of command for this IO
+    #[inline(always)]
+    fn cmd(&self) -> IoType {
+        self.io_type()
+    }
+
+    /// completion handler for the nexus when a child IO completes
+    pub fn complete(&mut self, child_io: Bio, success: bool) {
+        self.inner_channel().child_io_completed(&child_io.bdev());
+        if success {
+            self.child_io_completed(&child_io);
+        }
+        self.complete_child(&child_io, success);
+
+        // always free the child IO. The status of the child IO has been set by
+        // the underlying device before invocation of the callback.
+        child_io.free();
+    }
+
+    /// account the completion of a child IO of this IO, without freeing the
+    /// child IO as it may be shared by several IOs of a coalesced write. The
+    /// latency of a successful child IO is accounted by the caller.
+    fn complete_child(&mut self, child_io: &Bio, mut success: bool) {
+        assert_eq!(self.ctx().core, Cores::current());
+        self.unbounce(child_io, success);
+        let (status, nvme_status) = self.child_status(child_io);
+        self.trace(|| TracePoint::ChildComplete {
+            child: self.child_name(&child_io.bdev()),
+            status,
+            nvme_status,
+        });
+
+        #[cfg(feature = "io-recorder")]
+        {
+            let outcome = ChildOutcome {
+                child: self.child_name(&child_io.bdev()),
+                status,
+                nvme_status,
+                latency: child_io.elapsed(),
+            };
+            let io = self.as_ptr() as usize;
+            self.inner_channel().recorder.child_completed(io, outcome);
+            let ctx = self.ctx_as_mut();
+            ctx.children = ctx.children.saturating_add(1);
+        }
+
+        // a read which returned corrupted data is served from the other
+        // children instead, and the child that returned it is retired
+        if success && self.cmd() == IoType::Read && !self.verify_read(child_io)
+        {
+            self.ctx_as_mut().in_flight -= 1;
+            let bdev = child_io.bdev();
+            error!(
+                "{}: read of blocks at {} from child {} failed verification",
+                self.nexus().name,
+                self.offset(),
+                bdev.name()
+            );
+            self.retire(bdev.clone(), Reason::DataCorruption);
+            Reactors::current().send_future(Self::read_repair(
+                self.clone(),
+                bdev.name(),
+                true,
+            ));
+            return;
+        }
+
+        #[cfg(feature = "io-recorder")]
+        if success && self.cmd() == IoType::Read {
+            self.ctx_as_mut().served = Some(child_io.bdev());
+        }
+
+        // a sampled read is compared with another child before it completes
+        if success && self.cmd() == IoType::Read && self.sampled() {
+            Reactors::current()
+                .send_future(Self::sample_read(self.clone(), child_io.bdev()));
+            return;
+        }
+
+        // keep the detailed status of a child which failed with an NVMe error
+        let mut retire = true;
+        if !success && status == IoStatus::NvmeError {
+            retire = !self.nexus().policy.no_fault.contains(&nvme_status);
+            self.ctx_as_mut().nvme_status = nvme_status;
+            self.nexus().metrics.child_error(nvme_status);
+            if nvme_status.do_not_retry() {
+                self.nexus().metrics.child_dnr_error();
+            }
+        }
+
+        // a child IO aborted on request does not reflect on the health of the
+        // child, the IO completes as aborted such that the initiator knows
+        // the data of an aborted write is undefined on the children
+        if !success && status == IoStatus::Aborted {
+            let ctx = self.ctx_as_mut();
+            ctx.aborted = true;
+            ctx.nvme_status = NvmeStatusCode {
+                sct: SPDK_NVME_SCT_GENERIC as u8,
+                sc: SPDK_NVME_SC_ABORTED_BY_REQUEST as u8,
+            };
+            return self.child_completed(
+                child_io.bdev(),
+                IoStatus::Aborted,
+                false,
+            );
+        }
+
+        // a child which does not support the IO is handled according to the
+        // policy of the nexus rather than counting it as failed outright
+        let unsupported =
+            !success && nvme_status == NvmeStatusCode::INVALID_OPCODE;
+        if unsupported {
+            match self.nexus().policy.unsupported.action(self.cmd()) {
+                UnsupportedAction::Emulate
+                    if self.cmd() == IoType::WriteZeros =>
+                {
+                    Reactors::current().send_future(
+                        Self::emulate_write_zeroes(
+                            self.clone(),
+                            child_io.bdev(),
+                        ),
+                    );
+                    return;
+                }
+                UnsupportedAction::Emulate | UnsupportedAction::Ignore => {
+                    debug!(
+                        "{}: ignoring unsupported {:?} on child {}",
+                        self.nexus().name,
+                        self.cmd(),
+                        child_io.bdev().name()
+                    );
+                    success = true;
+                }
+                UnsupportedAction::Fail => {}
+            }
+        }
+
+        // data which differs on a child does not reflect on its health, the
+        // compare fails once it has completed on all children, unless it is
+        // decided by a quorum of the children
+        if !success && status == IoStatus::MisCompared {
+            let ctx = self.ctx_as_mut();
+            ctx.miscompares = ctx.miscompares.saturating_add(1);
+            ctx.nvme_status = NvmeStatusCode::COMPARE_FAILURE;
+            success = true;
+            if self.nexus().policy.unhandled.action(self.cmd())
+                == UnhandledAction::Quorum
+            {
+                self.inner_channel()
+                    .diverged
+                    .entry(self.as_ptr() as usize)
+                    .or_default()
+                    .push(child_io.bdev());
+            }
+        }
+
+        // a flush which failed on a child is handled according to the flush
+        // failure policy of the nexus
+        if !success && self.cmd() == IoType::Flush {
+            self.nexus().metrics.child_flush_failed();
+            match self.nexus().policy.flush_failure {
+                FlushFailurePolicy::Retry(retries)
+                    if self.ctx().flush_retries < retries =>
+                {
+                    if self.retry_flush(&child_io.bdev()) {
+                        return;
+                    }
+                }
+                FlushFailurePolicy::Fail => {
+                    self.ctx_as_mut().flush_failed = true;
+                    retire = false;
+                }
+                _ => {}
+            }
+        }
+
+        // children which failed with a status of the no fault policy, by
+        // default those which do not support the IO, are not retired
+        self.child_completed(child_io.bdev(), success.into(), retire);
+    }
+
+    /// resubmit a flush which failed on the given child to it, returns true
+    /// if it has been resubmitted
+    fn retry_flush(&mut self, child: &Bdev) -> bool {
+        let hdl = match self
+            .write_targets()
+            .find(|h| h.get_bdev().as_ptr() == child.as_ptr())
+        {
+            Some(hdl) => hdl,
+            None => return false,
+        };
+        if self.submit_flush_blocks(hdl).is_err() {
+            return false;
+        }
+        warn!(
+            "{}: retrying flush on child {}",
+            self.nexus().name,
+            child.name()
+        );
+        self.inner_channel().child_io_submitted(child);
+        self.ctx_as_mut().flush_retries += 1;
+        true
+    }
+
+    /// account the latency of a child IO which completed successfully with
+    /// its child
+    fn child_io_completed(&self, child_io: &Bio) {
+        let bdev = child_io.bdev();
+        let nexus = self.nexus();
+        if let Some(i) = nexus.children.iter().position(|c| {
+            c.bdev
+                .as_ref()
+                .map_or(false, |b| b.as_ptr() == bdev.as_ptr())
+        }) {
+            self.inner_channel().io_stats.record(
+                i,
+                &bdev,
+                child_io.io_type(),
+                child_io.elapsed(),
+            );
+            nexus.children[i].io_completed();
+        }
+    }
+
+    /// account for the completion of the IO on the given child with the
+    /// given status, one of success, failed or aborted, and complete the
+    /// parent IO once it has completed on all children. A child which failed
+    /// the IO is retired if `retire` is set.
+    fn child_completed(&mut self, child: Bdev, status: IoStatus, retire: bool) {
+        let success = status == IoStatus::Success;
+        let ctx = self.ctx_as_mut();
+        // decrement the counter of in flight IO
+        ctx.in_flight -= 1;
+
+        // record the state of at least one of the IO's, an aborted child IO
+        // does not hide the failure of another
+        match status {
+            IoStatus::Success => ctx.num_ok += 1,
+            IoStatus::Aborted if ctx.status == IoStatus::Failed => {}
+            IoStatus::Aborted => ctx.status = IoStatus::Aborted,
+            _ => ctx.status = IoStatus::Failed,
+        }
+
+        // an IO which expired fails whatever the outcome of its child IOs
+        if self.ctx().expired {
+            if self.ctx().in_flight == 0 {
+                self.fail_expired();
+            }
+            return;
+        }
+
+        // a write which a quorum of the children completed is acknowledged
+        // without waiting for the others
+        if success && self.quorum_reached() {
+            return self.ack_quorum();
+        }
+
+        match self.disposition() {
+            // the happy path, all is good
+            Disposition::Complete(IoStatus::Success) => {
+                if !self.compare_matched() || self.ctx().flush_failed {
+                    return self.fail();
+                }
+                if self.ctx().retried {
+                    self.nexus()
+                        .last_child_held
+                        .store(false, Ordering::Relaxed);
+                }
+                self.ok()
+            }
+            // All of IO's have failed but all remaining in flights completed
+            // now as well depending on the policy of the nexus we can
+            // attempt to do a retry. A failed read is retried in segments such
+            // that it can be satisfied by combining the data of several
+            // children. An error which must not be retried is never retried
+            // on the child that returned it, a read is then served by the
+            // other children only. Before that a failed read is resubmitted
+            // to each of the readers it has not failed on yet.
+            Disposition::Complete(IoStatus::Failed) => {
+                if self.cmd() == IoType::Read
+                    && !self.ctx().aborted
+                    && self.fail_over_read(&child, retire)
+                {
+                    return;
+                }
+                if self.hold_last_child(&child) {
+                    return;
+                }
+                self.nexus().metrics.io_all_failed();
+                // reads are submitted to a single child, which is accounted
+                // here, failed writes are never accounted when all children
+                // failed them, as they cannot all be retired
+                if retire && self.cmd() == IoType::Read {
+                    self.child_io_failed(child.clone());
+                }
+                let dnr = self.ctx().nvme_status.do_not_retry();
+                match self.nexus().policy.all_failed {
+                    // an aborted IO is neither retried nor repaired
+                    _ if self.ctx().aborted => self.fail(),
+                    AllFailedPolicy::RetryOnce
+                        if !self.ctx().retried && !dnr =>
+                    {
+                        self.retry()
+                    }
+                    _ if self.cmd() == IoType::Read => {
+                        Reactors::current().send_future(Self::read_repair(
+                            self.clone(),
+                            child.name(),
+                            dnr,
+                        ));
+                    }
+                    _ => self.fail_all(),
+                }
+            }
+
+            // IOs were submitted before we bumped into ENOMEM. The IO has
+            // now completed, so we can finally report back to the
+            // callee that we encountered ENOMEM during submission
+            Disposition::Complete(IoStatus::NoMemory) => self.no_mem(),
+
+            // a child IO was aborted, fail the IO with aborted by request
+            Disposition::Complete(IoStatus::Aborted) => {
+                self.nexus().metrics.io_aborted();
+                self.fail();
+            }
+
+            // We can mark the IO as success but before we do we need to retire
+            // this child. This typically would only match when the last IO
+            // has failed i.e [ok,ok,fail]
+            Disposition::Retire(IoStatus::Success) => {
+                assert_eq!(success, false);
+                error!(
+                    ?self,
+                    ?child,
+                    "{}:{}",
+                    Cores::current(),
+                    "last child IO failed completion"
+                );
+                if retire {
+                    self.child_io_failed(child.clone());
+                }
+                if !self.compare_matched()
+                    || self.ctx().flush_failed
+                    || self.ctx().aborted
+                {
+                    return self.fail();
+                }
+                self.ok();
+            }
+
+            // IO still in flight (pending) fail this IO and continue by setting
+            // the parent status back to pending for example [ok,
+            // fail, pending]
+            Disposition::Retire(IoStatus::Pending) => {
+                assert_eq!(success, false);
+                error!(
+                    ?self,
+                    ?child,
+                    "{}:{}",
+                    Cores::current(),
+                    "some child IO completion failed"
+                );
+
+                if retire {
+                    self.child_io_failed(child.clone());
+                }
+                // more IO is pending ensure we set the proper context state,
+                // keeping track of a child IO which was aborted before
+                let ctx = self.ctx_as_mut();
+                ctx.status = if ctx.aborted {
+                    IoStatus::Aborted
+                } else {
+                    IoStatus::Pending
+                };
+            }
+            // Disposition::Flying(_) => {
+            //     assert_eq!(self.ctx().status, IoStatus::Pending);
+            //     assert_ne!(self.ctx().in_flight, 0);
+            // },
+            _ => {}
+        }
+    }
+
+    /// whether a quorum of the children completed the write while others
+    /// have yet to complete it
+    fn quorum_reached(&self) -> bool {
+        let ctx = self.ctx();
+        match self.nexus().policy.write_ack {
+            WriteAck::Quorum(quorum) => {
+                ctx.quorum.is_some()
+                    && ctx.status == IoStatus::Pending
+                    && ctx.in_flight != 0
+                    && usize::from(ctx.num_ok) >= quorum
+            }
+            WriteAck::All => false,
+        }
+    }
+
+    /// acknowledge a write which a quorum of the children completed, the
+    /// child IOs still in flight complete through its QuorumWrite
+    fn ack_quorum(&mut self) {
+        // the write leaves the flush barrier once it has completed on all
+        // children, which the QuorumWrite accounts
+        let seq = std::mem::take(&mut self.ctx_as_mut().seq);
+        if let Some(mut write) = self.ctx_as_mut().quorum.take() {
+            unsafe { write.as_mut() }.detach(seq);
+        }
+        self.nexus().metrics.quorum_acked();
+        self.ok();
+    }
+
+    /// Whether the data of a compare which completed on all children matched.
+    /// A compare decided by a quorum matches when the data matched on more
+    /// children than it differed on, the children on which it differed are
+    /// then retired as they diverged from the others. Any other compare
+    /// matches only when the data matched on every child.
+    fn compare_matched(&self) -> bool {
+        let miscompares = self.ctx().miscompares;
+        if miscompares == 0 {
+            return true;
+        }
+        let diverged = match self
+            .inner_channel()
+            .diverged
+            .remove(&(self.as_ptr() as usize))
+        {
+            Some(diverged) => diverged,
+            None => return false,
+        };
+        let matched = self.ctx().num_ok - miscompares;
+        if matched <= miscompares {
+            warn!(
+                "{}: compare of {} blocks at {} differs on {} of {} children",
+                self.nexus().name,
+                self.num_blocks(),
+                self.offset(),
+                miscompares,
+                self.ctx().num_ok
+            );
+            return false;
+        }
+        for bdev in diverged {
+            error!(
+                "{}: compare of {} blocks at {} differs on child {} only",
+                self.nexus().name,
+                self.num_blocks(),
+                self.offset(),
+                bdev.name()
+            );
+            self.nexus().metrics.compare_diverged();
+            self.retire(bdev, Reason::DataCorruption);
+        }
+        true
+    }
+
+    /// retry an IO which failed on the last healthy child after a short delay
+    /// for as long as the grace period of the nexus allows, rather than
+    /// failing the IO and faulting the child. Returns true if the IO is
+    /// retried.
+    fn hold_last_child(&mut self, child: &Bdev) -> bool {
+        let nexus = self.nexus();
+        let ctx = self.ctx();
+        let grace = nexus.policy.last_child_grace;
+        if grace == Duration::default()
+            || ctx.aborted
+            || ctx.nvme_status.do_not_retry()
+        {
+            return false;
+        }
+
+        let mut open = nexus
+            .children
+            .iter()
+            .filter(|c| c.state() == ChildState::Open);
+        let last = match (open.next(), open.next()) {
+            (Some(c), None) => c
+                .bdev
+                .as_ref()
+                .map_or(false, |b| b.as_ptr() == child.as_ptr()),
+            _ => false,
+        };
+        if !last {
+            return false;
+        }
+
+        if ctx.submitted.elapsed() >= grace {
+            warn!(
+                "{}: IO kept failing on last child {} for {:?}, giving up",
+                nexus.name,
+                child.name(),
+                grace
+            );
+            nexus.last_child_held.store(false, Ordering::Relaxed);
+            self.ctx_as_mut().timed_out = true;
+            return false;
+        }
+
+        if !nexus.last_child_held.swap(true, Ordering::Relaxed) {
+            warn!(
+                "{}: IO failed on last child {}, retrying for up to {:?}",
+                nexus.name,
+                child.name(),
+                grace
+            );
+            nexus_event::emit(NexusEvent::LastChildProtected {
+                nexus: nexus.name.clone(),
+                child: child.name(),
+            });
+        }
+
+        let mut io = self.clone();
+        Reactors::current().send_future(async move {
+            throttle(LAST_CHILD_RETRY_INTERVAL).await;
+            io.retry();
+        });
+        true
+    }
+
+    /// reference to the inner channels. The inner channel contains the specific
+    /// per-core data structures.
+    #[allow(clippy::mut_from_ref)]
+    fn inner_channel(&self) -> &mut NexusChannelInner {
+        NexusChannel::inner_from_channel(self.ctx().channel.as_ptr())
+    }
+
+    /// offset of the IO on the given child, which accounts for the offset of
+    /// the data partition of that child
+    #[inline(always)]
+    fn child_offset(&self, hdl: &BdevHandle) -> Result<u64, Errno> {
+        self.child_range(hdl, self.num_blocks())
+    }
+
+    /// offset on the given child of the given number of blocks at the offset
+    /// of the IO, which are rejected with EINVAL when they do not fit on it
+    fn child_range(
+        &self,
+        hdl: &BdevHandle,
+        num_blocks: u64,
+    ) -> Result<u64, Errno> {
+        let bdev = hdl.get_bdev();
+        #[cfg(feature = "fault-injection")]
+        let child_blocks = injected_child_blocks(&bdev.name())
+            .unwrap_or_else(|| bdev.num_blocks());
+        #[cfg(not(feature = "fault-injection"))]
+        let child_blocks = bdev.num_blocks();
+        child_lba(
+            self.offset(),
+            self.inner_channel().data_offset(&bdev),
+            num_blocks,
+            child_blocks,
+        )
+        .map_err(|e| {
+            error!(
+                "{}: {} blocks at {} are out of range of child {}",
+                self.nexus().name,
+                num_blocks,
+                self.offset(),
+                bdev.name()
+            );
+            self.nexus().metrics.out_of_range();
+            e
+        })
+    }
+
+    /// helper routine to get a channel to read from
+    fn read_channel_at_index(&self, i: usize) -> &BdevHandle {
+        &self.inner_channel().readers[i]
+    }
+
+    /// submit a read operation to one of the children of this nexus
+    #[inline(always)]
+    fn submit_read(&self, hdl: &BdevHandle) -> Result<(), Errno> {
+        let (desc, chan) = hdl.io_tuple();
+        let offset = self.child_offset(hdl)?;
+        let bounce = self.bounce_buf(hdl)?;
+        let result = unsafe {
+            match bounce {
+                Some(buf) => spdk_bdev_read_blocks(
+                    desc,
+                    chan,
+                    buf,
+                    offset,
+                    self.num_blocks(),
+                    Some(Self::child_completion),
+                    self.as_ptr().cast(),
+                ),
+                None => spdk_bdev_readv_blocks(
+                    desc,
+                    chan,
+                    self.iovs(),
+                    self.iov_count(),
+                    offset,
+                    self.num_blocks(),
+                    Some(Self::child_completion),
+                    self.as_ptr().cast(),
+                ),
+            }
+        }
+        .to_result(Errno::from_i32);
+        if bounce.is_some() {
+            self.bounce_submitted(result.is_ok());
+        }
+        result
+    }
+
+    /// The bounce buffer to submit the IO to the child from when the child
+    /// does not accept as many iovecs as the IO has, see `nexus_bounce`. The
+    /// data of a write is copied into the buffer when it is taken.
+    fn bounce_buf(
+        &self,
+        hdl: &BdevHandle,
+    ) -> Result<Option<*mut c_void>, Errno> {
+        let bdev = hdl.get_bdev();
+        let max = bdev.max_num_segments();
+        if max == 0 || self.iov_count() as u32 <= max {
+            return Ok(None);
+        }
+        let len = self.num_blocks() * self.block_len();
+        let iovs = unsafe {
+            std::slice::from_raw_parts(self.iovs(), self.iov_count() as usize)
+        };
+        let (buf, taken) = self
+            .inner_channel()
+            .bounce
+            .get(self.as_ptr() as usize, len, bdev.alignment())
+            .ok_or(Errno::ENOMEM)?;
+        if taken && self.cmd() == IoType::Write {
+            copy_from_iovs(iovs, buf.as_mut_slice());
+        }
+        Ok(Some(**buf))
+    }
+
+    /// account a child IO submitted from the bounce buffer of the IO, the
+    /// buffer is returned to the pool if no child IO was submitted from it
+    fn bounce_submitted(&self, submitted: bool) {
+        let bounce = &mut self.inner_channel().bounce;
+        if submitted {
+            bounce.submitted(self.as_ptr() as usize);
+        } else {
+            bounce.settle(self.as_ptr() as usize);
+        }
+    }
+
+    /// Account the completion of a child IO submitted from the bounce buffer
+    /// of the IO, if it was. The data of a successful read is copied out of
+    /// the buffer into the iovecs of the IO.
+    fn unbounce(&self, child_io: &Bio, success: bool) {
+        if !matches!(self.cmd(), IoType::Read | IoType::Write)
+            || child_io.iovs().is_null()
+        {
+            return;
+        }
+        let data = unsafe { (*child_io.iovs()).iov_base };
+        if let Some(buf) = self
+            .inner_channel()
+            .bounce
+            .completed(self.as_ptr() as usize, data)
+        {
+            if success && self.cmd() == IoType::Read {
+                let iovs = unsafe {
+                    std::slice::from_raw_parts(
+                        self.iovs(),
+                        self.iov_count() as usize,
+                    )
+                };
+                copy_to_iovs(buf.as_slice(), iovs);
+            }
+        }
+    }
+
+    /// the bit of the given child in the children a read has been tried on,
+    /// none for a child which is not part of the nexus
+    fn tried_bit(&self, bdev: &Bdev) -> u64 {
+        self.nexus()
+            .children
+            .iter()
+            .take(64)
+            .position(|c| {
+                c.bdev.as_ref().map(|b| b.as_ptr()) == Some(bdev.as_ptr())
+            })
+            .map_or(0, |i| 1 << i)
+    }
+
+    /// Resubmit a read which failed on the given child to the next reader it
+    /// has not been tried on, the reader selected by the read policy of the
+    /// nexus first. Returns false when the read has failed on all readers
+    /// of the channel, such that the read is failed or repaired as a read
+    /// which failed on all children.
+    fn fail_over_read(&mut self, failed: &Bdev, retire: bool) -> bool {
+        let bit = self.tried_bit(failed);
+        self.ctx_as_mut().tried |= bit;
+        let readers = self.inner_channel().readers.len();
+        let selected = self.select_reader();
+        for i in selected.into_iter().chain(0 .. readers) {
+            let hdl = self.read_channel_at_index(i);
+            let bdev = hdl.get_bdev();
+            let bit = self.tried_bit(&bdev);
+            if bdev.as_ptr() == failed.as_ptr()
+                || self.ctx().tried & bit != 0
+                || (self.ctx().in_sync && !self.inner_channel().in_sync(&bdev))
+            {
+                continue;
+            }
+            self.ctx_as_mut().tried |= bit;
+            if let Err(e) = self.submit_read(self.read_channel_at_index(i)) {
+                self.nexus().metrics.submit_failed(IoType::Read, e);
+                continue;
+            }
+
+            warn!(
+                "{}: read of {} blocks at {} failed on child {}, resubmitted to child {}",
+                self.nexus().name,
+                self.num_blocks(),
+                self.offset(),
+                failed.name(),
+                bdev.name()
+            );
+            self.inner_channel().child_io_submitted(&bdev);
+            self.trace(|| TracePoint::Dispatch {
+                child: self.child_name(&bdev),
+            });
+            let ctx = self.ctx_as_mut();
+            ctx.status = IoStatus::Pending;
+            ctx.nvme_status = NvmeStatusCode::default();
+            ctx.in_flight += 1;
+            self.nexus().metrics.read_failed_over();
+            if retire {
+                self.child_io_failed(failed.clone());
+            }
+            return true;
+        }
+        false
+    }
+
+    /// select the reader of the channel a read is submitted to, which is one
+    /// in sync for a read submitted through a reader opened with
+    /// `ReadFreshness::InSync`
+    fn select_reader(&mut self) -> Option<usize> {
+        if self.ctx().in_sync {
+            self.inner_channel().child_select_in_sync()
+        } else {
+            self.inner_channel().child_select()
+        }
+    }
+
+    /// submit read IO to some child
+    fn readv(&mut self) -> Result<(), Errno> {
+        if !self.nexus().in_sync_descs.is_empty() {
+            let desc = self.desc() as usize;
+            let in_sync = self.nexus().in_sync_descs.contains(&desc);
+            self.ctx_as_mut().in_sync = in_sync;
+        }
+
+        // a read fails right away rather than being served by a degraded
+        // nexus, such that the consumer can fail over quickly
+        if let Some(min) = self.nexus().policy.min_readers {
+            if self.inner_channel().readers.len() < min {
+                self.nexus().metrics.read_fast_failed();
+                self.ctx_as_mut().nvme_status =
+                    NvmeStatusCode::NAMESPACE_NOT_READY;
+                self.fail();
+                return Ok(());
+            }
+        }
+
+        if let Some(i) = self.select_reader() {
+            #[cfg(feature = "io-recorder")]
+            {
+                let selection = self.inner_channel().selection(i);
+                self.inner_channel()
+                    .recorder
+                    .selected(self.as_ptr() as usize, selection);
+            }
+            let hdl = self.read_channel_at_index(i);
+            let bdev = hdl.get_bdev();
+            match self.submit_read(hdl) {
+                Ok(_) => {
+                    self.inner_channel().child_io_submitted(&bdev);
+                    self.trace(|| TracePoint::Dispatch {
+                        child: self.child_name(&bdev),
+                    });
+                    self.ctx_as_mut().in_flight += 1;
+                    Ok(())
+                }
+                Err(e) => {
+                    self.nexus().metrics.submit_failed(IoType::Read, e);
+                    if e == Errno::ENOMEM {
+                        self.no_mem();
+                    } else {
+                        self.fail_submission(e);
+                    }
+                    Err(e)
+                }
+            }
+        } else {
+            self.nexus()
+                .metrics
+                .submit_failed(IoType::Read, Errno::ENODEV);
+            // a reader which must only read from children in sync tells that
+            // none is from the failure of a read on a degraded nexus
+            if self.ctx().in_sync {
+                self.ctx_as_mut().nvme_status =
+                    NvmeStatusCode::NAMESPACE_NOT_READY;
+            }
+            self.fail();
+            Err(Errno::ENODEV)
+        }
+    }
+
+    /// submit a compare to one of the children of this nexus
+    fn submit_compare(&self, hdl: &BdevHandle) -> Result<(), Errno> {
+        let (desc, chan) = hdl.io_tuple();
+        let offset = self.child_offset(hdl)?;
+        unsafe {
+            spdk_bdev_comparev_blocks(
+                desc,
+                chan,
+                self.iovs(),
+                self.iov_count(),
+                offset,
+                self.num_blocks(),
+                Some(Self::child_completion),
+                self.as_ptr().cast(),
+            )
+        }
+        .to_result(Errno::from_i32)
+    }
+
+    /// Submit IO of a type which the nexus does not handle itself according
+    /// to the unhandled IO policy of the nexus. Only actions which are
+    /// implemented for the IO type can be set, see `set_unhandled_policy`.
+    fn submit_unhandled(&mut self) -> Result<(), Errno> {
+        let io_type = self.cmd();
+        self.nexus().metrics.io_unhandled(io_type);
+        match self.nexus().policy.unhandled.action(io_type) {
+            UnhandledAction::SingleChild => {
+                let i = match self.inner_channel().child_select() {
+                    Some(i) => i,
+                    None => {
+                        self.fail();
+                        return Err(Errno::ENODEV);
+                    }
+                };
+                let hdl = self.read_channel_at_index(i);
+                let bdev = hdl.get_bdev();
+                match self.submit_compare(hdl) {
+                    Ok(_) => {
+                        self.inner_channel().child_io_submitted(&bdev);
+                        self.trace(|| TracePoint::Dispatch {
+                            child: self.child_name(&bdev),
+                        });
+                        self.ctx_as_mut().in_flight += 1;
+                        Ok(())
+                    }
+                    Err(Errno::ENOMEM) => {
+                        self.no_mem();
+                        Err(Errno::ENOMEM)
+                    }
+                    Err(e) => {
+                        self.fail();
+                        Err(e)
+                    }
+                }
+            }
+            UnhandledAction::Broadcast | UnhandledAction::Quorum => {
+                self.submit_all()
+            }
+            UnhandledAction::Emulate => {
+                Reactors::current()
+                    .send_future(Self::emulate_compare(self.clone()));
+                Ok(())
+            }
+            UnhandledAction::Fail => {
+                trace!(io = ?self, "not supported");
+                self.fail();
+                Err(Errno::EOPNOTSUPP)
+            }
+        }
+    }
+
+    /// emulate a compare by reading the data from a healthy child and
+    /// comparing it with the data of the IO
+    async fn emulate_compare(mut self) {
+        let nexus = self.nexus();
+        let data = match nexus
+            .children
+            .iter()
+            .find(|c| c.state() == ChildState::Open)
+        {
+            Some(child) => self.read_from_child(child).await,
+            None => None,
+        };
+
+        match data {
+            Some(buf) if self.iovs_equal(buf.as_slice()) => self.ok(),
+            Some(_) => {
+                self.ctx_as_mut().nvme_status = NvmeStatusCode::COMPARE_FAILURE;
+                self.fail();
+            }
+            None => {
+                error!(
+                    "{}: failed to read {} blocks at {} to emulate a compare",
+                    nexus.name,
+                    self.num_blocks(),
+                    self.offset()
+                );
+                self.fail();
+            }
+        }
+    }
+
+    /// Evaluate the compare of a compare-and-write on a single authoritative
+    /// child, the first healthy child of the nexus, such that the outcome
+    /// cannot differ between the children. Only when the data matches is the
+    /// write submitted to all children, through the write path; otherwise
+    /// the IO fails with a compare failure and nothing is written. When the
+    /// authoritative child fails the read, or is faulted before the compare
+    /// has been evaluated, the compare is evaluated on the next child.
+    ///
+    /// The blocks are locked on the nexus from before the compare until the
+    /// write has completed, such that no other write or compare-and-write
+    /// to them gets in between.
+    async fn submit_fused(mut self) {
+        let nexus = self.nexus();
+        let lock =
+            match FusedLock::acquire(nexus, self.offset(), self.num_blocks())
+                .await
+            {
+                Ok(lock) => lock,
+                Err(e) => {
+                    error!(
+                        ?e,
+                        "{}: failed to lock {} blocks at {} to compare",
+                        nexus.name,
+                        self.num_blocks(),
+                        self.offset()
+                    );
+                    self.fail();
+                    return;
+                }
+            };
+
+        let mut evaluated = Vec::new();
+        let data = loop {
+            let child = match nexus.children.iter().find(|c| {
+                c.state() == ChildState::Open && !evaluated.contains(&c.name)
+            }) {
+                Some(child) => child,
+                None => break None,
+            };
+            evaluated.push(child.name.clone());
+            match self.read_from_child(child).await {
+                Some(buf) if child.state() == ChildState::Open => {
+                    break Some(buf)
+                }
+                Some(_) => warn!(
+                    "{}: child {} faulted during the compare of {} blocks at {}",
+                    nexus.name,
+                    child.name,
+                    self.num_blocks(),
+                    self.offset()
+                ),
+                None => {
+                    warn!(
+                        "{}: child {} failed the read to compare {} blocks at {}",
+                        nexus.name,
+                        child.name,
+                        self.num_blocks(),
+                        self.offset()
+                    );
+                    if let Some(bdev) = child.bdev.clone() {
+                        self.retire(bdev, Reason::IoError);
+                    }
+                }
+            }
+        };
+
+        match data {
+            Some(buf) if self.iovs_equal(buf.as_slice()) => {
+                // the lock is released once the write has completed
+                self.inner_channel()
+                    .fused_locks
+                    .insert(self.as_ptr() as usize, lock);
+                if let Err(e) = self.submit_all() {
+                    error!(?e, io = ?self, "Error during fused write submission");
+                }
+            }
+            Some(_) => {
+                lock.release().await;
+                self.ctx_as_mut().nvme_status = NvmeStatusCode::COMPARE_FAILURE;
+                self.fail();
+            }
+            None => {
+                lock.release().await;
+                error!(
+                    "{}: no child to compare {} blocks at {} with",
+                    nexus.name,
+                    self.num_blocks(),
+                    self.offset()
+                );
+                self.fail();
+            }
+        }
+    }
+
+    /// read the blocks of this IO from the given child into a new buffer,
+    /// through the handle of the channel to the child
+    async fn read_from_child(&self, child: &NexusChild) -> Option<DmaBuf> {
+        let bdev = child.bdev.as_ref()?;
+        let h = self
+            .inner_channel()
+            .writers
+            .iter()
+            .find(|h| h.get_bdev().as_ptr() == bdev.as_ptr())?;
+        let offset = self.child_offset(h).ok()?;
+        let mut buf =
+            h.dma_malloc(self.num_blocks() * self.block_len()).ok()?;
+        h.read_at(offset * self.block_len(), &mut buf)
+            .await
+            .ok()
+            .map(|_| buf)
+    }
+
+    /// true if the iovecs of this IO hold the given data
+    fn iovs_equal(&self, mut data: &[u8]) -> bool {
+        let iovs = unsafe {
+            std::slice::from_raw_parts(self.iovs(), self.iov_count() as usize)
+        };
+        iovs.iter().all(|iov| {
+            let len = iov.iov_len as usize;
+            if data.len() < len {
+                return false;
+            }
+            let (head, tail) = data.split_at(len);
+            data = tail;
+            head == unsafe {
+                std::slice::from_raw_parts(iov.iov_base as *const u8, len)
+            }
+        }) && data.is_empty()
+    }
+
+    /// copy of the data held by the iovecs of this IO
+    fn iov_data(&self) -> Vec<u8> {
+        let iovs = unsafe {
+            std::slice::from_raw_parts(self.iovs(), self.iov_count() as usize)
+        };
+        iovs.iter()
+            .flat_map(|iov| unsafe {
+                std::slice::from_raw_parts(
+                    iov.iov_base as *const u8,
+                    iov.iov_len as usize,
+                )
+            })
+            .copied()
+            .collect()
+    }
+
+    /// submit the write of a compare-and-write to a child
+    fn submit_fused_write(&self, hdl: &BdevHandle) -> Result<(), Errno> {
+        let (desc, chan) = hdl.io_tuple();
+        let offset = self.child_offset(hdl)?;
+        unsafe {
+            spdk_bdev_writev_blocks(
+                desc,
+                chan,
+                self.fused_iovs(),
+                self.fused_iov_count(),
+                offset,
+                self.num_blocks(),
+                Some(Self::child_completion),
+                self.as_ptr().cast(),
+            )
+        }
+        .to_result(Errno::from_i32)
+        .map(|_| self.physical_written(self.num_blocks() * self.block_len()))
+    }
+
+    #[inline(always)]
+    fn submit_write(&self, hdl: &BdevHandle) -> Result<(), Errno> {
+        let (desc, chan) = hdl.io_tuple();
+        let offset = self.child_offset(hdl)?;
+        let bounce = self.bounce_buf(hdl)?;
+        unsafe {
+            match bounce {
+                Some(buf) => spdk_bdev_write_blocks(
+                    desc,
+                    chan,
+                    buf,
+                    offset,
+                    self.num_blocks(),
+                    Some(Self::child_completion),
+                    self.as_ptr().cast(),
+                ),
+                None => spdk_bdev_writev_blocks(
+                    desc,
+                    chan,
+                    self.iovs(),
+                    self.iov_count(),
+                    offset,
+                    self.num_blocks(),
+                    Some(Self::child_completion),
+                    self.as_ptr().cast(),
+                ),
+            }
+        }
+        .to_result(Errno::from_i32)
+        .map(|_| {
+            if bounce.is_some() {
+                self.bounce_submitted(true);
+            }
+            self.physical_written(self.num_blocks() * self.block_len())
+        })
+    }
+
+    /// Pass an NVMe admin command through to the child nominated by the admin
+    /// passthru policy of the nexus, if the policy allows its opcode. The
+    /// child returns its response in the buffer of the command.
+    fn submit_admin(&mut self) -> Result<(), Errno> {
+        let nexus = self.nexus();
+        let opcode = self.nvme_cmd().opc() as u8;
+        let passthru = match &nexus.policy.admin_passthru {
+            Some(passthru) if passthru.allows(opcode) => passthru,
+            _ => {
+                debug!(
+                    "{}: rejecting NVMe admin command {:#x}",
+                    nexus.name, opcode
+                );
+                self.ctx_as_mut().nvme_status = NvmeStatusCode::INVALID_OPCODE;
+                self.fail();
+                return Err(Errno::EINVAL);
+            }
+        };
+
+        let bdev = nexus
+            .children
+            .iter()
+            .find(|c| c.name == passthru.child)
+            .and_then(|c| c.bdev.as_ref())
+            .map(|b| b.name());
+        let hdl = match self
+            .inner_channel()
+            .writers
+            .iter()
+            .find(|h| Some(h.get_bdev().name()) == bdev)
+        {
+            Some(hdl) => hdl,
+            None => {
+                self.fail();
+                return Err(Errno::ENODEV);
+            }
+        };
+
+        let (desc, chan) = hdl.io_tuple();
+        let cmd = self.nvme_cmd();
+        let rc = unsafe {
+            spdk_bdev_nvme_admin_passthru(
+                desc,
+                chan,
+                &cmd,
+                self.nvme_buf(),
+                self.nvme_nbytes(),
+                Some(Self::admin_completion),
+                self.as_ptr().cast(),
+            )
+        };
+        match rc.to_result(Errno::from_i32) {
+            Ok(_) => {
+                let bdev = hdl.get_bdev();
+                self.inner_channel().child_io_submitted(&bdev);
+                self.trace(|| TracePoint::Dispatch {
+                    child: self.child_name(&bdev),
+                });
+                self.ctx_as_mut().in_flight = 1;
+                Ok(())
+            }
+            Err(Errno::ENOMEM) => {
+                self.no_mem();
+                Err(Errno::ENOMEM)
+            }
+            Err(e) => {
+                self.fail();
+                Err(e)
+            }
+        }
+    }
+
+    /// invoked when an NVMe admin command passed through to a child
+    /// completes, the command completes as the child completed it
+    unsafe extern "C" fn admin_completion(
+        child_io: *mut spdk_bdev_io,
+        _success: bool,
+        nexus_io: *mut c_void,
+    ) {
+        let mut io = NexusBio::from(nexus_io);
+        let child_io = Bio::from(child_io);
+        io.inner_channel().child_io_completed(&child_io.bdev());
+        io.ctx_as_mut().in_flight -= 1;
+        let (cdw0, status) = child_io.nvme_completion();
+        child_io.free();
+
+        io.ctx_as_mut().nvme_status = status;
+        io.notify_complete(if status.is_success() {
+            IoStatus::Success
+        } else {
+            IoStatus::NvmeError
+        });
+        io.0.complete_nvme(cdw0, status);
+    }
+
+    /// the QuorumWrite the child IOs of a write complete through when it is
+    /// acknowledged once a quorum of the children completed it, none when it
+    /// waits for all children
+    fn quorum_write(&self) -> Result<Option<NonNull<QuorumWrite>>, Errno> {
+        let quorum = match self.nexus().policy.write_ack {
+            WriteAck::Quorum(quorum) if self.cmd() == IoType::Write => quorum,
+            _ => return Ok(None),
+        };
+        if self.write_targets().count() <= quorum {
+            return Ok(None);
+        }
+        let alignment = self
+            .write_targets()
+            .map(|h| h.get_bdev().alignment())
+            .max()
+            .unwrap_or(1);
+        QuorumWrite::new(
+            self,
+            &self.nexus().name,
+            self.ctx().channel,
+            alignment,
+        )
+        .map(Some)
+    }
+
+    /// submit a write acknowledged once a quorum of the children completed
+    /// it to a child, from the copy of its data
+    fn submit_quorum_write(
+        &self,
+        hdl: &BdevHandle,
+        mut write: NonNull<QuorumWrite>,
+    ) -> Result<(), Errno> {
+        let (desc, chan) = hdl.io_tuple();
+        let offset = self.child_offset(hdl)?;
+        unsafe {
+            spdk_bdev_write_blocks(
+                desc,
+                chan,
+                write.as_ref().buf(),
+                offset,
+                self.num_blocks(),
+                Some(QuorumWrite::child_completion),
+                write.as_ptr().cast(),
+            )
+        }
+        .to_result(Errno::from_i32)
+        .map(|_| {
+            unsafe { write.as_mut() }.submitted(hdl.get_bdev());
+            self.physical_written(self.num_blocks() * self.block_len())
+        })
+    }
+
+    #[inline(always)]
+    fn submit_unmap(&self, hdl: &BdevHandle) -> Result<(), Errno> {
+        let (desc, chan) = hdl.io_tuple();
+        let offset = self.child_offset(hdl)?;
+        unsafe {
+            spdk_bdev_unmap_blocks(
+                desc,
+                chan,
+                offset,
+                self.num_blocks(),
+                Some(Self::child_completion),
+                self.as_ptr().cast(),
+            )
+        }
+        .to_result(Errno::from_i32)
+    }
+
+    #[inline(always)]
     fn submit_write_zeroes(&self, hdl: &BdevHandle) -> Result<(), Errno> {
         let (desc, chan) = hdl.io_tuple();
+        let offset = self.child_offset(hdl)?;
         unsafe {
             spdk_bdev_write_zeroes_blocks(
                 desc,
                 chan,
-                self.offset() + self.data_ent_offset(),
+                offset,
+                self.num_blocks(),
+                Some(Self::child_completion),
+                self.as_ptr().cast(),
+            )
+        }
+        .to_result(Errno::from_i32)
+    }
+
+    #[inline(always)]
+    fn submit_flush_blocks(&self, hdl: &BdevHandle) -> Result<(), Errno> {
+        let (desc, chan) = hdl.io_tuple();
+        let offset = self.child_offset(hdl)?;
+        unsafe {
+            spdk_bdev_flush_blocks(
+                desc,
+                chan,
+                offset,
                 self.num_blocks(),
                 Some(Self::child_completion),
                 self.as_ptr().cast(),
@@ -413,99 +2779,530 @@ impl NexusBio {
         }
         .to_result(Errno::from_i32)
     }
-    /// Submit the IO to all underlying children, failing on the first error we
-    /// find. When an IO is partially submitted -- we must wait until all
-    /// the child IOs have completed before we mark the whole IO failed to
+
+    /// handles of the children an IO of the subset submitted by `submit_all`
+    /// is sent to. A rebuilding child only receives the IO when it starts
+    /// below its rebuild cursor; anything at or beyond the cursor is copied by
+    /// the rebuild later on, which locks the range and hence waits for this IO
+    /// to complete on the healthy children first. Resets and flushes go to all
+    /// children.
+    fn write_targets(&self) -> impl Iterator<Item = &BdevHandle> {
+        let channel: &NexusChannelInner = self.inner_channel();
+        let all = matches!(self.cmd(), IoType::Reset | IoType::Flush);
+        let blk = self.offset();
+
+        channel.writers.iter().chain(
+            channel
+                .rebuilding
+                .iter()
+                .filter(move |(hdl, cursor)| {
+                    all || blk + channel.data_offset(&hdl.get_bdev())
+                        < cursor.load(Ordering::SeqCst)
+                })
+                .map(|(hdl, _)| hdl),
+        )
+    }
+
+    /// Submit the IO to all underlying children. A child which fails the
+    /// submission does not keep the IO from being submitted to the others:
+    /// as long as the IO was submitted to at least one child, the children
+    /// that failed it are retired and the IO proceeds on the rest. When an IO
+    /// is partially submitted due to ENOMEM -- we must wait until all the
+    /// child IOs have completed before we report ENOMEM for the whole IO to
     /// avoid double frees. This function handles IO for a subset that must
-    /// be submitted to all the underlying children.
+    /// be submitted to all the underlying children. The IO fails right away
+    /// when there are no children to submit it to.
     fn submit_all(&mut self) -> Result<(), Errno> {
+        #[cfg(feature = "fault-injection")]
+        if take_no_memory(&self.nexus().name) {
+            self.nexus()
+                .metrics
+                .submit_failed(self.cmd(), Errno::ENOMEM);
+            self.no_mem();
+            return Err(Errno::ENOMEM);
+        }
+
+        if self.write_targets().next().is_none() {
+            self.fail_no_writers();
+            return Err(Errno::ENODEV);
+        }
+
+        let quorum = match self.quorum_write() {
+            Ok(quorum) => quorum,
+            Err(se) => {
+                self.nexus().metrics.submit_failed(self.cmd(), se);
+                self.no_mem();
+                return Err(se);
+            }
+        };
+        self.ctx_as_mut().quorum = quorum;
+
+        let io_type = self.cmd();
         let mut inflight = 0;
-        let mut status = IoStatus::Pending;
+        let mut failed = Vec::new();
 
-        let result = match self.cmd() {
-            IoType::Write => {
-                self.inner_channel().writers.iter().try_for_each(|h| {
-                    self.submit_write(h).map(|_| {
-                        inflight += 1;
-                    })
-                })
+        for h in self.write_targets() {
+            let result = match io_type {
+                IoType::Write => match quorum {
+                    Some(write) => self.submit_quorum_write(h, write),
+                    None => self.submit_write(h),
+                },
+                IoType::Unmap => self.submit_unmap(h),
+                IoType::WriteZeros => self.submit_write_zeroes(h),
+                IoType::Reset => self.submit_reset(h),
+                IoType::Flush
+                    if !h.get_bdev().io_type_supported(IoType::Flush) =>
+                {
+                    continue
+                }
+                IoType::Flush => self.submit_flush_blocks(h),
+                IoType::Compare => self.submit_compare(h),
+                IoType::CompareAndWrite => self.submit_fused_write(h),
+                // we should never reach here, if we do it is a bug.
+                _ => unreachable!(),
+            };
+            match result {
+                Ok(_) => {
+                    let bdev = h.get_bdev();
+                    self.inner_channel().child_io_submitted(&bdev);
+                    self.trace(|| TracePoint::Dispatch {
+                        child: self.child_name(&bdev),
+                    });
+                    inflight += 1
+                }
+                Err(se) => {
+                    self.nexus().metrics.submit_failed(io_type, se);
+                    failed.push((h.get_bdev(), se))
+                }
             }
-            IoType::Unmap => {
-                self.inner_channel().writers.iter().try_for_each(|h| {
-                    self.submit_unmap(h).map(|_| {
-                        inflight += 1;
-                    })
-                })
+        }
+        self.inner_channel().bounce.settle(self.as_ptr() as usize);
+        if let (Some(write), 0) = (quorum, inflight) {
+            self.ctx_as_mut().quorum = None;
+            QuorumWrite::release(write);
+        }
+
+        // ENOMEM takes precedence, as the whole IO must then be resubmitted
+        let result = match failed
+            .iter()
+            .find(|(_, se)| *se == Errno::ENOMEM)
+            .or_else(|| failed.first())
+        {
+            Some((_, se)) => Err(*se),
+            None => Ok(()),
+        };
+
+        if inflight != 0 {
+            self.ctx_as_mut().in_flight = inflight;
+            self.partial_submit(
+                io_type,
+                self.offset(),
+                self.num_blocks(),
+                inflight as usize,
+                &failed,
+            );
+            if matches!(result, Err(Errno::ENOMEM)) {
+                self.ctx_as_mut().status = IoStatus::NoMemory;
+                return result;
             }
-            IoType::WriteZeros => {
-                self.inner_channel().writers.iter().try_for_each(|h| {
-                    self.submit_write_zeroes(h).map(|_| {
-                        inflight += 1;
-                    })
-                })
+            for (bdev, _) in failed {
+                self.retire(bdev, Reason::IoError);
             }
-            IoType::Reset => {
-                self.inner_channel().writers.iter().try_for_each(|h| {
-                    self.submit_reset(h).map(|_| {
-                        inflight += 1;
-                    })
-                })
+            Ok(())
+        } else {
+            // if no IO was submitted at all, we can fail the IO now.
+            match result {
+                Err(Errno::ENOMEM) => self.no_mem(),
+                // the submission failed on every child, with whichever error
+                // each of them returned
+                Err(se) => self.fail_submission(se),
+                Ok(_) => self.fail(),
+            }
+            result
+        }
+    }
+
+    /// report the children on which the submission of an IO failed after it
+    /// was submitted to others, such that the given range may differ between
+    /// the children until the failed ones are retired
+    fn partial_submit(
+        &self,
+        io_type: IoType,
+        offset: u64,
+        num_blocks: u64,
+        submitted: usize,
+        failed: &[(Bdev, Errno)],
+    ) {
+        if failed.is_empty() {
+            return;
+        }
+        let nexus = self.nexus();
+        nexus.metrics.partial_submit();
+        for (bdev, se) in failed {
+            warn!(
+                "{}: {:?} of {} blocks at {} submission failed on child {}: {}, submitted to {} other children",
+                nexus.name,
+                io_type,
+                num_blocks,
+                offset,
+                bdev.name(),
+                se,
+                submitted
+            );
+            nexus_event::emit(NexusEvent::PartialSubmit {
+                nexus: nexus.name.clone(),
+                child: bdev.name(),
+                io_type,
+                offset,
+                num_blocks,
+                submitted,
+                errno: *se as i32,
+            });
+        }
+    }
+
+    /// copy the buffer into the iovecs of this IO, starting at the given byte
+    /// offset within the IO
+    fn copy_to_iovs(&self, mut offset: u64, mut buf: &[u8]) {
+        let iovs = unsafe {
+            std::slice::from_raw_parts(self.iovs(), self.iov_count() as usize)
+        };
+        for iov in iovs {
+            if buf.is_empty() {
+                break;
+            }
+            let len = iov.iov_len as u64;
+            if offset >= len {
+                offset -= len;
+                continue;
+            }
+            let n = std::cmp::min(len - offset, buf.len() as u64) as usize;
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    buf.as_ptr(),
+                    (iov.iov_base as *mut u8).add(offset as usize),
+                    n,
+                );
             }
-            // we should never reach here, if we do it is a bug.
-            _ => unreachable!(),
+            buf = &buf[n ..];
+            offset = 0;
         }
-        .map_err(|se| {
-            match se {
-                Errno::ENOMEM => status = IoStatus::NoMemory,
-                _ => status = IoStatus::Failed,
+    }
+
+    /// Emulate a write zeroes which the child does not support by writing
+    /// zeroed buffers in segments of WRITE_ZEROES_SEGMENT_SIZE bytes, and
+    /// account for the outcome as the completion of the child IO.
+    async fn emulate_write_zeroes(mut self, child: Bdev) {
+        let block_len = self.block_len();
+        let segment_blocks =
+            std::cmp::max(1, WRITE_ZEROES_SEGMENT_SIZE / block_len);
+        let offset = self.offset() + self.inner_channel().data_offset(&child);
+        let num_blocks = self.num_blocks();
+
+        let mut success = false;
+        if let Some(Ok(hdl)) =
+            self.nexus().child_lookup(&child.name()).map(|c| c.handle())
+        {
+            success = true;
+            let mut blk = 0;
+            while success && blk < num_blocks {
+                let count = std::cmp::min(segment_blocks, num_blocks - blk);
+                success = match hdl.dma_malloc(count * block_len) {
+                    Ok(mut buf) => {
+                        buf.fill(0);
+                        let ok = hdl
+                            .write_at((offset + blk) * block_len, &buf)
+                            .await
+                            .is_ok();
+                        if ok {
+                            self.physical_written(buf.len());
+                        }
+                        ok
+                    }
+                    Err(_) => false,
+                };
+                blk += count;
             }
-            debug!(
-                "IO submission failed with {} already submitted IOs {}",
-                se, inflight
+        }
+
+        if !success {
+            error!(
+                "{}: emulated write zeroes at {} failed on child {}",
+                self.nexus().name,
+                offset,
+                child.name()
             );
-            se
-        });
+        }
 
-        if inflight != 0 {
-            self.ctx_as_mut().in_flight = inflight;
-            self.ctx_as_mut().status = status;
-        } else {
-            // if no IO was submitted at all, we can fail the IO now.
-            if matches!(result, Err(Errno::ENOMEM)) {
-                self.no_mem();
-            } else {
-                // right now this could only be EINVAL, make sure to verify this
-                // during debug builds
-                debug_assert_eq!(result.err(), Some(Errno::EINVAL));
-                self.fail();
+        self.child_completed(child, success.into(), true);
+    }
+
+    /// fill in the checksums of the data read into the metadata buffer of the
+    /// IO, see `nexus_read_checksum`
+    fn fill_read_checksums(&self) {
+        let md = unsafe {
+            std::slice::from_raw_parts_mut(
+                self.md_buf() as *mut u8,
+                (self.num_blocks() * u64::from(READ_CHECKSUM_MD_SIZE)) as usize,
+            )
+        };
+        let iovs = unsafe {
+            std::slice::from_raw_parts(self.iovs(), self.iov_count() as usize)
+        };
+        fill_read_checksums(md, self.offset(), self.block_len(), iovs);
+    }
+
+    /// verify the data of a successful read with the read verifier of the
+    /// nexus, if any
+    fn verify_read(&self, child_io: &Bio) -> bool {
+        match &self.nexus().read_verifier {
+            Some(verifier) => {
+                let iovs = unsafe {
+                    std::slice::from_raw_parts(
+                        self.iovs(),
+                        self.iov_count() as usize,
+                    )
+                };
+                let data = iovs
+                    .iter()
+                    .map(|iov| unsafe {
+                        std::slice::from_raw_parts(
+                            iov.iov_base as *const u8,
+                            iov.iov_len as usize,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                verifier.verify(&child_io.bdev().name(), self.offset(), &data)
             }
+            None => true,
         }
-        result
     }
 
-    fn try_retire(&mut self, child_io: Bio) {
-        let nvme_status = child_io.nvme_status();
-        trace!(?nvme_status);
+    /// true if this read is to be sampled according to the read sample
+    /// interval of the nexus, which a read overlapping a write submitted on
+    /// the same channel never is
+    fn sampled(&self) -> bool {
+        let interval = self.nexus().policy.read_sample_interval;
+        if interval == 0 {
+            return false;
+        }
+        let inner = self.inner_channel();
+        inner.reads_since_sample += 1;
+        if inner.reads_since_sample < interval {
+            return false;
+        }
+        inner.reads_since_sample = 0;
+        !inner
+            .outstanding
+            .iter()
+            .any(|io| overlaps_write(&io, self.offset(), self.num_blocks()))
+    }
+
+    /// compare the data of a sampled read with another child, see
+    /// `nexus_read_sampling`, and complete the read on the child it was
+    /// served by. The read returns the data of the majority of the children
+    /// when it diverges from it.
+    async fn sample_read(mut self, served: Bdev) {
+        let nexus = self.nexus();
+        let data = self.iov_data();
+        if let Some((dissenters, buf)) =
+            compare_read(nexus, &served, self.offset(), &data).await
+        {
+            if dissenters.iter().any(|b| b.as_ptr() == served.as_ptr()) {
+                self.copy_to_iovs(0, buf.as_slice());
+            }
+            for bdev in dissenters {
+                self.retire(bdev, Reason::DataCorruption);
+            }
+        }
+        self.child_completed(served, IoStatus::Success, true);
+    }
+
+    /// Retry a failed read in segments of READ_REPAIR_SEGMENT_SIZE bytes. Each
+    /// segment is read from the child that failed first, and from the other
+    /// healthy children when that fails, such that a localised media error
+    /// does not fail the whole read. The parent IO only fails when a segment
+    /// cannot be read from any child.
+    ///
+    /// Segments which could not be read from the failed child are repaired by
+    /// writing back the data read from another child. The repair is done
+    /// before the parent IO completes, so it cannot overwrite the data of a
+    /// write that is issued after the read has completed.
+    ///
+    /// When `exclude` is set, because the failed child returned corrupted data
+    /// or an error which must not be retried, the failed child is not read
+    /// from, but all segments are repaired on it. Segments which fail
+    /// verification are treated as failed reads.
+    async fn read_repair(mut self, failed: String, exclude: bool) {
+        let nexus = self.nexus();
+        let block_len = self.block_len();
+        let segment_blocks =
+            std::cmp::max(1, READ_REPAIR_SEGMENT_SIZE / block_len);
+        let num_blocks = self.num_blocks();
+        let offset = |h: &BdevHandle| self.child_offset(h);
 
-        if nvme_status.status_code() != GenericStatusCode::InvalidOpcode {
-            Reactors::master().send_future(Self::child_retire(
-                self.nexus_as_ref().name.clone(),
-                child_io.bdev(),
-            ));
+        // the failed child is tried first, so that we learn which of the
+        // segments need to be repaired. It remains the target of the repair
+        // when it is not read from.
+        let in_sync = self.ctx().in_sync;
+        let mut handles = nexus
+            .children
+            .iter()
+            .filter(|c| c.state() == ChildState::Open)
+            .filter_map(|c| c.handle().ok())
+            .filter(|h| !in_sync || self.inner_channel().in_sync(&h.get_bdev()))
+            .collect::<Vec<_>>();
+        handles.sort_by_key(|h| h.get_bdev().name() != failed);
+        let failed_handle =
+            handles.first().filter(|h| h.get_bdev().name() == failed);
+        let skip = usize::from(exclude && failed_handle.is_some());
+        let readers = &handles[skip ..];
+
+        let mut repairs = Vec::new();
+        let mut blk = 0;
+        while blk < num_blocks {
+            let count = std::cmp::min(segment_blocks, num_blocks - blk);
+            let mut buf = match readers.first() {
+                Some(h) => match h.dma_malloc(count * block_len) {
+                    Ok(buf) => buf,
+                    Err(e) => {
+                        error!(
+                            ?e,
+                            "{}: read repair failed to allocate", nexus.name
+                        );
+                        self.fail();
+                        return;
+                    }
+                },
+                None => {
+                    self.fail_all();
+                    return;
+                }
+            };
+
+            let mut source = None;
+            let mut corrupted = false;
+            for (i, h) in readers.iter().enumerate() {
+                let at = match offset(h) {
+                    Ok(offset) => (offset + blk) * block_len,
+                    Err(_) => continue,
+                };
+                if h.read_at(at, &mut buf).await.is_err() {
+                    continue;
+                }
+                if nexus.read_verifier.as_ref().map_or(true, |v| {
+                    v.verify(
+                        &h.get_bdev().name(),
+                        self.offset() + blk,
+                        &[buf.as_slice()],
+                    )
+                }) {
+                    source = Some(i);
+                    break;
+                }
+                corrupted = true;
+            }
+
+            match source {
+                Some(i) => {
+                    self.copy_to_iovs(blk * block_len, buf.as_slice());
+                    if i + skip != 0 && failed_handle.is_some() {
+                        repairs.push((blk, buf));
+                    }
+                }
+                None => {
+                    error!(
+                        "{}: read of blocks {}..{} failed on all children",
+                        nexus.name,
+                        self.offset() + blk,
+                        self.offset() + blk + count
+                    );
+                    if corrupted
+                        && self.map_failure(FailureCondition::IntegrityMismatch)
+                    {
+                        self.fail();
+                    } else {
+                        self.fail_all();
+                    }
+                    return;
+                }
+            }
+            blk += count;
+        }
+
+        if let Some(h) = failed_handle {
+            for (blk, buf) in repairs {
+                let blk = match offset(h) {
+                    Ok(offset) => offset + blk,
+                    Err(_) => break,
+                };
+                match h.write_at(blk * block_len, &buf).await {
+                    Ok(_) => {
+                        nexus.metrics.physical_written(buf.len());
+                        info!(
+                            "{}: repaired {} blocks at {} of child {}",
+                            nexus.name,
+                            buf.len() / block_len,
+                            blk,
+                            failed
+                        )
+                    }
+                    Err(e) => error!(
+                        ?e,
+                        "{}: failed to repair blocks at {} of child {}",
+                        nexus.name,
+                        blk,
+                        failed
+                    ),
+                }
+            }
+        }
+
+        self.ok();
+    }
+
+    /// dispatch the retire of the child for the given reason
+    fn retire(&self, bdev: Bdev, reason: Reason) {
+        retire_child(self.nexus_as_ref(), bdev, reason);
+    }
+
+    /// account the failed IO on the child, and retire the child once its
+    /// failures of the kind reach the threshold of the fault policy
+    fn child_io_failed(&self, bdev: Bdev) {
+        let nexus = self.nexus_as_ref();
+        if let Some(child) = nexus.child_lookup(&bdev.name()) {
+            let io_type = self.cmd();
+            let errors = child.io_failed(io_type);
+            let threshold = nexus.policy.fault.threshold(io_type);
+            if threshold != 0 && errors >= u64::from(threshold) {
+                self.retire(bdev, Reason::IoError);
+            } else {
+                warn!(
+                    "{}: {:?} failed on child {}, {} of {} failures",
+                    nexus.name, io_type, child.name, errors, threshold
+                );
+            }
         }
     }
 
     /// Retire a child for this nexus.
-    async fn child_retire(nexus: String, child: Bdev) {
+    async fn child_retire(nexus: String, child: Bdev, reason: Reason) {
         match nexus_lookup(&nexus) {
             Some(nexus) => {
                 if let Some(child) = nexus.child_lookup(&child.name()) {
                     let current_state = child.state.compare_and_swap(
                         ChildState::Open,
-                        ChildState::Faulted(Reason::IoError),
+                        ChildState::Faulted(reason),
                     );
 
                     if current_state == ChildState::Open {
+                        child.prev_state.store(ChildState::Open);
+                        child.record_fault(reason);
+                        nexus_event::emit(NexusEvent::ChildFaulted {
+                            nexus: nexus.name.clone(),
+                            child: child.name.clone(),
+                            reason,
+                        });
                         warn!(
                             "core {} thread {:?}, faulting child {}",
                             Cores::current(),
@@ -514,22 +3311,38 @@ impl NexusBio {
                         );
 
                         nexus.pause().await.unwrap();
-                        nexus.reconfigure(DrEvent::ChildFault).await;
-                        // TODO: an error can occur here if a separate task,
-                        // e.g. grpc request is also deleting the child.
-                        if let Err(err) = child.destroy().await {
+                        // When the channels could not be reconfigured some
+                        // cores may still hold handles to the child, which
+                        // is therefore kept faulted rather than destroyed,
+                        // and its retire is reported as stalled until it is
+                        // removed from the nexus. The nexus needs attention
+                        // until a later reconfiguration succeeds.
+                        //
+                        // An error can occur on the destroy if a separate
+                        // task, e.g. a grpc request, is also deleting the
+                        // child. The destroy is retried as the destroy
+                        // retry policy of the nexus allows, after which the
+                        // child is not left half destroyed but kept in the
+                        // DestroyFailed state, and the channels are
+                        // reconfigured once more such that no core resumes
+                        // IO with it.
+                        if let Err(err) =
+                            nexus.try_reconfigure(DrEvent::ChildFault).await
+                        {
                             error!(
-                                "{}: destroying child {} failed {}",
+                                "{}: keeping child {} faulted: {}",
                                 nexus, child, err
                             );
+                            nexus.retire_stalled(child);
+                        } else if !Self::destroy_retired(nexus, child).await {
+                            child.set_state(ChildState::DestroyFailed);
+                            nexus.reconfigure(DrEvent::ChildFault).await;
                         }
 
                         nexus.resume().await.unwrap();
-                        if nexus.status() == NexusStatus::Faulted {
-                            error!(":{} has no children left... ", nexus);
-                        }
                     }
                 }
+                nexus.metrics.retire_completed();
             }
             None => {
                 debug!(
@@ -539,4 +3352,181 @@ impl NexusBio {
             }
         }
     }
+
+    /// Destroy a retired child, retrying as the destroy retry policy of the
+    /// nexus allows. Returns false if the child could not be destroyed.
+    async fn destroy_retired(nexus: &Nexus, child: &NexusChild) -> bool {
+        let retry = nexus.policy.destroy_retry;
+        let mut retries = 0;
+        loop {
+            match child.destroy().await {
+                Ok(_) => return true,
+                Err(err) if retries < retry.retries => {
+                    let delay = retry.backoff * 2u32.saturating_pow(retries);
+                    warn!(
+                        "{}: destroying child {} failed {}, retrying in {:?}",
+                        nexus, child, err, delay
+                    );
+                    retries += 1;
+                    nexus.metrics.child_destroy_retried();
+                    throttle(delay).await;
+                }
+                Err(err) => {
+                    error!(
+                        "{}: destroying child {} failed {} after {} retries, giving up",
+                        nexus, child, err, retries
+                    );
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// dispatch the retire of the child of the nexus with the given bdev for the
+/// given reason
+pub(crate) fn retire_child(nexus: &Nexus, bdev: Bdev, reason: Reason) {
+    // many IOs failing on the same child must not each queue a retire
+    match nexus.child_lookup(&bdev.name()) {
+        Some(child) if child.start_retire() => {
+            nexus.metrics.retire_dispatched();
+            nexus.retire_reactor().send_future(NexusBio::child_retire(
+                nexus.name.clone(),
+                bdev,
+                reason,
+            ));
+        }
+        Some(_) => nexus.metrics.retire_deduplicated(),
+        None => {}
+    }
+}
+
+/// The blocks of a compare-and-write locked on the nexus, which holds back
+/// the writes to them from before the compare until the write has completed.
+/// The lock is taken through a descriptor of its own, as `write_to_child`
+/// does.
+pub(crate) struct FusedLock {
+    channel: IoChannel,
+    desc: Descriptor,
+    range: Box<RangeContext>,
+}
+
+impl std::fmt::Debug for FusedLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} blocks at {} of {:?}",
+            self.range.len, self.range.offset, self.desc
+        )
+    }
+}
+
+impl FusedLock {
+    /// lock the given blocks of the nexus, once the writes to them which are
+    /// in flight have completed
+    async fn acquire(
+        nexus: &Nexus,
+        offset: u64,
+        num_blocks: u64,
+    ) -> Result<Self, Errno> {
+        let desc = Bdev::open_by_name(&nexus.name, false)
+            .map_err(|_| Errno::ENODEV)?;
+        let channel = desc.get_channel().ok_or(Errno::ENOMEM)?;
+        let mut range = Box::new(RangeContext::new(offset, num_blocks));
+        desc.lock_lba_range(&mut range, &channel).await?;
+        Ok(Self {
+            channel,
+            desc,
+            range,
+        })
+    }
+
+    /// unlock the blocks, the context of the unlock is that of the lock
+    async fn release(mut self) {
+        if let Err(e) = self
+            .desc
+            .unlock_lba_range(&mut self.range, &self.channel)
+            .await
+        {
+            error!(?e, "failed to unlock {:?}", self);
+        }
+    }
+}
+
+/// The first block on a child of an IO of `num_blocks` blocks at `offset` of
+/// a nexus, whose data starts at block `data_offset` of the child. The IO is
+/// rejected with EINVAL when its range of blocks on the child overflows or
+/// exceeds the `child_blocks` blocks of the child, rather than being
+/// submitted to a wrapped around offset.
+pub fn child_lba(
+    offset: u64,
+    data_offset: u64,
+    num_blocks: u64,
+    child_blocks: u64,
+) -> Result<u64, Errno> {
+    let start = offset.checked_add(data_offset).ok_or(Errno::EINVAL)?;
+    match start.checked_add(num_blocks) {
+        Some(end) if end <= child_blocks => Ok(start),
+        _ => Err(Errno::EINVAL),
+    }
+}
+
+/// number of blocks the range check of child IO takes a child to have, by
+/// the name of the bdev of the child
+#[cfg(feature = "fault-injection")]
+static CHILD_BLOCKS: once_cell::sync::Lazy<
+    std::sync::Mutex<std::collections::HashMap<String, u64>>,
+> = once_cell::sync::Lazy::new(Default::default);
+
+/// make the range check of the IO to the child with the given bdev name take
+/// the child to have `blocks` blocks, as if it had shrunk below the nexus;
+/// zero removes the injection
+#[cfg(feature = "fault-injection")]
+pub fn inject_child_blocks(bdev: &str, blocks: u64) {
+    let mut injected = CHILD_BLOCKS.lock().unwrap();
+    if blocks == 0 {
+        injected.remove(bdev);
+    } else {
+        injected.insert(bdev.to_string(), blocks);
+    }
+}
+
+/// the number of blocks injected for the child with the given bdev name
+#[cfg(feature = "fault-injection")]
+fn injected_child_blocks(bdev: &str) -> Option<u64> {
+    CHILD_BLOCKS.lock().unwrap().get(bdev).cloned()
+}
+
+/// number of IOs which are still to run out of memory, by nexus
+#[cfg(feature = "fault-injection")]
+static NO_MEMORY: once_cell::sync::Lazy<
+    std::sync::Mutex<std::collections::HashMap<String, u32>>,
+> = once_cell::sync::Lazy::new(Default::default);
+
+/// make the submission of the next `count` IOs of the nexus with the given
+/// name to its children fail with ENOMEM, before any child IO is submitted
+#[cfg(feature = "fault-injection")]
+pub fn inject_no_memory(nexus: &str, count: u32) {
+    let mut injected = NO_MEMORY.lock().unwrap();
+    if count == 0 {
+        injected.remove(nexus);
+    } else {
+        injected.insert(nexus.to_string(), count);
+    }
+}
+
+/// consume an injected failure of the nexus with the given name, if any
+#[cfg(feature = "fault-injection")]
+fn take_no_memory(nexus: &str) -> bool {
+    let mut injected = NO_MEMORY.lock().unwrap();
+    match injected.get_mut(nexus) {
+        Some(count) => {
+            *count -= 1;
+            if *count == 0 {
+                injected.remove(nexus);
+            }
+            true
+        }
+        None => false,
+    }
 }
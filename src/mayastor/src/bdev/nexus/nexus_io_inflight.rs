@@ -0,0 +1,181 @@
+//! Lists and aborts the IO in flight on a nexus, for when IO hangs on a
+//! child that stopped responding. Every channel keeps the IO submitted on its
+//! core until it completes, in a list linked through the contexts of the IOs
+//! such that tracking an IO takes neither an allocation nor a lookup on the
+//! IO path. The IO is only listed while the nexus is instrumented, see
+//! `Nexus::instrumented()`, which it is whenever it has an IO timeout. Both
+//! the listing and the abort visit the channel of each core on its own
+//! thread, so an IO cannot complete while it is being looked at. An IO may
+//! still complete between being listed and the request to abort it, in which
+//! case it is no longer found.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::{Error, Nexus},
+        nexus_io::NexusBio,
+    },
+    core::IoType,
+};
+
+/// the links of an IO in the list of the IO in flight on its channel
+#[derive(Debug, Default)]
+pub(crate) struct IoLink {
+    prev: Option<NexusBio>,
+    next: Option<NexusBio>,
+    /// the IO is in the list
+    linked: bool,
+}
+
+/// The IO submitted on a channel which has not completed yet, most recently
+/// submitted first. The list is linked through the contexts of the IOs.
+#[derive(Debug, Default)]
+pub(crate) struct IoList {
+    head: Option<NexusBio>,
+}
+
+impl IoList {
+    /// add an IO which has been submitted
+    pub(crate) fn push(&mut self, io: &NexusBio) {
+        let mut io = io.clone();
+        if let Some(mut head) = self.head.clone() {
+            head.link_mut().prev = Some(io.clone());
+        }
+        let link = io.link_mut();
+        link.prev = None;
+        link.next = self.head.take();
+        link.linked = true;
+        self.head = Some(io);
+    }
+
+    /// remove an IO which has completed, if it is in the list
+    pub(crate) fn remove(&mut self, io: &NexusBio) {
+        let mut io = io.clone();
+        let link = io.link_mut();
+        if !link.linked {
+            return;
+        }
+        link.linked = false;
+        let prev = link.prev.take();
+        let next = link.next.take();
+        match prev.clone() {
+            Some(mut prev) => prev.link_mut().next = next.clone(),
+            None => self.head = next.clone(),
+        }
+        if let Some(mut next) = next {
+            next.link_mut().prev = prev;
+        }
+    }
+
+    /// the IOs in the list, which must not be removed while iterating
+    pub(crate) fn iter(&self) -> impl Iterator<Item = NexusBio> + '_ {
+        std::iter::successors(self.head.clone(), |io| io.link().next.clone())
+    }
+
+    /// the IO with the given identifier, the address of its bdev IO
+    pub(crate) fn get(&self, id: usize) -> Option<NexusBio> {
+        self.iter().find(|io| io.as_ptr() as usize == id)
+    }
+}
+
+/// An IO submitted to the nexus which has not completed yet
+#[derive(Debug, Clone, PartialEq)]
+pub struct InFlightIo {
+    /// identifies the IO while it is in flight, the identifier of an IO may
+    /// be reused once it has completed
+    pub id: u64,
+    /// core the IO was submitted on
+    pub core: u32,
+    pub io_type: IoType,
+    /// offset of the IO in blocks, relative to the start of the nexus
+    pub offset: u64,
+    pub num_blocks: u64,
+    /// time since the IO was submitted to the nexus
+    pub age: Duration,
+    /// number of child IOs in flight, none while the IO is held because the
+    /// nexus is paused, at its outstanding IO limit or waiting on a flush
+    /// barrier
+    pub children: u8,
+}
+
+impl Nexus {
+    /// the IO in flight on every core
+    pub async fn in_flight_ios(&self) -> Vec<InFlightIo> {
+        let ios = Rc::new(RefCell::new(Vec::new()));
+        let i = Rc::clone(&ios);
+        self.traverse_io_channels(move |channel| {
+            i.borrow_mut()
+                .extend(channel.outstanding.iter().map(|io| io.in_flight_io()));
+        })
+        .await;
+
+        ios.replace(Vec::new())
+    }
+
+    /// abort the IO in flight with the given identifier. The child IOs of the
+    /// IO are completed as aborted by the children which support it, and the
+    /// IO then fails with aborted by request. Returns the number of children
+    /// which accepted the abort.
+    pub async fn abort_io(&self, id: u64) -> Result<usize, Error> {
+        let found = Rc::new(Cell::new(false));
+        let aborted = Rc::new(Cell::new(0));
+        let (f, a) = (Rc::clone(&found), Rc::clone(&aborted));
+        self.traverse_io_channels(move |channel| {
+            if let Some(io) = channel.outstanding.get(id as usize) {
+                f.set(true);
+                a.set(a.get() + io.abort());
+            }
+        })
+        .await;
+
+        if !found.get() {
+            return Err(Error::IoNotFound {
+                id,
+                name: self.name.clone(),
+            });
+        }
+        Ok(aborted.get())
+    }
+
+    /// abort all IO in flight on the nexus, which fails every IO that hangs
+    /// as well as those which would have completed shortly, hence `force` must
+    /// be set. Returns the number of IOs an abort was requested for.
+    pub async fn abort_all_ios(&self, force: bool) -> Result<usize, Error> {
+        if !force {
+            return Err(Error::AbortNotForced {
+                name: self.name.clone(),
+            });
+        }
+        Ok(self.abort_in_flight().await)
+    }
+
+    /// abort all IO in flight on the nexus, as when it is torn down or
+    /// requests are cancelled. The abort of the child IOs is requested from
+    /// the children, the IOs then complete as aborted by request without
+    /// faulting any child. Returns the number of IOs an abort was requested
+    /// for.
+    pub async fn abort_in_flight(&self) -> usize {
+        let aborted = Rc::new(Cell::new(0));
+        let a = Rc::clone(&aborted);
+        self.traverse_io_channels(move |channel| {
+            let ios: Vec<_> = channel.outstanding.iter().collect();
+            a.set(a.get() + ios.len());
+            ios.into_iter().for_each(|io| {
+                io.abort();
+            });
+        })
+        .await;
+
+        info!(
+            "{}: requested the abort of {} IOs",
+            self.name,
+            aborted.get()
+        );
+        aborted.get()
+    }
+}
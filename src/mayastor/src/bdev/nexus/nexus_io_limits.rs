@@ -0,0 +1,111 @@
+//! Limits of the unmaps and write zeroes a nexus accepts. An unmap or write
+//! zeroes is submitted to every child as is, hence the nexus advertises the
+//! most restrictive limits of its children: the smallest of their maximum
+//! sizes, and the least common multiple of the units their writes must be a
+//! multiple of. The limits are derived from the children which take writes
+//! when the nexus is opened and again whenever its IO channels are
+//! reconfigured, such that a child with tighter limits which joins the nexus
+//! is accounted for before it receives any IO.
+
+use serde::Serialize;
+
+use crate::{
+    bdev::{
+        nexus::{nexus_bdev::Nexus, nexus_child::ChildState},
+        Reason,
+    },
+    core::Bdev,
+};
+
+/// the limits of the unmaps and write zeroes of a bdev, in blocks
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct IoLimits {
+    /// most blocks of a single unmap, None when unlimited
+    pub max_unmap: Option<u32>,
+    /// most blocks of a single write zeroes, None when unlimited
+    pub max_write_zeroes: Option<u32>,
+    /// number of blocks the length of a write must be a multiple of
+    pub write_unit: u32,
+}
+
+impl Default for IoLimits {
+    fn default() -> Self {
+        Self {
+            max_unmap: None,
+            max_write_zeroes: None,
+            write_unit: 1,
+        }
+    }
+}
+
+impl IoLimits {
+    /// the limits advertised by the given bdev
+    pub fn of(bdev: &Bdev) -> Self {
+        let limit = |blocks| if blocks == 0 { None } else { Some(blocks) };
+        Self {
+            max_unmap: limit(bdev.max_unmap()),
+            max_write_zeroes: limit(bdev.max_write_zeroes()),
+            write_unit: std::cmp::max(bdev.write_unit_size(), 1),
+        }
+    }
+
+    /// the limits which satisfy both these and the other limits
+    pub fn intersect(self, other: Self) -> Self {
+        let min = |a: Option<u32>, b: Option<u32>| match (a, b) {
+            (Some(a), Some(b)) => Some(std::cmp::min(a, b)),
+            (a, b) => a.or(b),
+        };
+        Self {
+            max_unmap: min(self.max_unmap, other.max_unmap),
+            max_write_zeroes: min(
+                self.max_write_zeroes,
+                other.max_write_zeroes,
+            ),
+            write_unit: lcm(self.write_unit, other.write_unit),
+        }
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: u32, b: u32) -> u32 {
+    a / gcd(a, b) * b
+}
+
+impl Nexus {
+    /// the limits of the unmaps and write zeroes the nexus accepts
+    pub fn io_limits(&self) -> IoLimits {
+        IoLimits::of(&self.bdev)
+    }
+
+    /// derive the limits of the nexus from the children which take writes,
+    /// that is the open ones and those being rebuilt
+    pub(crate) fn update_io_limits(&self) {
+        let limits = self
+            .children
+            .iter()
+            .filter(|c| {
+                matches!(
+                    c.state(),
+                    ChildState::Open | ChildState::Faulted(Reason::OutOfSync)
+                )
+            })
+            .filter_map(|c| c.bdev.as_ref())
+            .map(IoLimits::of)
+            .fold(IoLimits::default(), IoLimits::intersect);
+
+        if limits != self.io_limits() {
+            info!("{}: IO limits set to {:?}", self.name, limits);
+            let mut bdev = self.bdev.clone();
+            bdev.set_max_unmap(limits.max_unmap.unwrap_or(0));
+            bdev.set_max_write_zeroes(limits.max_write_zeroes.unwrap_or(0));
+            bdev.set_write_unit_size(limits.write_unit);
+        }
+    }
+}
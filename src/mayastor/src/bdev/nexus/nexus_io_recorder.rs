@@ -0,0 +1,173 @@
+//! A flight recorder for the IO path of a nexus. Every IO channel keeps the
+//! last IO_RECORDER_DEPTH IOs completed on its core in a ring, which can be
+//! dumped with `Nexus::io_history()` when debugging latency spikes or data
+//! issues. The rings are only ever accessed from their own core, so recording
+//! does not take any locks.
+//!
+//! A recorded read names the child which served it, such that data found to
+//! be wrong can be attributed to a child, see `Nexus::reads_at()`, along with
+//! why the read policy of the nexus selected that child, such that the way a
+//! policy spreads reads can be audited.
+//!
+//! The outcome of every child IO of a recorded IO is kept with it, from which
+//! a trace of the failures of the children is taken, see
+//! `Nexus::failure_trace()`.
+//!
+//! The recorder is only built with the `io-recorder` feature.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bdev::nexus::{nexus_bdev::Nexus, nexus_policy::ReadPolicy},
+    core::{IoStatus, IoType, NvmeStatusCode},
+};
+
+/// number of IOs recorded per core
+pub const IO_RECORDER_DEPTH: usize = 64;
+
+/// an IO completed by the nexus
+#[derive(Debug, Clone, PartialEq)]
+pub struct IoRecord {
+    /// core the IO was submitted and completed on
+    pub core: u32,
+    pub io_type: IoType,
+    /// offset of the IO in blocks, relative to the start of the nexus
+    pub offset: u64,
+    pub num_blocks: u64,
+    /// status the IO was completed with
+    pub status: IoStatus,
+    /// time the IO was submitted at
+    pub submitted: Instant,
+    /// time between the submission and the completion of the IO
+    pub latency: Duration,
+    /// number of child IOs which completed for the IO
+    pub children: u8,
+    /// the child which served a read that completed successfully, which is
+    /// the child the data was last read from when the read was retried or
+    /// repaired
+    pub served: Option<String>,
+    /// why the child the read was last submitted to was selected
+    pub selection: Option<Selection>,
+    /// the outcomes of the child IOs, in the order they completed
+    pub outcomes: Vec<ChildOutcome>,
+}
+
+/// how a child IO of a recorded IO completed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildOutcome {
+    /// name of the child
+    pub child: String,
+    pub status: IoStatus,
+    pub nvme_status: NvmeStatusCode,
+    /// time between the submission and the completion of the child IO
+    pub latency: Duration,
+}
+
+/// why the read policy selected a child to read from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    /// read policy of the nexus at the time
+    pub policy: ReadPolicy,
+    /// position of the child among the readers of the core, the local ones
+    /// first; the round-robin position under the round-robin policies
+    pub position: usize,
+    /// number of readers at the front the policy selected from, the local
+    /// ones when the prefer local policy found any
+    pub candidates: usize,
+    /// the child is local
+    pub local: bool,
+    /// child IOs outstanding on the core per reader when the child was
+    /// selected, which the least outstanding policy selects by
+    pub outstanding: Vec<(String, u32)>,
+}
+
+/// the ring of the last IOs completed on a core
+#[derive(Debug)]
+pub(crate) struct IoRecorder {
+    records: VecDeque<IoRecord>,
+    /// selections of the reads in flight, by IO
+    selections: HashMap<usize, Selection>,
+    /// outcomes of the child IOs of the IOs in flight, by IO
+    outcomes: HashMap<usize, Vec<ChildOutcome>>,
+}
+
+impl Default for IoRecorder {
+    fn default() -> Self {
+        Self {
+            records: VecDeque::with_capacity(IO_RECORDER_DEPTH),
+            selections: HashMap::new(),
+            outcomes: HashMap::new(),
+        }
+    }
+}
+
+impl IoRecorder {
+    /// keep the selection of a read until the read is recorded, replacing
+    /// that of an earlier attempt
+    pub(crate) fn selected(&mut self, io: usize, selection: Selection) {
+        self.selections.insert(io, selection);
+    }
+
+    /// the selection of a read which is being recorded
+    pub(crate) fn take_selection(&mut self, io: usize) -> Option<Selection> {
+        self.selections.remove(&io)
+    }
+
+    /// keep the outcome of a child IO until its IO is recorded
+    pub(crate) fn child_completed(&mut self, io: usize, outcome: ChildOutcome) {
+        self.outcomes.entry(io).or_default().push(outcome);
+    }
+
+    /// the outcomes of the child IOs of an IO which is being recorded
+    pub(crate) fn take_outcomes(&mut self, io: usize) -> Vec<ChildOutcome> {
+        self.outcomes.remove(&io).unwrap_or_default()
+    }
+
+    /// record a completed IO, evicting the oldest one when the ring is full
+    pub(crate) fn record(&mut self, record: IoRecord) {
+        if self.records.len() == IO_RECORDER_DEPTH {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    /// the recorded IOs, oldest first
+    pub(crate) fn records(&self) -> impl Iterator<Item = &IoRecord> {
+        self.records.iter()
+    }
+}
+
+impl Nexus {
+    /// the last IOs completed on every core, oldest first per core
+    pub async fn io_history(&self) -> Vec<IoRecord> {
+        let history = Rc::new(RefCell::new(Vec::new()));
+        let h = Rc::clone(&history);
+        self.traverse_io_channels(move |channel| {
+            h.borrow_mut().extend(channel.recorder.records().cloned());
+        })
+        .await;
+
+        history.replace(Vec::new())
+    }
+
+    /// the recorded reads which covered the block at the given offset of the
+    /// nexus, oldest first per core
+    pub async fn reads_at(&self, offset: u64) -> Vec<IoRecord> {
+        self.io_history()
+            .await
+            .into_iter()
+            .filter(|r| {
+                r.io_type == IoType::Read
+                    && r.offset <= offset
+                    && offset < r.offset + r.num_blocks
+            })
+            .collect()
+    }
+}
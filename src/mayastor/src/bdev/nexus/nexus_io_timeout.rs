@@ -0,0 +1,117 @@
+//! Times out the IO of a nexus which waits on a child that stopped responding,
+//! such that a hung device does not stall the IO of the nexus indefinitely.
+//!
+//! Every channel registers a poller on the thread of its core, which scans
+//! the IO in flight on that core every `IO_TIMEOUT_SCAN`; an IO is therefore
+//! only ever looked at by the core it was submitted on. An IO which has waited
+//! on its child IOs for longer than the IO timeout of the nexus expires: the
+//! children which have had IO outstanding on the core without completing any
+//! of it for the whole timeout are retired with `Reason::IoTimeout`, and the
+//! child IOs of the IO are aborted. Once they have completed, the IO fails
+//! with the status of `FailureCondition::Timeout`. A device which does not
+//! even complete the abort completes the child IOs when it is destroyed as
+//! the child is retired.
+//!
+//! The timeout of a nexus defaults to longer than the timeout of the NVMe
+//! controllers, such that the NVMe layer gets to reset a controller before
+//! the child is retired.
+//!
+//! The same poller rebalances the share of the outstanding IO limit of the
+//! channel, see `nexus_admission`, and adds the reads and writes submitted on
+//! the channel to the throughput of the nexus, see `nexus_throughput`.
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    bdev::nexus::nexus_channel::NexusChannelInner,
+    core::{poller, Bdev},
+};
+
+/// interval at which the IO in flight on a core is scanned for expired IO
+pub const IO_TIMEOUT_SCAN: Duration = Duration::from_millis(100);
+
+impl NexusChannelInner {
+    /// register the poller which expires the IO of the channel, on the
+    /// thread the channel is created on
+    pub(crate) fn start_io_timeout(&mut self) {
+        let inner = self as *mut NexusChannelInner;
+        self.timeout_poller = Some(
+            poller::Builder::new()
+                .with_name("nexus_io_timeout")
+                .with_interval(IO_TIMEOUT_SCAN.as_micros() as u64)
+                .with_poll_fn(move || {
+                    let inner = unsafe { &mut *inner };
+                    let busy = inner.expire_ios();
+                    inner.rebalance_admission();
+                    inner.add_traffic();
+                    busy
+                })
+                .build(),
+        );
+    }
+
+    /// expire the IO in flight on the channel which has waited on its child
+    /// IOs for longer than the IO timeout of the nexus, retiring the children
+    /// which have not completed any IO for as long
+    fn expire_ios(&mut self) -> i32 {
+        self.clock = Instant::now();
+        let timeout = self.io_timeout();
+        if timeout == Duration::default() {
+            return 0;
+        }
+
+        let expired = self
+            .outstanding
+            .iter()
+            .filter(|io| io.expired(timeout))
+            .collect::<Vec<_>>();
+        if expired.is_empty() {
+            return 0;
+        }
+
+        let hung = self
+            .writers
+            .iter()
+            .chain(self.rebuilding.iter().map(|(h, _)| h))
+            .map(|h| h.get_bdev())
+            .filter(|bdev| self.child_hung(bdev, timeout))
+            .collect::<Vec<Bdev>>();
+        expired.into_iter().for_each(|io| io.expire(&hung));
+        1
+    }
+}
+
+/// number of child IOs still to hang, by the name of the bdev of the child
+#[cfg(feature = "fault-injection")]
+static HANGS: once_cell::sync::Lazy<
+    std::sync::Mutex<std::collections::HashMap<String, u32>>,
+> = once_cell::sync::Lazy::new(Default::default);
+
+/// make the next `count` child IOs to the child with the given bdev name
+/// hang: their completion is held back until the IO is aborted, as if the
+/// device did not respond
+#[cfg(feature = "fault-injection")]
+pub fn inject_child_hang(bdev: &str, count: u32) {
+    let mut hangs = HANGS.lock().unwrap();
+    if count == 0 {
+        hangs.remove(bdev);
+    } else {
+        hangs.insert(bdev.to_string(), count);
+    }
+}
+
+/// consume a hang injected for a child IO to the given bdev, if any
+#[cfg(feature = "fault-injection")]
+pub(crate) fn take_child_hang(bdev: &str) -> bool {
+    let mut hangs = HANGS.lock().unwrap();
+    match hangs.get_mut(bdev) {
+        Some(count) => {
+            *count -= 1;
+            if *count == 0 {
+                hangs.remove(bdev);
+            }
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,259 @@
+//! A verbose trace of the IO path of a single nexus, for capturing the IO
+//! around an incident. While a trace is active every IO submitted to the
+//! nexus emits an event when it is submitted, when it is dispatched to and
+//! completed by each of its children and when it completes, which are
+//! streamed to the consumer of the trace. The events of an IO carry the
+//! identifier the IO is listed with by `Nexus::in_flight_ios()`, so a trace
+//! can be correlated with the IO found hanging.
+//!
+//! Unlike the recorder this is not meant to be left on: every event takes a
+//! lock shared by all cores and is allocated and sent to the consumer, which
+//! adds noticeably to the latency of each IO. A trace therefore stops by
+//! itself after the duration it was started for, which is bounded by
+//! `IO_TRACE_MAX_DURATION`, or when its consumer goes away. When no trace is
+//! active the IO path only checks a flag.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use futures::{channel::mpsc, StreamExt};
+use serde::Serialize;
+
+use crate::{
+    bdev::{
+        nexus::{
+            nexus_bdev::{Error, Nexus},
+            nexus_bdev_verify::throttle,
+        },
+        nexus_lookup,
+    },
+    core::{IoStatus, IoType, NvmeStatusCode, Reactors},
+};
+
+/// longest duration a trace can be started for
+pub const IO_TRACE_MAX_DURATION: Duration = Duration::from_secs(600);
+
+/// identifies a trace across all nexuses, such that the expiry of a trace
+/// never stops a later one
+static SESSIONS: AtomicU64 = AtomicU64::new(0);
+
+/// the point of the IO path an event was emitted at
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum TracePoint {
+    /// the IO was submitted to the nexus
+    Submit,
+    /// the IO was submitted to a child
+    Dispatch { child: String },
+    /// a child completed the IO
+    ChildComplete {
+        child: String,
+        status: IoStatus,
+        nvme_status: NvmeStatusCode,
+    },
+    /// the nexus completed the IO
+    Complete {
+        status: IoStatus,
+        nvme_status: NvmeStatusCode,
+    },
+}
+
+/// an event of the IO path of a nexus
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceEvent {
+    /// time since the trace was started
+    pub time: Duration,
+    /// identifier of the IO while it is in flight
+    pub id: u64,
+    /// core the IO was submitted on
+    pub core: u32,
+    pub io_type: IoType,
+    /// offset of the IO in blocks, relative to the start of the nexus
+    pub offset: u64,
+    pub num_blocks: u64,
+    pub point: TracePoint,
+}
+
+#[derive(Debug)]
+struct TraceSession {
+    id: u64,
+    started: Instant,
+    expires: Instant,
+    sender: mpsc::UnboundedSender<TraceEvent>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct IoTrace {
+    /// a trace is active, checked on the IO path before anything else
+    active: AtomicBool,
+    session: Mutex<Option<TraceSession>>,
+}
+
+impl IoTrace {
+    #[inline(always)]
+    pub(crate) fn active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// emit an event built from the time since the start of the trace, if
+    /// the trace is still active
+    pub(crate) fn emit(&self, event: impl FnOnce(Duration) -> TraceEvent) {
+        let mut session = self.session.lock().unwrap();
+        let stop = match session.as_ref() {
+            Some(s) if Instant::now() < s.expires => {
+                s.sender.unbounded_send(event(s.started.elapsed())).is_err()
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if stop {
+            self.active.store(false, Ordering::Relaxed);
+            *session = None;
+        }
+    }
+
+    /// stop the trace with the given session identifier, or any trace;
+    /// returns whether a trace was stopped
+    fn stop(&self, id: Option<u64>) -> bool {
+        let mut session = self.session.lock().unwrap();
+        match session.as_ref() {
+            Some(s) if id.map_or(true, |id| id == s.id) => {
+                self.active.store(false, Ordering::Relaxed);
+                *session = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Nexus {
+    /// Start tracing the IO path of the nexus for the given duration. The
+    /// events are streamed to the returned receiver, which ends when the
+    /// trace stops; once the receiver is dropped the trace stops at its next
+    /// event. Only one trace can be active at a time.
+    pub fn start_io_trace(
+        &self,
+        duration: Duration,
+    ) -> Result<mpsc::UnboundedReceiver<TraceEvent>, Error> {
+        if duration == Duration::default() || duration > IO_TRACE_MAX_DURATION {
+            return Err(Error::InvalidIoTraceDuration {
+                duration,
+                name: self.name.clone(),
+            });
+        }
+
+        let (sender, receiver) = mpsc::unbounded();
+        let id = SESSIONS.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut session = self.io_trace.session.lock().unwrap();
+            if session.is_some() {
+                return Err(Error::IoTraceActive {
+                    name: self.name.clone(),
+                });
+            }
+            let started = Instant::now();
+            *session = Some(TraceSession {
+                id,
+                started,
+                expires: started + duration,
+                sender,
+            });
+            self.io_trace.active.store(true, Ordering::Relaxed);
+        }
+        self.update_instrumented();
+
+        warn!(
+            "{}: IO trace started for {:?}, IO latency is increased while it is active",
+            self.name, duration
+        );
+
+        // stop the trace when it expires even when no IO is submitted
+        let name = self.name.clone();
+        Reactors::master().send_future(async move {
+            throttle(duration).await;
+            if let Some(nexus) = nexus_lookup(&name) {
+                if nexus.io_trace.stop(Some(id)) {
+                    info!("{}: IO trace expired", name);
+                }
+                nexus.update_instrumented();
+            }
+        });
+
+        Ok(receiver)
+    }
+
+    /// Start tracing the IO path of the nexus for the given duration, writing
+    /// every event to the file at the given path as a line of JSON. The file
+    /// is written from a thread of its own and is complete once the trace
+    /// has stopped.
+    pub fn start_io_trace_to_file(
+        &self,
+        path: &Path,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        let mut receiver = self.start_io_trace(duration)?;
+        let file = File::create(path).map_err(|source| {
+            self.stop_io_trace();
+            Error::IoTraceFile {
+                source,
+                path: path.display().to_string(),
+                name: self.name.clone(),
+            }
+        })?;
+
+        let name = self.name.clone();
+        std::thread::Builder::new()
+            .name(format!("{}_trace", name))
+            .spawn(move || {
+                let mut writer = BufWriter::new(file);
+                let mut events = 0u64;
+                while let Some(event) =
+                    futures::executor::block_on(receiver.next())
+                {
+                    if let Err(error) =
+                        serde_json::to_writer(&mut writer, &event)
+                            .map_err(std::io::Error::from)
+                            .and_then(|_| writer.write_all(b"\n"))
+                    {
+                        error!("{}: failed to write IO trace: {}", name, error);
+                        return;
+                    }
+                    events += 1;
+                }
+                match writer.flush() {
+                    Ok(_) => {
+                        info!("{}: IO trace of {} events written", name, events)
+                    }
+                    Err(error) => {
+                        error!("{}: failed to write IO trace: {}", name, error)
+                    }
+                }
+            })
+            .expect("failed to spawn the IO trace thread");
+
+        Ok(())
+    }
+
+    /// stop the IO trace of the nexus, returns whether one was active
+    pub fn stop_io_trace(&self) -> bool {
+        let stopped = self.io_trace.stop(None);
+        self.update_instrumented();
+        if stopped {
+            info!("{}: IO trace stopped", self.name);
+        }
+        stopped
+    }
+
+    /// an IO trace of the nexus is active
+    pub fn io_trace_active(&self) -> bool {
+        self.io_trace.active()
+    }
+}
@@ -69,7 +69,10 @@ use std::{
 use uuid::{self, parser, Uuid};
 
 use crate::{
-    bdev::nexus::{nexus_bdev::Nexus, nexus_child::NexusChild},
+    bdev::nexus::{
+        nexus_bdev::Nexus,
+        nexus_child::{ChildState, NexusChild},
+    },
     core::{CoreError, DmaBuf, DmaError},
 };
 
@@ -592,7 +595,6 @@ impl NexusLabel {
         }
     }
 
-    #[allow(dead_code)]
     /// returns the offset of the first data block
     pub(crate) fn data_offset(&self) -> Result<u64, ProbeError> {
         match self.get_partition("MayaData") {
@@ -1155,18 +1157,21 @@ impl NexusChild {
         })
     }
 
-    // Check for the presence of "MayaMeta" and "MayaData" partitions
+    // Check for the presence of "MayaMeta" and "MayaData" partitions. The
+    // metadata partition may be larger than the reference one, as it was for
+    // children created with a larger reserved region, in which case the data
+    // partition starts further in.
     fn check_maya_partitions(
         reference: &[GptEntry],
         label: &NexusLabel,
         block_size: u32,
     ) -> bool {
-        match label.get_partition("MayaMeta") {
+        let meta_end = match label.get_partition("MayaMeta") {
             Some(entry) => {
                 if entry.ent_start != reference[0].ent_start {
                     return false;
                 }
-                if entry.ent_end != reference[0].ent_end {
+                if entry.ent_end < reference[0].ent_end {
                     return false;
                 }
                 if (entry.ent_end - entry.ent_start + 1) * u64::from(block_size)
@@ -1174,14 +1179,15 @@ impl NexusChild {
                 {
                     return false;
                 }
+                entry.ent_end
             }
             None => {
                 return false;
             }
-        }
+        };
 
         if let Some(entry) = label.get_partition("MayaData") {
-            if entry.ent_start == reference[1].ent_start {
+            if entry.ent_start > meta_end {
                 return true;
             }
         }
@@ -1293,7 +1299,11 @@ impl Nexus {
         )?;
         let data_offset = reference[1].ent_start;
 
-        for child in self.children.iter_mut() {
+        for child in self
+            .children
+            .iter_mut()
+            .filter(|c| c.state() != ChildState::Missing)
+        {
             let handle = child.handle().context(HandleError {
                 name: child.name.clone(),
             })?;
@@ -1303,6 +1313,8 @@ impl Nexus {
                 child.validate_label(&reference, bdev.block_len()).await?;
             let data_blocks =
                 label.data_block_count().context(InvalidLabel {})?;
+            child.data_offset =
+                Some(label.data_offset().context(InvalidLabel {})?);
 
             // Adjust size of data partition if necessary
             if data_blocks < min_blocks {
@@ -1322,7 +1334,11 @@ impl Nexus {
         &self,
         guid: GptGuid,
     ) -> Result<Option<LabelConfig>, LabelError> {
-        for child in self.children.iter() {
+        for child in self
+            .children
+            .iter()
+            .filter(|c| c.state() != ChildState::Missing)
+        {
             match child.probe_label().await {
                 Ok(label) => {
                     if label.primary.guid != guid {
@@ -1369,7 +1385,11 @@ impl Nexus {
             nexus_blocks,
         )?;
 
-        for child in self.children.iter_mut() {
+        for child in self
+            .children
+            .iter_mut()
+            .filter(|c| c.state() != ChildState::Missing)
+        {
             let handle = child.handle().context(HandleError {
                 name: child.name.clone(),
             })?;
@@ -1411,7 +1431,11 @@ impl Nexus {
         )?;
         let data_offset = reference[1].ent_start;
 
-        for child in self.children.iter_mut() {
+        for child in self
+            .children
+            .iter_mut()
+            .filter(|c| c.state() != ChildState::Missing)
+        {
             let handle = child.handle().context(HandleError {
                 name: child.name.clone(),
             })?;
@@ -1427,6 +1451,8 @@ impl Nexus {
                 .await?;
             let data_blocks =
                 label.data_block_count().context(InvalidLabel {})?;
+            child.data_offset =
+                Some(label.data_offset().context(InvalidLabel {})?);
 
             // Adjust size of data partition if necessary
             if data_blocks < min_blocks {
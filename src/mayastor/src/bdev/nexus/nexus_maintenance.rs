@@ -0,0 +1,105 @@
+//! Maintenance mode of a nexus. While in maintenance the background activity
+//! of the nexus is suspended, such that planned work on a node is not
+//! disturbed by it, while frontend IO continues as normal. Running rebuilds
+//! are paused, and rebuilds which would be started automatically (when a
+//! child is added or comes back) are deferred until maintenance ends.
+//! Rebuilds started explicitly through `start_rebuild` are not held off.
+
+use crate::bdev::{
+    nexus::{
+        nexus_bdev::{Error, Nexus},
+        nexus_event::{self, NexusEvent},
+    },
+    VerboseError,
+};
+
+/// Background work held off while the nexus is in maintenance
+#[derive(Debug, Default)]
+pub struct MaintenanceState {
+    /// children whose rebuild was paused on entering maintenance
+    paused_rebuilds: Vec<String>,
+    /// children whose rebuild was deferred during maintenance
+    deferred_rebuilds: Vec<String>,
+}
+
+impl Nexus {
+    /// true if the nexus is in maintenance
+    pub fn in_maintenance(&self) -> bool {
+        self.maintenance.is_some()
+    }
+
+    /// Enter or leave maintenance. Leaving maintenance resumes the rebuilds
+    /// paused on entering it and starts the rebuilds deferred meanwhile.
+    pub async fn set_maintenance(&mut self, maintenance: bool) {
+        if maintenance == self.in_maintenance() {
+            return;
+        }
+
+        if maintenance {
+            let mut state = MaintenanceState::default();
+            let children = self
+                .children
+                .iter()
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>();
+            for child in children {
+                if self.pause_rebuild(&child).await.is_ok() {
+                    state.paused_rebuilds.push(child);
+                }
+            }
+            info!(
+                "{}: entered maintenance, paused rebuilds {:?}",
+                self.name, state.paused_rebuilds
+            );
+            self.maintenance = Some(state);
+            nexus_event::emit(NexusEvent::MaintenanceEntered {
+                nexus: self.name.clone(),
+            });
+        } else if let Some(state) = self.maintenance.take() {
+            info!("{}: left maintenance", self.name);
+            for child in state.paused_rebuilds {
+                if let Err(e) = self.resume_rebuild(&child).await {
+                    error!(
+                        "{}: failed to resume rebuild of {}: {}",
+                        self.name,
+                        child,
+                        e.verbose()
+                    );
+                }
+            }
+            for child in state.deferred_rebuilds {
+                if let Err(e) = self.start_rebuild(&child).await {
+                    error!(
+                        "{}: failed to start deferred rebuild of {}: {}",
+                        self.name,
+                        child,
+                        e.verbose()
+                    );
+                }
+            }
+            nexus_event::emit(NexusEvent::MaintenanceExited {
+                nexus: self.name.clone(),
+            });
+        }
+    }
+
+    /// Start a rebuild of the child which is not explicitly requested, such
+    /// as when a child is added. The rebuild is deferred while the nexus is
+    /// in maintenance.
+    pub(crate) async fn start_auto_rebuild(
+        &mut self,
+        name: &str,
+    ) -> Result<(), Error> {
+        if let Some(state) = self.maintenance.as_mut() {
+            info!(
+                "{}: deferring rebuild of {} until after maintenance",
+                self.name, name
+            );
+            if !state.deferred_rebuilds.iter().any(|c| c == name) {
+                state.deferred_rebuilds.push(name.to_string());
+            }
+            return Ok(());
+        }
+        self.start_rebuild(name).await.map(|_| ())
+    }
+}
@@ -0,0 +1,608 @@
+//! Per nexus metrics. The metrics are plain atomic counters and gauges which
+//! are updated by the nexus and exported as a point in time snapshot.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use nix::errno::Errno;
+use serde::Serialize;
+
+use crate::{
+    bdev::nexus::nexus_throughput::{
+        ChannelTraffic,
+        Throughput,
+        ThroughputGauge,
+        Traffic,
+    },
+    core::{IoStatus, IoType, NvmeStatusCode},
+};
+
+#[derive(Debug, Default)]
+pub struct NexusMetrics {
+    /// 1 while the nexus is faulted, 0 otherwise
+    faulted: AtomicU64,
+    /// number of read IOs submitted to the nexus
+    reads: AtomicU64,
+    /// number of write IOs submitted to the nexus
+    writes: AtomicU64,
+    /// number of data modifying IOs submitted to the nexus, which identifies
+    /// the version of its data
+    write_generation: AtomicU64,
+    /// number of IOs which completed successfully
+    completed: AtomicU64,
+    /// number of IOs which completed with an error
+    failed: AtomicU64,
+    /// number of times an IO failed on all children it was submitted to
+    all_failed: AtomicU64,
+    /// number of child retires dispatched which have not completed yet
+    retires_in_flight: AtomicU64,
+    /// number of failed IOs which did not dispatch a retire as one was
+    /// dispatched already for the same child
+    retires_deduplicated: AtomicU64,
+    /// number of bytes on which consistency checks found the children to
+    /// diverge
+    divergent_bytes: AtomicU64,
+    /// number of bytes written to the nexus on IO channels which have been
+    /// destroyed, see `ChannelWrites`
+    logical_bytes_written: AtomicU64,
+    /// number of bytes written to the children by rebuilds and read repairs,
+    /// and on IO channels which have been destroyed
+    physical_bytes_written: AtomicU64,
+    /// number of IOs rejected as they did not satisfy the required alignment
+    misaligned: AtomicU64,
+    /// number of child IOs which failed with an error that must not be
+    /// retried
+    child_dnr_errors: AtomicU64,
+    /// number of bytes of writes held by the write cache which have not been
+    /// submitted to the children yet
+    cache_dirty_bytes: AtomicU64,
+    /// number of writes which were submitted to the children coalesced with
+    /// other writes
+    coalesced_writes: AtomicU64,
+    /// number of IOs which were submitted to some of the children but failed
+    /// submission on the others
+    partial_submits: AtomicU64,
+    /// number of writes and flushes failed as the nexus had no child to
+    /// submit them to
+    no_writers: AtomicU64,
+    /// number of child IOs rejected as their range of blocks overflowed or
+    /// exceeded the child
+    out_of_range: AtomicU64,
+    /// number of IOs which were queued or rejected as the nexus was at its
+    /// outstanding IO limit
+    throttled_ios: AtomicU64,
+    /// number of reads which were compared with the data of another child
+    sampled_reads: AtomicU64,
+    /// number of sampled reads on which the children diverged
+    sampled_mismatches: AtomicU64,
+    /// number of children retired as the data of a compare decided by a
+    /// quorum differed on them
+    compare_divergences: AtomicU64,
+    /// number of IOs which waited on their child IOs for longer than the IO
+    /// timeout
+    io_timeouts: AtomicU64,
+    /// number of IOs which completed as aborted as their child IOs were
+    /// aborted on request
+    aborted_ios: AtomicU64,
+    /// number of retries of the open of a child being added
+    child_open_retries: AtomicU64,
+    /// number of times the destroy of a retired child was retried
+    child_destroy_retries: AtomicU64,
+    /// number of reconfigurations of the IO channels which failed
+    reconfigure_failures: AtomicU64,
+    /// number of IO channels created, one for every thread the nexus was
+    /// used on
+    channels_created: AtomicU64,
+    /// number of reads failed right away as too few children could serve
+    /// them
+    fast_failed_reads: AtomicU64,
+    /// number of reads which failed on a child and were resubmitted to
+    /// another one
+    failed_over_reads: AtomicU64,
+    /// number of IOs which could not be submitted as the children ran out
+    /// of memory
+    no_memory: AtomicU64,
+    /// number of IOs requeued by the nexus as the children ran out of memory
+    no_memory_requeued: AtomicU64,
+    /// number of flushes completed by the nexus
+    flushes: AtomicU64,
+    /// number of writes acknowledged once a quorum of the children completed
+    /// them, before the others did
+    quorum_acks: AtomicU64,
+    /// number of writes which failed on a child after they were acknowledged
+    late_write_failures: AtomicU64,
+    /// number of snapshots taken of the nexus
+    snapshots: AtomicU64,
+    /// number of child flushes which failed
+    child_flush_failures: AtomicU64,
+    /// total time in microseconds between the submission and the completion
+    /// of the flushes
+    flush_latency_us: AtomicU64,
+    /// number of child IOs which failed per NVMe status, only updated on
+    /// failures
+    child_errors: Mutex<BTreeMap<NvmeStatusCode, u64>>,
+    /// number of child IOs which failed to be submitted per IO type and
+    /// errno, only updated on failures
+    submit_errors: Mutex<BTreeMap<(IoType, i32), u64>>,
+    /// number of IOs submitted per IO type which the nexus does not handle
+    /// itself
+    unhandled: Mutex<BTreeMap<IoType, u64>>,
+    /// bytes per second moved by the nexus, see `nexus_throughput`
+    throughput: ThroughputGauge,
+}
+
+/// number of child IOs which failed with an NVMe status
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NvmeErrorCount {
+    pub status: NvmeStatusCode,
+    pub count: u64,
+}
+
+/// number of child IOs of a type which failed to be submitted with an errno
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SubmitErrorCount {
+    pub io_type: IoType,
+    pub errno: i32,
+    pub count: u64,
+}
+
+/// number of IOs submitted of a type which the nexus does not handle itself
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UnhandledIoCount {
+    pub io_type: IoType,
+    pub count: u64,
+}
+
+/// The bytes written to the nexus and to its children on an IO channel. The
+/// writes are counted by the channel they are submitted on, such that the IO
+/// path does not contend on counters shared by all cores, and are summed
+/// over the channels when queried, see `Nexus::write_stats`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct ChannelWrites {
+    /// bytes written to the nexus
+    pub(crate) logical: u64,
+    /// bytes written to the children
+    pub(crate) physical: u64,
+}
+
+impl std::ops::AddAssign for ChannelWrites {
+    fn add_assign(&mut self, other: Self) {
+        self.logical += other.logical;
+        self.physical += other.physical;
+    }
+}
+
+/// the bytes written to a nexus and to its children
+#[derive(Debug, Default, Clone, Copy, Serialize, PartialEq)]
+pub struct WriteStats {
+    pub logical_bytes_written: u64,
+    pub physical_bytes_written: u64,
+    /// physical bytes written per logical byte, 0 when nothing was written
+    /// to the nexus yet
+    pub write_amplification: f64,
+}
+
+impl From<ChannelWrites> for WriteStats {
+    fn from(written: ChannelWrites) -> Self {
+        Self {
+            logical_bytes_written: written.logical,
+            physical_bytes_written: written.physical,
+            write_amplification: if written.logical == 0 {
+                0.0
+            } else {
+                written.physical as f64 / written.logical as f64
+            },
+        }
+    }
+}
+
+/// point in time copy of the metrics of a nexus
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct NexusMetricsSnapshot {
+    pub faulted: u64,
+    pub reads: u64,
+    pub writes: u64,
+    pub completed: u64,
+    pub failed: u64,
+    pub all_failed: u64,
+    pub retires_in_flight: u64,
+    pub retires_deduplicated: u64,
+    pub divergent_bytes: u64,
+    pub misaligned: u64,
+    pub child_dnr_errors: u64,
+    pub cache_dirty_bytes: u64,
+    pub coalesced_writes: u64,
+    pub partial_submits: u64,
+    pub no_writers: u64,
+    pub out_of_range: u64,
+    /// number of IOs admitted within the outstanding IO limit which have not
+    /// completed yet
+    pub outstanding_ios: u64,
+    /// limit of the outstanding IOs, 0 when unlimited
+    pub max_outstanding_ios: u64,
+    pub throttled_ios: u64,
+    pub sampled_reads: u64,
+    pub sampled_mismatches: u64,
+    pub compare_divergences: u64,
+    pub io_timeouts: u64,
+    pub aborted_ios: u64,
+    pub child_open_retries: u64,
+    pub child_destroy_retries: u64,
+    pub reconfigure_failures: u64,
+    pub channels_created: u64,
+    pub fast_failed_reads: u64,
+    pub failed_over_reads: u64,
+    pub no_memory: u64,
+    pub no_memory_requeued: u64,
+    pub flushes: u64,
+    pub quorum_acks: u64,
+    pub late_write_failures: u64,
+    pub snapshots: u64,
+    pub child_flush_failures: u64,
+    /// mean time between the submission and the completion of a flush, 0
+    /// when no flush completed yet
+    pub mean_flush_latency_us: u64,
+    pub child_errors: Vec<NvmeErrorCount>,
+    pub submit_errors: Vec<SubmitErrorCount>,
+    pub unhandled: Vec<UnhandledIoCount>,
+    pub throughput: Throughput,
+}
+
+impl NexusMetrics {
+    /// set the faulted gauge, returning whether it was set before
+    pub(crate) fn set_faulted(&self, faulted: bool) -> bool {
+        self.faulted.swap(faulted as u64, Ordering::Relaxed) != 0
+    }
+
+    /// account an IO submitted to the nexus
+    pub(crate) fn io_submitted(&self, io_type: IoType) {
+        match io_type {
+            IoType::Read => self.reads.fetch_add(1, Ordering::Relaxed),
+            IoType::Write => self.writes.fetch_add(1, Ordering::Relaxed),
+            _ => return,
+        };
+    }
+
+    /// account a data modifying IO submitted to the nexus
+    pub(crate) fn data_modified(&self) {
+        self.write_generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// the number of data modifying IOs submitted to the nexus so far
+    pub(crate) fn write_generation(&self) -> u64 {
+        self.write_generation.load(Ordering::Relaxed)
+    }
+
+    /// account an IO completed by the nexus
+    pub(crate) fn io_completed(&self, status: IoStatus) {
+        if status == IoStatus::Success {
+            self.completed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// account an IO which failed on all children
+    pub(crate) fn io_all_failed(&self) {
+        self.all_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a child retire dispatched
+    pub(crate) fn retire_dispatched(&self) {
+        self.retires_in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a child retire completed
+    pub(crate) fn retire_completed(&self) {
+        self.retires_in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// account a child retire which was not dispatched as one is in progress
+    pub(crate) fn retire_deduplicated(&self) {
+        self.retires_deduplicated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account bytes found divergent by a consistency check
+    pub(crate) fn divergent_bytes(&self, bytes: u64) {
+        self.divergent_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// add the reads and writes submitted on an IO channel to the
+    /// throughput, see `nexus_throughput`
+    pub(crate) fn channel_traffic(
+        &self,
+        traffic: &mut ChannelTraffic,
+        now: Instant,
+    ) {
+        self.throughput.add(traffic, now);
+    }
+
+    /// account bytes of background IO issued to a child
+    pub(crate) fn background_io(&self, bytes: u64) {
+        self.throughput.account(Traffic::Background, bytes);
+    }
+
+    /// account bytes written to a child outside of the IO path, which has
+    /// no IO channel to account them with
+    pub(crate) fn physical_written(&self, bytes: u64) {
+        self.physical_bytes_written
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// account the bytes written on an IO channel which is destroyed
+    pub(crate) fn channel_written(&self, written: ChannelWrites) {
+        self.logical_bytes_written
+            .fetch_add(written.logical, Ordering::Relaxed);
+        self.physical_bytes_written
+            .fetch_add(written.physical, Ordering::Relaxed);
+    }
+
+    /// the bytes written outside of the IO channels of the nexus
+    pub(crate) fn written(&self) -> ChannelWrites {
+        ChannelWrites {
+            logical: self.logical_bytes_written.load(Ordering::Relaxed),
+            physical: self.physical_bytes_written.load(Ordering::Relaxed),
+        }
+    }
+
+    /// account an IO rejected as it was not aligned
+    pub(crate) fn io_misaligned(&self) {
+        self.misaligned.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a child IO which failed with an error that must not be retried
+    pub(crate) fn child_dnr_error(&self) {
+        self.child_dnr_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account the bytes of a write held by the write cache
+    pub(crate) fn cache_held(&self, bytes: u64) {
+        self.cache_dirty_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// account the bytes of writes submitted by the write cache, and the
+    /// number of writes they were coalesced from
+    pub(crate) fn cache_released(&self, bytes: u64, writes: usize) {
+        self.cache_dirty_bytes.fetch_sub(bytes, Ordering::Relaxed);
+        if writes > 1 {
+            self.coalesced_writes
+                .fetch_add(writes as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// account an IO which failed submission on some of the children
+    pub(crate) fn partial_submit(&self) {
+        self.partial_submits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account an IO failed as the nexus had no writable child
+    pub(crate) fn no_writers(&self) {
+        self.no_writers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a child IO rejected as its range of blocks was out of range
+    pub(crate) fn out_of_range(&self) {
+        self.out_of_range.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account an IO queued or rejected at the outstanding IO limit
+    pub(crate) fn io_throttled(&self) {
+        self.throttled_ios.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a read compared with the data of another child
+    pub(crate) fn read_sampled(&self) {
+        self.sampled_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a sampled read on which the children diverged
+    pub(crate) fn read_mismatch(&self) {
+        self.sampled_mismatches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a child on which the data of a compare decided by a quorum
+    /// differed
+    pub(crate) fn compare_diverged(&self) {
+        self.compare_divergences.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account an IO which timed out
+    pub(crate) fn io_timed_out(&self) {
+        self.io_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account an IO which completed as aborted
+    pub(crate) fn io_aborted(&self) {
+        self.aborted_ios.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a retry of the open of a child being added
+    pub(crate) fn child_open_retried(&self) {
+        self.child_open_retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a retry of the destroy of a retired child
+    pub(crate) fn child_destroy_retried(&self) {
+        self.child_destroy_retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a reconfiguration of the IO channels which failed
+    pub(crate) fn reconfigure_failed(&self) {
+        self.reconfigure_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account an IO channel created
+    pub(crate) fn channel_created(&self) {
+        self.channels_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a read failed right away as too few children could serve it
+    pub(crate) fn read_fast_failed(&self) {
+        self.fast_failed_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a read resubmitted to another child after it failed
+    pub(crate) fn read_failed_over(&self) {
+        self.failed_over_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account an IO which the children ran out of memory for
+    pub(crate) fn no_memory(&self) {
+        self.no_memory.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account an IO requeued as the children ran out of memory
+    pub(crate) fn no_memory_requeued(&self) {
+        self.no_memory_requeued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a write acknowledged before all children completed it
+    pub(crate) fn quorum_acked(&self) {
+        self.quorum_acks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a write which failed on a child after it was acknowledged
+    pub(crate) fn late_write_failed(&self) {
+        self.late_write_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a flush completed after the given time
+    pub(crate) fn flush_completed(&self, latency: Duration) {
+        self.flushes.fetch_add(1, Ordering::Relaxed);
+        self.flush_latency_us
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// account a snapshot taken of the nexus
+    pub(crate) fn snapshot_taken(&self) {
+        self.snapshots.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a child flush which failed
+    pub(crate) fn child_flush_failed(&self) {
+        self.child_flush_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// account a child IO which failed with the given NVMe status
+    pub(crate) fn child_error(&self, status: NvmeStatusCode) {
+        *self.child_errors.lock().unwrap().entry(status).or_default() += 1;
+    }
+
+    /// account a child IO which failed to be submitted with the given errno
+    pub(crate) fn submit_failed(&self, io_type: IoType, errno: Errno) {
+        *self
+            .submit_errors
+            .lock()
+            .unwrap()
+            .entry((io_type, errno as i32))
+            .or_default() += 1;
+    }
+
+    /// account an IO of a type which the nexus does not handle itself
+    pub(crate) fn io_unhandled(&self, io_type: IoType) {
+        *self.unhandled.lock().unwrap().entry(io_type).or_default() += 1;
+    }
+
+    pub fn snapshot(&self) -> NexusMetricsSnapshot {
+        let flushes = self.flushes.load(Ordering::Relaxed);
+        // the completions are loaded before the submissions, such that a
+        // snapshot taken under IO never shows more IOs completed than
+        // submitted
+        let completed = self.completed.load(Ordering::Relaxed);
+        let failed = self.failed.load(Ordering::Relaxed);
+        NexusMetricsSnapshot {
+            faulted: self.faulted.load(Ordering::Relaxed),
+            reads: self.reads.load(Ordering::Relaxed),
+            writes: self.writes.load(Ordering::Relaxed),
+            completed,
+            failed,
+            all_failed: self.all_failed.load(Ordering::Relaxed),
+            retires_in_flight: self.retires_in_flight.load(Ordering::Relaxed),
+            retires_deduplicated: self
+                .retires_deduplicated
+                .load(Ordering::Relaxed),
+            divergent_bytes: self.divergent_bytes.load(Ordering::Relaxed),
+            misaligned: self.misaligned.load(Ordering::Relaxed),
+            child_dnr_errors: self.child_dnr_errors.load(Ordering::Relaxed),
+            cache_dirty_bytes: self.cache_dirty_bytes.load(Ordering::Relaxed),
+            coalesced_writes: self.coalesced_writes.load(Ordering::Relaxed),
+            partial_submits: self.partial_submits.load(Ordering::Relaxed),
+            no_writers: self.no_writers.load(Ordering::Relaxed),
+            out_of_range: self.out_of_range.load(Ordering::Relaxed),
+            outstanding_ios: 0,
+            max_outstanding_ios: 0,
+            throttled_ios: self.throttled_ios.load(Ordering::Relaxed),
+            sampled_reads: self.sampled_reads.load(Ordering::Relaxed),
+            sampled_mismatches: self.sampled_mismatches.load(Ordering::Relaxed),
+            compare_divergences: self
+                .compare_divergences
+                .load(Ordering::Relaxed),
+            io_timeouts: self.io_timeouts.load(Ordering::Relaxed),
+            aborted_ios: self.aborted_ios.load(Ordering::Relaxed),
+            child_open_retries: self.child_open_retries.load(Ordering::Relaxed),
+            child_destroy_retries: self
+                .child_destroy_retries
+                .load(Ordering::Relaxed),
+            reconfigure_failures: self
+                .reconfigure_failures
+                .load(Ordering::Relaxed),
+            channels_created: self.channels_created.load(Ordering::Relaxed),
+            fast_failed_reads: self.fast_failed_reads.load(Ordering::Relaxed),
+            failed_over_reads: self.failed_over_reads.load(Ordering::Relaxed),
+            no_memory: self.no_memory.load(Ordering::Relaxed),
+            no_memory_requeued: self.no_memory_requeued.load(Ordering::Relaxed),
+            flushes,
+            quorum_acks: self.quorum_acks.load(Ordering::Relaxed),
+            late_write_failures: self
+                .late_write_failures
+                .load(Ordering::Relaxed),
+            snapshots: self.snapshots.load(Ordering::Relaxed),
+            child_flush_failures: self
+                .child_flush_failures
+                .load(Ordering::Relaxed),
+            mean_flush_latency_us: if flushes == 0 {
+                0
+            } else {
+                self.flush_latency_us.load(Ordering::Relaxed) / flushes
+            },
+            child_errors: self
+                .child_errors
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(status, count)| NvmeErrorCount {
+                    status: *status,
+                    count: *count,
+                })
+                .collect(),
+            submit_errors: self
+                .submit_errors
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|((io_type, errno), count)| SubmitErrorCount {
+                    io_type: *io_type,
+                    errno: *errno,
+                    count: *count,
+                })
+                .collect(),
+            unhandled: self
+                .unhandled
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(io_type, count)| UnhandledIoCount {
+                    io_type: *io_type,
+                    count: *count,
+                })
+                .collect(),
+            throughput: self.throughput.throughput(),
+        }
+    }
+}
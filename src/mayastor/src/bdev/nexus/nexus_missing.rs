@@ -0,0 +1,177 @@
+//! Placeholders for children which are known to be part of a nexus but are
+//! not reachable when it is assembled, e.g. as the node of a replica has not
+//! come up yet after a restart. Rather than assembling the nexus without them
+//! and adding them back as new children, which copies all of their data, a
+//! placeholder keeps them in the topology of the nexus in the `Missing`
+//! state. A missing child takes no part in the IO, but the segments written
+//! while any child is missing are tracked, so that once the child appears
+//! only those segments are rebuilt.
+//!
+//! A placeholder asserts that the child held the same data as the other
+//! children when it went missing, as the segments which were not written
+//! since are not copied to it.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use crate::{
+    bdev::{
+        nexus::{
+            nexus_bdev::{Nexus, NexusState},
+            nexus_child::{ChildState, NexusChild},
+        },
+        VerboseError,
+    },
+    rebuild::SEGMENT_SIZE,
+};
+
+/// The segments of a nexus written while a child was missing, one bit per
+/// segment of the size copied by a rebuild.
+#[derive(Debug)]
+pub(crate) struct MissingWrites {
+    segment_blks: u64,
+    segments: Box<[AtomicU64]>,
+}
+
+impl MissingWrites {
+    fn new(num_blocks: u64, block_len: u64) -> Self {
+        let segment_blks = std::cmp::max(SEGMENT_SIZE / block_len, 1);
+        let segments = (num_blocks + segment_blks - 1) / segment_blks;
+        Self {
+            segment_blks,
+            segments: (0 .. (segments + 63) / 64)
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+        }
+    }
+
+    /// mark the segments of the given range of blocks as written
+    pub(crate) fn mark(&self, offset: u64, num_blocks: u64) {
+        if num_blocks == 0 {
+            return;
+        }
+        let first = offset / self.segment_blks;
+        let last = (offset + num_blocks - 1) / self.segment_blks;
+        for segment in first ..= last {
+            if let Some(word) = self.segments.get((segment / 64) as usize) {
+                word.fetch_or(1 << (segment % 64), Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// any segment of the given range of blocks has been written
+    pub(crate) fn written(&self, offset: u64, num_blocks: u64) -> bool {
+        if num_blocks == 0 {
+            return false;
+        }
+        let first = offset / self.segment_blks;
+        let last = (offset + num_blocks - 1) / self.segment_blks;
+        (first ..= last).any(|segment| {
+            self.segments
+                .get((segment / 64) as usize)
+                .map_or(false, |w| {
+                    w.load(Ordering::SeqCst) & (1 << (segment % 64)) != 0
+                })
+        })
+    }
+
+    /// number of segments written
+    fn count(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|w| u64::from(w.load(Ordering::Relaxed).count_ones()))
+            .sum()
+    }
+}
+
+impl Nexus {
+    /// Register a placeholder for a child which is known to be part of the
+    /// nexus but is not reachable yet, only allowed during the nexus init
+    /// phase. The child is brought in with `online_child` once it appears.
+    pub fn register_missing(&mut self, uri: &str) {
+        assert_eq!(*self.state.lock().unwrap(), NexusState::Init);
+        warn!("{}: child {} is missing", self.name, uri);
+        self.children
+            .push(NexusChild::missing(uri.to_string(), self.name.clone()));
+        self.child_count += 1;
+    }
+
+    /// start tracking the segments written while a placeholder is not
+    /// rebuilt, once the size of the nexus is known
+    pub(crate) fn track_missing_writes(&mut self) {
+        if self.missing_writes.is_none()
+            && self.children.iter().any(|c| c.placeholder)
+        {
+            self.missing_writes = Some(Arc::new(MissingWrites::new(
+                self.bdev.num_blocks(),
+                u64::from(self.bdev.block_len()),
+            )));
+        }
+    }
+
+    /// stop tracking the written segments once no placeholder needs them
+    pub(crate) fn release_missing_writes(&mut self) {
+        if self.missing_writes.is_some()
+            && !self.children.iter().any(|c| c.placeholder)
+        {
+            info!(
+                "{}: all missing children rebuilt, {} segments were written",
+                self.name,
+                self.missing_writes.as_ref().unwrap().count()
+            );
+            self.missing_writes = None;
+        }
+    }
+
+    /// Take the data offset of a missing child which appeared from its label.
+    /// A child without a label does not hold the data of the nexus after
+    /// all, it is labelled and rebuilt in full.
+    pub(crate) async fn adopt_placeholder(&mut self, name: &str) {
+        let offset = match self.children.iter().find(|c| c.name == name) {
+            Some(child) => child
+                .probe_label()
+                .await
+                .ok()
+                .and_then(|label| label.data_offset().ok()),
+            None => return,
+        };
+        if let Some(child) = self.children.iter_mut().find(|c| c.name == name) {
+            match offset {
+                Some(offset) => child.data_offset = Some(offset),
+                None => {
+                    warn!(
+                        "{}: missing child {} has no label, it is rebuilt in full",
+                        self.name, name
+                    );
+                    child.placeholder = false;
+                    self.release_missing_writes();
+                    if let Err(error) = self.sync_labels().await {
+                        error!(
+                            "{}: failed to label child {}: {}",
+                            self.name,
+                            name,
+                            error.verbose()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// names of the children of the nexus which are missing
+    pub fn missing_children(&self) -> Vec<String> {
+        self.children
+            .iter()
+            .filter(|c| c.state() == ChildState::Missing)
+            .map(|c| c.name.clone())
+            .collect()
+    }
+
+    /// number of segments written while a child was missing, if any child
+    /// still needs them to be rebuilt
+    pub fn missing_written_segments(&self) -> Option<u64> {
+        self.missing_writes.as_ref().map(|w| w.count())
+    }
+}
@@ -0,0 +1,118 @@
+//! Requeues the IO which the children ran out of memory for, with the
+//! `NoMemoryPolicy::Backoff` policy, rather than completing it with NoMemory
+//! for SPDK to resubmit it as soon as another IO completes, which under
+//! sustained memory pressure resubmits the IO over and over.
+//!
+//! Every channel keeps its requeued IO and resubmits all of it once the
+//! backoff has passed, from a poller on the thread of its core. The backoff
+//! doubles every time IO runs out of memory again when it is resubmitted, up
+//! to the maximum of the policy, and starts over once a resubmission goes
+//! through. IO which runs out of memory while the queue is full is completed
+//! with NoMemory as with the `Resubmit` policy.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    bdev::nexus::{
+        nexus_channel::NexusChannelInner,
+        nexus_io::NexusBio,
+        nexus_policy::NoMemoryBackoff,
+    },
+    core::poller::{self, Poller},
+};
+
+/// interval at which a channel checks whether its requeued IO is due
+const NO_MEMORY_TICK: Duration = Duration::from_millis(1);
+
+/// the IO of a channel which is requeued as the children ran out of memory
+#[derive(Debug, Default)]
+pub(crate) struct NoMemoryQueue {
+    ios: VecDeque<NexusBio>,
+    /// the backoff before the next resubmission, none until IO is requeued
+    backoff: Option<Duration>,
+    /// time at which the requeued IO is resubmitted
+    due: Option<Instant>,
+    /// the policy the IO was last requeued with
+    policy: NoMemoryBackoff,
+    /// resubmits the requeued IO, registered once IO is first requeued
+    poller: Option<Poller<'static>>,
+}
+
+impl NoMemoryQueue {
+    /// the IO requeued which has not been resubmitted yet
+    pub(crate) fn drain(&mut self) -> Vec<NexusBio> {
+        self.due = None;
+        self.ios.drain(..).collect()
+    }
+
+    /// remove the given IO from the queue, if it is requeued
+    pub(crate) fn remove(&mut self, io: &NexusBio) -> bool {
+        match self.ios.iter().position(|i| i.as_ptr() == io.as_ptr()) {
+            Some(i) => self.ios.remove(i).is_some(),
+            None => false,
+        }
+    }
+
+    /// stop resubmitting the requeued IO
+    pub(crate) fn stop(&mut self) {
+        self.poller.take();
+    }
+}
+
+impl NexusChannelInner {
+    /// requeue an IO which the children ran out of memory for, to be
+    /// resubmitted after the backoff. Returns false when the queue is full.
+    pub(crate) fn requeue_no_mem(
+        &mut self,
+        io: NexusBio,
+        policy: NoMemoryBackoff,
+    ) -> bool {
+        if self.no_memory.ios.len() >= policy.max_queued {
+            return false;
+        }
+        if self.no_memory.poller.is_none() {
+            let inner = self as *mut NexusChannelInner;
+            self.no_memory.poller = Some(
+                poller::Builder::new()
+                    .with_name("nexus_no_memory")
+                    .with_interval(NO_MEMORY_TICK.as_micros() as u64)
+                    .with_poll_fn(move || {
+                        unsafe { &mut *inner }.resubmit_no_mem()
+                    })
+                    .build(),
+            );
+        }
+        let queue = &mut self.no_memory;
+        queue.policy = policy;
+        let backoff = *queue.backoff.get_or_insert(policy.initial);
+        queue.due.get_or_insert_with(|| Instant::now() + backoff);
+        queue.ios.push_back(io);
+        true
+    }
+
+    /// resubmit the requeued IO once its backoff has passed, doubling the
+    /// backoff when IO runs out of memory again
+    fn resubmit_no_mem(&mut self) -> i32 {
+        match self.no_memory.due {
+            Some(due) if Instant::now() >= due => {}
+            _ => return 0,
+        }
+        let ios = self.no_memory.drain();
+        let backoff = self.no_memory.backoff.unwrap_or_default();
+        ios.into_iter().for_each(|io| io.resubmit_no_mem());
+
+        let queue = &mut self.no_memory;
+        if queue.ios.is_empty() {
+            queue.backoff = None;
+        } else {
+            let max = queue.policy.max;
+            let backoff = std::cmp::min(backoff * 2, max);
+            queue.backoff = Some(backoff);
+            queue.due = Some(Instant::now() + backoff);
+        }
+        1
+    }
+}
@@ -0,0 +1,57 @@
+//! Observers of the IO path of a nexus. An observer is registered per nexus
+//! and is invoked when an IO is submitted to the nexus and when the IO is
+//! completed back to the upper layer.
+//!
+//! Observers are invoked inline on the reactor that submits and completes the
+//! IO. They must therefore be cheap and never block: any expensive work should
+//! be deferred, e.g. by sending it to another reactor. Registering an
+//! observer instruments the nexus, see `Nexus::instrumented()`; a nexus
+//! which is not instrumented only checks that flag per IO.
+
+use std::{fmt, sync::Arc};
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::Nexus,
+        nexus_io::NioCtx,
+        nexus_metrics::NexusMetrics,
+    },
+    core::{IoStatus, IoType},
+};
+
+pub trait IoObserver: Send + Sync {
+    /// called when the IO is submitted to the nexus, before it is submitted
+    /// to any of the children
+    fn on_submit(&self, ctx: &NioCtx, io_type: IoType);
+    /// called with the final status of the IO right before it is completed
+    fn on_complete(&self, ctx: &NioCtx, status: IoStatus);
+}
+
+impl fmt::Debug for dyn IoObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IoObserver")
+    }
+}
+
+/// Observer which feeds the IO counters of the nexus metrics
+pub struct MetricsObserver {
+    metrics: Arc<NexusMetrics>,
+}
+
+impl MetricsObserver {
+    pub fn new(nexus: &Nexus) -> Self {
+        Self {
+            metrics: Arc::clone(&nexus.metrics),
+        }
+    }
+}
+
+impl IoObserver for MetricsObserver {
+    fn on_submit(&self, _ctx: &NioCtx, io_type: IoType) {
+        self.metrics.io_submitted(io_type);
+    }
+
+    fn on_complete(&self, _ctx: &NioCtx, status: IoStatus) {
+        self.metrics.io_completed(status);
+    }
+}
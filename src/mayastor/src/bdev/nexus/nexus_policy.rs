@@ -0,0 +1,722 @@
+//! Policies which tune the behaviour of a nexus. Policies are set per nexus
+//! and take effect immediately, also while IO is flowing.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{nvme_admin_opc, IoType, NvmeStatusCode};
+
+/// default number of IOs per core which are queued while a nexus is paused
+pub const PAUSE_QUEUE_DEPTH: usize = 256;
+
+/// Determines how IO which is submitted while the nexus is paused (e.g. while
+/// a faulted child is being retired) is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PausePolicy {
+    /// queue up to the given number of IOs per core and submit them when the
+    /// nexus is resumed. IO beyond that depth is rejected as with `Reject`.
+    Queue(usize),
+    /// complete the IO with a retriable NVMe status (namespace not ready),
+    /// such that the initiator retries it
+    Reject,
+}
+
+impl Default for PausePolicy {
+    fn default() -> Self {
+        Self::Queue(PAUSE_QUEUE_DEPTH)
+    }
+}
+
+/// Determines how an IO which failed on all children it was submitted to is
+/// completed. Failed reads are always retried segment by segment first, the
+/// policy applies when that does not succeed either.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AllFailedPolicy {
+    /// fail the IO
+    Fail,
+    /// resubmit the IO once, as children may have recovered in the meantime,
+    /// and fail it as with `Fail` when the retry fails on all children too
+    RetryOnce,
+    /// fail the IO with a media error NVMe status (unrecovered read error
+    /// for reads, write fault otherwise), such that the initiator can tell
+    /// it apart from an IO which failed on a single child
+    AllReplicasFailed,
+}
+
+impl Default for AllFailedPolicy {
+    fn default() -> Self {
+        Self::Fail
+    }
+}
+
+/// Determines what is done with an IO which a child fails as unsupported
+/// (invalid opcode). Such children are never retired, as this indicates a
+/// missing capability rather than a failing device.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum UnsupportedAction {
+    /// count the IO as failed on the child
+    Fail,
+    /// count the IO as successful on the child, which is only safe for
+    /// advisory operations
+    Ignore,
+    /// emulate the operation on the child: write zeroes are emulated with
+    /// regular writes of zeroed buffers, unmaps and flushes are skipped
+    Emulate,
+}
+
+impl Default for UnsupportedAction {
+    fn default() -> Self {
+        Self::Fail
+    }
+}
+
+/// The action per IO type when a child fails an IO as unsupported. IO types
+/// which are not listed are always counted as failed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UnsupportedPolicy {
+    pub write_zeroes: UnsupportedAction,
+    pub unmap: UnsupportedAction,
+    pub flush: UnsupportedAction,
+}
+
+impl UnsupportedPolicy {
+    /// the action for the given IO type
+    pub fn action(&self, io_type: IoType) -> UnsupportedAction {
+        match io_type {
+            IoType::WriteZeros => self.write_zeroes,
+            IoType::Unmap => self.unmap,
+            IoType::Flush => self.flush,
+            _ => UnsupportedAction::Fail,
+        }
+    }
+}
+
+/// Determines what is done with IO of a type which the nexus does not handle
+/// itself. Not every action is implemented for every IO type, see
+/// `UnhandledAction::implemented`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum UnhandledAction {
+    /// fail the IO as not supported
+    Fail,
+    /// submit the IO to one of the children which serve reads, as a read
+    SingleChild,
+    /// submit the IO to all children, as a write
+    Broadcast,
+    /// submit the IO to all children, as a write, the IO succeeds when it
+    /// succeeds on most of them
+    Quorum,
+    /// emulate the IO with IO which the nexus handles
+    Emulate,
+}
+
+impl Default for UnhandledAction {
+    fn default() -> Self {
+        Self::Fail
+    }
+}
+
+impl UnhandledAction {
+    /// True if the nexus implements the action for the IO type. A compare
+    /// can be submitted to a single child or to all children, in which case
+    /// it fails if the data differs on any of them, or it fails only if the
+    /// data differs on as many children as it matches on for a quorum, which
+    /// retires the children that diverge from the others. A compare can also
+    /// be emulated by reading the data from a child. Failing is implemented
+    /// for every IO type.
+    pub fn implemented(&self, io_type: IoType) -> bool {
+        match self {
+            Self::Fail => true,
+            Self::SingleChild
+            | Self::Broadcast
+            | Self::Quorum
+            | Self::Emulate => io_type == IoType::Compare,
+        }
+    }
+}
+
+/// The action per IO type which the nexus does not handle itself. IO types
+/// which are not listed are failed as not supported, as are IO types which
+/// the nexus handles itself regardless of the table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UnhandledPolicy(BTreeMap<IoType, UnhandledAction>);
+
+impl UnhandledPolicy {
+    pub fn new(
+        actions: impl IntoIterator<Item = (IoType, UnhandledAction)>,
+    ) -> Self {
+        Self(actions.into_iter().collect())
+    }
+
+    /// the action for the given IO type
+    pub fn action(&self, io_type: IoType) -> UnhandledAction {
+        self.0.get(&io_type).copied().unwrap_or_default()
+    }
+
+    /// the actions per IO type
+    pub fn actions(&self) -> Vec<(IoType, UnhandledAction)> {
+        self.0.iter().map(|(t, a)| (*t, *a)).collect()
+    }
+}
+
+/// Determines which children reads are spread over
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ReadPolicy {
+    /// round-robin over all children which serve reads
+    RoundRobin,
+    /// round-robin over the local children which serve reads, remote
+    /// children only serve reads when no local child does
+    PreferLocal,
+    /// read from the child with the fewest child IOs outstanding on the core,
+    /// reads or writes, such that reads avoid a child which is backed up;
+    /// children with equally many are rotated between
+    LeastOutstanding,
+    /// read from the preferred child of the nexus, such that reads hit its
+    /// cache, see `Nexus::set_preferred_reader`; the other children are
+    /// rotated between when the preferred child does not serve reads
+    Preferred,
+}
+
+impl Default for ReadPolicy {
+    fn default() -> Self {
+        Self::RoundRobin
+    }
+}
+
+/// Determines after how many failed IOs a child is retired, separately for
+/// reads and for writes and other data modifying IO. A threshold of 0 never
+/// retires the child. By default a child is retired on the first failed
+/// write, as it cannot be kept in sync, while failed reads are served by the
+/// other children and never retire the child.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FaultPolicy {
+    pub read_errors: u32,
+    pub write_errors: u32,
+}
+
+impl FaultPolicy {
+    /// the number of failed IOs of the given type which retire a child
+    pub fn threshold(&self, io_type: IoType) -> u32 {
+        if io_type == IoType::Read {
+            self.read_errors
+        } else {
+            self.write_errors
+        }
+    }
+}
+
+impl Default for FaultPolicy {
+    fn default() -> Self {
+        Self {
+            read_errors: 0,
+            write_errors: 1,
+        }
+    }
+}
+
+/// The NVMe statuses with which a child may fail an IO without being retired,
+/// as they indicate a benign condition of the device rather than a failing
+/// device. By default only an invalid opcode, i.e. an unsupported IO, is
+/// benign.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoFaultPolicy(BTreeSet<NvmeStatusCode>);
+
+impl NoFaultPolicy {
+    pub fn new(statuses: impl IntoIterator<Item = NvmeStatusCode>) -> Self {
+        Self(statuses.into_iter().collect())
+    }
+
+    /// true if a child which failed an IO with the status is not retired
+    pub fn contains(&self, status: &NvmeStatusCode) -> bool {
+        self.0.contains(status)
+    }
+
+    /// the statuses which do not retire a child
+    pub fn statuses(&self) -> Vec<NvmeStatusCode> {
+        self.0.iter().copied().collect()
+    }
+}
+
+impl Default for NoFaultPolicy {
+    fn default() -> Self {
+        Self::new(vec![NvmeStatusCode::INVALID_OPCODE])
+    }
+}
+
+/// Internal conditions on which the nexus fails an IO, see
+/// `FailureStatusPolicy`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum FailureCondition {
+    /// the IO failed on all children it was submitted to
+    AllChildrenFailed,
+    /// the nexus has no child left which can be written
+    NoWritableChildren,
+    /// the data read failed verification on all children which could be read
+    IntegrityMismatch,
+    /// the IO kept failing on the last healthy child for the grace period of
+    /// the nexus
+    Timeout,
+}
+
+/// The NVMe status an IO which fails on an internal condition is completed
+/// with, such that the initiator can tell whether to retry it, fail it or
+/// fail over to another path. Initiators of other protocols see the status as
+/// translated by the bdev layer. An IO failing on a condition without a
+/// status is completed as before, i.e. with the status a child failed it with
+/// and subject to the all failed policy. By default a nexus without writable
+/// children fails IO with an internal device error, data which failed
+/// verification with a guard check error and an IO which timed out with
+/// namespace not ready, which the initiator retries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailureStatusPolicy(BTreeMap<FailureCondition, NvmeStatusCode>);
+
+impl FailureStatusPolicy {
+    pub fn new(
+        statuses: impl IntoIterator<Item = (FailureCondition, NvmeStatusCode)>,
+    ) -> Self {
+        Self(statuses.into_iter().collect())
+    }
+
+    /// the status an IO failing on the condition is completed with, if any
+    pub fn status(
+        &self,
+        condition: FailureCondition,
+    ) -> Option<NvmeStatusCode> {
+        self.0.get(&condition).copied()
+    }
+
+    /// set the status of a condition, None to complete the IO as before
+    pub fn set(
+        &mut self,
+        condition: FailureCondition,
+        status: Option<NvmeStatusCode>,
+    ) {
+        match status {
+            Some(status) => self.0.insert(condition, status),
+            None => self.0.remove(&condition),
+        };
+    }
+
+    /// the conditions with a status and their status
+    pub fn statuses(&self) -> Vec<(FailureCondition, NvmeStatusCode)> {
+        self.0.iter().map(|(c, s)| (*c, *s)).collect()
+    }
+}
+
+impl Default for FailureStatusPolicy {
+    fn default() -> Self {
+        Self::new(vec![
+            (
+                FailureCondition::NoWritableChildren,
+                NvmeStatusCode::INTERNAL_DEVICE_ERROR,
+            ),
+            (
+                FailureCondition::IntegrityMismatch,
+                NvmeStatusCode::GUARD_CHECK_ERROR,
+            ),
+            (
+                FailureCondition::Timeout,
+                NvmeStatusCode::NAMESPACE_NOT_READY,
+            ),
+        ])
+    }
+}
+
+/// Determines the order in which writes (writes, write zeroes and unmaps)
+/// submitted on the same core are submitted to the children.
+///
+/// The block layer carries no barrier flag with a write, so ordering applies
+/// to all writes of the nexus. With `Barrier`, every write is a barrier: it is
+/// only submitted to the children once all writes submitted on the same core
+/// before it have completed on all children, and writes submitted after it
+/// wait for its completion in turn. A write thus never lands on a child before
+/// the writes that preceded it. Completion is durable only on children
+/// without a volatile write cache; otherwise a flush is needed to make the
+/// preceding writes durable. Writes submitted on different cores (i.e.
+/// different queues of the initiator) are not ordered with respect to each
+/// other. As writes are serialized per core, this costs throughput.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WriteOrdering {
+    /// writes are submitted as they arrive, without any ordering
+    Unordered,
+    /// every write waits for the writes submitted before it
+    Barrier,
+}
+
+impl Default for WriteOrdering {
+    fn default() -> Self {
+        Self::Unordered
+    }
+}
+
+/// Determines when a write is acknowledged, see `nexus_quorum_write`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WriteAck {
+    /// a write is acknowledged once all children have completed it
+    All,
+    /// a write is acknowledged once the given number of children have
+    /// completed it, the others complete it in the background and are retired
+    /// if they fail it. Writes submitted to no more children than the quorum
+    /// are acknowledged once all of them have completed.
+    Quorum(usize),
+}
+
+impl Default for WriteAck {
+    fn default() -> Self {
+        Self::All
+    }
+}
+
+/// Determines whether writes are held by the nexus before they are submitted
+/// to the children, such that small contiguous writes can be combined.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WriteCache {
+    /// writes are submitted to the children as they arrive
+    Disabled,
+    /// contiguous writes submitted on the same core are coalesced into a
+    /// single write per child of up to `max_bytes`. A write is held at most
+    /// until the reactor has handled the IO submitted with it, and a flush
+    /// submits the writes held on its core right away. Writes are only
+    /// acknowledged once the children have completed them, so this is as
+    /// durable as `Disabled`. Writes are not coalesced while write ordering
+    /// is enabled.
+    WriteThrough { max_bytes: u64 },
+    /// writes are acknowledged as soon as they are cached, up to
+    /// `max_dirty_bytes`, and written to the children later on. Acknowledged
+    /// writes which have not reached the children yet are lost when the
+    /// nexus fails or the node crashes, only a flush guarantees that the
+    /// writes acknowledged before it are durable. Not supported yet.
+    WriteBack { max_dirty_bytes: u64 },
+}
+
+impl Default for WriteCache {
+    fn default() -> Self {
+        Self::Disabled
+    }
+}
+
+/// Determines the NVMe admin commands submitted to the nexus which are passed
+/// through to one of its children, which returns its response in the buffer
+/// of the command. Commands which only read from the child are passed
+/// through, commands which modify it only when `allow_writes` is set. Other
+/// commands fail with invalid opcode.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminPassthru {
+    /// name of the child the commands are passed through to
+    pub child: String,
+    /// pass through the commands which modify the child as well, such as
+    /// format and sanitize
+    pub allow_writes: bool,
+}
+
+impl AdminPassthru {
+    /// whether an admin command with the given opcode is passed through
+    pub fn allows(&self, opcode: u8) -> bool {
+        match opcode {
+            nvme_admin_opc::IDENTIFY
+            | nvme_admin_opc::GET_LOG_PAGE
+            | nvme_admin_opc::GET_FEATURES => true,
+            nvme_admin_opc::SET_FEATURES
+            | nvme_admin_opc::FIRMWARE_COMMIT
+            | nvme_admin_opc::FIRMWARE_IMAGE_DOWNLOAD
+            | nvme_admin_opc::FORMAT_NVM
+            | nvme_admin_opc::SANITIZE => self.allow_writes,
+            _ => false,
+        }
+    }
+}
+
+/// Determines how the completions of child IOs are processed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CompletionBatching {
+    /// every child IO is processed as it completes
+    PerIo,
+    /// child IOs which complete successfully on the same core are collected
+    /// and processed together, up to `max_ios` at once and at the latest
+    /// when the reactor has handled the completions polled along with them.
+    /// A failed child IO is processed right away, after the ones collected
+    /// before it, so IOs complete and children are retired exactly as with
+    /// `PerIo`. This saves accounting per child IO under a high IO rate, at
+    /// the cost of completing IO slightly later.
+    Batched { max_ios: usize },
+}
+
+impl Default for CompletionBatching {
+    fn default() -> Self {
+        Self::PerIo
+    }
+}
+
+/// Limits the IO outstanding on a nexus across all cores. IO submitted while
+/// `max_ios` IOs are outstanding is queued on its core, up to `queue_depth`
+/// IOs per core, and submitted as IO of the nexus completes on that core. IO
+/// beyond the queue depth is completed with a retriable NVMe status
+/// (namespace not ready). The limit is split into a share per core, see
+/// `nexus_admission`. A core without any IO outstanding always admits an IO,
+/// such that IO queued on a core never waits for completions on other cores;
+/// the limit may thus be exceeded by up to one IO per core.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OutstandingLimit {
+    pub max_ios: u64,
+    pub queue_depth: usize,
+}
+
+/// Retries of the creation and the open of a child being added to a nexus,
+/// as a remote backend may need a moment to become reachable. The delay
+/// before a retry starts at `backoff` and doubles with every retry.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OpenRetry {
+    /// number of retries after the first attempt failed
+    pub retries: u32,
+    pub backoff: Duration,
+}
+
+impl Default for OpenRetry {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Retries of the destroy of a child which is retired, as the destroy may
+/// fail transiently, e.g. when a separate task is also deleting the child.
+/// The delay before a retry starts at `backoff` and doubles with every retry.
+/// A child which could not be destroyed is kept in the `DestroyFailed` state.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DestroyRetry {
+    /// number of retries after the first attempt failed
+    pub retries: u32,
+    pub backoff: Duration,
+}
+
+impl Default for DestroyRetry {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Time after which an IO still waiting on its child IOs times out, see
+/// `nexus_io_timeout`. Zero disables the timeout. The default is longer than
+/// the timeout of the NVMe controllers, which get to reset first.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IoTimeout(pub Duration);
+
+impl Default for IoTimeout {
+    fn default() -> Self {
+        Self(Duration::from_secs(60))
+    }
+}
+
+/// Accounting of the IO of a nexus in its throughput and its write
+/// amplification, see `Nexus::instrumented()`. Enabled by default; a nexus
+/// without it, and without any other policy or consumer which needs every IO
+/// to be accounted, submits its IO without any accounting.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IoAccounting(pub bool);
+
+impl Default for IoAccounting {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Verification of a child which is brought back online, before it is
+/// rebuilt. A child which missed no writes while it was offline and whose data
+/// matches that of an open child in all sampled segments is reopened without a
+/// rebuild. The samples are spread evenly over the nexus.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OnlineVerify {
+    /// number of segments which are compared, at least one
+    pub samples: u32,
+    /// size in bytes of a segment
+    pub segment_size: u64,
+}
+
+impl Default for OnlineVerify {
+    fn default() -> Self {
+        Self {
+            samples: 16,
+            segment_size: 64 * 1024,
+        }
+    }
+}
+
+/// Recovery of the children which were retired for IO errors, such that a
+/// nexus heals itself once the backend of such a child is reachable again.
+/// The retired children are probed every `interval`, and a child which can be
+/// reopened and passes the checks of `online_child` is brought back online
+/// and rebuilt, or reopened without a rebuild under the online verification
+/// policy. Children taken out by the operator are left alone.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AutoRecovery {
+    pub interval: Duration,
+}
+
+impl Default for AutoRecovery {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Determines the completion of an IO which could not be submitted to the
+/// children as they ran out of memory, i.e. request descriptors. SPDK queues
+/// an IO completed with NoMemory and resubmits it once another IO of the
+/// nexus completes on the same core.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NoMemoryPolicy {
+    /// the IO is completed with NoMemory right away
+    Resubmit,
+    /// the IO is completed with NoMemory after the given delay, giving the
+    /// children time to free their resources before it is resubmitted
+    Delay(Duration),
+    /// the IO is failed with a status which makes the initiator retry it
+    Busy,
+    /// the IO is requeued and resubmitted by the nexus after a backoff which
+    /// grows while the children remain out of memory, see `nexus_no_memory`
+    Backoff(NoMemoryBackoff),
+}
+
+/// The backoff of the IO requeued as the children ran out of memory. The
+/// backoff starts at `initial` and doubles up to `max` while the resubmitted
+/// IO runs out of memory again. At most `max_queued` IOs are requeued per
+/// core, the IO beyond is completed with NoMemory right away.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NoMemoryBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub max_queued: usize,
+}
+
+impl Default for NoMemoryBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(100),
+            max_queued: 128,
+        }
+    }
+}
+
+impl Default for NoMemoryPolicy {
+    fn default() -> Self {
+        Self::Resubmit
+    }
+}
+
+/// Determines the outcome of a flush which failed on some of the children
+/// but succeeded on the others. A flush which failed on all children always
+/// fails.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FlushFailurePolicy {
+    /// the children which failed the flush are retired, the flush succeeds
+    /// as the data is durable on the other children
+    FaultAndSucceed,
+    /// the flush fails, without retiring the children which failed it
+    Fail,
+    /// the flush is resubmitted to a child which failed it, up to the given
+    /// number of times per flush, before the child is retired and the flush
+    /// succeeds
+    Retry(u8),
+}
+
+impl Default for FlushFailurePolicy {
+    fn default() -> Self {
+        Self::FaultAndSucceed
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NexusPolicy {
+    /// handling of IO submitted while the nexus is paused
+    pub pause: PausePolicy,
+    /// handling of IO which failed on all children
+    pub all_failed: AllFailedPolicy,
+    /// handling of IO which a child fails as unsupported
+    pub unsupported: UnsupportedPolicy,
+    /// handling of IO of a type which the nexus does not handle itself
+    pub unhandled: UnhandledPolicy,
+    /// selection of the children reads are served by
+    pub read: ReadPolicy,
+    /// the child reads are served by with the preferred read policy
+    pub preferred_reader: Option<String>,
+    /// statuses of child IO which do not retire the child
+    pub no_fault: NoFaultPolicy,
+    /// number of failed IOs which retire a child
+    pub fault: FaultPolicy,
+    /// alignment in bytes required of the offset and length of reads,
+    /// writes, write zeroes and unmaps, the block size when not set. IO
+    /// which is not aligned is rejected instead of being submitted to the
+    /// children.
+    pub alignment: Option<u32>,
+    /// ordering of writes submitted on the same core
+    pub write_ordering: WriteOrdering,
+    /// time for which an IO which failed on the last healthy child is
+    /// retried before the child is faulted, such that a brief failure of all
+    /// children does not fault the nexus. Zero faults the child right away.
+    pub last_child_grace: Duration,
+    /// core on whose reactor the snapshot operations of the nexus run, the
+    /// master reactor when not set
+    pub snapshot_core: Option<u32>,
+    /// core on whose reactor the children of the nexus which failed IO are
+    /// retired, the master reactor when not set. Defaults to
+    /// `NexusOpts::retire_core` for a nexus when it is created.
+    pub retire_core: Option<u32>,
+    /// caching of writes before they are submitted to the children
+    pub write_cache: WriteCache,
+    /// processing of the completions of child IOs
+    pub completion_batching: CompletionBatching,
+    /// limit of the IO outstanding on the nexus, unlimited when not set
+    pub max_outstanding: Option<OutstandingLimit>,
+    /// one in every so many reads completed on a core is compared with the
+    /// data of another child, see `nexus_read_sampling`. Zero disables the
+    /// sampling.
+    pub read_sample_interval: u32,
+    /// number of open children the nexus needs to be ready for writes, see
+    /// `Nexus::readiness()`; one when not set
+    pub write_quorum: Option<usize>,
+    /// retries of the open of a child being added
+    pub open_retry: OpenRetry,
+    /// number of times a reconfiguration of the IO channels which failed is
+    /// retried before the nexus is marked as needing attention
+    pub reconfigure_retries: u32,
+    /// number of children which must be able to serve reads for the nexus to
+    /// serve them; below it reads fail right away with namespace not ready.
+    /// Reads are served by any healthy child when not set.
+    pub min_readers: Option<usize>,
+    /// completion of IO which the children ran out of memory for
+    pub no_memory: NoMemoryPolicy,
+    /// outcome of a flush which failed on some of the children
+    pub flush_failure: FlushFailurePolicy,
+    /// retries of the destroy of a child which is retired
+    pub destroy_retry: DestroyRetry,
+    /// verification of a child brought back online, which skips its rebuild
+    /// when it is consistent; the child is always rebuilt when not set
+    pub online_verify: Option<OnlineVerify>,
+    /// status of IO which fails on an internal condition
+    pub failure_status: FailureStatusPolicy,
+    /// recovery of the children retired for IO errors; they are left to the
+    /// operator when not set
+    pub auto_recovery: Option<AutoRecovery>,
+    /// time after which IO waiting on a child which does not respond fails
+    /// and the child is retired
+    pub io_timeout: IoTimeout,
+    /// acknowledgement of writes
+    pub write_ack: WriteAck,
+    /// passthrough of NVMe admin commands to a child, admin commands fail
+    /// when not set
+    pub admin_passthru: Option<AdminPassthru>,
+    /// accounting of the IO in the throughput and the write amplification
+    pub io_accounting: IoAccounting,
+}
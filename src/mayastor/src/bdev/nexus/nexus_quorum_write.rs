@@ -0,0 +1,250 @@
+//! Acknowledges a write once a quorum of the children completed it, with the
+//! `WriteAck::Quorum` policy, such that a single slow child does not hold up
+//! the writes of the nexus.
+//!
+//! The child IOs of such a write complete through a `QuorumWrite` rather than
+//! through the IO itself, as the IO is reused by SPDK once it has been
+//! acknowledged. For the same reason the data of the write is copied into a
+//! buffer of the `QuorumWrite` at submission, and the children are written
+//! from that buffer rather than from the buffers of the initiator.
+//!
+//! Until the write is acknowledged its child IOs are completed by the IO as
+//! any other, hence a write which fails on children before it reaches the
+//! quorum is handled as usual. The child IOs which complete afterwards are
+//! accounted and freed by the `QuorumWrite`; a child which fails the write
+//! then is retired, as it no longer holds the data which was acknowledged.
+//! The `QuorumWrite` is freed with its last child IO.
+//!
+//! The initiator does not wait for the children which lag behind. Until the
+//! write has completed on all of them, the channel it was submitted on
+//! accounts them as lagging, such that reads are not served by them, and
+//! holds back the writes which overlap it, such that these do not complete
+//! before it on a child which lags behind. The write remains in the flush
+//! barrier of the channel for as long, as it is only on stable storage once
+//! all children completed it.
+
+use std::{ffi::c_void, ptr::NonNull};
+
+use nix::errno::Errno;
+use spdk_sys::{spdk_bdev_io, spdk_io_channel};
+
+use crate::{
+    bdev::{
+        nexus::{
+            nexus_bounce::copy_from_iovs,
+            nexus_channel::NexusChannel,
+            nexus_io::{retire_child, NexusBio},
+        },
+        nexus_lookup,
+        Reason,
+    },
+    core::{Bdev, Bio, DmaBuf, IoType},
+};
+
+#[cfg(feature = "fault-injection")]
+use crate::{
+    bdev::nexus::{
+        nexus_bdev_verify::throttle,
+        nexus_failure_trace::take_replayed,
+    },
+    core::{IoStatus, Reactors},
+};
+
+/// a write which is acknowledged once a quorum of the children completed it
+#[derive(Debug)]
+pub(crate) struct QuorumWrite {
+    /// the IO of the write, until it has been acknowledged
+    io: Option<NexusBio>,
+    /// name of the nexus
+    nexus: String,
+    /// the channel the write was submitted on
+    channel: NonNull<spdk_io_channel>,
+    /// the data of the write, which the children are written from
+    buf: DmaBuf,
+    /// number of child IOs in flight
+    in_flight: u8,
+    /// the children the child IOs in flight were submitted to
+    children: Vec<Bdev>,
+    /// sequence number of the write in the flush barrier of the channel,
+    /// which the QuorumWrite holds once the write has been acknowledged
+    seq: u64,
+    /// offset and number of blocks of the write
+    range: (u64, u64),
+}
+
+impl QuorumWrite {
+    /// allocate the QuorumWrite of the given write, with a copy of its data
+    /// aligned for all children
+    pub(crate) fn new(
+        io: &NexusBio,
+        nexus: &str,
+        channel: NonNull<spdk_io_channel>,
+        alignment: u64,
+    ) -> Result<NonNull<Self>, Errno> {
+        let len = io.num_blocks() * io.block_len();
+        let mut buf = DmaBuf::new(len, alignment).map_err(|_| Errno::ENOMEM)?;
+        let iovs = unsafe {
+            std::slice::from_raw_parts(io.iovs(), io.iov_count() as usize)
+        };
+        copy_from_iovs(iovs, buf.as_mut_slice());
+        let write = Box::new(Self {
+            io: Some(io.clone()),
+            nexus: nexus.to_string(),
+            channel,
+            buf,
+            in_flight: 0,
+            children: Vec::new(),
+            seq: 0,
+            range: (io.offset(), io.num_blocks()),
+        });
+        Ok(NonNull::from(Box::leak(write)))
+    }
+
+    /// the buffer the children are written from
+    pub(crate) fn buf(&self) -> *mut c_void {
+        *self.buf
+    }
+
+    /// account a child IO submitted for the write to the given child
+    pub(crate) fn submitted(&mut self, bdev: Bdev) {
+        self.in_flight += 1;
+        self.children.push(bdev);
+    }
+
+    /// free a QuorumWrite from which no child IO was submitted
+    pub(crate) fn release(write: NonNull<Self>) {
+        let write = unsafe { Box::from_raw(write.as_ptr()) };
+        debug_assert_eq!(write.in_flight, 0);
+    }
+
+    /// the write has been acknowledged, the child IOs still in flight
+    /// complete through the QuorumWrite from now on. The children they were
+    /// submitted to lag behind until then, and the write keeps its sequence
+    /// number in the flush barrier.
+    pub(crate) fn detach(&mut self, seq: u64) {
+        self.io = None;
+        self.seq = seq;
+        let (offset, num_blocks) = self.range;
+        NexusChannel::inner_from_channel(self.channel.as_ptr()).write_lagging(
+            &self.children,
+            offset,
+            num_blocks,
+        );
+    }
+
+    /// invoked when a child IO of the write completes
+    pub(crate) unsafe extern "C" fn child_completion(
+        child_io: *mut spdk_bdev_io,
+        success: bool,
+        write: *mut c_void,
+    ) {
+        let write = write as *mut Self;
+        let child_io = Bio::from(child_io);
+        #[cfg(feature = "fault-injection")]
+        if (*write).replay(&child_io) {
+            return;
+        }
+        Self::completed(write, child_io, success);
+    }
+
+    /// complete a child IO with the outcome replayed for its child, if any,
+    /// once the child IO has taken as long as the outcome. Returns true if
+    /// the outcome of the child IO is replayed.
+    #[cfg(feature = "fault-injection")]
+    fn replay(&mut self, child_io: &Bio) -> bool {
+        let nexus = match nexus_lookup(&self.nexus) {
+            Some(nexus) => nexus,
+            None => return false,
+        };
+        let bdev = child_io.bdev();
+        let child = nexus
+            .child_lookup(&bdev.name())
+            .map_or_else(|| bdev.name(), |child| child.name.clone());
+        let entry = match take_replayed(&child, IoType::Write) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        let success = entry.status == IoStatus::Success;
+        NexusChannel::inner_from_channel(self.channel.as_ptr())
+            .replayed
+            .insert(
+                child_io.as_ptr() as usize,
+                (entry.status, entry.nvme_status),
+            );
+        let delay = entry.latency.checked_sub(child_io.elapsed());
+        let write = self as *mut Self;
+        let child_io = child_io.clone();
+        Reactors::current().send_future(async move {
+            if let Some(delay) = delay {
+                throttle(delay).await;
+            }
+            unsafe { Self::completed(write, child_io, success) };
+        });
+        true
+    }
+
+    /// complete a child IO of the write with the IO until it has been
+    /// acknowledged and with the QuorumWrite afterwards, freeing the
+    /// QuorumWrite with the last child IO
+    unsafe fn completed(write: *mut Self, child_io: Bio, success: bool) {
+        (*write).in_flight -= 1;
+        let bdev = child_io.bdev();
+        if let Some(i) = (*write)
+            .children
+            .iter()
+            .position(|b| b.as_ptr() == bdev.as_ptr())
+        {
+            (*write).children.swap_remove(i);
+        }
+        match (*write).io.clone() {
+            Some(mut io) => io.complete(child_io, success),
+            None => (*write).late(child_io, success),
+        }
+        if (*write).in_flight == 0 {
+            let write = Box::from_raw(write);
+            if write.io.is_none() {
+                let (offset, num_blocks) = write.range;
+                NexusBio::late_write_completed(
+                    write.channel,
+                    write.seq,
+                    offset,
+                    num_blocks,
+                );
+            }
+        }
+    }
+
+    /// account a child IO which completed after the write was acknowledged,
+    /// retiring its child if it failed
+    fn late(&self, child_io: Bio, success: bool) {
+        if let Some(nexus) = nexus_lookup(&self.nexus) {
+            let bdev = child_io.bdev();
+            let inner = NexusChannel::inner_from_channel(self.channel.as_ptr());
+            inner.child_io_completed(&bdev);
+            inner.lagging_completed(&bdev);
+            #[cfg(feature = "fault-injection")]
+            inner.replayed.remove(&(child_io.as_ptr() as usize));
+            if success {
+                if let Some(i) = nexus.children.iter().position(|c| {
+                    c.bdev
+                        .as_ref()
+                        .map_or(false, |b| b.as_ptr() == bdev.as_ptr())
+                }) {
+                    let latency = child_io.elapsed();
+                    inner.io_stats.record(i, &bdev, IoType::Write, latency);
+                    nexus.children[i].io_completed();
+                }
+            } else {
+                error!(
+                    "{}: write of {} blocks failed on child {} after it was acknowledged",
+                    nexus.name,
+                    child_io.num_blocks(),
+                    bdev.name()
+                );
+                nexus.metrics.late_write_failed();
+                retire_child(nexus, bdev, Reason::IoError);
+            }
+        }
+        child_io.free();
+    }
+}
@@ -0,0 +1,77 @@
+//! Checksums of the data returned by reads, for initiators which verify the
+//! data themselves and so detect corruption introduced above the nexus. Once
+//! enabled the nexus bdev is formatted with separate metadata of
+//! `READ_CHECKSUM_MD_SIZE` bytes per block. A read submitted with a metadata
+//! buffer (`spdk_bdev_readv_blocks_with_md`) has the metadata of each block
+//! filled in from the data returned by the children; a read without one is
+//! not burdened with any checksum.
+//!
+//! The metadata of a block is laid out as follows, in little endian:
+//!
+//! | bytes | content                                             |
+//! |-------|-----------------------------------------------------|
+//! | 0..4  | CRC-32C (Castagnoli) of the data of the block       |
+//! | 4..8  | low 32 bits of the block offset within the nexus    |
+//!
+//! The offset makes a block which was read from the wrong place stand out,
+//! in the same way as the reference tag of T10 protection information. The
+//! metadata is computed by the nexus regardless of the format of the
+//! children, and the metadata submitted with a write is not stored.
+
+use crc::crc32::{self, Hasher32};
+use spdk_sys::iovec;
+
+/// size in bytes of the metadata of a block holding its checksum
+pub const READ_CHECKSUM_MD_SIZE: u32 = 8;
+
+/// the metadata of the block with the given offset and data
+pub fn read_checksum(offset: u64, data: &[u8]) -> [u8; 8] {
+    let mut md = [0; 8];
+    md[.. 4].copy_from_slice(&crc32::checksum_castagnoli(data).to_le_bytes());
+    md[4 ..].copy_from_slice(&(offset as u32).to_le_bytes());
+    md
+}
+
+/// fill in the metadata of the blocks held by the iovecs, the first of which
+/// is at the given block offset
+pub(crate) fn fill_read_checksums(
+    md: &mut [u8],
+    offset: u64,
+    block_len: u64,
+    iovs: &[iovec],
+) {
+    let mut digest = crc32::Digest::new(crc32::CASTAGNOLI);
+    let mut filled = 0;
+    let mut block = 0;
+    let mut chunks = md.chunks_exact_mut(READ_CHECKSUM_MD_SIZE as usize);
+
+    for iov in iovs {
+        let mut data = unsafe {
+            std::slice::from_raw_parts(
+                iov.iov_base as *const u8,
+                iov.iov_len as usize,
+            )
+        };
+        while !data.is_empty() {
+            let n = data.len().min((block_len - filled) as usize);
+            digest.write(&data[.. n]);
+            data = &data[n ..];
+            filled += n as u64;
+            if filled == block_len {
+                match chunks.next() {
+                    Some(chunk) => {
+                        chunk[.. 4]
+                            .copy_from_slice(&digest.sum32().to_le_bytes());
+                        chunk[4 ..].copy_from_slice(
+                            &((offset + block) as u32).to_le_bytes(),
+                        );
+                    }
+                    None => return,
+                }
+                digest.reset();
+                filled = 0;
+                block += 1;
+            }
+        }
+    }
+}
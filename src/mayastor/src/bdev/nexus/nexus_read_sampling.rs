@@ -0,0 +1,180 @@
+//! Sampling of reads, which catches silent divergence of the children during
+//! normal operation without a dedicated verification pass. One in every
+//! `read_sample_interval` reads completed on a core (see `NexusPolicy`) is
+//! read again from another healthy child before it completes, and the data
+//! is compared. A sampled read is only delayed by the second read; all
+//! other reads are not affected.
+//!
+//! When the data differs and no write to the range is in flight on any
+//! core, the range is read again from all healthy children and the data of
+//! the majority is taken as the source of truth. The children which disagree
+//! with it are retired with `Reason::DataCorruption`, such that they are
+//! repaired by a rebuild, and the read returns the data of the majority.
+//! Without a majority, e.g. with two children, the divergence is reported
+//! but nothing is repaired. Every divergence raises a
+//! `NexusEvent::ReadMismatch`.
+//!
+//! A read which overlaps a write submitted on the same core is never sampled,
+//! as the children may legitimately differ until the write completes.
+
+use std::{cell::Cell, rc::Rc};
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::Nexus,
+        nexus_child::ChildState,
+        nexus_event::{self, NexusEvent},
+        nexus_io::NexusBio,
+    },
+    core::{Bdev, BdevHandle, DmaBuf, IoType},
+};
+
+/// true if the IO modifies any of the blocks of the given range
+pub(crate) fn overlaps_write(
+    io: &NexusBio,
+    offset: u64,
+    num_blocks: u64,
+) -> bool {
+    matches!(
+        io.io_type(),
+        IoType::Write | IoType::WriteZeros | IoType::Unmap
+    ) && io.offset() < offset + num_blocks
+        && offset < io.offset() + io.num_blocks()
+}
+
+/// true if a write to the given range is in flight on any core
+async fn write_in_flight(nexus: &Nexus, offset: u64, num_blocks: u64) -> bool {
+    let found = Rc::new(Cell::new(false));
+    let f = Rc::clone(&found);
+    nexus
+        .traverse_io_channels(move |channel| {
+            if channel
+                .outstanding
+                .iter()
+                .any(|io| overlaps_write(&io, offset, num_blocks))
+            {
+                f.set(true);
+            }
+        })
+        .await;
+    found.get()
+}
+
+/// read the range of the nexus from a child
+async fn read_child(
+    h: &BdevHandle,
+    data_offset: u64,
+    offset: u64,
+    len: u64,
+) -> Option<DmaBuf> {
+    let block_len = u64::from(h.get_bdev().block_len());
+    let mut buf = h.dma_malloc(len).ok()?;
+    h.read_at((data_offset + offset) * block_len, &mut buf)
+        .await
+        .ok()?;
+    Some(buf)
+}
+
+/// Compare the data of a sampled read at the given block offset of the nexus,
+/// which was read from the `served` child, with the data of another healthy
+/// child. On divergence the children which disagree with the majority are
+/// returned along with the data of the majority, none when the data is
+/// consistent or no majority could be established.
+pub(crate) async fn compare_read(
+    nexus: &Nexus,
+    served: &Bdev,
+    offset: u64,
+    data: &[u8],
+) -> Option<(Vec<Bdev>, DmaBuf)> {
+    nexus.metrics.read_sampled();
+    let len = data.len() as u64;
+    let num_blocks = len / u64::from(served.block_len());
+
+    // the child which served the read comes first
+    let mut handles = nexus
+        .children
+        .iter()
+        .filter(|c| c.state() == ChildState::Open)
+        .filter_map(|c| {
+            c.handle().ok().map(|h| (nexus.child_data_offset(c), h))
+        })
+        .collect::<Vec<_>>();
+    handles.sort_by_key(|(_, h)| h.get_bdev().as_ptr() != served.as_ptr());
+
+    let (data_offset, other) = handles
+        .iter()
+        .find(|(_, h)| h.get_bdev().as_ptr() != served.as_ptr())?;
+    match read_child(other, *data_offset, offset, len).await {
+        Some(buf) if buf.as_slice() != data => {}
+        _ => return None,
+    }
+
+    if write_in_flight(nexus, offset, num_blocks).await {
+        debug!(
+            "{}: sampled read of {} blocks at {} raced with a write",
+            nexus.name, num_blocks, offset
+        );
+        return None;
+    }
+
+    // read the range again from all children, now that no write is in flight
+    let mut copies = Vec::new();
+    for (data_offset, h) in &handles {
+        if let Some(buf) = read_child(h, *data_offset, offset, len).await {
+            copies.push((h.get_bdev(), buf));
+        }
+    }
+    let (majority, votes) = copies
+        .iter()
+        .enumerate()
+        .map(|(i, (_, buf))| {
+            (
+                i,
+                copies
+                    .iter()
+                    .filter(|(_, b)| b.as_slice() == buf.as_slice())
+                    .count(),
+            )
+        })
+        .max_by_key(|(_, votes)| *votes)?;
+    if votes == copies.len() {
+        // the children agree by now, the data changed in the meantime
+        return None;
+    }
+
+    nexus.metrics.read_mismatch();
+    let decided = votes * 2 > copies.len();
+    let dissenters = copies
+        .iter()
+        .filter(|(_, buf)| {
+            !decided || buf.as_slice() != copies[majority].1.as_slice()
+        })
+        .map(|(bdev, _)| bdev.clone())
+        .collect::<Vec<_>>();
+    let names = dissenters.iter().map(|b| b.name()).collect::<Vec<_>>();
+    error!(
+        "{}: sampled read of {} blocks at {} diverges on children {:?}, {}",
+        nexus.name,
+        num_blocks,
+        offset,
+        names,
+        if decided {
+            "repairing them"
+        } else {
+            "no majority to repair them from"
+        }
+    );
+    nexus_event::emit(NexusEvent::ReadMismatch {
+        nexus: nexus.name.clone(),
+        offset,
+        num_blocks,
+        children: names,
+        repaired: decided,
+    });
+
+    if !decided {
+        return None;
+    }
+    let (_, buf) = copies.swap_remove(majority);
+    Some((dissenters, buf))
+}
@@ -0,0 +1,121 @@
+//! Weights by which the reads of a nexus are spread over its children, such
+//! that read traffic can be steered away from a child which is healthy but
+//! under pressure, e.g. as its node is being drained, without faulting it or
+//! disabling its reads. The readers the read policy selects from are chosen
+//! in proportion to their weight; a child with a weight of zero serves no new
+//! reads while it keeps receiving all writes. When all readers have a weight
+//! of zero the weights are ignored.
+//!
+//! Weights are reset to `READ_WEIGHT_DEFAULT` when the IO channels of the
+//! nexus are reconfigured, unless they are pinned.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use serde::Serialize;
+
+use crate::bdev::nexus::nexus_bdev::{Error, Nexus};
+
+/// weight of a child whose weight has not been set
+pub const READ_WEIGHT_DEFAULT: u32 = 100;
+
+/// The read weight of a child
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReadWeight {
+    /// name of the child
+    pub child: String,
+    pub weight: u32,
+    /// the weight is kept when the nexus is reconfigured
+    pub pinned: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct ReadWeighting {
+    weight: AtomicU32,
+    pinned: AtomicBool,
+}
+
+impl Default for ReadWeighting {
+    fn default() -> Self {
+        Self {
+            weight: AtomicU32::new(READ_WEIGHT_DEFAULT),
+            pinned: AtomicBool::new(false),
+        }
+    }
+}
+
+impl ReadWeighting {
+    pub(crate) fn weight(&self) -> u32 {
+        self.weight.load(Ordering::Relaxed)
+    }
+
+    fn pinned(&self) -> bool {
+        self.pinned.load(Ordering::Relaxed)
+    }
+
+    fn set(&self, weight: u32, pinned: bool) {
+        self.weight.store(weight, Ordering::Relaxed);
+        self.pinned.store(pinned, Ordering::Relaxed);
+    }
+
+    /// reset the weight to the default unless it is pinned
+    fn reset(&self) {
+        if !self.pinned() {
+            self.weight.store(READ_WEIGHT_DEFAULT, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Nexus {
+    /// Set the weight of a child by which the reads of the nexus are spread
+    /// over the children, zero to stop new reads to it. A weight which is
+    /// not pinned is reset by the next reconfiguration of the nexus.
+    pub async fn set_child_read_weight(
+        &self,
+        name: &str,
+        weight: u32,
+        pinned: bool,
+    ) -> Result<(), Error> {
+        let child =
+            self.children
+                .iter()
+                .find(|c| c.name == name)
+                .ok_or_else(|| Error::ChildNotFound {
+                    child: name.to_owned(),
+                    name: self.name.clone(),
+                })?;
+
+        info!(
+            "{}: read weight of child {} set to {}{}",
+            self.name,
+            name,
+            weight,
+            if pinned { " (pinned)" } else { "" }
+        );
+        child.read_weight.set(weight, pinned);
+
+        if let Some(bdev) = child.bdev.as_ref().map(|b| b.name()) {
+            self.traverse_io_channels(move |channel| {
+                channel.set_read_weight(&bdev, weight)
+            })
+            .await;
+        }
+        Ok(())
+    }
+
+    /// the current read weights of the children
+    pub fn read_weights(&self) -> Vec<ReadWeight> {
+        self.children
+            .iter()
+            .map(|c| ReadWeight {
+                child: c.name.clone(),
+                weight: c.read_weight.weight(),
+                pinned: c.read_weight.pinned(),
+            })
+            .collect()
+    }
+
+    /// reset the read weights of the children which are not pinned
+    pub(crate) fn reset_read_weights(&self) {
+        self.children.iter().for_each(|c| c.read_weight.reset());
+    }
+}
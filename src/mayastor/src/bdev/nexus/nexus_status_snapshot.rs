@@ -0,0 +1,109 @@
+//! A point in time view of a nexus combining its health, the state of its
+//! children and its IO statistics, such that a control plane gets the full
+//! picture with a single call rather than assembling it from separate queries
+//! which each see the nexus at a different moment.
+//!
+//! The latency histograms of the children are kept per core and are gathered
+//! from all cores first. The snapshot is then taken in a single pass on the
+//! calling reactor without yielding, and every derived figure is computed from
+//! the values read in that pass: the health and the readiness of the nexus are
+//! tallied from the child states of the snapshot rather than read anew, and
+//! the aggregate latency is merged from the child histograms of the snapshot.
+//! The IO counters are updated from every core as IO goes on; completions are
+//! read before submissions, so the snapshot never shows more IOs completed
+//! than were submitted.
+
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::bdev::nexus::{
+    nexus_bdev::Nexus,
+    nexus_child::{ChildState, Reason},
+    nexus_child_latency::LatencyPercentiles,
+    nexus_health::{NexusHealth, Readiness},
+    nexus_metrics::NexusMetricsSnapshot,
+};
+
+/// the status of a child at the time of the snapshot of its nexus
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChildStatusSnapshot {
+    /// name of the child
+    pub child: String,
+    pub state: ChildState,
+    /// reason of the last fault of the child
+    pub last_fault: Option<Reason>,
+    /// progress in percent of the rebuild of the child, if it is rebuilt
+    pub rebuild_progress: Option<u64>,
+    /// latency of the IO completed by the child since it was last opened
+    pub latency: LatencyPercentiles,
+}
+
+/// The status of a nexus at a single point in time
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NexusStatusSnapshot {
+    pub nexus: String,
+    /// time the snapshot was taken
+    pub time: SystemTime,
+    pub health: NexusHealth,
+    pub readiness: Readiness,
+    pub children: Vec<ChildStatusSnapshot>,
+    /// latency of the IO completed by all children
+    pub latency: LatencyPercentiles,
+    /// the metrics of the nexus, which include its outstanding IOs, its IOPS
+    /// and throughput and the number of snapshots taken of it
+    pub metrics: NexusMetricsSnapshot,
+}
+
+impl Nexus {
+    /// the health, the state of the children and the IO statistics of the
+    /// nexus, all taken at the same time
+    pub async fn status_snapshot(&self) -> NexusStatusSnapshot {
+        let mut latencies = self.child_io_counts().await;
+        let time = SystemTime::now();
+        let mut counts = Vec::new();
+        let children = self
+            .children
+            .iter()
+            .map(|c| {
+                let state = c.state();
+                let rebuild_progress = self
+                    .rebuild_progress(&c.name)
+                    .filter(|_| state == ChildState::Faulted(Reason::OutOfSync))
+                    .map(|p| p.percent);
+                let latency = latencies
+                    .iter()
+                    .position(|(name, _)| *name == c.name)
+                    .map(|i| latencies.swap_remove(i).1.all())
+                    .unwrap_or_default();
+                if counts.len() < latency.len() {
+                    counts.resize(latency.len(), 0);
+                }
+                counts.iter_mut().zip(&latency).for_each(|(t, c)| *t += c);
+                ChildStatusSnapshot {
+                    child: c.name.clone(),
+                    state,
+                    last_fault: c.history().last_fault.map(|(r, _)| r),
+                    rebuild_progress,
+                    latency: LatencyPercentiles::from_counts(&latency),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let states = children
+            .iter()
+            .map(|c| (c.state, c.rebuild_progress.is_some()))
+            .collect::<Vec<_>>();
+        let health = NexusHealth::tally(&states);
+
+        NexusStatusSnapshot {
+            nexus: self.name.clone(),
+            time,
+            health,
+            readiness: self.readiness_with(health.open),
+            children,
+            latency: LatencyPercentiles::from_counts(&counts),
+            metrics: self.metrics(),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Live throughput of a nexus, in bytes per second of the reads and writes
+//! submitted to it and of the background IO issued to its children by
+//! rebuilds and consistency checks, along with the IOs per second. The reads
+//! and writes are counted by the IO channel they are submitted on, and added
+//! to the counters of the nexus by the poller of the channel every
+//! `IO_TIMEOUT_SCAN`, which also closes the window once it has elapsed. The
+//! rates are derived from the counters over windows of a second; a snapshot
+//! closes a window which no poller closed. The IO path thus neither touches
+//! shared counters nor reads the clock.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+/// window over which the throughput is measured
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(1);
+
+/// the throughput of a nexus over the last completed window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Throughput {
+    pub read_bytes_per_sec: u64,
+    pub write_bytes_per_sec: u64,
+    /// bytes per second of the IO of rebuilds and consistency checks
+    pub background_bytes_per_sec: u64,
+    pub read_iops: u64,
+    pub write_iops: u64,
+}
+
+/// the kinds of IO the throughput is measured of
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Traffic {
+    Read = 0,
+    Write = 1,
+    Background = 2,
+}
+
+#[derive(Debug)]
+struct Window {
+    /// start of the window being measured
+    start: Instant,
+    /// the byte counters at the start of the window
+    bytes: [u64; 3],
+    /// the bytes per second over the last completed window
+    rates: [u64; 3],
+    /// the IO counters at the start of the window
+    ios: [u64; 3],
+    /// the IOs per second over the last completed window
+    iops: [u64; 3],
+}
+
+/// the bytes and IOs of each kind of traffic submitted on an IO channel since
+/// they were last added to the gauge of the nexus
+#[derive(Debug, Default)]
+pub(crate) struct ChannelTraffic {
+    bytes: [u64; 3],
+    ios: [u64; 3],
+}
+
+impl ChannelTraffic {
+    /// account the bytes of an IO of the given kind
+    pub(crate) fn account(&mut self, traffic: Traffic, bytes: u64) {
+        self.bytes[traffic as usize] += bytes;
+        self.ios[traffic as usize] += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ThroughputGauge {
+    bytes: [AtomicU64; 3],
+    ios: [AtomicU64; 3],
+    window: Mutex<Option<Window>>,
+}
+
+impl ThroughputGauge {
+    /// account the bytes of an IO of the given kind issued outside of the
+    /// IO channels of the nexus
+    pub(crate) fn account(&self, traffic: Traffic, bytes: u64) {
+        self.bytes[traffic as usize].fetch_add(bytes, Ordering::Relaxed);
+        self.ios[traffic as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// add the traffic of an IO channel and reset it, then close the window
+    /// if it has elapsed by the given time. A window being closed by another
+    /// core is left to it.
+    pub(crate) fn add(&self, traffic: &mut ChannelTraffic, now: Instant) {
+        let ChannelTraffic {
+            bytes,
+            ios,
+        } = std::mem::take(traffic);
+        for i in 0 .. 3 {
+            if ios[i] != 0 {
+                self.bytes[i].fetch_add(bytes[i], Ordering::Relaxed);
+                self.ios[i].fetch_add(ios[i], Ordering::Relaxed);
+            }
+        }
+        if let Ok(mut window) = self.window.try_lock() {
+            self.roll(&mut window, now);
+        }
+    }
+
+    /// the throughput over the last completed window, which ends now when
+    /// no IO closed it
+    pub(crate) fn throughput(&self) -> Throughput {
+        let mut window = self.window.lock().unwrap();
+        self.roll(&mut window, Instant::now());
+        let (rates, iops) = window
+            .as_ref()
+            .map_or(([0; 3], [0; 3]), |w| (w.rates, w.iops));
+        Throughput {
+            read_bytes_per_sec: rates[Traffic::Read as usize],
+            write_bytes_per_sec: rates[Traffic::Write as usize],
+            background_bytes_per_sec: rates[Traffic::Background as usize],
+            read_iops: iops[Traffic::Read as usize],
+            write_iops: iops[Traffic::Write as usize],
+        }
+    }
+
+    /// close the window once it has elapsed and start the next one
+    fn roll(&self, window: &mut Option<Window>, now: Instant) {
+        let bytes = [
+            self.bytes[0].load(Ordering::Relaxed),
+            self.bytes[1].load(Ordering::Relaxed),
+            self.bytes[2].load(Ordering::Relaxed),
+        ];
+        let ios = [
+            self.ios[0].load(Ordering::Relaxed),
+            self.ios[1].load(Ordering::Relaxed),
+            self.ios[2].load(Ordering::Relaxed),
+        ];
+        match window {
+            None => {
+                *window = Some(Window {
+                    start: now,
+                    bytes,
+                    rates: [0; 3],
+                    ios,
+                    iops: [0; 3],
+                })
+            }
+            Some(w) => {
+                let elapsed = now - w.start;
+                if elapsed >= THROUGHPUT_WINDOW {
+                    let rate = |count: u64| {
+                        (count as u128 * 1_000_000 / elapsed.as_micros()) as u64
+                    };
+                    for i in 0 .. 3 {
+                        w.rates[i] = rate(bytes[i] - w.bytes[i]);
+                        w.iops[i] = rate(ios[i] - w.ios[i]);
+                    }
+                    w.start = now;
+                    w.bytes = bytes;
+                    w.ios = ios;
+                }
+            }
+        }
+    }
+}
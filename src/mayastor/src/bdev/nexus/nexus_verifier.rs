@@ -0,0 +1,23 @@
+//! Verification of the data returned by successful child reads, for instance
+//! against checksums or protection information. A read which completes
+//! successfully but returns data that fails verification is treated as a
+//! corruption rather than an IO error: the child is faulted with
+//! `Reason::DataCorruption` and the read is repaired from the other children.
+//!
+//! The verifier is invoked inline on the reactor that completes the read and
+//! must not block.
+
+use std::fmt;
+
+/// Verifies the data of a read
+pub trait ReadVerifier: Send + Sync {
+    /// returns false if the data read from the given child, starting at the
+    /// given block offset of the nexus, is corrupt
+    fn verify(&self, child: &str, offset: u64, data: &[&[u8]]) -> bool;
+}
+
+impl fmt::Debug for dyn ReadVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ReadVerifier")
+    }
+}
@@ -0,0 +1,97 @@
+//! Coalescing of the writes submitted to a nexus, see `WriteCache`. Every
+//! channel collects a run of contiguous writes which is submitted to the
+//! children as a single write once the run cannot be extended any further:
+//! when a write arrives which does not continue it, when it reaches the size
+//! limit of the cache or the number of iovecs a child accepts in a single IO,
+//! when a flush is submitted, or at the latest once the
+//! reactor has handled the IO submitted in the same poll. The writes of a run
+//! are completed individually, as each of them completes on the children.
+
+use spdk_sys::iovec;
+
+use crate::bdev::nexus::nexus_io::NexusBio;
+
+/// maximum number of writes which are coalesced into one
+pub const COALESCE_MAX_WRITES: usize = 64;
+
+/// the run of contiguous writes being collected on a channel
+#[derive(Debug, Default)]
+pub(crate) struct WriteCoalescer {
+    ios: Vec<NexusBio>,
+    /// offset in blocks just past the end of the run
+    end: u64,
+    /// size of the run in bytes
+    bytes: u64,
+    /// number of iovecs of the writes of the run
+    iovs: u32,
+}
+
+impl WriteCoalescer {
+    /// true if a write of the given offset, size and number of iovecs can be
+    /// added to the run without exceeding the given size, nor the number of
+    /// iovecs a write target of the run accepts, if limited
+    pub(crate) fn extends(
+        &self,
+        offset: u64,
+        bytes: u64,
+        iovs: u32,
+        max: u64,
+        max_iovs: Option<u32>,
+    ) -> bool {
+        self.ios.is_empty()
+            || (offset == self.end
+                && self.bytes + bytes <= max
+                && max_iovs.map_or(true, |max| self.iovs + iovs <= max)
+                && self.ios.len() < COALESCE_MAX_WRITES)
+    }
+
+    /// add a write to the run, returns true if it starts the run
+    pub(crate) fn push(
+        &mut self,
+        io: NexusBio,
+        end: u64,
+        bytes: u64,
+        iovs: u32,
+    ) -> bool {
+        self.ios.push(io);
+        self.end = end;
+        self.bytes += bytes;
+        self.iovs += iovs;
+        self.ios.len() == 1
+    }
+
+    /// number of iovecs of the writes of the run
+    pub(crate) fn iovs(&self) -> u32 {
+        self.iovs
+    }
+
+    /// size of the run in bytes
+    pub(crate) fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// number of writes in the run
+    pub(crate) fn len(&self) -> usize {
+        self.ios.len()
+    }
+
+    /// take the run for submission, along with its size in bytes
+    pub(crate) fn take(&mut self) -> Option<(Vec<NexusBio>, u64)> {
+        if self.ios.is_empty() {
+            return None;
+        }
+        let bytes = std::mem::take(&mut self.bytes);
+        self.iovs = 0;
+        Some((std::mem::take(&mut self.ios), bytes))
+    }
+}
+
+/// a run of writes submitted to the children as a single write, which lives
+/// until the write has completed on all of them
+pub(crate) struct WriteRun {
+    pub(crate) ios: Vec<NexusBio>,
+    /// the iovecs of all writes of the run, in order
+    pub(crate) iovs: Vec<iovec>,
+    /// number of child writes which have not completed yet
+    pub(crate) pending: usize,
+}
@@ -0,0 +1,168 @@
+//! A consistent view of the writable children of a nexus. Operations which fan
+//! out to all children (snapshots, NVMe admin broadcasts) capture the writer
+//! set when they start, run against that set only and verify afterwards that
+//! the topology of the nexus did not change in the meantime. When it did, the
+//! operation is restarted against the new set, such that a concurrent fault
+//! cannot leave the operation applied to only a part of the children.
+
+use std::{future::Future, sync::atomic::Ordering};
+
+use crate::{
+    bdev::nexus::{
+        nexus_bdev::{Error, Nexus},
+        nexus_child::ChildState,
+    },
+    core::{BdevHandle, CoreError},
+};
+
+/// number of times an operation is attempted before giving up when the
+/// topology keeps changing
+pub const WRITER_SET_ATTEMPTS: u32 = 3;
+
+/// the writable children of a nexus at a given topology epoch
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriterSet {
+    epoch: u64,
+    children: Vec<String>,
+}
+
+impl WriterSet {
+    /// topology epoch at which the set was captured
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// names of the children in the set
+    pub fn children(&self) -> &[String] {
+        &self.children
+    }
+
+    /// true if the topology of the nexus has not changed since the set was
+    /// captured, and all children of the set are still open
+    pub fn is_current(&self, nexus: &Nexus) -> bool {
+        nexus.epoch() == self.epoch
+            && self.children.iter().all(|name| {
+                nexus
+                    .child_lookup(name)
+                    .map_or(false, |c| c.state() == ChildState::Open)
+            })
+    }
+}
+
+impl Nexus {
+    /// topology epoch of the nexus, which changes whenever the IO channels
+    /// are reconfigured
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    /// capture the current set of writable children
+    pub fn writer_set(&self) -> WriterSet {
+        WriterSet {
+            epoch: self.epoch(),
+            children: self
+                .children
+                .iter()
+                .filter(|c| c.state() == ChildState::Open)
+                .map(|c| c.name.clone())
+                .collect(),
+        }
+    }
+
+    /// Run the operation against a consistent writer set. The operation is
+    /// restarted when the topology changed while it ran, up to
+    /// `WRITER_SET_ATTEMPTS` times. Returns the set the operation completed
+    /// against along with its result.
+    pub async fn with_writer_set<F, Fut, T>(
+        &self,
+        mut op: F,
+    ) -> Result<(WriterSet, T), Error>
+    where
+        F: FnMut(WriterSet) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        for attempt in 1 ..= WRITER_SET_ATTEMPTS {
+            let set = self.writer_set();
+            let result = op(set.clone()).await;
+            if set.is_current(self) {
+                return result.map(|r| (set, r));
+            }
+            warn!(
+                "{}: children changed during attempt {}, restarting",
+                self.name, attempt
+            );
+        }
+
+        Err(Error::TopologyChanged {
+            name: self.name.clone(),
+            attempts: WRITER_SET_ATTEMPTS,
+        })
+    }
+
+    /// Run the operation on every child of a consistent writer set, see
+    /// `with_writer_set`. Returns the result per child.
+    pub async fn for_each_writer<F, Fut, T>(
+        &self,
+        mut op: F,
+    ) -> Result<Vec<(String, T)>, Error>
+    where
+        F: FnMut(BdevHandle) -> Fut,
+        Fut: Future<Output = Result<T, CoreError>>,
+    {
+        let name = self.name.clone();
+        self.with_writer_set(|set| {
+            let mut handles = Vec::new();
+            for child in set.children() {
+                handles.push((
+                    child.clone(),
+                    self.child_lookup(child).map(|c| c.handle()),
+                ));
+            }
+            let futures = handles
+                .into_iter()
+                .map(|(child, hdl)| match hdl {
+                    Some(Ok(hdl)) => Ok((child, op(hdl))),
+                    Some(Err(source)) => Err(Error::WriterOperation {
+                        source,
+                        child,
+                        name: name.clone(),
+                    }),
+                    None => Err(Error::ChildNotFound {
+                        child,
+                        name: name.clone(),
+                    }),
+                })
+                .collect::<Vec<_>>();
+            let name = name.clone();
+            async move {
+                let mut results = Vec::new();
+                for f in futures {
+                    let (child, f) = f?;
+                    match f.await {
+                        Ok(r) => results.push((child, r)),
+                        Err(source) => {
+                            return Err(Error::WriterOperation {
+                                source,
+                                child,
+                                name,
+                            })
+                        }
+                    }
+                }
+                Ok(results)
+            }
+        })
+        .await
+        .map(|(_, results)| results)
+    }
+
+    /// send the NVMe admin command with the given opcode to every writable
+    /// child
+    pub async fn nvme_admin_broadcast(&self, opcode: u8) -> Result<(), Error> {
+        self.for_each_writer(|hdl| async move {
+            hdl.nvme_admin_custom(opcode).await
+        })
+        .await
+        .map(|_| ())
+    }
+}
@@ -203,6 +203,7 @@ async fn nexus_create(
             uuid: uuid.clone(),
             size,
             children,
+            ..Default::default()
         })
         .await
         .context(GrpcStatus)?;
@@ -18,6 +18,7 @@ pub async fn handler(
 ) -> crate::Result<()> {
     match matches.subcommand() {
         ("create", Some(args)) => create(ctx, &args).await,
+        ("destroy", Some(args)) => destroy(ctx, &args).await,
         (cmd, _) => {
             Err(Status::not_found(format!("command {} does not exist", cmd)))
                 .context(GrpcStatus)
@@ -35,6 +36,21 @@ pub fn subcommands<'a, 'b>() -> App<'a, 'b> {
                 .help("uuid of the nexus"),
         );
 
+    let destroy = SubCommand::with_name("destroy")
+        .about("destroy a snapshot")
+        .arg(
+            Arg::with_name("uuid")
+                .required(true)
+                .index(1)
+                .help("uuid of the nexus"),
+        )
+        .arg(
+            Arg::with_name("name")
+                .required(true)
+                .index(2)
+                .help("name of the snapshot"),
+        );
+
     SubCommand::with_name("snapshot")
         .settings(&[
             AppSettings::SubcommandRequiredElseHelp,
@@ -43,6 +59,7 @@ pub fn subcommands<'a, 'b>() -> App<'a, 'b> {
         ])
         .about("Snapshot management")
         .subcommand(create)
+        .subcommand(destroy)
 }
 
 async fn create(
@@ -81,3 +98,47 @@ async fn create(
 
     Ok(())
 }
+
+async fn destroy(
+    mut ctx: Context,
+    matches: &ArgMatches<'_>,
+) -> crate::Result<()> {
+    let uuid = matches
+        .value_of("uuid")
+        .ok_or_else(|| Error::MissingValue {
+            field: "uuid".to_string(),
+        })?
+        .to_string();
+    let name = matches
+        .value_of("name")
+        .ok_or_else(|| Error::MissingValue {
+            field: "name".to_string(),
+        })?
+        .to_string();
+
+    let response = ctx
+        .client
+        .destroy_snapshot(rpc::DestroySnapshotRequest {
+            uuid,
+            name: name.clone(),
+        })
+        .await
+        .context(GrpcStatus)?;
+
+    match ctx.output {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&response.get_ref())
+                    .unwrap()
+                    .to_colored_json_auto()
+                    .unwrap()
+            );
+        }
+        OutputFormat::Default => {
+            println!("{}", &name);
+        }
+    };
+
+    Ok(())
+}
@@ -81,6 +81,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let futures = vec![
         master.boxed_local(),
         subsys::Registration::run().boxed_local(),
+        subsys::EventPublisher::run().boxed_local(),
         grpc::MayastorGrpcServer::run(grpc_endpoint, rpc_address).boxed_local(),
     ];
 
@@ -211,7 +211,7 @@ impl Bdev {
 
         if rc != 0 {
             Err(CoreError::OpenBdev {
-                source: Errno::from_i32(rc),
+                source: Errno::from_i32(rc.abs()),
             })
         } else {
             Ok(Descriptor::from_null_checked(descriptor).unwrap())
@@ -268,6 +268,54 @@ impl Bdev {
         }
     }
 
+    /// maximum number of blocks of a single unmap, 0 when unlimited
+    pub fn max_unmap(&self) -> u32 {
+        unsafe { self.0.as_ref().max_unmap }
+    }
+
+    /// set the maximum number of blocks of a single unmap
+    pub fn set_max_unmap(&mut self, blocks: u32) {
+        unsafe {
+            self.0.as_mut().max_unmap = blocks;
+        }
+    }
+
+    /// maximum number of blocks of a single write zeroes, 0 when unlimited
+    pub fn max_write_zeroes(&self) -> u32 {
+        unsafe { self.0.as_ref().max_write_zeroes }
+    }
+
+    /// set the maximum number of blocks of a single write zeroes
+    pub fn set_max_write_zeroes(&mut self, blocks: u32) {
+        unsafe {
+            self.0.as_mut().max_write_zeroes = blocks;
+        }
+    }
+
+    /// maximum number of iovecs of a single IO, 0 when unlimited
+    pub fn max_num_segments(&self) -> u32 {
+        unsafe { self.0.as_ref().max_num_segments }
+    }
+
+    /// set the maximum number of iovecs of a single IO
+    pub fn set_max_num_segments(&mut self, segments: u32) {
+        unsafe {
+            self.0.as_mut().max_num_segments = segments;
+        }
+    }
+
+    /// number of blocks the length of a write must be a multiple of
+    pub fn write_unit_size(&self) -> u32 {
+        unsafe { self.0.as_ref().write_unit_size }
+    }
+
+    /// set the number of blocks the length of a write must be a multiple of
+    pub fn set_write_unit_size(&mut self, blocks: u32) {
+        unsafe {
+            self.0.as_mut().write_unit_size = blocks;
+        }
+    }
+
     /// return the bdev size in bytes
     pub fn size_in_bytes(&self) -> u64 {
         self.num_blocks() * self.block_len() as u64
@@ -2,18 +2,36 @@ use core::fmt;
 use std::{
     fmt::{Debug, Formatter},
     ptr::NonNull,
+    time::Duration,
 };
 
 use libc::c_void;
-
-use spdk_sys::{spdk_bdev_free_io, spdk_bdev_io, spdk_bdev_io_complete};
+use serde::{Deserialize, Serialize};
+
+use spdk_sys::{
+    spdk_bdev_desc,
+    spdk_bdev_free_io,
+    spdk_bdev_io,
+    spdk_bdev_io_complete,
+    spdk_bdev_io_complete_nvme_status,
+    spdk_bdev_io_get_nvme_status,
+    spdk_get_ticks,
+    spdk_get_ticks_hz,
+    SPDK_NVME_SCT_GENERIC,
+    SPDK_NVME_SCT_MEDIA_ERROR,
+    SPDK_NVME_SC_NAMESPACE_NOT_READY,
+    SPDK_NVME_SC_UNRECOVERED_READ_ERROR,
+    SPDK_NVME_SC_WRITE_FAULTS,
+};
 
 use crate::{
     bdev::nexus::nexus_bdev::{Nexus, NEXUS_PRODUCT_ID},
-    core::{Bdev, NvmeStatus},
+    core::{Bdev, NvmeStatus, NvmeStatusCode},
 };
 
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Eq)]
+#[derive(
+    Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize,
+)]
 pub enum IoType {
     Invalid,
     Read,
@@ -35,7 +53,9 @@ pub enum IoType {
     IoNumTypes,
 }
 
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Eq)]
+#[derive(
+    Debug, Copy, Clone, PartialOrd, PartialEq, Eq, Serialize, Deserialize,
+)]
 #[non_exhaustive]
 pub enum IoStatus {
     Aborted,
@@ -139,6 +159,17 @@ impl From<i8> for IoStatus {
     }
 }
 
+/// the status of an IO which either succeeded or failed
+impl From<bool> for IoStatus {
+    fn from(success: bool) -> Self {
+        if success {
+            Self::Success
+        } else {
+            Self::Failed
+        }
+    }
+}
+
 #[derive(Clone)]
 #[repr(transparent)]
 pub struct Bio(NonNull<spdk_bdev_io>);
@@ -176,6 +207,66 @@ impl Bio {
         }
     }
 
+    /// mark the IO as failed with a retriable NVMe status (namespace not
+    /// ready), which makes the initiator retry the IO
+    #[inline]
+    pub(crate) fn fail_retriable(&self) {
+        unsafe {
+            spdk_bdev_io_complete_nvme_status(
+                self.0.as_ptr(),
+                0,
+                SPDK_NVME_SCT_GENERIC as i32,
+                SPDK_NVME_SC_NAMESPACE_NOT_READY as i32,
+            )
+        }
+    }
+
+    /// mark the IO as failed with a media error NVMe status, used when the IO
+    /// failed on all children of the nexus
+    #[inline]
+    pub(crate) fn fail_all_replicas(&self) {
+        let sc = if self.io_type() == IoType::Read {
+            SPDK_NVME_SC_UNRECOVERED_READ_ERROR
+        } else {
+            SPDK_NVME_SC_WRITE_FAULTS
+        };
+        unsafe {
+            spdk_bdev_io_complete_nvme_status(
+                self.0.as_ptr(),
+                0,
+                SPDK_NVME_SCT_MEDIA_ERROR as i32,
+                sc as i32,
+            )
+        }
+    }
+
+    /// mark the IO as failed with the given NVMe status
+    #[inline]
+    pub(crate) fn fail_nvme(&self, status: NvmeStatusCode) {
+        unsafe {
+            spdk_bdev_io_complete_nvme_status(
+                self.0.as_ptr(),
+                0,
+                status.sct as i32,
+                status.sc as i32,
+            )
+        }
+    }
+
+    /// complete the IO with the given completion dword 0 and NVMe status, as
+    /// a device it was passed through to completed it
+    #[inline]
+    pub(crate) fn complete_nvme(&self, cdw0: u32, status: NvmeStatusCode) {
+        unsafe {
+            spdk_bdev_io_complete_nvme_status(
+                self.0.as_ptr(),
+                cdw0,
+                status.sct as i32,
+                status.sc as i32,
+            )
+        }
+    }
+
     /// mark the IO as impossible to submit due to a memory constraint
     #[inline]
     pub(crate) fn no_mem(&self) {
@@ -210,6 +301,26 @@ impl Bio {
         unsafe { self.0.as_ref().u.bdev.iovs }
     }
 
+    /// get a raw pointer to the base of the iov holding the data a
+    /// compare-and-write writes, the iov of the IO holds the data compared
+    #[inline]
+    pub(crate) fn fused_iovs(&self) -> *mut spdk_sys::iovec {
+        unsafe { self.0.as_ref().u.bdev.fused_iovs }
+    }
+
+    /// number of iovs holding the data a compare-and-write writes
+    #[inline]
+    pub(crate) fn fused_iov_count(&self) -> i32 {
+        unsafe { self.0.as_ref().u.bdev.fused_iovcnt }
+    }
+
+    /// the separate metadata buffer of this IO, null if the IO was submitted
+    /// without one
+    #[inline]
+    pub(crate) fn md_buf(&self) -> *mut c_void {
+        unsafe { self.0.as_ref().u.bdev.md_buf }
+    }
+
     /// number of iovs that are part of this IO
     #[inline]
     pub(crate) fn iov_count(&self) -> i32 {
@@ -244,6 +355,23 @@ impl Bio {
         unsafe { self.0.as_ref().internal.status }.into()
     }
 
+    /// the descriptor the IO was submitted with
+    #[inline]
+    pub(crate) fn desc(&self) -> *mut spdk_bdev_desc {
+        unsafe { self.0.as_ref().internal.desc }
+    }
+
+    /// time since the IO was submitted to its bdev
+    #[inline]
+    pub(crate) fn elapsed(&self) -> Duration {
+        let (now, hz) = unsafe { (spdk_get_ticks(), spdk_get_ticks_hz()) };
+        let ticks =
+            now.saturating_sub(unsafe { self.0.as_ref().internal.submit_tsc });
+        Duration::from_micros(
+            (u128::from(ticks) * 1_000_000 / u128::from(hz.max(1))) as u64,
+        )
+    }
+
     /// get the block length of this IO
     #[inline]
     #[allow(dead_code)]
@@ -253,21 +381,18 @@ impl Bio {
 
     /// NVMe passthru command
     #[inline]
-    #[allow(dead_code)]
     pub(crate) fn nvme_cmd(&self) -> spdk_sys::spdk_nvme_cmd {
         unsafe { self.0.as_ref().u.nvme_passthru.cmd }
     }
 
     /// raw pointer to NVMe passthru data buffer
     #[inline]
-    #[allow(dead_code)]
     pub(crate) fn nvme_buf(&self) -> *mut c_void {
         unsafe { self.0.as_ref().u.nvme_passthru.buf as *mut _ }
     }
 
     /// NVMe passthru number of bytes to transfer
     #[inline]
-    #[allow(dead_code)]
     pub(crate) fn nvme_nbytes(&self) -> u64 {
         unsafe { self.0.as_ref().u.nvme_passthru.nbytes }
     }
@@ -290,6 +415,33 @@ impl Bio {
         NvmeStatus::from(self)
     }
 
+    /// the NVMe status of the IO as is
+    pub(crate) fn nvme_status_code(&self) -> NvmeStatusCode {
+        NvmeStatusCode::from(self)
+    }
+
+    /// the completion dword 0 of the IO along with its NVMe status
+    pub(crate) fn nvme_completion(&self) -> (u32, NvmeStatusCode) {
+        let mut cdw0: u32 = 0;
+        let mut sct: i32 = 0;
+        let mut sc: i32 = 0;
+        unsafe {
+            spdk_bdev_io_get_nvme_status(
+                self.0.as_ptr(),
+                &mut cdw0,
+                &mut sct,
+                &mut sc,
+            )
+        }
+        (
+            cdw0,
+            NvmeStatusCode {
+                sct: sct as u8,
+                sc: sc as u8,
+            },
+        )
+    }
+
     pub(crate) fn as_ptr(&self) -> *mut spdk_bdev_io {
         self.0.as_ptr()
     }
@@ -9,13 +9,19 @@ use libc::c_void;
 use nix::errno::Errno;
 
 use spdk_sys::{
+    iovec,
     spdk_bdev_desc,
+    spdk_bdev_flush,
     spdk_bdev_free_io,
     spdk_bdev_io,
     spdk_bdev_nvme_admin_passthru_ro,
     spdk_bdev_read,
+    spdk_bdev_read_with_md,
+    spdk_bdev_readv,
     spdk_bdev_reset,
+    spdk_bdev_unmap,
     spdk_bdev_write,
+    spdk_bdev_writev,
     spdk_io_channel,
 };
 
@@ -189,6 +195,139 @@ impl BdevHandle {
         }
     }
 
+    /// write the ['DmaBuf']s to the given offset as a single IO with an iovec
+    /// per buffer
+    pub async fn writev_at(
+        &self,
+        offset: u64,
+        buffers: &[DmaBuf],
+    ) -> Result<usize, CoreError> {
+        let mut iovs = Self::iovs(buffers);
+        let len = buffers.iter().map(|b| b.len()).sum::<u64>();
+        let (s, r) = oneshot::channel::<bool>();
+        let errno = unsafe {
+            spdk_bdev_writev(
+                self.desc.as_ptr(),
+                self.channel.as_ptr(),
+                iovs.as_mut_ptr(),
+                iovs.len() as i32,
+                offset,
+                len,
+                Some(Self::io_completion_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(CoreError::WriteDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset,
+                len,
+            });
+        }
+
+        if r.await.expect("Failed awaiting write IO") {
+            Ok(len as usize)
+        } else {
+            Err(CoreError::WriteFailed {
+                offset,
+                len,
+            })
+        }
+    }
+
+    /// read at the given offset into the ['DmaBuf']s as a single IO with an
+    /// iovec per buffer
+    pub async fn readv_at(
+        &self,
+        offset: u64,
+        buffers: &mut [DmaBuf],
+    ) -> Result<u64, CoreError> {
+        let mut iovs = Self::iovs(buffers);
+        let len = buffers.iter().map(|b| b.len()).sum::<u64>();
+        let (s, r) = oneshot::channel::<bool>();
+        let errno = unsafe {
+            spdk_bdev_readv(
+                self.desc.as_ptr(),
+                self.channel.as_ptr(),
+                iovs.as_mut_ptr(),
+                iovs.len() as i32,
+                offset,
+                len,
+                Some(Self::io_completion_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(CoreError::ReadDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset,
+                len,
+            });
+        }
+
+        if r.await.expect("Failed awaiting read IO") {
+            Ok(len)
+        } else {
+            Err(CoreError::ReadFailed {
+                offset,
+                len,
+            })
+        }
+    }
+
+    /// an iovec for each of the buffers
+    fn iovs(buffers: &[DmaBuf]) -> Vec<iovec> {
+        buffers
+            .iter()
+            .map(|b| iovec {
+                iov_base: **b,
+                iov_len: b.len(),
+            })
+            .collect()
+    }
+
+    /// read at the given offset into the buffer, along with the separate
+    /// metadata of the blocks read into the metadata buffer
+    pub async fn read_with_md_at(
+        &self,
+        offset: u64,
+        buffer: &mut DmaBuf,
+        md: &mut DmaBuf,
+    ) -> Result<u64, CoreError> {
+        let (s, r) = oneshot::channel::<bool>();
+        let errno = unsafe {
+            spdk_bdev_read_with_md(
+                self.desc.as_ptr(),
+                self.channel.as_ptr(),
+                **buffer,
+                **md,
+                offset,
+                buffer.len() as u64,
+                Some(Self::io_completion_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(CoreError::ReadDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset,
+                len: buffer.len(),
+            });
+        }
+
+        if r.await.expect("Failed awaiting read IO") {
+            Ok(buffer.len())
+        } else {
+            Err(CoreError::ReadFailed {
+                offset,
+                len: buffer.len(),
+            })
+        }
+    }
+
     pub async fn reset(&self) -> Result<usize, CoreError> {
         let (s, r) = oneshot::channel::<bool>();
         let errno = unsafe {
@@ -213,6 +352,66 @@ impl BdevHandle {
         }
     }
 
+    /// flush the whole bdev
+    pub async fn flush(&self) -> Result<usize, CoreError> {
+        let (s, r) = oneshot::channel::<bool>();
+        let bdev = self.get_bdev();
+        let errno = unsafe {
+            spdk_bdev_flush(
+                self.desc.as_ptr(),
+                self.channel.as_ptr(),
+                0,
+                bdev.num_blocks() * bdev.block_len() as u64,
+                Some(Self::io_completion_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(CoreError::FlushDispatch {
+                source: Errno::from_i32(errno.abs()),
+            });
+        }
+
+        if r.await.expect("Failed awaiting flush IO") {
+            Ok(0)
+        } else {
+            Err(CoreError::FlushFailed {})
+        }
+    }
+
+    /// unmap the given number of bytes at the given offset
+    pub async fn unmap(&self, offset: u64, len: u64) -> Result<(), CoreError> {
+        let (s, r) = oneshot::channel::<bool>();
+        let errno = unsafe {
+            spdk_bdev_unmap(
+                self.desc.as_ptr(),
+                self.channel.as_ptr(),
+                offset,
+                len,
+                Some(Self::io_completion_cb),
+                cb_arg(s),
+            )
+        };
+
+        if errno != 0 {
+            return Err(CoreError::UnmapDispatch {
+                source: Errno::from_i32(errno.abs()),
+                offset,
+                len,
+            });
+        }
+
+        if r.await.expect("Failed awaiting unmap IO") {
+            Ok(())
+        } else {
+            Err(CoreError::UnmapFailed {
+                offset,
+                len,
+            })
+        }
+    }
+
     /// create a snapshot, only works for nvme bdev
     /// returns snapshot time as u64 seconds since Unix epoch
     pub async fn create_snapshot(&self) -> Result<u64, CoreError> {
@@ -224,6 +423,16 @@ impl BdevHandle {
         Ok(now as u64)
     }
 
+    /// create a snapshot at the given time in seconds since Unix epoch, which
+    /// the name of the snapshot is derived from, only works for nvme bdev
+    pub async fn create_snapshot_at(&self, time: u64) -> Result<(), CoreError> {
+        let mut cmd = spdk_sys::spdk_nvme_cmd::default();
+        cmd.set_opc(nvme_admin_opc::CREATE_SNAPSHOT.into());
+        subsys::encode_snapshot_time(&mut cmd, time);
+        debug!("Creating snapshot at {}", time);
+        self.nvme_admin(&cmd, None).await
+    }
+
     /// identify controller
     /// buffer must be at least 4096B
     pub async fn nvme_identify_ctrlr(
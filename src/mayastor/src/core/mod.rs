@@ -20,7 +20,7 @@ pub use env::{
 
 pub use bio::{Bio, IoStatus, IoType};
 pub use handle::BdevHandle;
-pub use nvme::{nvme_admin_opc, GenericStatusCode, NvmeStatus};
+pub use nvme::{nvme_admin_opc, GenericStatusCode, NvmeStatus, NvmeStatusCode};
 pub use reactor::{Reactor, ReactorState, Reactors, REACTOR_LIST};
 pub use share::{Protocol, Share};
 pub use thread::Mthread;
@@ -87,6 +87,20 @@ pub enum CoreError {
     ResetDispatch {
         source: Errno,
     },
+    #[snafu(display("Failed to dispatch flush",))]
+    FlushDispatch {
+        source: Errno,
+    },
+    #[snafu(display(
+        "Failed to dispatch unmap at offset {} length {}",
+        offset,
+        len
+    ))]
+    UnmapDispatch {
+        source: Errno,
+        offset: u64,
+        len: u64,
+    },
     #[snafu(display("Failed to dispatch NVMe Admin command {:x}h", opcode))]
     NvmeAdminDispatch {
         source: Errno,
@@ -104,6 +118,13 @@ pub enum CoreError {
     },
     #[snafu(display("Reset failed"))]
     ResetFailed {},
+    #[snafu(display("Flush failed"))]
+    FlushFailed {},
+    #[snafu(display("Unmap failed at offset {} length {}", offset, len))]
+    UnmapFailed {
+        offset: u64,
+        len: u64,
+    },
     #[snafu(display("NVMe Admin command {:x}h failed", opcode))]
     NvmeAdminFailed {
         opcode: u16,
@@ -8,6 +8,7 @@ use crate::core::{
     },
     Bio,
 };
+use serde::{Deserialize, Serialize};
 use spdk_sys::spdk_bdev_io_get_nvme_status;
 
 #[derive(Debug, Copy, Clone, Eq, PartialOrd, PartialEq)]
@@ -195,13 +196,117 @@ impl From<&Bio> for NvmeStatus {
     }
 }
 
+/// The status code type and status code of an NVMe completion as is, such
+/// that it can be passed on without losing detail. The bdev layer does not
+/// convey the do not retry bit, hence it is not part of the status.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct NvmeStatusCode {
+    /// NVMe status code type
+    pub sct: u8,
+    /// NVMe status code
+    pub sc: u8,
+}
+
+impl NvmeStatusCode {
+    /// true if the status does not indicate an error
+    pub fn is_success(&self) -> bool {
+        self.sct == 0 && self.sc == 0
+    }
+
+    /// the status of a command with an opcode the device does not support
+    pub const INVALID_OPCODE: Self = Self {
+        sct: 0x00,
+        sc: 0x01,
+    };
+
+    /// the status of a command which failed on an internal error of the
+    /// device
+    pub const INTERNAL_DEVICE_ERROR: Self = Self {
+        sct: 0x00,
+        sc: 0x06,
+    };
+
+    /// the status of a command whose range of blocks exceeds the namespace
+    pub const LBA_OUT_OF_RANGE: Self = Self {
+        sct: 0x00,
+        sc: 0x80,
+    };
+
+    /// the status of a command to a namespace which is not ready to serve it
+    pub const NAMESPACE_NOT_READY: Self = Self {
+        sct: 0x00,
+        sc: 0x82,
+    };
+
+    /// the status of a command whose data failed an end-to-end guard check
+    pub const GUARD_CHECK_ERROR: Self = Self {
+        sct: 0x02,
+        sc: 0x82,
+    };
+
+    /// the status of a compare command which found the data to differ
+    pub const COMPARE_FAILURE: Self = Self {
+        sct: 0x02,
+        sc: 0x85,
+    };
+
+    /// True if retrying the command with the same device cannot succeed. As
+    /// the bdev layer does not convey the do not retry bit, the status is
+    /// classified by its code instead: only a command which is invalid in
+    /// itself is never resolved by a retry. Command specific and media
+    /// errors, such as an unrecovered read error, may well succeed on a
+    /// retry or once the blocks have been rewritten.
+    pub fn do_not_retry(&self) -> bool {
+        // invalid opcode, invalid field, invalid namespace or format and
+        // LBA out of range
+        self.sct == 0x00 && matches!(self.sc, 0x01 | 0x02 | 0x0b | 0x80)
+    }
+}
+
+impl From<&Bio> for NvmeStatusCode {
+    fn from(b: &Bio) -> Self {
+        let mut cdw0: u32 = 0;
+        let mut sct: i32 = 0;
+        let mut sc: i32 = 0;
+
+        unsafe {
+            spdk_bdev_io_get_nvme_status(
+                b.as_ptr(),
+                &mut cdw0,
+                &mut sct,
+                &mut sc,
+            )
+        }
+
+        Self {
+            sct: sct as u8,
+            sc: sc as u8,
+        }
+    }
+}
+
 /// NVMe Admin opcode, from nvme_spec.h
 pub mod nvme_admin_opc {
-    // pub const GET_LOG_PAGE: u8 = 0x02;
+    pub const GET_LOG_PAGE: u8 = 0x02;
     pub const IDENTIFY: u8 = 0x06;
     // pub const ABORT: u8 = 0x08;
-    // pub const SET_FEATURES: u8 = 0x09;
-    // pub const GET_FEATURES: u8 = 0x0a;
+    pub const SET_FEATURES: u8 = 0x09;
+    pub const GET_FEATURES: u8 = 0x0a;
+    pub const FIRMWARE_COMMIT: u8 = 0x10;
+    pub const FIRMWARE_IMAGE_DOWNLOAD: u8 = 0x11;
+    pub const FORMAT_NVM: u8 = 0x80;
+    pub const SANITIZE: u8 = 0x84;
     // Vendor-specific
     pub const CREATE_SNAPSHOT: u8 = 0xc0;
 }
@@ -55,6 +55,15 @@ impl<'a> Poller<'a> {
     }
 }
 
+impl<'a> std::fmt::Debug for Poller<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Poller")
+            .field("inner", &self.inner)
+            .field("stopped", &self.stopped)
+            .finish()
+    }
+}
+
 impl<'a> Drop for Poller<'a> {
     fn drop(&mut self) {
         if !self.stopped {
@@ -126,8 +126,32 @@ impl mayastor_server::Mayastor for MayastorSvc {
             let args = request.into_inner();
             let uuid = args.uuid.clone();
             let name = uuid_to_name(&args.uuid)?;
+            let read_policy = NexusReadPolicy::from_i32(args.read_policy)
+                .ok_or_else(|| {
+                    Status::invalid_argument(format!(
+                        "invalid read policy {}",
+                        args.read_policy
+                    ))
+                })?;
+            let preferred = Some(args.preferred_child)
+                .filter(|child| !child.is_empty());
+            if let Some(child) = &preferred {
+                if !args.children.contains(child) {
+                    return Err(Status::invalid_argument(format!(
+                        "preferred child {} is not a child of the nexus",
+                        child
+                    )));
+                }
+            }
             locally! { async move {
-                nexus_create(&name, args.size, Some(&args.uuid), &args.children).await
+                nexus_create(&name, args.size, Some(&args.uuid), &args.children).await?;
+                let nexus = crate::bdev::nexus_lookup(&name).ok_or_else(|| {
+                    nexus_bdev::Error::NexusNotFound {
+                        name: name.clone(),
+                    }
+                })?;
+                nexus.set_read_policy(read_policy.into());
+                nexus.set_preferred_reader(preferred.as_deref()).await
             }};
             let nexus = nexus_lookup(&uuid)?;
             info!("Created nexus {}", uuid);
@@ -158,12 +182,21 @@ impl mayastor_server::Mayastor for MayastorSvc {
     ) -> GrpcResult<ListNexusReply> {
         let args = request.into_inner();
         trace!("{:?}", args);
-        let reply = ListNexusReply {
-            nexus_list: instances()
+        let reply = locally! { async move {
+            let names = instances()
                 .iter()
-                .map(|n| n.to_grpc())
-                .collect::<Vec<_>>(),
-        };
+                .map(|n| n.name.clone())
+                .collect::<Vec<_>>();
+            let mut nexus_list = Vec::new();
+            for name in names {
+                if let Some(nexus) = crate::bdev::nexus_lookup(&name) {
+                    nexus_list.push(nexus.to_grpc_with_stats().await);
+                }
+            }
+            Ok::<_, nexus_bdev::Error>(ListNexusReply {
+                nexus_list,
+            })
+        }};
         trace!("{:?}", reply);
         Ok(Response::new(reply))
     }
@@ -226,6 +259,22 @@ impl mayastor_server::Mayastor for MayastorSvc {
         .await
     }
 
+    #[instrument(level = "debug", err)]
+    async fn set_nexus_io_timeout(
+        &self,
+        request: Request<SetNexusIoTimeoutRequest>,
+    ) -> GrpcResult<Null> {
+        let args = request.into_inner();
+        let uuid = args.uuid.clone();
+        let timeout = std::time::Duration::from_millis(args.timeout_ms);
+        debug!("Setting the IO timeout of nexus {} ...", uuid);
+        locally! { async move {
+            nexus_lookup(&args.uuid).map(|n| n.set_io_timeout(timeout))
+        }};
+        info!("Set the IO timeout of nexus {} to {:?}", uuid, timeout);
+        Ok(Response::new(Null {}))
+    }
+
     #[instrument(level = "debug", err)]
     async fn publish_nexus(
         &self,
@@ -473,6 +522,25 @@ impl mayastor_server::Mayastor for MayastorSvc {
         .await
     }
 
+    #[instrument(level = "debug", err)]
+    async fn destroy_snapshot(
+        &self,
+        request: Request<DestroySnapshotRequest>,
+    ) -> GrpcResult<DestroySnapshotReply> {
+        sync_config(async {
+            let args = request.into_inner();
+            let uuid = args.uuid.clone();
+            debug!("Destroying snapshot {} on nexus {} ...", args.name, uuid);
+            let reply = locally! { async move {
+                nexus_lookup(&args.uuid)?.delete_snapshot(&args.name).await
+            }};
+            info!("Destroyed snapshot {} on nexus {}", reply.name, uuid);
+            trace!("{:?}", reply);
+            Ok(Response::new(reply))
+        })
+        .await
+    }
+
     #[instrument(level = "debug", err)]
     async fn list_block_devices(
         &self,
@@ -1,7 +1,10 @@
 //! Helpers related to nexus grpc methods.
 
 use ::rpc::mayastor as rpc;
-use std::convert::From;
+use std::{
+    convert::From,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use uuid::Uuid;
 
 use crate::{
@@ -9,6 +12,9 @@ use crate::{
         instances,
         nexus_bdev::{Error, Nexus, NexusStatus},
         nexus_child::{ChildState, NexusChild, Reason},
+        nexus_child_history::{ChildHistory, ChildHistoryEvent},
+        nexus_child_io_stats::ChildIoStats,
+        nexus_policy::ReadPolicy,
     },
     rebuild::RebuildJob,
 };
@@ -23,6 +29,8 @@ impl From<ChildState> for rpc::ChildState {
             ChildState::Open => rpc::ChildState::ChildOnline,
             ChildState::Destroying => rpc::ChildState::ChildDegraded,
             ChildState::Closed => rpc::ChildState::ChildDegraded,
+            ChildState::Missing => rpc::ChildState::ChildDegraded,
+            ChildState::DestroyFailed => rpc::ChildState::ChildFaulted,
             ChildState::Faulted(reason) => match reason {
                 Reason::OutOfSync => rpc::ChildState::ChildDegraded,
                 _ => rpc::ChildState::ChildFaulted,
@@ -30,6 +38,19 @@ impl From<ChildState> for rpc::ChildState {
         }
     }
 }
+impl From<rpc::NexusReadPolicy> for ReadPolicy {
+    fn from(policy: rpc::NexusReadPolicy) -> Self {
+        match policy {
+            rpc::NexusReadPolicy::ReadRoundRobin => ReadPolicy::RoundRobin,
+            rpc::NexusReadPolicy::ReadPreferLocal => ReadPolicy::PreferLocal,
+            rpc::NexusReadPolicy::ReadLeastOutstanding => {
+                ReadPolicy::LeastOutstanding
+            }
+            rpc::NexusReadPolicy::ReadPreferred => ReadPolicy::Preferred,
+        }
+    }
+}
+
 impl From<NexusStatus> for rpc::NexusState {
     fn from(nexus: NexusStatus) -> Self {
         match nexus {
@@ -40,16 +61,91 @@ impl From<NexusStatus> for rpc::NexusState {
     }
 }
 
+/// seconds since the unix epoch
+fn unix_time(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+impl From<ChildHistoryEvent> for rpc::ChildHistoryEvent {
+    fn from(event: ChildHistoryEvent) -> Self {
+        match event {
+            ChildHistoryEvent::Faulted {
+                reason,
+                time,
+            } => rpc::ChildHistoryEvent {
+                kind: "faulted".to_string(),
+                detail: reason.to_string(),
+                time: unix_time(time),
+            },
+            ChildHistoryEvent::Rebuilt {
+                duration,
+                bytes,
+                time,
+            } => rpc::ChildHistoryEvent {
+                kind: "rebuilt".to_string(),
+                detail: format!(
+                    "copied {} bytes in {} ms",
+                    bytes,
+                    duration.as_millis()
+                ),
+                time: unix_time(time),
+            },
+        }
+    }
+}
+
+impl From<ChildHistory> for rpc::ChildHistory {
+    fn from(history: ChildHistory) -> Self {
+        let (last_fault_reason, last_fault_time) = history
+            .last_fault
+            .map(|(reason, time)| (reason.to_string(), unix_time(time)))
+            .unwrap_or_default();
+        let (last_rebuild_duration_ms, last_rebuild_bytes) = history
+            .last_rebuild
+            .map(|(duration, bytes)| (duration.as_millis() as u64, bytes))
+            .unwrap_or_default();
+        rpc::ChildHistory {
+            faults: history.faults,
+            last_fault_reason,
+            last_fault_time,
+            rebuilds: history.rebuilds,
+            last_rebuild_duration_ms,
+            last_rebuild_bytes,
+            events: history.events.into_iter().map(From::from).collect(),
+        }
+    }
+}
+
+impl From<ChildIoStats> for rpc::ChildIoStats {
+    fn from(stats: ChildIoStats) -> Self {
+        rpc::ChildIoStats {
+            num_read_ops: stats.reads,
+            num_write_ops: stats.writes,
+            read_latency_p50_us: stats.read_latency.p50.as_micros() as u64,
+            read_latency_p99_us: stats.read_latency.p99.as_micros() as u64,
+            write_latency_p50_us: stats.write_latency.p50.as_micros() as u64,
+            write_latency_p99_us: stats.write_latency.p99.as_micros() as u64,
+        }
+    }
+}
+
 impl NexusChild {
     /// Convert nexus child object to grpc representation.
     ///
     /// We cannot use From trait because it is not value to value conversion.
     /// All we have is a reference to a child.
     pub fn to_grpc(&self) -> rpc::Child {
+        let record = self.track_record();
         rpc::Child {
             uri: self.name.clone(),
             state: rpc::ChildState::from(self.state()) as i32,
             rebuild_progress: self.get_rebuild_progress(),
+            history: Some(self.history().into()),
+            uptime_secs: record.uptime.as_secs(),
+            error_free_ios: record.error_free_ios,
+            io_stats: None,
         }
     }
 }
@@ -68,11 +164,30 @@ impl Nexus {
             children: self
                 .children
                 .iter()
-                .map(|ch| ch.to_grpc())
+                .map(|ch| {
+                    let mut child = ch.to_grpc();
+                    if let Some(p) = self.rebuild_progress(&ch.name) {
+                        child.rebuild_progress = p.percent as i32;
+                    }
+                    child
+                })
                 .collect::<Vec<_>>(),
             rebuilds: RebuildJob::count() as u32,
         }
     }
+
+    /// Convert nexus object to grpc representation, including the IO
+    /// statistics of its children which are gathered from all cores.
+    pub async fn to_grpc_with_stats(&self) -> rpc::Nexus {
+        let mut stats = self.child_io_stats().await;
+        let mut nexus = self.to_grpc();
+        for child in nexus.children.iter_mut() {
+            if let Some(i) = stats.iter().position(|s| s.child == child.uri) {
+                child.io_stats = Some(stats.swap_remove(i).into());
+            }
+        }
+        nexus
+    }
 }
 
 /// Convert nexus name to uuid.
@@ -38,6 +38,17 @@ pub enum Error {
     #[snafu(display("failed to destroy lvol {}", name))]
     RepDestroy { source: Errno, name: String },
 
+    #[snafu(display(
+        "failed to create snapshot {} of lvol {}",
+        snapshot,
+        name
+    ))]
+    RepSnapshot {
+        source: Errno,
+        snapshot: String,
+        name: String,
+    },
+
     #[snafu(display("bdev {} is not a lvol", name))]
     NotALvol { source: Errno, name: String },
 
@@ -264,6 +264,35 @@ impl Lvol {
         Ok(name)
     }
 
+    /// create a snapshot of the lvol with the given name, the snapshot is
+    /// returned as a read-only lvol
+    #[instrument(level = "debug", err)]
+    pub async fn snapshot(&self, snapshot_name: &str) -> Result<Lvol, Error> {
+        let c_snapshot_name = snapshot_name.into_cstring();
+        let (s, r) = pair::<ErrnoResult<*mut spdk_lvol>>();
+        unsafe {
+            vbdev_lvol_create_snapshot(
+                self.0.as_ptr(),
+                c_snapshot_name.as_ptr(),
+                Some(Self::lvol_cb),
+                cb_arg(s),
+            )
+        };
+
+        let snapshot = r
+            .await
+            .expect("lvol snapshot callback dropped")
+            .map_err(|e| Error::RepSnapshot {
+                source: e,
+                snapshot: snapshot_name.to_string(),
+                name: self.name(),
+            })
+            .map(|lvol| Lvol(NonNull::new(lvol).unwrap()))?;
+
+        info!("Created snapshot {} of {}", snapshot_name, self);
+        Ok(snapshot)
+    }
+
     /// callback executed after synchronizing the lvols metadata
     extern "C" fn blob_sync_cb(sender_ptr: *mut c_void, errno: i32) {
         let sender =
@@ -1,13 +1,19 @@
 #![warn(missing_docs)]
 
-use std::fmt;
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use crossbeam::channel::{Receiver, Sender};
 use futures::channel::oneshot;
 use snafu::Snafu;
 
 use crate::{
-    bdev::VerboseError,
+    bdev::{nexus::nexus_missing::MissingWrites, VerboseError},
     core::{CoreError, Descriptor, DmaError},
     nexus_uri::NexusBdevError,
 };
@@ -121,6 +127,12 @@ pub struct RebuildJob {
     pub(super) block_size: u64,
     pub(super) range: std::ops::Range<u64>,
     pub(super) next: u64,
+    /// next block to be copied, shared with the nexus channels so that
+    /// frontend writes beyond it are left to the rebuild
+    pub(super) cursor: Arc<AtomicU64>,
+    /// number of blocks recovered so far, shared with the nexus so that the
+    /// progress can be read without going through the job
+    pub(super) recovered: Arc<AtomicU64>,
     pub(super) segment_size_blks: u64,
     pub(super) task_pool: RebuildTasks,
     pub(super) notify_fn: fn(String, String) -> (),
@@ -132,6 +144,11 @@ pub struct RebuildJob {
     pub(super) complete_chan: Vec<oneshot::Sender<RebuildState>>,
     /// rebuild copy error, if any
     pub error: Option<RebuildError>,
+    /// time at which the rebuild job was created
+    pub(super) start_time: std::time::Instant,
+    /// the segments written while the destination was missing, the only
+    /// ones copied by a differential rebuild
+    pub(super) written: Option<Arc<MissingWrites>>,
 }
 
 /// rebuild statistics
@@ -140,6 +157,9 @@ pub struct RebuildStats {
     pub blocks_total: u64,
     /// number of blocks recovered
     pub blocks_recovered: u64,
+    /// number of the recovered blocks which were not copied, as a
+    /// differential rebuild found them in sync already
+    pub blocks_skipped: u64,
     /// rebuild progress in %
     pub progress: u64,
     /// granularity of each recovery copy in blocks
@@ -192,6 +212,27 @@ impl RebuildJob {
         Self::lookup(destination)
     }
 
+    /// Creates a new RebuildJob as `create`, which only copies the segments
+    /// written while the destination was missing. The destination receives
+    /// all frontend writes while it is rebuilt, as it is in sync beyond the
+    /// rebuild cursor as well, other than for the written segments which are
+    /// still to be copied and are locked while they are.
+    pub(crate) fn create_differential<'a>(
+        nexus: &str,
+        source: &str,
+        destination: &'a str,
+        range: std::ops::Range<u64>,
+        written: Arc<MissingWrites>,
+        notify_fn: fn(String, String) -> (),
+    ) -> Result<&'a mut Self, RebuildError> {
+        let mut job = Self::new(nexus, source, destination, range, notify_fn)?;
+        job.cursor.store(job.range.end, Ordering::SeqCst);
+        job.written = Some(written);
+        job.store()?;
+
+        Self::lookup(destination)
+    }
+
     /// Lookup a rebuild job by its destination uri and return it
     pub fn lookup(name: &str) -> Result<&mut Self, RebuildError> {
         if let Some(job) = Self::get_instances().get_mut(name) {
@@ -1,6 +1,13 @@
 #![warn(missing_docs)]
 
-use std::{cell::UnsafeCell, collections::HashMap};
+use std::{
+    cell::UnsafeCell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use crossbeam::channel::unbounded;
 use futures::{
@@ -13,7 +20,7 @@ use snafu::ResultExt;
 use spdk_sys::{spdk_get_thread, SPDK_BDEV_LARGE_BUF_MAX_SIZE};
 
 use crate::{
-    bdev::VerboseError,
+    bdev::{nexus_lookup, VerboseError},
     core::{Bdev, BdevHandle, DmaBuf, RangeContext, Reactors},
     nexus_uri::bdev_get_name,
 };
@@ -67,6 +74,8 @@ pub(super) struct RebuildTasks {
     total: usize,
 
     segments_done: u64,
+    /// segments of a differential rebuild which were not copied
+    segments_skipped: u64,
 }
 
 /// Checks whether a range is contained within another range
@@ -134,6 +143,7 @@ impl RebuildJob {
             active: 0,
             total: SEGMENT_TASKS,
             segments_done: 0,
+            segments_skipped: 0,
         };
 
         for _ in 0 .. tasks.total {
@@ -164,6 +174,8 @@ impl RebuildJob {
             source,
             destination,
             next: range.start,
+            cursor: Arc::new(AtomicU64::new(range.start)),
+            recovered: Arc::new(AtomicU64::new(0)),
             range,
             block_size,
             segment_size_blks,
@@ -173,9 +185,69 @@ impl RebuildJob {
             states: Default::default(),
             complete_chan: Vec::new(),
             error: None,
+            start_time: std::time::Instant::now(),
+            written: None,
         })
     }
 
+    /// Shared rebuild cursor: blocks below it have been (or are being) copied
+    /// to the destination, blocks at or beyond it are yet to be copied.
+    pub(crate) fn cursor(&self) -> Arc<AtomicU64> {
+        self.cursor.clone()
+    }
+
+    /// Shared count of the blocks recovered so far, which only the rebuild
+    /// task updates, with relaxed ordering as it is merely observed.
+    pub(crate) fn recovered(&self) -> Arc<AtomicU64> {
+        self.recovered.clone()
+    }
+
+    /// Publish the number of blocks recovered after a segment is done
+    fn segment_done(&mut self) {
+        self.task_pool.segments_done += 1;
+        let blocks = std::cmp::min(
+            self.task_pool.segments_done * self.segment_size_blks,
+            self.range.end - self.range.start,
+        );
+        self.recovered.store(blocks, Ordering::Relaxed);
+    }
+
+    /// Advance the next block to be copied and publish it as the cursor,
+    /// unless the rebuild is differential and the cursor stays at the end
+    fn set_next(&mut self, next: u64) {
+        self.next = next;
+        if self.written.is_none() {
+            self.cursor.store(next, Ordering::SeqCst);
+        }
+    }
+
+    /// Skip the segments of a differential rebuild which were not written
+    /// while the destination was missing, which are accounted as done
+    fn skip_unwritten(&mut self) {
+        let written = match self.written.as_ref() {
+            Some(written) => written.clone(),
+            None => return,
+        };
+        while self.next < self.range.end
+            && !written.written(
+                self.next - self.range.start,
+                self.get_segment_size_blks(self.next),
+            )
+        {
+            self.next = std::cmp::min(
+                self.next + self.segment_size_blks,
+                self.range.end,
+            );
+            self.segment_done();
+            self.task_pool.segments_skipped += 1;
+        }
+    }
+
+    /// Time elapsed since the rebuild job was created
+    pub fn elapsed(&self) -> std::time::Duration {
+        self.start_time.elapsed()
+    }
+
     // Runs the management async task that kicks off N rebuild copy tasks and
     // awaits each completion. When any task completes it kicks off another
     // until the bdev is fully rebuilt
@@ -311,6 +383,10 @@ impl RebuildJob {
             &mut copy_buffer
         };
 
+        let nexus = nexus_lookup(&self.nexus);
+        if let Some(nexus) = &nexus {
+            nexus.background_io(&self.source, copy_buffer.len()).await;
+        }
         source_hdl
             .read_at(blk * self.block_size, copy_buffer)
             .await
@@ -318,6 +394,11 @@ impl RebuildJob {
                 bdev: &self.source,
             })?;
 
+        if let Some(nexus) = &nexus {
+            nexus
+                .background_io(&self.destination, copy_buffer.len())
+                .await;
+        }
         destination_hdl
             .write_at(blk * self.block_size, copy_buffer)
             .await
@@ -325,6 +406,10 @@ impl RebuildJob {
                 bdev: &self.destination,
             })?;
 
+        if let Some(nexus) = nexus {
+            nexus.metrics.physical_written(copy_buffer.len());
+        }
+
         Ok(())
     }
 
@@ -478,6 +563,10 @@ impl ClientOperations for RebuildJob {
         );
 
         let progress = (blocks_recovered * 100) / blocks_total;
+        let blocks_skipped = std::cmp::min(
+            self.task_pool.segments_skipped * self.segment_size_blks,
+            blocks_recovered,
+        );
 
         info!(
             "State: {}, Src: {}, Dst: {}, range: {:?}, next: {}, \
@@ -496,6 +585,7 @@ impl ClientOperations for RebuildJob {
         RebuildStats {
             blocks_total,
             blocks_recovered,
+            blocks_skipped,
             progress,
             segment_size_blks: self.segment_size_blks,
             block_size: self.block_size,
@@ -560,7 +650,8 @@ impl RebuildJob {
         );
 
         for n in 0 .. self.task_pool.total {
-            self.next = match self.send_segment_task(n) {
+            self.skip_unwritten();
+            let next = match self.send_segment_task(n) {
                 Some(next) => {
                     self.task_pool.active += 1;
                     next
@@ -568,14 +659,21 @@ impl RebuildJob {
                 None => break, /* we've already got enough tasks to rebuild
                                 * the bdev */
             };
+            self.set_next(next);
+        }
+
+        // a differential rebuild may have nothing to copy at all
+        if self.task_pool.active == 0 {
+            self.complete();
         }
     }
 
     fn start_task_by_id(&mut self, id: usize) {
+        self.skip_unwritten();
         match self.send_segment_task(id) {
             Some(next) => {
                 self.task_pool.active += 1;
-                self.next = next;
+                self.set_next(next);
             }
             None => {
                 if self.task_pool.active == 0 {
@@ -586,10 +684,11 @@ impl RebuildJob {
     }
 
     async fn await_one_task(&mut self) -> Option<TaskResult> {
-        self.task_pool.channel.1.next().await.map(|f| {
+        let f = self.task_pool.channel.1.next().await;
+        f.map(|f| {
             self.task_pool.active -= 1;
             if f.error.is_none() {
-                self.task_pool.segments_done += 1;
+                self.segment_done();
             } else {
                 self.task_pool.tasks[f.id].error = Some(f.clone());
             }
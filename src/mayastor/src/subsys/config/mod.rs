@@ -440,7 +440,7 @@ impl Config {
                     for child in degraded_children {
                         dbg!("Start rebuilding child {}", &child.name);
                         if nexus_instance
-                            .start_rebuild(&child.name)
+                            .start_auto_rebuild(&child.name)
                             .await
                             .is_err()
                         {
@@ -52,6 +52,16 @@ pub struct NexusOpts {
     pub iscsi_nexus_port: u16,
     /// Port for replica target portal
     pub iscsi_replica_port: u16,
+    /// core on which the children that failed IO are retired, for the nexuses
+    /// created from then on; see `Nexus::set_retire_core` to set it per
+    /// nexus. Retiring a child pauses the nexus, reconfigures the channels of
+    /// all cores and destroys the child; by default this work runs on the
+    /// master reactor which also serves IO. Moving it to a dedicated core
+    /// keeps the IO latency on the master reactor stable while a child is
+    /// being retired, at the cost of an extra cross-core message per retired
+    /// child. The success path of the IO is always handled inline. The
+    /// `retire_core_benchmark` test measures the latency of both modes.
+    pub retire_core: Option<u32>,
 }
 
 /// Default nvmf port used for replicas.
@@ -74,6 +84,7 @@ impl Default for NexusOpts {
             iscsi_enable: true,
             iscsi_nexus_port: ISCSI_PORT_NEXUS,
             iscsi_replica_port: ISCSI_PORT_REPLICA,
+            retire_core: None,
         }
     }
 }
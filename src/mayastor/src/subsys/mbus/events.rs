@@ -0,0 +1,69 @@
+//! Event publisher forwarding events raised by mayastor (e.g. a nexus which
+//! became faulted) to the control plane.
+//!
+//! Events are raised from the reactors so they are queued on a channel and
+//! published by a runner on the message bus runtime.
+
+use mbus_api::{v0::*, *};
+use once_cell::sync::OnceCell;
+
+#[derive(Clone)]
+pub struct EventPublisher {
+    /// Id of the node that mayastor is running on
+    node: NodeId,
+    /// Sender side of the event queue
+    snd_chan: smol::channel::Sender<NexusEvent>,
+    /// Receive side of the event queue
+    rcv_chan: smol::channel::Receiver<NexusEvent>,
+}
+
+static MESSAGE_BUS_EVENTS: OnceCell<EventPublisher> = OnceCell::new();
+impl EventPublisher {
+    /// initialise the global event publisher instance
+    pub(super) fn init(node: &str) {
+        MESSAGE_BUS_EVENTS.get_or_init(|| EventPublisher::new(node));
+    }
+
+    /// stop publishing events
+    pub(super) fn fini(&self) {
+        self.snd_chan.close();
+    }
+
+    pub(super) fn get() -> Option<&'static EventPublisher> {
+        MESSAGE_BUS_EVENTS.get()
+    }
+
+    fn new(node: &str) -> Self {
+        let (snd_chan, rcv_chan) = smol::channel::unbounded::<NexusEvent>();
+        Self {
+            node: NodeId::from(node),
+            snd_chan,
+            rcv_chan,
+        }
+    }
+
+    /// queue an event to be published on the message bus. Events are dropped
+    /// when the message bus has not been configured.
+    pub fn publish(mut event: NexusEvent) {
+        if let Some(publisher) = Self::get() {
+            event.node = publisher.node.clone();
+            if let Err(error) = publisher.snd_chan.try_send(event) {
+                warn!("Failed to queue event: {}", error);
+            }
+        }
+    }
+
+    /// runner responsible for publishing the queued events until the
+    /// publisher is terminated
+    pub async fn run() -> Result<(), ()> {
+        if let Some(publisher) = Self::get() {
+            while let Ok(event) = publisher.rcv_chan.recv().await {
+                if let Err(error) = event.publish().await {
+                    error!("Failed to publish event {:?}: {:?}", event, error);
+                }
+            }
+            info!("Terminating the event publisher");
+        }
+        Ok(())
+    }
+}
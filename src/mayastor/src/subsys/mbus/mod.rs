@@ -4,11 +4,14 @@
 //!
 //! A Registration subsystem is used to keep moac in the loop
 //! about the lifecycle of mayastor instances.
+//! An event publisher forwards events raised by mayastor to the control plane.
 
+pub mod events;
 pub mod registration;
 
 use crate::core::MayastorEnvironment;
 use dns_lookup::{lookup_addr, lookup_host};
+use events::EventPublisher;
 use registration::Registration;
 use spdk_sys::{
     spdk_add_subsystem,
@@ -63,6 +66,9 @@ impl MessageBusSubsystem {
     extern "C" fn init() {
         debug!("mayastor mbus subsystem init");
         let args = MayastorEnvironment::global_or_default();
+        if args.mbus_endpoint.is_some() {
+            EventPublisher::init(&args.node_name);
+        }
         if let (Some(_), Some(grpc)) = (args.mbus_endpoint, args.grpc_endpoint)
         {
             Registration::init(&args.node_name, &grpc.to_string());
@@ -73,6 +79,9 @@ impl MessageBusSubsystem {
     extern "C" fn fini() {
         debug!("mayastor mbus subsystem fini");
         let args = MayastorEnvironment::global_or_default();
+        if let Some(publisher) = EventPublisher::get() {
+            publisher.fini();
+        }
         if args.mbus_endpoint.is_some() && args.grpc_endpoint.is_some() {
             Registration::get().fini();
         }
@@ -11,6 +11,7 @@ pub use config::{
 };
 pub use nvmf::{
     create_snapshot,
+    encode_snapshot_time,
     set_snapshot_time,
     Error as NvmfError,
     NvmeCpl,
@@ -26,6 +27,7 @@ use spdk_sys::{
 };
 
 pub use mbus::{
+    events::EventPublisher,
     mbus_endpoint,
     message_bus_init,
     registration::Registration,
@@ -58,16 +58,22 @@ impl From<*mut c_void> for NvmfReq {
 /// Set the snapshot time in an spdk_nvme_cmd struct to the current time
 /// Returns seconds since Unix epoch
 pub fn set_snapshot_time(cmd: &mut spdk_nvme_cmd) -> u64 {
-    // encode snapshot time in cdw10/11
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
+    encode_snapshot_time(cmd, now);
+    now as u64
+}
+
+/// Set the snapshot time in an spdk_nvme_cmd struct to the given time in
+/// seconds since Unix epoch
+pub fn encode_snapshot_time(cmd: &mut spdk_nvme_cmd, time: u64) {
+    // encode snapshot time in cdw10/11
     unsafe {
-        *spdk_sys::nvme_cmd_cdw10_get(&mut *cmd) = now as u32;
-        *spdk_sys::nvme_cmd_cdw11_get(&mut *cmd) = (now >> 32) as u32;
+        *spdk_sys::nvme_cmd_cdw10_get(&mut *cmd) = time as u32;
+        *spdk_sys::nvme_cmd_cdw11_get(&mut *cmd) = (time >> 32) as u32;
     }
-    now as u64
 }
 
 /// NVMf custom command handler for opcode c0h
@@ -13,7 +13,13 @@ use std::cell::RefCell;
 use nix::errno::Errno;
 use snafu::Snafu;
 
-pub use admin_cmd::{create_snapshot, set_snapshot_time, NvmeCpl, NvmfReq};
+pub use admin_cmd::{
+    create_snapshot,
+    encode_snapshot_time,
+    set_snapshot_time,
+    NvmeCpl,
+    NvmfReq,
+};
 use poll_groups::PollGroup;
 use spdk_sys::{
     spdk_subsystem,
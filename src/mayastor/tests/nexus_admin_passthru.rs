@@ -0,0 +1,108 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, AdminPassthru},
+    core::{nvme_admin_opc, BdevHandle, MayastorCliArgs},
+};
+use rpc::mayastor::{BdevShareRequest, BdevUri};
+
+pub mod common;
+use common::{compose::Builder, MayastorTest};
+
+static NEXUS_NAME: &str = "admin_passthru_nexus";
+static LOCAL_CHILD: &str = "malloc:///malloc0?blk_size=512&size_mb=100";
+
+#[tokio::test]
+async fn admin_passthru() {
+    let test = Builder::new()
+        .name("admin_passthru_test")
+        .network("10.1.0.0/16")
+        .add_container("ms1")
+        .with_clean(true)
+        .build()
+        .await
+        .unwrap();
+    let mut hdls = test.grpc_handles().await.unwrap();
+
+    // the remote child is an NVMe device, which handles admin commands
+    hdls[0]
+        .bdev
+        .create(BdevUri {
+            uri: "malloc:///disk0?size_mb=100".into(),
+        })
+        .await
+        .unwrap();
+    hdls[0]
+        .bdev
+        .share(BdevShareRequest {
+            name: "disk0".into(),
+            proto: "nvmf".into(),
+        })
+        .await
+        .unwrap();
+    let remote_child = format!(
+        "nvmf://{}:8420/nqn.2019-05.io.openebs:disk0",
+        hdls[0].endpoint.ip()
+    );
+
+    let mayastor = MayastorTest::new(MayastorCliArgs::default());
+    mayastor
+        .spawn(async move {
+            nexus_create(
+                NEXUS_NAME,
+                1024 * 1024 * 50,
+                None,
+                &[LOCAL_CHILD.to_string(), remote_child.clone()],
+            )
+            .await
+            .unwrap();
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+            let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+            let mut buf = h.dma_malloc(4096).unwrap();
+
+            // admin commands fail unless they are passed through
+            assert!(h.nvme_identify_ctrlr(&mut buf).await.is_err());
+            assert!(nexus
+                .set_admin_passthru(Some(AdminPassthru {
+                    child: "other".to_string(),
+                    allow_writes: false,
+                }))
+                .is_err());
+            nexus
+                .set_admin_passthru(Some(AdminPassthru {
+                    child: remote_child.clone(),
+                    allow_writes: false,
+                }))
+                .unwrap();
+
+            // the identify data of the nexus is that of the child
+            buf.fill(0);
+            h.nvme_identify_ctrlr(&mut buf).await.unwrap();
+            let child_bdev = nexus.children[1].bdev.as_ref().unwrap().name();
+            let ch = BdevHandle::open(&child_bdev, false, false).unwrap();
+            let mut expected = ch.dma_malloc(4096).unwrap();
+            ch.nvme_identify_ctrlr(&mut expected).await.unwrap();
+            assert!(buf.as_slice().iter().any(|b| *b != 0));
+            assert_eq!(buf.as_slice(), expected.as_slice());
+            drop(ch);
+
+            // commands which modify the child are rejected, as are those
+            // which are not known to be safe
+            assert!(h
+                .nvme_admin_custom(nvme_admin_opc::FORMAT_NVM)
+                .await
+                .is_err());
+            assert!(h
+                .nvme_admin_custom(nvme_admin_opc::CREATE_SNAPSHOT)
+                .await
+                .is_err());
+            assert!(!nexus
+                .policy()
+                .admin_passthru
+                .as_ref()
+                .unwrap()
+                .allows(nvme_admin_opc::SANITIZE));
+
+            drop(h);
+            nexus.destroy().await.unwrap();
+        })
+        .await;
+}
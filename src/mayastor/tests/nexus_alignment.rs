@@ -0,0 +1,68 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "alignment_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_alignment() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // the alignment must be a power of two multiple of the block size
+        assert!(nexus.set_alignment(Some(256)).is_err());
+        assert!(nexus.set_alignment(Some(3 * 512)).is_err());
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut small = h.dma_malloc(512).unwrap();
+        let mut large = h.dma_malloc(4096).unwrap();
+
+        // by default IO only needs to be aligned to the block size
+        h.write_at(512, &small).await.unwrap();
+        h.read_at(512, &mut small).await.unwrap();
+        h.unmap(512, 512).await.unwrap();
+
+        nexus.set_alignment(Some(4096)).unwrap();
+
+        // aligned offset and length
+        h.write_at(4096, &large).await.unwrap();
+        h.read_at(4096, &mut large).await.unwrap();
+        h.unmap(4096, 4096).await.unwrap();
+        assert_eq!(nexus.metrics().misaligned, 0);
+
+        // misaligned offset
+        assert!(h.write_at(512, &large).await.is_err());
+        assert!(h.read_at(512, &mut large).await.is_err());
+        assert!(h.unmap(512, 4096).await.is_err());
+        assert_eq!(nexus.metrics().misaligned, 3);
+
+        // misaligned length
+        assert!(h.write_at(4096, &small).await.is_err());
+        assert!(h.read_at(4096, &mut small).await.is_err());
+        assert!(h.unmap(4096, 512).await.is_err());
+        assert_eq!(nexus.metrics().misaligned, 6);
+
+        nexus.set_alignment(None).unwrap();
+        h.write_at(512, &small).await.unwrap();
+        assert_eq!(nexus.metrics().misaligned, 6);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_lookup,
+        AllFailedPolicy,
+        ChildState,
+        IoObserver,
+        NioCtx,
+    },
+    core::{BdevHandle, IoStatus, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_READ,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "all_failed_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/all_failed_disk1.img";
+static DISKNAME2: &str = "/tmp/all_failed_disk2.img";
+static ERROR_DEVICE1: &str = "all_failed_error_device1";
+static ERROR_DEVICE2: &str = "all_failed_error_device2";
+static EE_ERROR_DEVICE1: &str = "EE_all_failed_error_device1";
+static EE_ERROR_DEVICE2: &str = "EE_all_failed_error_device2";
+
+#[derive(Default)]
+struct StatusObserver {
+    statuses: Mutex<Vec<IoStatus>>,
+}
+
+impl IoObserver for StatusObserver {
+    fn on_submit(&self, _ctx: &NioCtx, _io_type: IoType) {}
+
+    fn on_complete(&self, _ctx: &NioCtx, status: IoStatus) {
+        self.statuses.lock().unwrap().push(status);
+    }
+}
+
+#[tokio::test]
+async fn all_failed_policy() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE1, DISKNAME1);
+        create_error_bdev(ERROR_DEVICE2, DISKNAME2);
+        let children = vec![
+            format!("bdev:///{}", EE_ERROR_DEVICE1),
+            format!("bdev:///{}", EE_ERROR_DEVICE2),
+        ];
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let observer = Arc::new(StatusObserver::default());
+        nexus.register_io_observer(observer.clone());
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // a read which failed on one child and then on the other is retried
+        nexus.set_all_failed_policy(AllFailedPolicy::RetryOnce);
+        for device in &[EE_ERROR_DEVICE1, EE_ERROR_DEVICE2] {
+            inject_error(device, SPDK_BDEV_IO_TYPE_READ, VBDEV_IO_FAILURE, 1);
+        }
+
+        for _ in 0 .. 2 {
+            buf.fill(0);
+            h.read_at(0, &mut buf).await.unwrap();
+            assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        }
+        assert_eq!(nexus.metrics().all_failed, 1);
+        assert_eq!(nexus.metrics().failed_over_reads, 1);
+
+        // with both children failing, the read completes with a distinct
+        // status
+        nexus.set_all_failed_policy(AllFailedPolicy::AllReplicasFailed);
+        for device in &[EE_ERROR_DEVICE1, EE_ERROR_DEVICE2] {
+            inject_error(device, SPDK_BDEV_IO_TYPE_READ, VBDEV_IO_FAILURE, 10);
+        }
+
+        observer.statuses.lock().unwrap().clear();
+        h.read_at(0, &mut buf).await.unwrap_err();
+        assert_eq!(
+            *observer.statuses.lock().unwrap(),
+            vec![IoStatus::NvmeError]
+        );
+        assert_eq!(nexus.metrics().all_failed, 2);
+
+        // failed reads do not take the children out
+        assert!(nexus.children.iter().all(|c| c.state() == ChildState::Open));
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
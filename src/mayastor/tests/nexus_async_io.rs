@@ -0,0 +1,50 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{BdevHandle, IoStatus, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "async_io_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_async_io() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, false, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        drop(h);
+
+        buf.fill(0xaa);
+        assert_eq!(
+            nexus.write_at(4096, &buf).await.unwrap(),
+            IoStatus::Success
+        );
+
+        buf.fill(0);
+        assert_eq!(
+            nexus.read_at(4096, &mut buf).await.unwrap(),
+            IoStatus::Success
+        );
+        assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+
+        // IO beyond the end of the nexus cannot be dispatched
+        assert!(nexus.read_at(NEXUS_SIZE, &mut buf).await.is_err());
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_event,
+        nexus_lookup,
+        AutoRecovery,
+        ChildState,
+        NexusEvent,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_WRITE,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "auto_recovery_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/auto_recovery_disk1.img";
+static ERROR_DEVICE: &str = "auto_recovery_error_device";
+static EE_ERROR_DEVICE: &str = "EE_auto_recovery_error_device";
+static CHILD_2: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn auto_recovery() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    let mut events = ms
+        .spawn(async {
+            create_error_bdev(ERROR_DEVICE, DISKNAME1);
+            let child1 = format!("bdev:///{}", EE_ERROR_DEVICE);
+            nexus_create(
+                NEXUS_NAME,
+                NEXUS_SIZE,
+                None,
+                &[child1, CHILD_2.to_string(), CHILD_3.to_string()],
+            )
+            .await
+            .unwrap();
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+            assert_eq!(nexus.policy().auto_recovery, None);
+
+            let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+            let mut buf = h.dma_malloc(4096).unwrap();
+            buf.fill(0xaa);
+            h.write_at(0, &buf).await.unwrap();
+
+            // a child taken offline by the operator is not recovered
+            nexus.offline_child(CHILD_2).await.unwrap();
+            nexus.set_auto_recovery(Some(AutoRecovery {
+                interval: Duration::from_millis(100),
+            }));
+            let events = nexus_event::subscribe();
+
+            // the first child fails a write and is retired for it, its
+            // backend is reachable again right away
+            inject_error(
+                EE_ERROR_DEVICE,
+                SPDK_BDEV_IO_TYPE_WRITE,
+                VBDEV_IO_FAILURE,
+                1,
+            );
+            buf.fill(0xbb);
+            h.write_at(4096, &buf).await.unwrap();
+            events
+        })
+        .await;
+
+    // the child is recovered and rebuilt without intervention
+    let mut recovered = false;
+    for _ in 0 .. 100 {
+        recovered = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.children[0].history().faults == 1
+                    && nexus.children[0].state() == ChildState::Open
+            })
+            .await;
+        if recovered {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(recovered);
+
+    ms.spawn(async move {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let child1 = format!("bdev:///{}", EE_ERROR_DEVICE);
+        let mut recovering = Vec::new();
+        while let Ok(Some(event)) = events.try_next() {
+            if let NexusEvent::ChildRecovering {
+                nexus,
+                child,
+            } = event
+            {
+                assert_eq!(nexus, NEXUS_NAME);
+                recovering.push(child);
+            }
+        }
+        assert_eq!(recovering, vec![child1]);
+        assert_eq!(nexus.children[0].history().rebuilds, 1);
+        assert_eq!(nexus.children[1].state(), ChildState::Closed);
+
+        // the recovered child holds the writes it missed
+        nexus.offline_child(CHILD_3).await.unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        h.read_at(4096, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xbb));
+        drop(h);
+
+        nexus.set_auto_recovery(None);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string()]);
+}
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, BackgroundLimit, VerifyOptions},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "background_limit_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+/// background bytes per second the rebuilding child is limited to
+const LIMIT: u64 = 4 * 1024 * 1024;
+
+#[tokio::test]
+async fn background_io_stays_under_child_limit() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    let (completion, start) = ms
+        .spawn(async {
+            nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+                .await
+                .unwrap();
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+            nexus.add_child(CHILD_2, true).await.unwrap();
+
+            // a bound of zero admits no IO, and the child must exist
+            let zero = BackgroundLimit {
+                bytes_per_sec: Some(0),
+                iops: None,
+            };
+            assert!(nexus
+                .set_child_background_limit(CHILD_2, Some(zero))
+                .is_err());
+            let limit = BackgroundLimit {
+                bytes_per_sec: Some(LIMIT),
+                iops: None,
+            };
+            assert!(nexus
+                .set_child_background_limit("nope", Some(limit))
+                .is_err());
+            nexus
+                .set_child_background_limit(CHILD_2, Some(limit))
+                .unwrap();
+
+            let completion = nexus.rebuild_completion(CHILD_2);
+            let start = Instant::now();
+            nexus.start_rebuild(CHILD_2).await.unwrap();
+            (completion, start)
+        })
+        .await;
+
+    let completion = tokio::time::timeout(Duration::from_secs(30), completion)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(completion.success);
+
+    // the whole nexus is written to the child at no more than its limit
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_micros(NEXUS_SIZE * 1_000_000 / LIMIT));
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let stats = nexus
+            .children
+            .iter()
+            .find(|c| c.name == CHILD_2)
+            .unwrap()
+            .background();
+        assert_eq!(stats.bytes, NEXUS_SIZE);
+        // the rate is measured from the issue of an IO, which is allowed the
+        // bytes of the slot it waited for
+        assert!(stats.bytes_per_sec > 0);
+        assert!(stats.bytes_per_sec <= LIMIT + LIMIT / 10);
+
+        // the source is read without a limit
+        let source = nexus
+            .children
+            .iter()
+            .find(|c| c.name == CHILD_1)
+            .unwrap()
+            .background();
+        assert_eq!(source.limit, None);
+        assert_eq!(source.bytes, NEXUS_SIZE);
+
+        // the reads of a consistency check are limited as well
+        let start = Instant::now();
+        let report = nexus
+            .verify_consistency(VerifyOptions::default())
+            .await
+            .unwrap();
+        assert!(report.is_consistent());
+        assert!(
+            start.elapsed()
+                >= Duration::from_micros(NEXUS_SIZE * 1_000_000 / LIMIT)
+        );
+
+        // without a limit the check is not held back
+        nexus.set_child_background_limit(CHILD_2, None).unwrap();
+        let start = Instant::now();
+        nexus
+            .verify_consistency(VerifyOptions::default())
+            .await
+            .unwrap();
+        assert!(
+            start.elapsed()
+                < Duration::from_micros(NEXUS_SIZE * 1_000_000 / LIMIT)
+        );
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,50 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "channel_dump_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn channel_dump() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let dumps = nexus.dump_channels().await;
+        assert!(!dumps.is_empty());
+        for dump in &dumps {
+            assert_eq!(dump.readers.len(), 2);
+            assert_eq!(dump.writers.len(), 2);
+            assert!(dump.rebuilding.is_empty());
+            for child in dump.readers.iter().chain(dump.writers.iter()) {
+                assert!(child.child.is_some());
+                assert_eq!(child.state, Some(ChildState::Open));
+            }
+        }
+
+        nexus.remove_child(CHILD_2).await.unwrap();
+        for dump in nexus.dump_channels().await {
+            assert_eq!(dump.readers.len(), 1);
+            assert_eq!(dump.writers.len(), 1);
+            assert_eq!(dump.readers[0].bdev, "m0");
+            assert_eq!(dump.writers[0].child.as_deref(), Some(CHILD_1));
+        }
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,85 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{BdevHandle, MayastorCliArgs},
+    lvs::Lvs,
+};
+use rpc::mayastor::CreatePoolRequest;
+
+pub mod common;
+
+static DISKNAME: &str = "/tmp/child_backup_disk.img";
+static POOL_NAME: &str = "child_backup_pool";
+static NEXUS_NAME: &str = "child_backup_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static MALLOC_CHILD: &str = "malloc:///m0?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_child_backup() {
+    common::delete_file(&[DISKNAME.to_string()]);
+    common::truncate_file(DISKNAME, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        let pool = Lvs::create_or_import(CreatePoolRequest {
+            name: POOL_NAME.into(),
+            disks: vec![format!("aio://{}", DISKNAME)],
+        })
+        .await
+        .unwrap();
+        let lvol = pool
+            .create_lvol("backup", 12 * 1024 * 1024, false)
+            .await
+            .unwrap();
+        let lvol_child = format!("bdev:///{}", lvol.name());
+
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[lvol_child.clone(), MALLOC_CHILD.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // only local replicas can be backed up
+        assert!(nexus.backup_child(MALLOC_CHILD).await.is_err());
+        assert!(nexus.backup_child("bdev:///missing").await.is_err());
+
+        let backup = nexus.backup_child(&lvol_child).await.unwrap();
+        assert_eq!(backup.child, lvol_child);
+        assert!(backup.snapshot.starts_with(&lvol.name()));
+
+        // the snapshot holds the data written before the backup
+        let mut data = backup.reader.dma_malloc(4096).unwrap();
+        backup
+            .reader
+            .read_at(nexus.data_ent_offset * 512, &mut data)
+            .await
+            .unwrap();
+        assert!(data.as_slice().iter().all(|b| *b == 0xaa));
+
+        // writes to the nexus do not reach the backup
+        buf.fill(0xbb);
+        h.write_at(0, &buf).await.unwrap();
+        backup
+            .reader
+            .read_at(nexus.data_ent_offset * 512, &mut data)
+            .await
+            .unwrap();
+        assert!(data.as_slice().iter().all(|b| *b == 0xaa));
+
+        drop(backup);
+        drop(h);
+        nexus.destroy().await.unwrap();
+        pool.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME.to_string()]);
+}
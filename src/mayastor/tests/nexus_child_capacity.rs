@@ -0,0 +1,34 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{Bdev, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "child_capacity_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+// too small to hold the data partition of the nexus
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=10";
+
+#[tokio::test]
+async fn add_undersized_child() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let error = nexus.add_child(CHILD_2, true).await.unwrap_err();
+        assert!(error.to_string().contains("too small"), "{}", error);
+
+        // the child never became part of the nexus
+        assert_eq!(nexus.children.len(), 1);
+        assert!(nexus.child_lookup("m1").is_none());
+        assert!(Bdev::lookup_by_name("m1").is_none());
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,153 @@
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+};
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, GptEntry, GptHeader},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "data_offset_nexus";
+static UUID: &str = "5c1e0d3a-8a4f-4f8e-9d2b-6a7e3c2f1b48";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/data_offset_disk1.img";
+static DISKNAME2: &str = "/tmp/data_offset_disk2.img";
+
+/// number of blocks by which the reserved region of the second disk is grown
+const SHIFT: u64 = 2048;
+
+fn uri(disk: &str) -> String {
+    format!("aio://{}?blk_size=512", disk)
+}
+
+/// rewrite the label of the disk such that its reserved region is larger by
+/// SHIFT blocks, and move its data along with the data partition
+fn grow_reserved_region(disk: &str) {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(disk)
+        .unwrap();
+    let mut read = |offset: u64, len: usize| {
+        let mut buf = vec![0; len];
+        file.seek(SeekFrom::Start(offset)).unwrap();
+        file.read_exact(&mut buf).unwrap();
+        buf
+    };
+
+    let mut primary = GptHeader::from_slice(&read(512, 512)).unwrap();
+    let mut secondary =
+        GptHeader::from_slice(&read(primary.lba_alt * 512, 512)).unwrap();
+    let mut entries = GptEntry::from_slice(
+        &read(primary.lba_table * 512, 128 * primary.num_entries as usize),
+        primary.num_entries,
+    )
+    .unwrap();
+
+    let data_start = entries[1].ent_start;
+    let data = read(data_start * 512, NEXUS_SIZE as usize);
+
+    entries[0].ent_end += SHIFT;
+    entries[1].ent_start += SHIFT;
+    entries[1].ent_end += SHIFT;
+    let table_crc = GptEntry::checksum(&entries, primary.num_entries);
+
+    let mut write = |offset: u64, buf: &[u8]| {
+        file.seek(SeekFrom::Start(offset)).unwrap();
+        file.write_all(buf).unwrap();
+    };
+    write((data_start + SHIFT) * 512, &data);
+    let table = entries
+        .iter()
+        .flat_map(|e| bincode::serialize(e).unwrap())
+        .collect::<Vec<_>>();
+    for hdr in [&mut primary, &mut secondary].iter_mut() {
+        hdr.table_crc = table_crc;
+        hdr.checksum();
+        write(hdr.lba_table * 512, &table);
+        write(hdr.lba_self * 512, &bincode::serialize(&**hdr).unwrap());
+    }
+}
+
+#[tokio::test]
+async fn children_with_different_data_offsets() {
+    let disks = [DISKNAME1, DISKNAME2];
+    disks
+        .iter()
+        .for_each(|d| common::truncate_file(d, 64 * 1024));
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            Some(UUID),
+            &[uri(DISKNAME1), uri(DISKNAME2)],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xa5);
+        h.write_at(0, &buf).await.unwrap();
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    grow_reserved_region(DISKNAME2);
+
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            Some(UUID),
+            &[uri(DISKNAME1), uri(DISKNAME2)],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let offset = nexus.data_ent_offset;
+        assert_eq!(nexus.children[0].data_offset(), Some(offset));
+        assert_eq!(nexus.children[1].data_offset(), Some(offset + SHIFT));
+
+        // the data written before is read back from either child
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        for _ in 0 .. 4 {
+            h.read_at(0, &mut buf).await.unwrap();
+            assert!(buf.as_slice().iter().all(|b| *b == 0xa5));
+        }
+
+        // and a write lands in the data partition of each child
+        buf.fill(0x5a);
+        h.write_at(4096, &buf).await.unwrap();
+        for (child, offset) in
+            nexus.children.iter().zip([offset, offset + SHIFT].iter())
+        {
+            let ch = child.handle().unwrap();
+            let mut cbuf = ch.dma_malloc(4096).unwrap();
+            ch.read_at(offset * 512 + 4096, &mut cbuf).await.unwrap();
+            assert!(cbuf.as_slice().iter().all(|b| *b == 0x5a));
+        }
+        drop(h);
+        nexus.destroy().await.unwrap();
+
+        // without a child sharing its data offset the child cannot be rebuilt
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, Some(UUID), &[uri(DISKNAME1)])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert!(nexus.add_child(&uri(DISKNAME2), true).await.is_err());
+        assert_eq!(nexus.children.len(), 1);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
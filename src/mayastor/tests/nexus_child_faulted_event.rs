@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_event,
+        nexus_lookup,
+        ChildState,
+        FaultPolicy,
+        NexusEvent,
+        Reason,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_WRITE,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "child_faulted_event_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/child_faulted_event_disk1.img";
+static DISKNAME2: &str = "/tmp/child_faulted_event_disk2.img";
+static ERROR_DEVICE: &str = "child_faulted_event_error_device";
+static EE_ERROR_DEVICE: &str = "EE_child_faulted_event_error_device";
+
+#[tokio::test]
+async fn child_faulted_event() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    let mut events = ms
+        .spawn(async {
+            create_error_bdev(ERROR_DEVICE, DISKNAME1);
+            let children = vec![
+                format!("bdev:///{}", EE_ERROR_DEVICE),
+                format!("aio://{}?blk_size=512", DISKNAME2),
+            ];
+            nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+                .await
+                .unwrap();
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+            nexus.set_fault_policy(FaultPolicy {
+                read_errors: 1,
+                write_errors: 1,
+            });
+            let events = nexus_event::subscribe();
+
+            // several writes fail on the child before it is retired
+            inject_error(
+                EE_ERROR_DEVICE,
+                SPDK_BDEV_IO_TYPE_WRITE,
+                VBDEV_IO_FAILURE,
+                3,
+            );
+            let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+            let mut buf = h.dma_malloc(4096).unwrap();
+            buf.fill(0xaa);
+            let _ = futures::join!(
+                h.write_at(0, &buf),
+                h.write_at(4096, &buf),
+                h.write_at(8192, &buf)
+            );
+            events
+        })
+        .await;
+
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.metrics().retires_in_flight == 0
+                    && nexus.children[0].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    // exactly one event is raised for the fault, with its reason
+    let child = format!("bdev:///{}", EE_ERROR_DEVICE);
+    let mut faults = Vec::new();
+    while let Ok(Some(event)) = events.try_next() {
+        if let NexusEvent::ChildFaulted {
+            nexus,
+            child,
+            reason,
+        } = event
+        {
+            faults.push((nexus, child, reason));
+        }
+    }
+    assert_eq!(
+        faults,
+        vec![(NEXUS_NAME.to_string(), child, Reason::IoError)]
+    );
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
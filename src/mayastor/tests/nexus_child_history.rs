@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildHistoryEvent, ChildState, Reason},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "child_history_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn child_history() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        nexus.fault_child(CHILD_2, Reason::Rpc).await.unwrap();
+        let history = nexus.child_lookup("m1").unwrap().history();
+        assert_eq!(history.faults, 1);
+        assert_eq!(history.last_fault.unwrap().0, Reason::Rpc);
+        assert_eq!(history.rebuilds, 0);
+        assert!(matches!(
+            history.events.back().unwrap(),
+            ChildHistoryEvent::Faulted {
+                reason: Reason::Rpc,
+                ..
+            }
+        ));
+
+        // adding a child starts a rebuild of it
+        nexus.add_child(CHILD_3, false).await.unwrap();
+    })
+    .await;
+
+    // the nexus is notified of the completion of the rebuild asynchronously
+    let mut rebuilt = false;
+    for _ in 0 .. 100 {
+        rebuilt = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.child_lookup("m2").unwrap().state() == ChildState::Open
+            })
+            .await;
+        if rebuilt {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(rebuilt);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let history = nexus.child_lookup("m2").unwrap().history();
+        assert_eq!(history.faults, 0);
+        assert_eq!(history.rebuilds, 1);
+        let (_, bytes) = history.last_rebuild.unwrap();
+        assert_eq!(bytes, nexus.size());
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
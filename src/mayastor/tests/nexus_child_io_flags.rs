@@ -0,0 +1,137 @@
+use once_cell::sync::Lazy;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{Bdev, BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::MayastorTest;
+
+static NEXUS_NAME: &str = "child_io_flags_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static RECONFIGURE_NEXUS_NAME: &str = "child_io_flags_reconfigure_nexus";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+static CHILD_4: &str = "malloc:///m3?blk_size=512&size_mb=12";
+static CHILD_5: &str = "malloc:///m4?blk_size=512&size_mb=12";
+
+fn setup() -> &'static MayastorTest<'static> {
+    static MAYASTOR: Lazy<MayastorTest<'static>> =
+        Lazy::new(|| MayastorTest::new(MayastorCliArgs::default()));
+    &MAYASTOR
+}
+
+/// returns the number of (read, write) ops of the given bdev
+async fn child_ops(name: &str) -> (u64, u64) {
+    let stats = Bdev::lookup_by_name(name).unwrap().stats().await.unwrap();
+    (stats.num_read_ops, stats.num_write_ops)
+}
+
+#[tokio::test]
+async fn read_disabled_child_still_written() {
+    let ms = setup();
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        // open the handle first, such that the IO channel of this core exists
+        // when the flags are applied
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus
+            .set_child_io_flags(CHILD_2, false, true)
+            .await
+            .unwrap();
+
+        let (m0_reads, m0_writes) = child_ops("m0").await;
+        let (m1_reads, m1_writes) = child_ops("m1").await;
+
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xff);
+        for i in 0 .. 8 {
+            h.write_at(i * 4096, &buf).await.unwrap();
+        }
+        for i in 0 .. 8 {
+            h.read_at(i * 4096, &mut buf).await.unwrap();
+        }
+
+        let (m0_reads_now, m0_writes_now) = child_ops("m0").await;
+        let (m1_reads_now, m1_writes_now) = child_ops("m1").await;
+
+        // all reads are served by m0, and the writes reach both children
+        assert_eq!(m0_reads_now - m0_reads, 8);
+        assert_eq!(m1_reads_now - m1_reads, 0);
+        assert_eq!(m0_writes_now - m0_writes, 8);
+        assert_eq!(m1_writes_now - m1_writes, 8);
+
+        // enabling reads again makes m1 serve reads as well
+        nexus.set_child_io_flags(CHILD_2, true, true).await.unwrap();
+        for i in 0 .. 8 {
+            h.read_at(i * 4096, &mut buf).await.unwrap();
+        }
+        let (m1_reads_after, _) = child_ops("m1").await;
+        assert!(m1_reads_after > m1_reads_now);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn read_disabled_child_survives_reconfigure() {
+    let ms = setup();
+    ms.spawn(async {
+        nexus_create(
+            RECONFIGURE_NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[
+                CHILD_3.to_string(),
+                CHILD_4.to_string(),
+                CHILD_5.to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(RECONFIGURE_NEXUS_NAME).unwrap();
+        nexus
+            .set_child_io_flags(CHILD_4, false, true)
+            .await
+            .unwrap();
+
+        // the channels are refreshed when another child goes offline, and a
+        // channel is created for the handle opened afterwards
+        nexus.offline_child(CHILD_5).await.unwrap();
+        let h = BdevHandle::open(RECONFIGURE_NEXUS_NAME, true, false).unwrap();
+
+        let (m2_reads, _) = child_ops("m2").await;
+        let (m3_reads, m3_writes) = child_ops("m3").await;
+
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xff);
+        for i in 0 .. 8 {
+            h.write_at(i * 4096, &buf).await.unwrap();
+            h.read_at(i * 4096, &mut buf).await.unwrap();
+        }
+
+        // the drained child still receives no reads
+        let (m2_reads_now, _) = child_ops("m2").await;
+        let (m3_reads_now, m3_writes_now) = child_ops("m3").await;
+        assert_eq!(m2_reads_now - m2_reads, 8);
+        assert_eq!(m3_reads_now - m3_reads, 0);
+        assert_eq!(m3_writes_now - m3_writes, 8);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
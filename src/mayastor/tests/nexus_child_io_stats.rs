@@ -0,0 +1,66 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "child_io_stats_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+const WRITES: u64 = 32;
+const READS: u64 = 48;
+
+#[tokio::test]
+async fn child_io_stats() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let stats = nexus.child_io_stats().await;
+        assert_eq!(stats.len(), 2);
+        assert!(stats.iter().all(|s| s.reads == 0 && s.writes == 0));
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        for i in 0 .. WRITES {
+            h.write_at(i * 4096, &buf).await.unwrap();
+        }
+        for i in 0 .. READS {
+            h.read_at((i % WRITES) * 4096, &mut buf).await.unwrap();
+        }
+
+        // every write lands on both children, every read on one of them
+        let stats = nexus.child_io_stats().await;
+        assert_eq!(stats[0].child, CHILD_1);
+        assert_eq!(stats[1].child, CHILD_2);
+        assert!(stats.iter().all(|s| s.writes == WRITES));
+        assert_eq!(stats.iter().map(|s| s.reads).sum::<u64>(), READS);
+        for s in &stats {
+            assert_eq!(s.write_latency.samples, s.writes);
+            assert_eq!(s.read_latency.samples, s.reads);
+            assert!(s.write_latency.p50 <= s.write_latency.p99);
+            assert!(s.read_latency.p50 <= s.read_latency.p99);
+        }
+
+        // a child starts afresh once it is reopened
+        nexus.offline_child(CHILD_2).await.unwrap();
+        let stats = nexus.child_io_stats().await;
+        assert_eq!(stats[0].writes, WRITES);
+        assert_eq!((stats[1].reads, stats[1].writes), (0, 0));
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,55 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "child_latency_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn child_latency_percentiles() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let before = nexus
+            .child_io_stats()
+            .await
+            .iter()
+            .map(|s| s.latency.samples)
+            .collect::<Vec<_>>();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xff);
+        for i in 0 .. 100 {
+            h.write_at(i * 4096, &buf).await.unwrap();
+        }
+
+        // every write completed on both children
+        for (stats, before) in nexus.child_io_stats().await.iter().zip(before) {
+            let latency = &stats.latency;
+            assert_eq!(latency.samples, before + 100);
+            assert_eq!(stats.write_latency.samples, stats.writes);
+            assert!(latency.p50 <= latency.p95);
+            assert!(latency.p95 <= latency.p99);
+            assert!(latency.p99.as_micros() > 0);
+        }
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
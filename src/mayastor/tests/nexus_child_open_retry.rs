@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, OpenRetry},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "open_retry_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static DISKNAME: &str = "/tmp/open_retry_disk.img";
+
+#[tokio::test]
+async fn child_open_retry() {
+    common::delete_file(&[DISKNAME.into()]);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let uri = format!("aio://{}?blk_size=512", DISKNAME);
+
+        // without retries a backend which is not there fails the add
+        assert_eq!(nexus.policy().open_retry.retries, 0);
+        assert!(nexus.add_child(&uri, true).await.is_err());
+        assert_eq!(nexus.metrics().child_open_retries, 0);
+
+        // the backend becomes available after the first attempts failed
+        nexus.set_open_retry(OpenRetry {
+            retries: 6,
+            backoff: Duration::from_millis(50),
+        });
+        std::thread::spawn(|| {
+            std::thread::sleep(Duration::from_millis(200));
+            common::truncate_file(DISKNAME, 64 * 1024);
+        });
+        nexus.add_child(&uri, true).await.unwrap();
+
+        let child = nexus.children.iter().find(|c| c.name == uri).unwrap();
+        assert!(child.open_retries() > 0);
+        assert_eq!(
+            nexus.metrics().child_open_retries,
+            child.open_retries() as u64
+        );
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME.into()]);
+}
@@ -0,0 +1,74 @@
+use nix::errno::Errno;
+
+use mayastor::{
+    bdev::{child_lba, nexus_create, nexus_lookup},
+    core::{BdevHandle, CoreError, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "child_range_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+
+#[test]
+fn child_lba_range() {
+    // IOs which fit on the child, up to its last block
+    assert_eq!(child_lba(0, 10, 8, 100), Ok(10));
+    assert_eq!(child_lba(82, 10, 8, 100), Ok(92));
+    assert_eq!(child_lba(u64::MAX - 8, 0, 8, u64::MAX), Ok(u64::MAX - 8));
+
+    // one block past the end of the child
+    assert_eq!(child_lba(83, 10, 8, 100), Err(Errno::EINVAL));
+
+    // ranges which wrap around rather than exceed the child
+    assert_eq!(child_lba(u64::MAX, 1, 1, u64::MAX), Err(Errno::EINVAL));
+    assert_eq!(child_lba(1, u64::MAX, 0, u64::MAX), Err(Errno::EINVAL));
+    assert_eq!(child_lba(u64::MAX - 8, 0, 9, u64::MAX), Err(Errno::EINVAL));
+    assert_eq!(
+        child_lba(u64::MAX - 4, 2, u64::MAX, 100),
+        Err(Errno::EINVAL)
+    );
+}
+
+#[tokio::test]
+async fn child_range() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // the last blocks of the nexus map to the child
+        let end = nexus.size();
+        h.write_at(end - 4096, &buf).await.unwrap();
+
+        // IOs at the exact end of the nexus and near the top of the address
+        // space are rejected rather than wrapped
+        for offset in &[end, end - 512, u64::MAX - 4095, u64::MAX - 511] {
+            assert!(matches!(
+                h.write_at(*offset, &buf).await,
+                Err(CoreError::WriteDispatch {
+                    source: Errno::EINVAL,
+                    ..
+                })
+            ));
+            assert!(h.read_at(*offset, &mut buf).await.is_err());
+        }
+
+        // and nothing landed on the start of the child
+        buf.fill(0);
+        h.read_at(0, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        assert_eq!(nexus.metrics().out_of_range, 0);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
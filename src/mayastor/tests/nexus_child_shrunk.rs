@@ -0,0 +1,51 @@
+#![cfg(feature = "fault-injection")]
+
+use mayastor::{
+    bdev::{inject_child_blocks, nexus_create, nexus_lookup},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "child_shrunk_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn child_shrunk() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        let end = nexus.size();
+        h.write_at(end - 4096, &buf).await.unwrap();
+
+        // the child no longer holds the last 8 blocks of the nexus
+        inject_child_blocks("m0", nexus.data_ent_offset + end / 512 - 8);
+
+        // IO to them passes the checks of the nexus bdev but not the range
+        // check of the child, and fails rather than hangs
+        assert!(h.read_at(end - 4096, &mut buf).await.is_err());
+        assert_eq!(nexus.metrics().out_of_range, 1);
+        assert!(h.write_at(end - 4096, &buf).await.is_err());
+        assert_eq!(nexus.metrics().out_of_range, 2);
+
+        // IO within the child is still served
+        h.write_at(end - 8192, &buf).await.unwrap();
+        h.read_at(end - 8192, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        assert_eq!(nexus.metrics().out_of_range, 2);
+
+        inject_child_blocks("m0", 0);
+        h.read_at(end - 4096, &mut buf).await.unwrap();
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, Reason},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "track_record_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+/// number of writes submitted
+const WRITES: u64 = 16;
+
+#[tokio::test]
+async fn child_track_record() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let buf = h.dma_malloc(4096).unwrap();
+        for i in 0 .. WRITES {
+            h.write_at(i * 4096, &buf).await.unwrap();
+        }
+
+        // every write completed on both children
+        for child in nexus.children.iter() {
+            let record = child.track_record();
+            assert!(record.error_free_ios >= WRITES);
+            assert!(record.uptime > Duration::default());
+        }
+
+        // a fault ends the error free run of the child, and its uptime no
+        // longer grows
+        nexus.fault_child(CHILD_2, Reason::Rpc).await.unwrap();
+        let faulted = nexus.children[1].track_record();
+        assert_eq!(faulted.error_free_ios, 0);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(nexus.children[1].track_record().uptime, faulted.uptime);
+
+        // the other child keeps its record
+        h.write_at(0, &buf).await.unwrap();
+        let healthy = nexus.children[0].track_record();
+        assert!(healthy.error_free_ios > WRITES);
+        assert!(healthy.uptime > faulted.uptime);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,86 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState},
+    core::{Bdev, BdevHandle, IoStatus, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "compare_and_write_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+/// assert that both children hold the given pattern at the given byte offset
+/// of the nexus
+async fn assert_children(offset: u64, pattern: u8) {
+    let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+    let offset = nexus.data_ent_offset * 512 + offset;
+    for child in &["m0", "m1"] {
+        let h = BdevHandle::open(child, false, false).unwrap();
+        let mut buf = h.dma_malloc(512).unwrap();
+        h.read_at(offset, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == pattern));
+    }
+}
+
+#[tokio::test]
+async fn compare_and_write() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let bdev = Bdev::lookup_by_name(NEXUS_NAME).unwrap();
+        assert!(bdev.io_type_supported(IoType::CompareAndWrite));
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut old = h.dma_malloc(512).unwrap();
+        old.fill(0xaa);
+        let mut new = h.dma_malloc(512).unwrap();
+        new.fill(0x55);
+        h.write_at(512, &old).await.unwrap();
+
+        // the data matches, the write lands on all children
+        let status = nexus.compare_and_write_at(512, &old, &new).await.unwrap();
+        assert_eq!(status, IoStatus::Success);
+        assert_children(512, 0x55).await;
+
+        // the data no longer matches, nothing is written
+        let mut other = h.dma_malloc(512).unwrap();
+        other.fill(0x11);
+        let status =
+            nexus.compare_and_write_at(512, &old, &other).await.unwrap();
+        assert_eq!(status, IoStatus::NvmeError);
+        assert_children(512, 0x55).await;
+
+        // a data mismatch does not reflect on the health of the children
+        assert_eq!(
+            nexus
+                .children
+                .iter()
+                .filter(|c| c.state() == ChildState::Open)
+                .count(),
+            2
+        );
+
+        // of two compare-and-writes of the same data, the blocks are locked
+        // for the first until its write has completed, hence the compare of
+        // the second sees the data written by the first
+        let first = nexus.compare_and_write_at(512, &new, &old);
+        let second = nexus.compare_and_write_at(512, &new, &other);
+        let (first, second) = futures::join!(first, second);
+        assert_eq!(first.unwrap(), IoStatus::Success);
+        assert_eq!(second.unwrap(), IoStatus::NvmeError);
+        assert_children(512, 0xaa).await;
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_lookup,
+        ChildState,
+        UnhandledAction,
+        UnhandledPolicy,
+    },
+    core::{Bdev, BdevHandle, IoStatus, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "compare_quorum_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn compare_quorum() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[
+                CHILD_1.to_string(),
+                CHILD_2.to_string(),
+                CHILD_3.to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus
+            .set_unhandled_policy(UnhandledPolicy::new(vec![(
+                IoType::Compare,
+                UnhandledAction::Quorum,
+            )]))
+            .unwrap();
+        let bdev = Bdev::lookup_by_name(NEXUS_NAME).unwrap();
+        assert!(bdev.io_type_supported(IoType::Compare));
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // the data matches on all children
+        assert_eq!(nexus.compare_at(0, &buf).await.unwrap(), IoStatus::Success);
+
+        // the data differs on all children, the compare fails and no child
+        // is retired
+        let mut other = h.dma_malloc(4096).unwrap();
+        other.fill(0x11);
+        assert_eq!(
+            nexus.compare_at(0, &other).await.unwrap(),
+            IoStatus::NvmeError
+        );
+        assert!(nexus.children.iter().all(|c| c.state() == ChildState::Open));
+
+        // the data differs on a single child, the compare succeeds as the
+        // others match, and the diverging child is retired
+        let ch = BdevHandle::open("m2", true, false).unwrap();
+        let mut bad = ch.dma_malloc(4096).unwrap();
+        bad.fill(0x55);
+        ch.write_at(nexus.data_ent_offset * 512, &bad)
+            .await
+            .unwrap();
+        drop(ch);
+        assert_eq!(nexus.compare_at(0, &buf).await.unwrap(), IoStatus::Success);
+        assert_eq!(nexus.metrics().compare_divergences, 1);
+        drop(h);
+    })
+    .await;
+
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.children[2].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.children[0].state(), ChildState::Open);
+        assert_eq!(nexus.children[1].state(), ChildState::Open);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,219 @@
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use once_cell::sync::Lazy;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState, CompletionBatching},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::{
+    error_bdev::{
+        create_error_bdev,
+        inject_error,
+        SPDK_BDEV_IO_TYPE_WRITE,
+        VBDEV_IO_FAILURE,
+    },
+    MayastorTest,
+};
+
+static NEXUS_NAME: &str = "completion_batching_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+static ERROR_NEXUS_NAME: &str = "completion_batching_error_nexus";
+static ERROR_NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+static DISKNAME1: &str = "/tmp/completion_batching_disk1.img";
+static DISKNAME2: &str = "/tmp/completion_batching_disk2.img";
+static ERROR_DEVICE: &str = "completion_batching_error_device";
+static EE_ERROR_DEVICE: &str = "EE_completion_batching_error_device";
+
+/// number of IOs submitted at once
+const DEPTH: u64 = 32;
+
+async fn create_nexus() {
+    nexus_create(
+        NEXUS_NAME,
+        NEXUS_SIZE,
+        None,
+        &[CHILD_1.to_string(), CHILD_2.to_string()],
+    )
+    .await
+    .unwrap();
+}
+
+fn setup() -> &'static MayastorTest<'static> {
+    static MAYASTOR: Lazy<MayastorTest<'static>> =
+        Lazy::new(|| MayastorTest::new(MayastorCliArgs::default()));
+    &MAYASTOR
+}
+
+#[tokio::test]
+async fn completion_batching() {
+    let ms = setup();
+    ms.spawn(async {
+        create_nexus().await;
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(
+            nexus.policy().completion_batching,
+            CompletionBatching::PerIo
+        );
+        nexus.set_completion_batching(CompletionBatching::Batched {
+            max_ios: 8,
+        });
+
+        // more IO than fits in a batch completes, and reads return the data
+        // written
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let bufs = (0 .. DEPTH)
+            .map(|i| {
+                let mut buf = h.dma_malloc(4096).unwrap();
+                buf.fill(i as u8);
+                buf
+            })
+            .collect::<Vec<_>>();
+        join_all(
+            bufs.iter()
+                .enumerate()
+                .map(|(i, buf)| h.write_at(i as u64 * 4096, buf)),
+        )
+        .await
+        .into_iter()
+        .for_each(|r| assert_eq!(r.unwrap(), 4096));
+
+        let mut rbufs = (0 .. DEPTH)
+            .map(|_| h.dma_malloc(4096).unwrap())
+            .collect::<Vec<_>>();
+        join_all(
+            rbufs
+                .iter_mut()
+                .enumerate()
+                .map(|(i, buf)| h.read_at(i as u64 * 4096, buf)),
+        )
+        .await
+        .into_iter()
+        .for_each(|r| assert_eq!(r.unwrap(), 4096));
+        for (i, buf) in rbufs.iter().enumerate() {
+            assert!(buf.as_slice().iter().all(|b| *b == i as u8));
+        }
+
+        // a single IO does not wait for the batch to fill up
+        h.write_at(0, &bufs[0]).await.unwrap();
+
+        // all child IOs have been accounted
+        for dump in nexus.dump_channels().await {
+            for child in dump.readers.iter().chain(dump.writers.iter()) {
+                assert_eq!(child.outstanding, 0);
+            }
+        }
+        assert!(nexus.children.iter().all(|c| c.state() == ChildState::Open));
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn completion_batching_retires_failed_child() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = setup();
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE, DISKNAME1);
+        let children = vec![
+            format!("bdev:///{}", EE_ERROR_DEVICE),
+            format!("aio://{}?blk_size=512", DISKNAME2),
+        ];
+        nexus_create(ERROR_NEXUS_NAME, ERROR_NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(ERROR_NEXUS_NAME).unwrap();
+        nexus.set_completion_batching(CompletionBatching::Batched {
+            max_ios: 8,
+        });
+
+        // the write succeeds on the other child, and the failed one is
+        // counted as without batching
+        let h = BdevHandle::open(ERROR_NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_WRITE,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        h.write_at(0, &buf).await.unwrap();
+        assert_eq!(nexus.children[0].write_errors(), 1);
+    })
+    .await;
+
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(ERROR_NEXUS_NAME).unwrap();
+                nexus.children[0].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(ERROR_NEXUS_NAME).unwrap();
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
+
+/// number of IOs completed per second at a queue depth of `DEPTH`
+async fn iops(h: &BdevHandle) -> f64 {
+    let bufs = (0 .. DEPTH)
+        .map(|_| h.dma_malloc(4096).unwrap())
+        .collect::<Vec<_>>();
+    let start = Instant::now();
+    for _ in 0 .. 1000 {
+        join_all(
+            bufs.iter()
+                .enumerate()
+                .map(|(i, buf)| h.write_at(i as u64 * 4096, buf)),
+        )
+        .await;
+    }
+    (1000 * DEPTH) as f64 / start.elapsed().as_secs_f64()
+}
+
+/// compares the write throughput with and without completion batching, run
+/// with `--ignored` to benchmark
+#[tokio::test]
+#[ignore]
+async fn completion_batching_benchmark() {
+    let ms = setup();
+    ms.spawn(async {
+        create_nexus().await;
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+
+        let per_io = iops(&h).await;
+        nexus.set_completion_batching(CompletionBatching::Batched {
+            max_ios: DEPTH as usize,
+        });
+        let batched = iops(&h).await;
+        println!("write IOPS per IO: {:.0} batched: {:.0}", per_io, batched);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,100 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, Error},
+    core::MayastorCliArgs,
+    lvs::{Lvol, Lvs},
+};
+use rpc::mayastor::CreatePoolRequest;
+
+pub mod common;
+
+static NEXUS_NAME: &str = "delete_snapshot_nexus";
+static NEXUS_SIZE: u64 = 8 * 1024 * 1024;
+static POOL_NAME: &str = "delete_snapshot_pool";
+static UUID1: &str = "00000000-76b6-4fcf-864d-1027d4038771";
+static UUID2: &str = "00000000-76b6-4fcf-864d-1027d4038772";
+
+/// snapshot the replica as a snapshot of the nexus taken at time t would
+async fn snapshot(pool: &Lvs, uuid: &str, t: u64) {
+    let lvol = pool.lvols().unwrap().find(|l| l.name() == uuid).unwrap();
+    lvol.snapshot(&Lvol::format_snapshot_name(uuid, t))
+        .await
+        .unwrap();
+}
+
+/// the number of snapshots taken at time t which the pool holds
+fn snapshots(pool: &Lvs, t: u64) -> usize {
+    pool.lvols()
+        .unwrap()
+        .filter(|l| {
+            l.name() == Lvol::format_snapshot_name(UUID1, t)
+                || l.name() == Lvol::format_snapshot_name(UUID2, t)
+        })
+        .count()
+}
+
+#[tokio::test]
+async fn delete_snapshot() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        Lvs::create_or_import(CreatePoolRequest {
+            name: POOL_NAME.to_string(),
+            disks: vec!["malloc:///disk0?size_mb=64".into()],
+        })
+        .await
+        .unwrap();
+        let pool = Lvs::lookup(POOL_NAME).unwrap();
+        for uuid in &[UUID1, UUID2] {
+            pool.create_lvol(uuid, 12 * 1024 * 1024, false)
+                .await
+                .unwrap();
+        }
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[
+                format!("loopback:///{}", UUID1),
+                format!("loopback:///{}", UUID2),
+            ],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let name = |t| Lvol::format_snapshot_name(NEXUS_NAME, t);
+
+        // names which do not belong to a snapshot of the nexus, and snapshots
+        // which were never taken, are not found
+        for snapshot in &["other".to_string(), name(1)] {
+            assert!(matches!(
+                nexus.delete_snapshot(snapshot).await,
+                Err(Error::SnapshotNotFound { .. })
+            ));
+        }
+
+        // the snapshot is deleted from all children, but not the snapshots
+        // taken at other times
+        snapshot(&pool, UUID1, 1).await;
+        snapshot(&pool, UUID2, 1).await;
+        snapshot(&pool, UUID1, 2).await;
+        snapshot(&pool, UUID2, 2).await;
+        let reply = nexus.delete_snapshot(&name(1)).await.unwrap();
+        assert_eq!(reply.name, name(1));
+        assert_eq!(snapshots(&pool, 1), 0);
+        assert_eq!(snapshots(&pool, 2), 2);
+
+        // deleting it again is told apart from a failure
+        assert!(matches!(
+            nexus.delete_snapshot(&name(1)).await,
+            Err(Error::SnapshotNotFound { .. })
+        ));
+
+        // a snapshot held by some children only is deleted from those
+        snapshot(&pool, UUID1, 3).await;
+        nexus.delete_snapshot(&name(3)).await.unwrap();
+        assert_eq!(snapshots(&pool, 3), 0);
+
+        nexus.destroy().await.unwrap();
+        pool.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,215 @@
+#![cfg(all(feature = "fault-injection", feature = "io-recorder"))]
+
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        clear_failure_trace,
+        nexus_create,
+        nexus_lookup,
+        replay_failure_trace,
+        ChildState,
+        FailureTrace,
+        NexusStatus,
+    },
+    core::{BdevHandle, IoStatus, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_READ,
+    SPDK_BDEV_IO_TYPE_WRITE,
+    VBDEV_IO_FAILURE,
+};
+
+static INCIDENT_NEXUS: &str = "failure_replay_incident";
+static REPLAY_NEXUS: &str = "failure_replay_test";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/failure_replay_disk1.img";
+static DISKNAME2: &str = "/tmp/failure_replay_disk2.img";
+static ERROR_DEVICE1: &str = "failure_replay_error_device1";
+static ERROR_DEVICE2: &str = "failure_replay_error_device2";
+static EE_CHILD1: &str = "bdev:///EE_failure_replay_error_device1";
+static EE_CHILD2: &str = "bdev:///EE_failure_replay_error_device2";
+static INCIDENT_CHILD3: &str = "malloc:///m0?blk_size=512&size_mb=12";
+
+static CHILD_1: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m2?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m3?blk_size=512&size_mb=12";
+
+/// wait for the child of the nexus to be closed
+async fn wait_closed(ms: &common::MayastorTest<'_>, nexus: &str, child: &str) {
+    for _ in 0 .. 100 {
+        let (nexus, child) = (nexus.to_string(), child.to_string());
+        let closed = ms
+            .spawn(async move {
+                let nexus = nexus_lookup(&nexus).unwrap();
+                nexus.metrics().retires_in_flight == 0
+                    && nexus
+                        .children
+                        .iter()
+                        .find(|c| c.name == child)
+                        .unwrap()
+                        .state()
+                        != ChildState::Open
+            })
+            .await;
+        if closed {
+            return;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    panic!("child {} of nexus {} was not closed", child, nexus);
+}
+
+/// write to the nexus, and once the first child is closed for it read the
+/// data back until the second one is closed too
+async fn run_io(ms: &common::MayastorTest<'_>, nexus: &'static str) {
+    ms.spawn(async move {
+        let h = BdevHandle::open(nexus, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+    })
+    .await;
+    let children = ms
+        .spawn(async move {
+            let nexus = nexus_lookup(nexus).unwrap();
+            nexus
+                .children
+                .iter()
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>()
+        })
+        .await;
+    wait_closed(ms, nexus, &children[0]).await;
+
+    ms.spawn(async move {
+        let h = BdevHandle::open(nexus, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        for _ in 0 .. 4 {
+            buf.fill(0);
+            h.read_at(0, &mut buf).await.unwrap();
+            assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        }
+    })
+    .await;
+    wait_closed(ms, nexus, &children[1]).await;
+}
+
+/// the failures of the trace as (child, type, status)
+fn failures(trace: &FailureTrace) -> Vec<(String, IoType, IoStatus)> {
+    trace
+        .failures()
+        .map(|e| (e.child.clone(), e.io_type, e.status))
+        .collect()
+}
+
+#[tokio::test]
+async fn failure_replay() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+
+    // the incident: a write fails on one child and a read on another one
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE1, DISKNAME1);
+        create_error_bdev(ERROR_DEVICE2, DISKNAME2);
+        nexus_create(
+            INCIDENT_NEXUS,
+            NEXUS_SIZE,
+            None,
+            &[
+                EE_CHILD1.to_string(),
+                EE_CHILD2.to_string(),
+                INCIDENT_CHILD3.to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        inject_error(
+            "EE_failure_replay_error_device1",
+            SPDK_BDEV_IO_TYPE_WRITE,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        inject_error(
+            "EE_failure_replay_error_device2",
+            SPDK_BDEV_IO_TYPE_READ,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+    })
+    .await;
+    run_io(&ms, INCIDENT_NEXUS).await;
+
+    // the trace of the incident survives being shipped off the node
+    let json = ms
+        .spawn(async {
+            let nexus = nexus_lookup(INCIDENT_NEXUS).unwrap();
+            let trace = nexus.failure_trace().await;
+            assert_eq!(
+                failures(&trace),
+                vec![
+                    (EE_CHILD1.to_string(), IoType::Write, IoStatus::Failed),
+                    (EE_CHILD2.to_string(), IoType::Read, IoStatus::Failed),
+                ]
+            );
+            nexus.destroy().await.unwrap();
+            serde_json::to_string(&trace).unwrap()
+        })
+        .await;
+    let mut trace: FailureTrace = serde_json::from_str(&json).unwrap();
+    trace.rename_child(EE_CHILD1, CHILD_1);
+    trace.rename_child(EE_CHILD2, CHILD_2);
+    trace.rename_child(INCIDENT_CHILD3, CHILD_3);
+
+    // the incident is replayed against healthy children
+    let replayed = trace.clone();
+    ms.spawn(async move {
+        nexus_create(
+            REPLAY_NEXUS,
+            NEXUS_SIZE,
+            None,
+            &[
+                CHILD_1.to_string(),
+                CHILD_2.to_string(),
+                CHILD_3.to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        replay_failure_trace(&replayed);
+    })
+    .await;
+    run_io(&ms, REPLAY_NEXUS).await;
+
+    // the nexus fails the same children, and recovers on the remaining one
+    ms.spawn(async move {
+        let nexus = nexus_lookup(REPLAY_NEXUS).unwrap();
+        assert_eq!(nexus.status(), NexusStatus::Degraded);
+        assert_eq!(nexus.children[2].state(), ChildState::Open);
+        assert_eq!(failures(&nexus.failure_trace().await), failures(&trace));
+
+        let h = BdevHandle::open(REPLAY_NEXUS, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xbb);
+        h.write_at(4096, &buf).await.unwrap();
+        buf.fill(0);
+        h.read_at(4096, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xbb));
+        drop(h);
+
+        for child in &[CHILD_1, CHILD_2, CHILD_3] {
+            clear_failure_trace(child);
+        }
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
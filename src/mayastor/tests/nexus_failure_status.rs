@@ -0,0 +1,206 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_lookup,
+        FailureCondition,
+        IoObserver,
+        NioCtx,
+        ReadVerifier,
+    },
+    core::{BdevHandle, IoStatus, IoType, MayastorCliArgs, NvmeStatusCode},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_READ,
+    SPDK_BDEV_IO_TYPE_WRITE,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+static MIRROR: &str = "failure_status_mirror";
+static VERIFIED: &str = "failure_status_verified";
+static SINGLE: &str = "failure_status_single";
+
+static DISKNAME1: &str = "/tmp/failure_status_disk1.img";
+static DISKNAME2: &str = "/tmp/failure_status_disk2.img";
+static DISKNAME3: &str = "/tmp/failure_status_disk3.img";
+static ERROR_DEVICE1: &str = "failure_status_error_device1";
+static ERROR_DEVICE2: &str = "failure_status_error_device2";
+static ERROR_DEVICE3: &str = "failure_status_error_device3";
+static EE_ERROR_DEVICE1: &str = "EE_failure_status_error_device1";
+static EE_ERROR_DEVICE2: &str = "EE_failure_status_error_device2";
+static EE_ERROR_DEVICE3: &str = "EE_failure_status_error_device3";
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=64";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=64";
+
+/// asymmetric access inaccessible, which makes the initiator fail over
+const INACCESSIBLE: NvmeStatusCode = NvmeStatusCode {
+    sct: 0x03,
+    sc: 0x02,
+};
+
+/// unrecovered read error
+const UNRECOVERED: NvmeStatusCode = NvmeStatusCode {
+    sct: 0x02,
+    sc: 0x81,
+};
+
+/// records the NVMe status of the failed IOs
+#[derive(Default)]
+struct StatusObserver {
+    statuses: Mutex<Vec<NvmeStatusCode>>,
+}
+
+impl StatusObserver {
+    fn take(&self) -> Vec<NvmeStatusCode> {
+        std::mem::take(&mut *self.statuses.lock().unwrap())
+    }
+}
+
+impl IoObserver for StatusObserver {
+    fn on_submit(&self, _ctx: &NioCtx, _io_type: IoType) {}
+
+    fn on_complete(&self, ctx: &NioCtx, status: IoStatus) {
+        if status != IoStatus::Success {
+            self.statuses.lock().unwrap().push(ctx.nvme_status());
+        }
+    }
+}
+
+/// rejects all data
+struct RejectVerifier;
+
+impl ReadVerifier for RejectVerifier {
+    fn verify(&self, _child: &str, _offset: u64, _data: &[&[u8]]) -> bool {
+        false
+    }
+}
+
+#[tokio::test]
+async fn failure_status() {
+    for disk in &[DISKNAME1, DISKNAME2, DISKNAME3] {
+        common::truncate_file(disk, 64 * 1024);
+    }
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE1, DISKNAME1);
+        create_error_bdev(ERROR_DEVICE2, DISKNAME2);
+        create_error_bdev(ERROR_DEVICE3, DISKNAME3);
+        nexus_create(
+            MIRROR,
+            NEXUS_SIZE,
+            None,
+            &[
+                format!("bdev:///{}", EE_ERROR_DEVICE1),
+                format!("bdev:///{}", EE_ERROR_DEVICE2),
+            ],
+        )
+        .await
+        .unwrap();
+        nexus_create(
+            VERIFIED,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        nexus_create(
+            SINGLE,
+            NEXUS_SIZE,
+            None,
+            &[format!("bdev:///{}", EE_ERROR_DEVICE3)],
+        )
+        .await
+        .unwrap();
+
+        let observer = Arc::new(StatusObserver::default());
+        for name in &[MIRROR, VERIFIED, SINGLE] {
+            let nexus = nexus_lookup(name).unwrap();
+            assert_eq!(
+                nexus
+                    .policy()
+                    .failure_status
+                    .status(FailureCondition::AllChildrenFailed),
+                None
+            );
+            nexus.set_failure_status(
+                FailureCondition::AllChildrenFailed,
+                Some(INACCESSIBLE),
+            );
+            nexus.set_failure_status(
+                FailureCondition::IntegrityMismatch,
+                Some(UNRECOVERED),
+            );
+            nexus.register_io_observer(observer.clone());
+
+            let h = BdevHandle::open(name, true, false).unwrap();
+            let mut buf = h.dma_malloc(4096).unwrap();
+            buf.fill(0xaa);
+            h.write_at(0, &buf).await.unwrap();
+        }
+
+        // a read failing on all children
+        for device in &[EE_ERROR_DEVICE1, EE_ERROR_DEVICE2] {
+            inject_error(device, SPDK_BDEV_IO_TYPE_READ, VBDEV_IO_FAILURE, 10);
+        }
+        let h = BdevHandle::open(MIRROR, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        assert!(h.read_at(0, &mut buf).await.is_err());
+        assert_eq!(observer.take(), vec![INACCESSIBLE]);
+        drop(h);
+
+        // a read whose data fails verification on all children
+        let nexus = nexus_lookup(VERIFIED).unwrap();
+        nexus.set_read_verifier(Some(Arc::new(RejectVerifier)));
+        let h = BdevHandle::open(VERIFIED, true, false).unwrap();
+        assert!(h.read_at(0, &mut buf).await.is_err());
+        assert_eq!(observer.take(), vec![UNRECOVERED]);
+        drop(h);
+
+        // a write failing on the last child for longer than its grace
+        let nexus = nexus_lookup(SINGLE).unwrap();
+        nexus.set_last_child_grace(Duration::from_millis(100));
+        inject_error(
+            EE_ERROR_DEVICE3,
+            SPDK_BDEV_IO_TYPE_WRITE,
+            VBDEV_IO_FAILURE,
+            1000,
+        );
+        let h = BdevHandle::open(SINGLE, true, false).unwrap();
+        assert!(h.write_at(0, &buf).await.is_err());
+        assert_eq!(observer.take(), vec![NvmeStatusCode::NAMESPACE_NOT_READY]);
+
+        // a write to a nexus without writable children
+        nexus
+            .offline_child(&format!("bdev:///{}", EE_ERROR_DEVICE3))
+            .await
+            .unwrap();
+        assert!(h.write_at(0, &buf).await.is_err());
+        assert_eq!(
+            observer.take(),
+            vec![NvmeStatusCode::INTERNAL_DEVICE_ERROR]
+        );
+        drop(h);
+
+        for name in &[MIRROR, VERIFIED, SINGLE] {
+            nexus_lookup(name).unwrap().destroy().await.unwrap();
+        }
+    })
+    .await;
+
+    common::delete_file(&[
+        DISKNAME1.to_string(),
+        DISKNAME2.to_string(),
+        DISKNAME3.to_string(),
+    ]);
+}
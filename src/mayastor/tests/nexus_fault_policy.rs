@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState, FaultPolicy},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_READ,
+    SPDK_BDEV_IO_TYPE_WRITE,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "fault_policy_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/fault_policy_disk1.img";
+static DISKNAME2: &str = "/tmp/fault_policy_disk2.img";
+static ERROR_DEVICE: &str = "fault_policy_error_device";
+static EE_ERROR_DEVICE: &str = "EE_fault_policy_error_device";
+
+#[tokio::test]
+async fn fault_policy_thresholds() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE, DISKNAME1);
+        let children = vec![
+            format!("bdev:///{}", EE_ERROR_DEVICE),
+            format!("aio://{}?blk_size=512", DISKNAME2),
+        ];
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().fault, FaultPolicy::default());
+        nexus.set_fault_policy(FaultPolicy {
+            read_errors: 3,
+            write_errors: 2,
+        });
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // reads are spread round robin, so one of them hits the error device
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_READ,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        for _ in 0 .. 2 {
+            h.read_at(0, &mut buf).await.unwrap();
+        }
+        assert_eq!(nexus.children[0].read_errors(), 1);
+        assert_eq!(nexus.children[0].write_errors(), 0);
+
+        // the first failed write stays below the threshold
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_WRITE,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        h.write_at(0, &buf).await.unwrap();
+        assert_eq!(nexus.children[0].write_errors(), 1);
+        assert_eq!(nexus.children[0].state(), ChildState::Open);
+
+        // the second one reaches it
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_WRITE,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        h.write_at(0, &buf).await.unwrap();
+        assert_eq!(nexus.children[0].write_errors(), 2);
+        assert_eq!(nexus.children[0].read_errors(), 1);
+    })
+    .await;
+
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.children[0].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
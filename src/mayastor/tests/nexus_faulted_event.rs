@@ -0,0 +1,37 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_event, nexus_lookup, NexusEvent, NexusStatus},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "faulted_event_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_faulted_event() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.metrics().faulted, 0);
+
+        let mut events = nexus_event::subscribe();
+
+        // offlining the only child leaves the nexus without healthy children
+        let status = nexus.offline_child(CHILD_1).await.unwrap();
+        assert_eq!(status, NexusStatus::Faulted);
+        assert_eq!(nexus.metrics().faulted, 1);
+
+        match events.try_next().unwrap().unwrap() {
+            NexusEvent::Faulted {
+                nexus, ..
+            } => assert_eq!(nexus, NEXUS_NAME),
+            event => panic!("unexpected event {:?}", event),
+        }
+    })
+    .await;
+}
@@ -0,0 +1,136 @@
+use std::{
+    cell::RefCell,
+    sync::{Arc, Mutex},
+};
+
+use futures::channel::oneshot;
+use once_cell::sync::Lazy;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{BdevHandle, MayastorCliArgs, Reactors},
+};
+
+pub mod common;
+use common::MayastorTest;
+
+static NEXUS_NAME: &str = "flush_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/flush_disk1.img";
+static DISKNAME2: &str = "/tmp/flush_disk2.img";
+
+static CORES_NEXUS_NAME: &str = "flush_cores_nexus";
+static CORES_DISKNAME1: &str = "/tmp/flush_cores_disk1.img";
+static CORES_DISKNAME2: &str = "/tmp/flush_cores_disk2.img";
+
+fn setup() -> &'static MayastorTest<'static> {
+    static MAYASTOR: Lazy<MayastorTest<'static>> = Lazy::new(|| {
+        MayastorTest::new(MayastorCliArgs {
+            reactor_mask: "0x3".into(),
+            ..Default::default()
+        })
+    });
+    &MAYASTOR
+}
+
+async fn create_nexus(name: &str, disks: &[&str]) {
+    let children = disks
+        .iter()
+        .map(|d| format!("aio://{}?blk_size=512", d))
+        .collect::<Vec<_>>();
+    nexus_create(name, NEXUS_SIZE, None, &children)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn flush_after_preceding_writes() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = setup();
+    ms.spawn(async {
+        create_nexus(NEXUS_NAME, &[DISKNAME1, DISKNAME2]).await;
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4 * 1024 * 1024).unwrap();
+        buf.fill(0xaa);
+
+        // the flush is submitted while the write is in flight, but must only
+        // complete after the write has completed on all children
+        let order = RefCell::new(Vec::new());
+        let write = async {
+            h.write_at(0, &buf).await.unwrap();
+            order.borrow_mut().push("write");
+        };
+        let flush = async {
+            h.flush().await.unwrap();
+            order.borrow_mut().push("flush");
+        };
+        futures::join!(write, flush);
+        assert_eq!(*order.borrow(), vec!["write", "flush"]);
+
+        // a flush without writes in flight completes right away
+        h.flush().await.unwrap();
+
+        drop(h);
+        nexus_lookup(NEXUS_NAME).unwrap().destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
+
+#[tokio::test]
+async fn flush_after_writes_on_other_core() {
+    common::truncate_file(CORES_DISKNAME1, 64 * 1024);
+    common::truncate_file(CORES_DISKNAME2, 64 * 1024);
+
+    let ms = setup();
+    ms.spawn(async {
+        create_nexus(CORES_NEXUS_NAME, &[CORES_DISKNAME1, CORES_DISKNAME2])
+            .await;
+
+        let h = BdevHandle::open(CORES_NEXUS_NAME, true, false).unwrap();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // the write is submitted on the channel of the second core
+        let (submitted_s, submitted_r) = oneshot::channel();
+        let (written_s, written_r) = oneshot::channel();
+        let o = Arc::clone(&order);
+        Reactors::get_by_core(1).unwrap().send_future(async move {
+            let h = BdevHandle::open(CORES_NEXUS_NAME, true, false).unwrap();
+            let mut buf = h.dma_malloc(4 * 1024 * 1024).unwrap();
+            buf.fill(0xaa);
+            let write = h.write_at(0, &buf);
+            futures::pin_mut!(write);
+            assert!(futures::poll!(&mut write).is_pending());
+            submitted_s.send(()).unwrap();
+            write.await.unwrap();
+            o.lock().unwrap().push("write");
+            written_s.send(()).unwrap();
+        });
+        submitted_r.await.unwrap();
+
+        // the flush on the first core covers the write in flight on the
+        // second core
+        h.flush().await.unwrap();
+        order.lock().unwrap().push("flush");
+        written_r.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), vec!["write", "flush"]);
+
+        drop(h);
+        nexus_lookup(CORES_NEXUS_NAME)
+            .unwrap()
+            .destroy()
+            .await
+            .unwrap();
+    })
+    .await;
+
+    common::delete_file(&[
+        CORES_DISKNAME1.to_string(),
+        CORES_DISKNAME2.to_string(),
+    ]);
+}
@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState, FlushFailurePolicy},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::{
+    error_bdev::{
+        create_error_bdev,
+        inject_error,
+        SPDK_BDEV_IO_TYPE_FLUSH,
+        VBDEV_IO_FAILURE,
+    },
+    MayastorTest,
+};
+
+static NEXUS_NAME: &str = "flush_failure_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/flush_failure_disk1.img";
+static DISKNAME2: &str = "/tmp/flush_failure_disk2.img";
+static ERROR_DEVICE: &str = "flush_failure_error_device";
+static EE_ERROR_DEVICE: &str = "EE_flush_failure_error_device";
+
+static ALL_NEXUS_NAME: &str = "flush_failure_all_nexus";
+static ALL_DISKNAME1: &str = "/tmp/flush_failure_all_disk1.img";
+static ALL_DISKNAME2: &str = "/tmp/flush_failure_all_disk2.img";
+static ALL_ERROR_DEVICE1: &str = "flush_failure_all_error_device1";
+static ALL_ERROR_DEVICE2: &str = "flush_failure_all_error_device2";
+
+fn setup() -> &'static MayastorTest<'static> {
+    static MAYASTOR: Lazy<MayastorTest<'static>> =
+        Lazy::new(|| MayastorTest::new(MayastorCliArgs::default()));
+    &MAYASTOR
+}
+
+#[tokio::test]
+async fn flush_failure() {
+    common::truncate_file(DISKNAME1, 16 * 1024);
+    common::truncate_file(DISKNAME2, 16 * 1024);
+
+    let ms = setup();
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE, DISKNAME1);
+        let children = vec![
+            format!("bdev:///{}", EE_ERROR_DEVICE),
+            format!("aio://{}?blk_size=512", DISKNAME2),
+        ];
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+
+        // the flush succeeds once it is retried on the child
+        nexus.set_flush_failure_policy(FlushFailurePolicy::Retry(1));
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_FLUSH,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        h.flush().await.unwrap();
+        assert_eq!(nexus.children[0].state(), ChildState::Open);
+        assert_eq!(nexus.metrics().child_flush_failures, 1);
+
+        // the flush fails, and the child is kept
+        nexus.set_flush_failure_policy(FlushFailurePolicy::Fail);
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_FLUSH,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        assert!(h.flush().await.is_err());
+        assert_eq!(nexus.children[0].state(), ChildState::Open);
+        assert_eq!(nexus.metrics().child_flush_failures, 2);
+        h.flush().await.unwrap();
+
+        // the flush succeeds on the other child, and the child is faulted
+        nexus.set_flush_failure_policy(FlushFailurePolicy::FaultAndSucceed);
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_FLUSH,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        h.flush().await.unwrap();
+        assert_eq!(nexus.metrics().child_flush_failures, 3);
+    })
+    .await;
+
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.metrics().retires_in_flight == 0
+                    && nexus.children[0].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async {
+        nexus_lookup(NEXUS_NAME).unwrap().destroy().await.unwrap();
+    })
+    .await;
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
+
+#[tokio::test]
+async fn flush_forwarded_to_all_children() {
+    common::truncate_file(ALL_DISKNAME1, 16 * 1024);
+    common::truncate_file(ALL_DISKNAME2, 16 * 1024);
+
+    let ms = setup();
+    ms.spawn(async {
+        create_error_bdev(ALL_ERROR_DEVICE1, ALL_DISKNAME1);
+        create_error_bdev(ALL_ERROR_DEVICE2, ALL_DISKNAME2);
+        let devices = [
+            format!("EE_{}", ALL_ERROR_DEVICE1),
+            format!("EE_{}", ALL_ERROR_DEVICE2),
+        ];
+        let children = devices
+            .iter()
+            .map(|d| format!("bdev:///{}", d))
+            .collect::<Vec<_>>();
+        nexus_create(ALL_NEXUS_NAME, NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(ALL_NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(ALL_NEXUS_NAME, true, false).unwrap();
+
+        // the flush is submitted to every child, hence it fails when only
+        // the last child fails it
+        nexus.set_flush_failure_policy(FlushFailurePolicy::Fail);
+        inject_error(&devices[1], SPDK_BDEV_IO_TYPE_FLUSH, VBDEV_IO_FAILURE, 1);
+        assert!(h.flush().await.is_err());
+        assert_eq!(nexus.metrics().child_flush_failures, 1);
+
+        h.flush().await.unwrap();
+
+        // the last child is retired like on a failed write, the flush
+        // succeeds on the first
+        nexus.set_flush_failure_policy(FlushFailurePolicy::FaultAndSucceed);
+        inject_error(&devices[1], SPDK_BDEV_IO_TYPE_FLUSH, VBDEV_IO_FAILURE, 1);
+        h.flush().await.unwrap();
+        assert_eq!(nexus.metrics().child_flush_failures, 2);
+        assert_eq!(nexus.children[0].state(), ChildState::Open);
+    })
+    .await;
+
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(ALL_NEXUS_NAME).unwrap();
+                nexus.metrics().retires_in_flight == 0
+                    && nexus.children[1].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async {
+        nexus_lookup(ALL_NEXUS_NAME)
+            .unwrap()
+            .destroy()
+            .await
+            .unwrap();
+    })
+    .await;
+    common::delete_file(&[
+        ALL_DISKNAME1.to_string(),
+        ALL_DISKNAME2.to_string(),
+    ]);
+}
@@ -0,0 +1,63 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_1: &str = "foreign_child_nexus_1";
+static NEXUS_2: &str = "foreign_child_nexus_2";
+static UUID_1: &str = "b4e1c4d6-1f2a-4c57-9f36-4d0f2f1a7a01";
+static UUID_2: &str = "b4e1c4d6-1f2a-4c57-9f36-4d0f2f1a7a02";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/foreign_child_disk1.img";
+static DISKNAME2: &str = "/tmp/foreign_child_disk2.img";
+static DISKNAME3: &str = "/tmp/foreign_child_disk3.img";
+
+#[tokio::test]
+async fn foreign_child_is_rejected() {
+    let disks = [DISKNAME1, DISKNAME2, DISKNAME3];
+    disks
+        .iter()
+        .for_each(|d| common::truncate_file(d, 64 * 1024));
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        let uri = |disk: &str| format!("aio://{}?blk_size=512", disk);
+
+        // label the first disk as a child of the first nexus
+        nexus_create(NEXUS_1, NEXUS_SIZE, Some(UUID_1), &[uri(DISKNAME1)])
+            .await
+            .unwrap();
+        nexus_lookup(NEXUS_1).unwrap().destroy().await.unwrap();
+
+        nexus_create(NEXUS_2, NEXUS_SIZE, Some(UUID_2), &[uri(DISKNAME2)])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_2).unwrap();
+
+        // the disk carries the label of the first nexus
+        let err = nexus.add_child(&uri(DISKNAME1), true).await.unwrap_err();
+        assert!(err.to_string().contains(UUID_1));
+        assert_eq!(nexus.children.len(), 1);
+
+        // a disk without a label is accepted
+        nexus.add_child(&uri(DISKNAME3), true).await.unwrap();
+        assert_eq!(nexus.children.len(), 2);
+
+        // and the disk of the first nexus is accepted by the first nexus
+        nexus.destroy().await.unwrap();
+        nexus_create(NEXUS_1, NEXUS_SIZE, Some(UUID_1), &[uri(DISKNAME2)])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_1).unwrap();
+        nexus.add_child(&uri(DISKNAME1), true).await.unwrap();
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(
+        &disks.iter().map(|d| d.to_string()).collect::<Vec<_>>(),
+    );
+}
@@ -0,0 +1,89 @@
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_lookup,
+        ChildState,
+        HealthLevel,
+        Readiness,
+        Reason,
+    },
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "health_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_health() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[
+                CHILD_1.to_string(),
+                CHILD_2.to_string(),
+                CHILD_3.to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let health = nexus.health();
+        assert_eq!(health.open, 3);
+        assert_eq!(health.level, HealthLevel::Healthy);
+        assert!(nexus.is_ready());
+
+        // a paused nexus serves no IO
+        nexus.pause().await.unwrap();
+        assert_eq!(nexus.readiness(), Readiness::NotReady);
+        nexus.resume().await.unwrap();
+        assert!(nexus.is_ready());
+
+        assert!(nexus.set_write_quorum(Some(0)).is_err());
+        nexus.set_write_quorum(Some(2)).unwrap();
+
+        nexus.fault_child(CHILD_3, Reason::Rpc).await.unwrap();
+        let health = nexus.health();
+        assert_eq!(health.open, 2);
+        assert_eq!(health.faulted, 1);
+        assert_eq!(health.rebuilding, 0);
+        assert_eq!(health.level, HealthLevel::Degraded);
+        assert!(nexus.is_ready());
+
+        nexus.fault_child(CHILD_2, Reason::Rpc).await.unwrap();
+        let detail = nexus.health_detail();
+        assert_eq!(detail.health.open, 1);
+        assert_eq!(detail.health.faulted, 2);
+        assert_eq!(detail.health.level, HealthLevel::Critical);
+        // reads are still served, but writes lack their quorum
+        assert_eq!(detail.readiness, Readiness::ReadOnly);
+        assert!(!nexus.is_ready());
+        nexus.set_write_quorum(None).unwrap();
+        assert!(nexus.is_ready());
+        assert_eq!(detail.children.len(), 3);
+        assert_eq!(detail.children[0].child, CHILD_1);
+        assert_eq!(detail.children[0].state, ChildState::Open);
+        assert_eq!(detail.children[2].state, ChildState::Faulted(Reason::Rpc));
+        assert!(detail.children.iter().all(|c| !c.rebuilding));
+
+        // without an open child no IO is served; the last child cannot be
+        // faulted on request, only by failing IO
+        assert!(nexus.fault_child(CHILD_1, Reason::Rpc).await.is_err());
+        nexus.children[0]
+            .state
+            .store(ChildState::Faulted(Reason::IoError));
+        assert_eq!(nexus.readiness(), Readiness::NotReady);
+        nexus.children[0].state.store(ChildState::Open);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,82 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use futures::future::join_all;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, IoObserver, NioCtx},
+    core::{BdevHandle, IoStatus, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "instrumented_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[derive(Default)]
+struct CountingObserver {
+    submitted: AtomicU64,
+}
+
+impl IoObserver for CountingObserver {
+    fn on_submit(&self, _ctx: &NioCtx, _io_type: IoType) {
+        self.submitted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_complete(&self, _ctx: &NioCtx, _status: IoStatus) {}
+}
+
+#[tokio::test]
+async fn instrumented() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // the IO timeout alone keeps the nexus instrumented
+        assert!(nexus.instrumented());
+        nexus.set_io_accounting(false);
+        assert!(nexus.instrumented());
+        nexus.set_io_timeout(Default::default());
+        assert!(!nexus.instrumented());
+
+        // the IO is neither listed nor accounted
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xff);
+        nexus.pause().await.unwrap();
+        let mut writes =
+            Box::pin(join_all((0 .. 4).map(|i| h.write_at(i * 4096, &buf))));
+        assert!(futures::poll!(&mut writes).is_pending());
+        assert!(nexus.in_flight_ios().await.is_empty());
+        nexus.resume().await.unwrap();
+        writes
+            .await
+            .into_iter()
+            .for_each(|r| assert_eq!(r.unwrap(), 4096));
+        assert_eq!(nexus.write_stats().await.logical_bytes_written, 0);
+
+        // an observer instruments the nexus
+        let observer = Arc::new(CountingObserver::default());
+        nexus.register_io_observer(observer.clone());
+        assert!(nexus.instrumented());
+        h.write_at(0, &buf).await.unwrap();
+        assert_eq!(observer.submitted.load(Ordering::Relaxed), 1);
+        assert_eq!(nexus.write_stats().await.logical_bytes_written, 4096);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
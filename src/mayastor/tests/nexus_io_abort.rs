@@ -0,0 +1,117 @@
+#![cfg(feature = "fault-injection")]
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures_timer::Delay;
+use mayastor::{
+    bdev::{
+        inject_child_hang,
+        nexus_create,
+        nexus_lookup,
+        ChildState,
+        IoObserver,
+        NioCtx,
+    },
+    core::{BdevHandle, IoStatus, IoType, MayastorCliArgs, NvmeStatusCode},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "io_abort_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+/// aborted by request
+const ABORTED: NvmeStatusCode = NvmeStatusCode {
+    sct: 0x00,
+    sc: 0x07,
+};
+
+/// records the NVMe status of the failed IOs
+#[derive(Default)]
+struct StatusObserver {
+    statuses: Mutex<Vec<NvmeStatusCode>>,
+}
+
+impl StatusObserver {
+    fn take(&self) -> Vec<NvmeStatusCode> {
+        std::mem::take(&mut *self.statuses.lock().unwrap())
+    }
+}
+
+impl IoObserver for StatusObserver {
+    fn on_submit(&self, _ctx: &NioCtx, _io_type: IoType) {}
+
+    fn on_complete(&self, ctx: &NioCtx, status: IoStatus) {
+        if status != IoStatus::Success {
+            self.statuses.lock().unwrap().push(ctx.nvme_status());
+        }
+    }
+}
+
+#[tokio::test]
+async fn io_abort() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let observer = Arc::new(StatusObserver::default());
+        nexus.register_io_observer(observer.clone());
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+        assert_eq!(nexus.abort_in_flight().await, 0);
+
+        // a write which completed on one child but hangs on the other
+        // completes as aborted
+        inject_child_hang("m1", 1);
+        let mut write = Box::pin(h.write_at(0, &buf));
+        assert!(futures::poll!(&mut write).is_pending());
+        Delay::new(Duration::from_millis(100)).await;
+        assert_eq!(nexus.in_flight_ios().await.len(), 1);
+        assert_eq!(nexus.abort_in_flight().await, 1);
+        assert!(write.await.is_err());
+        assert_eq!(observer.take(), vec![ABORTED]);
+
+        // as does a read which hangs on the child it was submitted to
+        inject_child_hang("m0", 1);
+        inject_child_hang("m1", 1);
+        let mut read = Box::pin(h.read_at(0, &mut buf));
+        assert!(futures::poll!(&mut read).is_pending());
+        Delay::new(Duration::from_millis(100)).await;
+        assert_eq!(nexus.abort_in_flight().await, 1);
+        assert!(read.await.is_err());
+        assert_eq!(observer.take(), vec![ABORTED]);
+        inject_child_hang("m0", 0);
+        inject_child_hang("m1", 0);
+
+        // neither child is faulted for the aborted IOs
+        assert_eq!(nexus.metrics().aborted_ios, 2);
+        assert!(nexus.in_flight_ios().await.is_empty());
+        assert!(nexus.children.iter().all(|c| c.state() == ChildState::Open));
+        assert!(nexus
+            .children
+            .iter()
+            .all(|c| c.history().last_fault.is_none()));
+        h.write_at(0, &buf).await.unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+        assert!(observer.take().is_empty());
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
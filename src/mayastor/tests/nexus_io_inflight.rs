@@ -0,0 +1,93 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState},
+    core::{BdevHandle, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "inflight_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn list_and_abort_in_flight_io() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xff);
+
+        // nothing is in flight on an idle nexus
+        h.write_at(0, &buf).await.unwrap();
+        assert!(nexus.in_flight_ios().await.is_empty());
+        assert!(nexus.abort_io(1).await.is_err());
+
+        // aborting everything must be forced
+        assert!(nexus.abort_all_ios(false).await.is_err());
+        assert_eq!(nexus.abort_all_ios(true).await.unwrap(), 0);
+
+        // IO held while the nexus is paused is in flight without any child IO
+        nexus.pause().await.unwrap();
+        let mut write = Box::pin(h.write_at(4096, &buf));
+        assert!(futures::poll!(&mut write).is_pending());
+
+        let ios = nexus.in_flight_ios().await;
+        assert_eq!(ios.len(), 1);
+        assert_eq!(ios[0].io_type, IoType::Write);
+        assert_eq!(ios[0].offset, 8);
+        assert_eq!(ios[0].num_blocks, 8);
+        assert_eq!(ios[0].children, 0);
+
+        // the aborted IO fails, and is no longer in flight
+        assert_eq!(nexus.abort_io(ios[0].id).await.unwrap(), 1);
+        assert!(write.await.is_err());
+        assert!(nexus.in_flight_ios().await.is_empty());
+        assert!(nexus.abort_io(ios[0].id).await.is_err());
+        nexus.resume().await.unwrap();
+
+        // the children are not retired for an aborted IO
+        h.write_at(4096, &buf).await.unwrap();
+        assert!(nexus.children.iter().all(|c| c.state() == ChildState::Open));
+
+        // an IO completing in the middle of the IO in flight leaves the
+        // others listed
+        nexus.pause().await.unwrap();
+        let mut writes = (0 .. 4)
+            .map(|i| Box::pin(h.write_at(i * 4096, &buf)))
+            .collect::<Vec<_>>();
+        for write in writes.iter_mut() {
+            assert!(futures::poll!(write).is_pending());
+        }
+        let mut ios = nexus.in_flight_ios().await;
+        assert_eq!(ios.len(), 4);
+        ios.sort_by_key(|io| io.offset);
+        assert_eq!(nexus.abort_io(ios[1].id).await.unwrap(), 1);
+        assert!(writes.remove(1).await.is_err());
+        let mut left = nexus.in_flight_ios().await;
+        left.sort_by_key(|io| io.offset);
+        assert_eq!(
+            left.iter().map(|io| io.offset).collect::<Vec<_>>(),
+            vec![0, 16, 24]
+        );
+        nexus.resume().await.unwrap();
+        for write in writes {
+            write.await.unwrap();
+        }
+        assert!(nexus.in_flight_ios().await.is_empty());
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,69 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, IoLimits, Reason},
+    core::{Bdev, MayastorCliArgs},
+    nexus_uri::bdev_create,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "io_limits_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+/// create a child bdev with the given unmap and write zeroes limits and write
+/// unit, returns the URI the nexus opens it with
+async fn child(uri: &str, unmap: u32, write_zeroes: u32, unit: u32) -> String {
+    let name = bdev_create(uri).await.unwrap();
+    let mut bdev = Bdev::lookup_by_name(&name).unwrap();
+    bdev.set_max_unmap(unmap);
+    bdev.set_max_write_zeroes(write_zeroes);
+    bdev.set_write_unit_size(unit);
+    format!("bdev:///{}", name)
+}
+
+#[tokio::test]
+async fn nexus_io_limits() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        let c1 = child(CHILD_1, 128, 0, 1).await;
+        let c2 = child(CHILD_2, 64, 256, 2).await;
+        let c3 = child(CHILD_3, 32, 512, 4).await;
+
+        // the nexus advertises the most restrictive limits of its children,
+        // a child without a limit does not restrict it
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[c1, c2])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let limits = IoLimits {
+            max_unmap: Some(64),
+            max_write_zeroes: Some(256),
+            write_unit: 2,
+        };
+        assert_eq!(nexus.io_limits(), limits);
+        let bdev = Bdev::lookup_by_name(NEXUS_NAME).unwrap();
+        assert_eq!(bdev.max_unmap(), 64);
+        assert_eq!(bdev.max_write_zeroes(), 256);
+        assert_eq!(bdev.write_unit_size(), 2);
+
+        // a child with tighter limits restricts the nexus once it is added
+        nexus.add_child(&c3, true).await.unwrap();
+        assert_eq!(
+            nexus.io_limits(),
+            IoLimits {
+                max_unmap: Some(32),
+                max_write_zeroes: Some(256),
+                write_unit: 4,
+            }
+        );
+
+        // and no longer once it is faulted
+        nexus.fault_child(&c3, Reason::Rpc).await.unwrap();
+        assert_eq!(nexus.io_limits(), limits);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
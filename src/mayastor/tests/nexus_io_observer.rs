@@ -0,0 +1,78 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, IoObserver, MetricsObserver, NioCtx},
+    core::{BdevHandle, IoStatus, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "io_observer_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[derive(Default)]
+struct CountingObserver {
+    submitted: AtomicU64,
+    completed: AtomicU64,
+}
+
+impl IoObserver for CountingObserver {
+    fn on_submit(&self, _ctx: &NioCtx, _io_type: IoType) {
+        self.submitted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_complete(&self, ctx: &NioCtx, status: IoStatus) {
+        assert_eq!(ctx.in_flight(), 0);
+        assert_eq!(status, IoStatus::Success);
+        self.completed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[tokio::test]
+async fn io_observer() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let observer = Arc::new(CountingObserver::default());
+        nexus.register_io_observer(observer.clone());
+        let metrics_observer = Arc::new(MetricsObserver::new(nexus));
+        nexus.register_io_observer(metrics_observer);
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xff);
+        for i in 0 .. 4 {
+            h.write_at(i * 4096, &buf).await.unwrap();
+        }
+        for i in 0 .. 2 {
+            h.read_at(i * 4096, &mut buf).await.unwrap();
+        }
+
+        assert_eq!(observer.submitted.load(Ordering::Relaxed), 6);
+        assert_eq!(observer.completed.load(Ordering::Relaxed), 6);
+
+        let metrics = nexus.metrics();
+        assert_eq!(metrics.writes, 4);
+        assert_eq!(metrics.reads, 2);
+        assert_eq!(metrics.completed, 6);
+        assert_eq!(metrics.failed, 0);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
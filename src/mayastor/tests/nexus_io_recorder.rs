@@ -0,0 +1,114 @@
+#![cfg(feature = "io-recorder")]
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ReadPolicy, IO_RECORDER_DEPTH},
+    core::{BdevHandle, IoStatus, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "io_recorder_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn io_recorder() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        h.write_at(8 * 512, &buf).await.unwrap();
+        h.read_at(8 * 512, &mut buf).await.unwrap();
+
+        let history = nexus.io_history().await;
+        let write = history
+            .iter()
+            .find(|r| r.io_type == IoType::Write && r.offset == 8)
+            .unwrap();
+        assert_eq!(write.num_blocks, 8);
+        assert_eq!(write.status, IoStatus::Success);
+        assert_eq!(write.children, 2);
+        assert_eq!(write.selection, None);
+
+        let read = history.last().unwrap();
+        assert_eq!(read.io_type, IoType::Read);
+        assert_eq!(read.status, IoStatus::Success);
+        assert_eq!(read.children, 1);
+
+        // the read records why its child was selected, and the child IOs
+        // outstanding on each reader at the time
+        let selection = read.selection.as_ref().unwrap();
+        assert_eq!(selection.policy, ReadPolicy::RoundRobin);
+        assert_eq!(selection.candidates, 2);
+        assert!(selection.position < 2);
+        let mut outstanding = selection.outstanding.clone();
+        outstanding.sort();
+        assert_eq!(
+            outstanding,
+            vec![(CHILD_1.to_string(), 0), (CHILD_2.to_string(), 0)]
+        );
+        assert_eq!(
+            read.served.as_deref(),
+            Some(selection.outstanding[selection.position].0.as_str())
+        );
+
+        // the ring is bounded
+        for _ in 0 .. IO_RECORDER_DEPTH {
+            h.write_at(0, &buf).await.unwrap();
+        }
+        let history = nexus.io_history().await;
+        assert_eq!(history.len(), IO_RECORDER_DEPTH);
+        assert!(history.iter().all(|r| r.offset == 0));
+
+        // the child which served a read is recorded, draining the first child
+        // from reads has the second one serve them
+        assert!(history.iter().all(|r| r.served.is_none()));
+        nexus
+            .set_child_io_flags(CHILD_1, false, true)
+            .await
+            .unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+        let reads = nexus.reads_at(3).await;
+        assert_eq!(reads.len(), 1);
+        assert_eq!(reads[0].served.as_deref(), Some(CHILD_2));
+        let selection = reads[0].selection.as_ref().unwrap();
+        assert_eq!(selection.candidates, 1);
+        assert_eq!(selection.outstanding.len(), 1);
+        assert!(nexus.reads_at(8).await.is_empty());
+
+        nexus.set_child_io_flags(CHILD_1, true, true).await.unwrap();
+        nexus
+            .set_child_io_flags(CHILD_2, false, true)
+            .await
+            .unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+        let reads = nexus.reads_at(0).await;
+        assert_eq!(reads.len(), 2);
+        assert_eq!(reads[1].served.as_deref(), Some(CHILD_1));
+
+        // as does the least outstanding policy
+        nexus.set_child_io_flags(CHILD_2, true, true).await.unwrap();
+        nexus.set_read_policy(ReadPolicy::LeastOutstanding);
+        h.read_at(0, &mut buf).await.unwrap();
+        let reads = nexus.reads_at(0).await;
+        let selection = reads.last().unwrap().selection.as_ref().unwrap();
+        assert_eq!(selection.policy, ReadPolicy::LeastOutstanding);
+        assert_eq!(selection.candidates, 2);
+        assert_eq!(selection.outstanding.len(), 2);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
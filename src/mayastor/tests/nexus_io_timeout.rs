@@ -0,0 +1,95 @@
+#![cfg(feature = "fault-injection")]
+
+use std::time::{Duration, Instant};
+
+use mayastor::{
+    bdev::{
+        inject_child_hang,
+        nexus_create,
+        nexus_lookup,
+        ChildState,
+        IoTimeout,
+        Reason,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "io_timeout_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+#[tokio::test]
+async fn io_timeout() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().io_timeout, IoTimeout::default());
+        nexus.set_io_timeout(TIMEOUT);
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // the write never completes on the second child, it fails once the
+        // timeout has expired
+        inject_child_hang("m1", 1);
+        let start = Instant::now();
+        assert!(h.write_at(0, &buf).await.is_err());
+        assert!(start.elapsed() >= TIMEOUT);
+        assert_eq!(nexus.metrics().io_timeouts, 1);
+        drop(h);
+    })
+    .await;
+
+    // the child which did not respond is retired
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.children[1].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(
+            nexus.children[1].history().last_fault.map(|(r, _)| r),
+            Some(Reason::IoTimeout)
+        );
+        assert_eq!(nexus.children[0].state(), ChildState::Open);
+
+        // the IO of the nexus goes on with the child which is left
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xbb);
+        h.write_at(0, &buf).await.unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xbb));
+        assert_eq!(nexus.metrics().io_timeouts, 1);
+        drop(h);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
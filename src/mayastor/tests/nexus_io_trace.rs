@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, TracePoint, IO_TRACE_MAX_DURATION},
+    core::{BdevHandle, IoStatus, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "io_trace_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static TRACE_FILE: &str = "/tmp/io_trace_nexus.json";
+
+#[tokio::test]
+async fn io_trace() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    let mut expiring = ms
+        .spawn(async {
+            nexus_create(
+                NEXUS_NAME,
+                NEXUS_SIZE,
+                None,
+                &[CHILD_1.to_string(), CHILD_2.to_string()],
+            )
+            .await
+            .unwrap();
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+            let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+            let mut buf = h.dma_malloc(4096).unwrap();
+            buf.fill(0xaa);
+
+            // a trace must expire, and within the bound
+            assert!(nexus.start_io_trace(Duration::default()).is_err());
+            assert!(nexus
+                .start_io_trace(IO_TRACE_MAX_DURATION + Duration::from_secs(1))
+                .is_err());
+
+            // nothing is traced until a trace is started
+            h.write_at(0, &buf).await.unwrap();
+            assert!(!nexus.io_trace_active());
+            let mut events =
+                nexus.start_io_trace(Duration::from_secs(60)).unwrap();
+            assert!(nexus.io_trace_active());
+            assert!(nexus.start_io_trace(Duration::from_secs(60)).is_err());
+
+            // a write is dispatched to and completed by both children
+            h.write_at(4096, &buf).await.unwrap();
+            let mut write = Vec::new();
+            while let Ok(Some(event)) = events.try_next() {
+                write.push(event);
+            }
+            assert_eq!(write.len(), 6);
+            assert!(write.iter().all(|e| e.id == write[0].id
+                && e.io_type == IoType::Write
+                && e.offset == 8
+                && e.num_blocks == 8));
+            assert_eq!(write[0].point, TracePoint::Submit);
+            let mut dispatched: Vec<_> = write[1 .. 3]
+                .iter()
+                .map(|e| match &e.point {
+                    TracePoint::Dispatch {
+                        child,
+                    } => child.clone(),
+                    point => panic!("unexpected {:?}", point),
+                })
+                .collect();
+            dispatched.sort();
+            assert_eq!(dispatched, vec![CHILD_1, CHILD_2]);
+            assert!(write[3 .. 5].iter().all(|e| matches!(
+                e.point,
+                TracePoint::ChildComplete {
+                    status: IoStatus::Success,
+                    ..
+                }
+            )));
+            assert!(matches!(
+                write[5].point,
+                TracePoint::Complete {
+                    status: IoStatus::Success,
+                    ..
+                }
+            ));
+            assert!(write.windows(2).all(|w| w[0].time <= w[1].time));
+
+            // a read is served by a single child
+            h.read_at(4096, &mut buf).await.unwrap();
+            let mut read = Vec::new();
+            while let Ok(Some(event)) = events.try_next() {
+                read.push(event);
+            }
+            assert_eq!(read.len(), 4);
+            assert!(read.iter().all(|e| e.io_type == IoType::Read));
+
+            // once stopped the stream ends
+            assert!(nexus.stop_io_trace());
+            assert!(!nexus.stop_io_trace());
+            h.write_at(0, &buf).await.unwrap();
+            assert!(matches!(events.try_next(), Ok(None)));
+
+            nexus.start_io_trace(Duration::from_millis(200)).unwrap()
+        })
+        .await;
+
+    // a trace stops by itself when it expires, without any IO
+    tokio::time::delay_for(Duration::from_millis(500)).await;
+    ms.spawn(async move {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert!(!nexus.io_trace_active());
+        assert!(matches!(expiring.try_next(), Ok(None)));
+
+        // the events of a trace can be written to a file
+        nexus
+            .start_io_trace_to_file(
+                std::path::Path::new(TRACE_FILE),
+                Duration::from_secs(60),
+            )
+            .unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let buf = h.dma_malloc(4096).unwrap();
+        h.write_at(0, &buf).await.unwrap();
+        assert!(nexus.stop_io_trace());
+    })
+    .await;
+
+    let mut lines = Vec::new();
+    for _ in 0 .. 50 {
+        lines = std::fs::read_to_string(TRACE_FILE)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .collect();
+        if lines.len() == 6 {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0]["point"], "Submit");
+    assert_eq!(lines[5]["point"]["Complete"]["status"], "Success");
+
+    ms.spawn(async {
+        nexus_lookup(NEXUS_NAME).unwrap().destroy().await.unwrap();
+    })
+    .await;
+    common::delete_file(&[TRACE_FILE.to_string()]);
+}
@@ -0,0 +1,90 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, WriteCache},
+    core::{Bdev, BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "iov_bounce_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn iov_bounce() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // the second child accepts no more than two iovecs per IO
+        Bdev::lookup_by_name("m1").unwrap().set_max_num_segments(2);
+
+        // a write scattered over more iovecs reaches both children
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut bufs = (0 .. 4)
+            .map(|_| h.dma_malloc(4096).unwrap())
+            .collect::<Vec<_>>();
+        for (i, buf) in bufs.iter_mut().enumerate() {
+            buf.fill(0xa0 + i as u8);
+        }
+        h.writev_at(8192, &bufs).await.unwrap();
+        for child in &[CHILD_1, CHILD_2] {
+            let data =
+                nexus.read_from_child(child, 8192, 4 * 4096).await.unwrap();
+            for (i, chunk) in data.as_slice().chunks(4096).enumerate() {
+                assert!(chunk.iter().all(|b| *b == 0xa0 + i as u8));
+            }
+        }
+
+        // so does a read, whichever child serves it
+        for _ in 0 .. 4 {
+            let mut bufs = (0 .. 4)
+                .map(|_| h.dma_malloc(4096).unwrap())
+                .collect::<Vec<_>>();
+            h.readv_at(8192, &mut bufs).await.unwrap();
+            for (i, buf) in bufs.iter().enumerate() {
+                assert!(buf.as_slice().iter().all(|b| *b == 0xa0 + i as u8));
+            }
+        }
+
+        // IO within the limit is submitted as is
+        h.writev_at(0, &bufs[.. 2]).await.unwrap();
+        let data = nexus.read_from_child(CHILD_2, 0, 2 * 4096).await.unwrap();
+        assert!(data.as_slice()[4096 ..].iter().all(|b| *b == 0xa1));
+
+        // contiguous writes coalesced by the write cache are cut into runs
+        // the child accepts, a write with too many iovecs of its own is
+        // bounced
+        nexus
+            .set_write_cache(WriteCache::WriteThrough {
+                max_bytes: 1 << 20,
+            })
+            .unwrap();
+        let writes = (0 .. 4)
+            .map(|i| h.writev_at(32768 + i as u64 * 4096, &bufs[i ..= i]))
+            .chain(std::iter::once(h.writev_at(49152, &bufs)));
+        for r in futures::future::join_all(writes).await {
+            r.unwrap();
+        }
+        assert!(nexus.metrics().coalesced_writes > 0);
+        for child in &[CHILD_1, CHILD_2] {
+            let data =
+                nexus.read_from_child(child, 32768, 8 * 4096).await.unwrap();
+            for (i, chunk) in data.as_slice().chunks(4096).enumerate() {
+                assert!(chunk.iter().all(|b| *b == 0xa0 + (i % 4) as u8));
+            }
+        }
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
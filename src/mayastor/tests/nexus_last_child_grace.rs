@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_event, nexus_lookup, ChildState, NexusEvent},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_READ,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "last_child_grace_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/last_child_grace_disk1.img";
+static ERROR_DEVICE: &str = "last_child_grace_error_device";
+static EE_ERROR_DEVICE: &str = "EE_last_child_grace_error_device";
+
+#[tokio::test]
+async fn last_child_grace() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE, DISKNAME1);
+        let children = vec![format!("bdev:///{}", EE_ERROR_DEVICE)];
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().last_child_grace, Duration::default());
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // without a grace period a read failing on the only child fails,
+        // once its repair has failed as well
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_READ,
+            VBDEV_IO_FAILURE,
+            2,
+        );
+        assert!(h.read_at(0, &mut buf).await.is_err());
+
+        // with one it is retried until the child recovers
+        let mut events = nexus_event::subscribe();
+        nexus.set_last_child_grace(Duration::from_secs(1));
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_READ,
+            VBDEV_IO_FAILURE,
+            3,
+        );
+        h.read_at(0, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        assert_eq!(nexus.children[0].state(), ChildState::Open);
+
+        let mut protected = false;
+        while let Ok(Some(event)) = events.try_next() {
+            protected |= event
+                == NexusEvent::LastChildProtected {
+                    nexus: NEXUS_NAME.to_string(),
+                    child: EE_ERROR_DEVICE.to_string(),
+                };
+        }
+        assert!(protected);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string()]);
+}
@@ -0,0 +1,100 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::MayastorCliArgs,
+    lvs::{Lvol, Lvs},
+};
+use rpc::mayastor::CreatePoolRequest;
+
+pub mod common;
+
+static NEXUS_NAME: &str = "list_snapshots_nexus";
+static NEXUS_SIZE: u64 = 8 * 1024 * 1024;
+static POOL_NAME: &str = "list_snapshots_pool";
+static UUID1: &str = "00000000-76b6-4fcf-864d-1027d4038781";
+static UUID2: &str = "00000000-76b6-4fcf-864d-1027d4038782";
+
+/// snapshot the replica as a snapshot of the nexus taken at time t would
+async fn snapshot(pool: &Lvs, uuid: &str, t: u64) {
+    let lvol = pool.lvols().unwrap().find(|l| l.name() == uuid).unwrap();
+    lvol.snapshot(&Lvol::format_snapshot_name(uuid, t))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn list_snapshots() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        Lvs::create_or_import(CreatePoolRequest {
+            name: POOL_NAME.to_string(),
+            disks: vec!["malloc:///disk0?size_mb=64".into()],
+        })
+        .await
+        .unwrap();
+        let pool = Lvs::lookup(POOL_NAME).unwrap();
+        for uuid in &[UUID1, UUID2] {
+            pool.create_lvol(uuid, 12 * 1024 * 1024, false)
+                .await
+                .unwrap();
+        }
+        let child1 = format!("loopback:///{}", UUID1);
+        let child2 = format!("loopback:///{}", UUID2);
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[child1.clone(), child2.clone()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert!(nexus.list_snapshots().await.unwrap().is_empty());
+
+        // the first snapshot is taken on both children, the second one is
+        // missing on the second child
+        snapshot(&pool, UUID1, 1).await;
+        snapshot(&pool, UUID2, 1).await;
+        snapshot(&pool, UUID1, 2).await;
+
+        let snapshots = nexus.list_snapshots().await.unwrap();
+        assert_eq!(snapshots.len(), 2);
+
+        let first = &snapshots[0];
+        assert_eq!(first.name, Lvol::format_snapshot_name(NEXUS_NAME, 1));
+        assert_eq!(first.time, 1);
+        assert!(first.complete);
+        assert!(first.missing.is_empty());
+        assert_eq!(first.children.len(), 2);
+        assert_eq!(first.children[0].child, child1);
+        assert_eq!(
+            first.children[0].bdev,
+            Lvol::format_snapshot_name(UUID1, 1)
+        );
+        assert_eq!(first.children[1].child, child2);
+        assert_eq!(
+            first.children[1].bdev,
+            Lvol::format_snapshot_name(UUID2, 1)
+        );
+
+        let second = &snapshots[1];
+        assert_eq!(second.name, Lvol::format_snapshot_name(NEXUS_NAME, 2));
+        assert_eq!(second.time, 2);
+        assert!(!second.complete);
+        assert_eq!(second.missing, vec![child2.clone()]);
+        assert_eq!(second.children.len(), 1);
+        assert_eq!(second.children[0].child, child1);
+
+        // a deleted snapshot is no longer listed
+        nexus
+            .delete_snapshot(&Lvol::format_snapshot_name(NEXUS_NAME, 1))
+            .await
+            .unwrap();
+        let snapshots = nexus.list_snapshots().await.unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].time, 2);
+
+        nexus.destroy().await.unwrap();
+        pool.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_event,
+        nexus_lookup,
+        ChildState,
+        NexusEvent,
+        Reason,
+    },
+    core::MayastorCliArgs,
+    rebuild::RebuildJob,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "maintenance_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_maintenance() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert!(!nexus.in_maintenance());
+
+        let mut events = nexus_event::subscribe();
+        nexus.set_maintenance(true).await;
+        assert!(nexus.in_maintenance());
+        assert_eq!(
+            events.try_next().unwrap().unwrap(),
+            NexusEvent::MaintenanceEntered {
+                nexus: NEXUS_NAME.to_string()
+            }
+        );
+
+        // the rebuild of the added child is deferred
+        nexus.add_child(CHILD_2, false).await.unwrap();
+        assert_eq!(
+            nexus.child_lookup("m1").unwrap().state(),
+            ChildState::Faulted(Reason::OutOfSync)
+        );
+        assert!(RebuildJob::lookup(CHILD_2).is_err());
+
+        nexus.set_maintenance(false).await;
+        assert!(!nexus.in_maintenance());
+        assert_eq!(
+            events.try_next().unwrap().unwrap(),
+            NexusEvent::MaintenanceExited {
+                nexus: NEXUS_NAME.to_string()
+            }
+        );
+    })
+    .await;
+
+    // the deferred rebuild runs once maintenance has ended
+    let mut rebuilt = false;
+    for _ in 0 .. 100 {
+        rebuilt = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.child_lookup("m1").unwrap().state() == ChildState::Open
+            })
+            .await;
+        if rebuilt {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(rebuilt);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
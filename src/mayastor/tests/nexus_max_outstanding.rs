@@ -0,0 +1,107 @@
+use futures::future::join_all;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, OutstandingLimit},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "max_outstanding_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+/// number of writes submitted at once
+const WRITES: u64 = 6;
+
+#[tokio::test]
+async fn max_outstanding_io() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().max_outstanding, None);
+        assert!(nexus
+            .set_max_outstanding(Some(OutstandingLimit {
+                max_ios: 0,
+                queue_depth: 8,
+            }))
+            .is_err());
+        nexus
+            .set_max_outstanding(Some(OutstandingLimit {
+                max_ios: 4,
+                queue_depth: 8,
+            }))
+            .unwrap();
+        assert_eq!(nexus.metrics().max_outstanding_ios, 4);
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xff);
+
+        // hold the admitted IO by pausing the nexus, the IO beyond the limit
+        // is queued
+        nexus.pause().await.unwrap();
+        let mut writes = Box::pin(join_all(
+            (0 .. WRITES).map(|i| h.write_at(i * 4096, &buf)),
+        ));
+        assert!(futures::poll!(&mut writes).is_pending());
+        let metrics = nexus.metrics();
+        assert_eq!(metrics.outstanding_ios, 4);
+        assert_eq!(metrics.throttled_ios, 2);
+        assert_eq!(nexus.in_flight_ios().await.len(), WRITES as usize);
+
+        // the queued IO is admitted as the admitted IO completes
+        nexus.resume().await.unwrap();
+        writes
+            .await
+            .into_iter()
+            .for_each(|r| assert_eq!(r.unwrap(), 4096));
+        assert_eq!(nexus.metrics().outstanding_ios, 0);
+
+        // IO beyond the queue depth is rejected
+        nexus
+            .set_max_outstanding(Some(OutstandingLimit {
+                max_ios: 4,
+                queue_depth: 1,
+            }))
+            .unwrap();
+        nexus.pause().await.unwrap();
+        let mut writes = Box::pin(join_all(
+            (0 .. WRITES).map(|i| h.write_at(i * 4096, &buf)),
+        ));
+        assert!(futures::poll!(&mut writes).is_pending());
+        nexus.resume().await.unwrap();
+        let results = writes.await;
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 5);
+        assert_eq!(nexus.metrics().outstanding_ios, 0);
+
+        // without a limit nothing is queued, the IO is not even admitted
+        nexus.set_max_outstanding(None).unwrap();
+        let throttled = nexus.metrics().throttled_ios;
+        nexus.pause().await.unwrap();
+        let mut writes = Box::pin(join_all(
+            (0 .. WRITES).map(|i| h.write_at(i * 4096, &buf)),
+        ));
+        assert!(futures::poll!(&mut writes).is_pending());
+        assert_eq!(nexus.metrics().outstanding_ios, 0);
+        nexus.resume().await.unwrap();
+        writes
+            .await
+            .into_iter()
+            .for_each(|r| assert_eq!(r.unwrap(), 4096));
+        assert_eq!(nexus.metrics().throttled_ios, throttled);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
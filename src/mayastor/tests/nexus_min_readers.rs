@@ -0,0 +1,57 @@
+use std::time::{Duration, Instant};
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, Reason},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "min_readers_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn reads_fail_fast_below_min_readers() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().min_readers, None);
+        assert!(nexus.set_min_readers(Some(0)).is_err());
+        nexus.set_min_readers(Some(2)).unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+        assert_eq!(nexus.metrics().fast_failed_reads, 0);
+
+        // with a single child left reads fail right away, writes do not
+        nexus.fault_child(CHILD_2, Reason::Rpc).await.unwrap();
+        let start = Instant::now();
+        assert!(h.read_at(0, &mut buf).await.is_err());
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert_eq!(nexus.metrics().fast_failed_reads, 1);
+        h.write_at(0, &buf).await.unwrap();
+
+        // by default reads are served by any healthy child
+        nexus.set_min_readers(None).unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        assert_eq!(nexus.metrics().fast_failed_reads, 1);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
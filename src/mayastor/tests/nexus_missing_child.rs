@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_create_with_missing,
+        nexus_lookup,
+        ChildState,
+        HealthLevel,
+        NexusStatus,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+    rebuild::SEGMENT_SIZE,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "missing_child_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static NEXUS_UUID: &str = "a9b9ebc9-9e03-4f4e-8d7b-2b1d2f6e0c55";
+
+static DISKNAME1: &str = "/tmp/missing_child_disk1.img";
+static DISKNAME2: &str = "/tmp/missing_child_disk2.img";
+
+fn child_1() -> String {
+    format!("aio://{}?blk_size=512", DISKNAME1)
+}
+
+fn child_2() -> String {
+    format!("aio://{}?blk_size=512", DISKNAME2)
+}
+
+#[tokio::test]
+async fn missing_child() {
+    common::truncate_file(DISKNAME1, 16 * 1024);
+    common::truncate_file(DISKNAME2, 16 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    let completion = ms
+        .spawn(async {
+            // both children hold the data of the nexus
+            nexus_create(
+                NEXUS_NAME,
+                NEXUS_SIZE,
+                Some(NEXUS_UUID),
+                &[child_1(), child_2()],
+            )
+            .await
+            .unwrap();
+            let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+            let mut buf = h.dma_malloc(2 * SEGMENT_SIZE).unwrap();
+            buf.fill(0xaa);
+            h.write_at(0, &buf).await.unwrap();
+            drop(h);
+            nexus_lookup(NEXUS_NAME).unwrap().destroy().await.unwrap();
+
+            // the nexus is assembled while the second child is unreachable
+            nexus_create_with_missing(
+                NEXUS_NAME,
+                NEXUS_SIZE,
+                Some(NEXUS_UUID),
+                &[child_1()],
+                &[child_2()],
+            )
+            .await
+            .unwrap();
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+            assert_eq!(nexus.children.len(), 2);
+            assert_eq!(nexus.children[1].state(), ChildState::Missing);
+            assert_eq!(nexus.missing_children(), vec![child_2()]);
+            assert_eq!(nexus.status(), NexusStatus::Degraded);
+            assert!(nexus.is_ready());
+            let health = nexus.health();
+            assert_eq!(health.open, 1);
+            assert_eq!(health.missing, 1);
+            assert_eq!(health.level, HealthLevel::Critical);
+            let detail = nexus.health_detail();
+            assert_eq!(detail.children[1].child, child_2());
+            assert_eq!(detail.children[1].state, ChildState::Missing);
+
+            // the missing child takes no part in the IO, but the segments
+            // written meanwhile are tracked
+            for dump in nexus.dump_channels().await {
+                assert_eq!(dump.writers.len(), 1);
+                assert_eq!(dump.readers.len(), 1);
+            }
+            assert_eq!(nexus.missing_written_segments(), Some(0));
+            let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+            let mut buf = h.dma_malloc(4096).unwrap();
+            buf.fill(0xbb);
+            h.write_at(0, &buf).await.unwrap();
+            assert_eq!(nexus.missing_written_segments(), Some(1));
+            h.read_at(0, &mut buf).await.unwrap();
+            assert!(buf.as_slice().iter().all(|b| *b == 0xbb));
+
+            // once it appears it is rebuilt from the written segments only
+            let completion = nexus.rebuild_completion(&child_2());
+            nexus.online_child(&child_2()).await.unwrap();
+            assert!(nexus.missing_children().is_empty());
+            completion
+        })
+        .await;
+
+    let completion = tokio::time::timeout(Duration::from_secs(10), completion)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(completion.success);
+    assert_eq!(completion.bytes, SEGMENT_SIZE);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.children[1].state(), ChildState::Open);
+        assert_eq!(nexus.status(), NexusStatus::Online);
+        assert_eq!(nexus.health().missing, 0);
+        assert_eq!(nexus.missing_written_segments(), None);
+
+        // the written segment was copied, the segment after it was in sync
+        // already
+        let h = nexus.children[1].handle().unwrap();
+        let offset = nexus.data_ent_offset * 512;
+        let mut buf = h.dma_malloc(4096).unwrap();
+        h.read_at(offset, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xbb));
+        h.read_at(offset + SEGMENT_SIZE, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        drop(h);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
@@ -66,6 +66,7 @@ async fn nexus_multipath() {
             uuid: UUID.to_string(),
             size: 32 * 1024 * 1024,
             children: [format!("loopback:///{}", UUID)].to_vec(),
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -0,0 +1,58 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, NoFaultPolicy},
+    core::{MayastorCliArgs, NvmeStatusCode},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "no_fault_policy_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+// namespace not ready
+const NOT_READY: NvmeStatusCode = NvmeStatusCode {
+    sct: 0x00,
+    sc: 0x82,
+};
+
+#[test]
+fn no_fault_policy_statuses() {
+    let policy = NoFaultPolicy::default();
+    assert!(policy.contains(&NvmeStatusCode::INVALID_OPCODE));
+    assert!(!policy.contains(&NOT_READY));
+
+    let policy =
+        NoFaultPolicy::new(vec![NOT_READY, NvmeStatusCode::INVALID_OPCODE]);
+    assert!(policy.contains(&NOT_READY));
+    assert_eq!(
+        policy.statuses(),
+        vec![NvmeStatusCode::INVALID_OPCODE, NOT_READY]
+    );
+}
+
+#[tokio::test]
+async fn set_no_fault_policy() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(
+            nexus.policy().no_fault.statuses(),
+            vec![NvmeStatusCode::INVALID_OPCODE]
+        );
+
+        nexus.set_no_fault_policy(NoFaultPolicy::new(vec![NOT_READY]));
+        assert_eq!(nexus.policy().no_fault.statuses(), vec![NOT_READY]);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "fault-injection")]
+
+use std::time::{Duration, Instant};
+
+use mayastor::{
+    bdev::{
+        inject_no_memory,
+        nexus_create,
+        nexus_lookup,
+        NoMemoryBackoff,
+        NoMemoryPolicy,
+        WriteOrdering,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "no_memory_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn no_memory() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().no_memory, NoMemoryPolicy::Resubmit);
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+
+        // the IO is resubmitted once the write outstanding with it completes
+        let (first, second) = futures::join!(h.write_at(0, &buf), async {
+            inject_no_memory(NEXUS_NAME, 1);
+            h.write_at(4096, &buf).await
+        });
+        first.unwrap();
+        second.unwrap();
+        assert_eq!(nexus.metrics().no_memory, 1);
+
+        // the IO is resubmitted after the delay, the ordered write behind it
+        // is held until then
+        nexus.set_no_memory_policy(NoMemoryPolicy::Delay(
+            Duration::from_millis(300),
+        ));
+        nexus.set_write_ordering(WriteOrdering::Barrier);
+        inject_no_memory(NEXUS_NAME, 1);
+        let started = Instant::now();
+        let (first, second) =
+            futures::join!(h.write_at(0, &buf), h.write_at(4096, &buf));
+        first.unwrap();
+        second.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert_eq!(nexus.metrics().no_memory, 2);
+        nexus.set_write_ordering(WriteOrdering::Unordered);
+
+        // the IO is failed with a retriable status instead
+        nexus.set_no_memory_policy(NoMemoryPolicy::Busy);
+        inject_no_memory(NEXUS_NAME, 1);
+        assert!(h.write_at(0, &buf).await.is_err());
+        assert_eq!(nexus.metrics().no_memory, 3);
+        h.write_at(0, &buf).await.unwrap();
+        assert_eq!(nexus.metrics().no_memory, 3);
+
+        // the IO is requeued until it goes through, the backoff doubles every
+        // time it runs out of memory again: 50 + 100 + 200ms
+        nexus.set_no_memory_policy(NoMemoryPolicy::Backoff(NoMemoryBackoff {
+            initial: Duration::from_millis(50),
+            max: Duration::from_secs(1),
+            max_queued: 1,
+        }));
+        inject_no_memory(NEXUS_NAME, 3);
+        let started = Instant::now();
+        h.write_at(0, &buf).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(350));
+        assert_eq!(nexus.metrics().no_memory, 6);
+        assert_eq!(nexus.metrics().no_memory_requeued, 3);
+
+        // the backoff does not grow beyond its maximum: 50 + 50 + 50ms
+        nexus.set_no_memory_policy(NoMemoryPolicy::Backoff(NoMemoryBackoff {
+            initial: Duration::from_millis(50),
+            max: Duration::from_millis(50),
+            max_queued: 1,
+        }));
+        inject_no_memory(NEXUS_NAME, 3);
+        let started = Instant::now();
+        h.write_at(0, &buf).await.unwrap();
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(150));
+        assert!(elapsed < Duration::from_millis(350));
+        assert_eq!(nexus.metrics().no_memory_requeued, 6);
+
+        // the IO beyond the queue depth is completed with NoMemory instead,
+        // and resubmitted once the requeued write completes
+        inject_no_memory(NEXUS_NAME, 2);
+        let (first, second) =
+            futures::join!(h.write_at(0, &buf), h.write_at(4096, &buf));
+        first.unwrap();
+        second.unwrap();
+        assert_eq!(nexus.metrics().no_memory, 11);
+        assert_eq!(nexus.metrics().no_memory_requeued, 7);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
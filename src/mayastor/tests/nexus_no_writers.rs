@@ -0,0 +1,85 @@
+use nix::errno::Errno;
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_event,
+        nexus_lookup,
+        NexusEvent,
+        NexusStatus,
+        SubmitErrorCount,
+    },
+    core::{BdevHandle, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "no_writers_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_no_writers() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // offlining the only child leaves the nexus without writers
+        let status = nexus.offline_child(CHILD_1).await.unwrap();
+        assert_eq!(status, NexusStatus::Faulted);
+        let mut events = nexus_event::subscribe();
+
+        // writes and flushes fail cleanly rather than succeed silently
+        assert!(h.write_at(0, &buf).await.is_err());
+        assert!(h.flush().await.is_err());
+        assert_eq!(nexus.metrics().no_writers, 2);
+
+        // as do reads, and the failures are accounted by errno
+        assert!(h.read_at(0, &mut buf).await.is_err());
+        let enodev = Errno::ENODEV as i32;
+        assert_eq!(
+            nexus.metrics().submit_errors,
+            vec![
+                SubmitErrorCount {
+                    io_type: IoType::Read,
+                    errno: enodev,
+                    count: 1,
+                },
+                SubmitErrorCount {
+                    io_type: IoType::Write,
+                    errno: enodev,
+                    count: 1,
+                },
+                SubmitErrorCount {
+                    io_type: IoType::Flush,
+                    errno: enodev,
+                    count: 1,
+                },
+            ]
+        );
+
+        let mut failed = Vec::new();
+        while let Ok(Some(event)) = events.try_next() {
+            if let NexusEvent::NoWritableChildren {
+                nexus,
+                io_type,
+            } = event
+            {
+                assert_eq!(nexus, NEXUS_NAME);
+                failed.push(io_type);
+            }
+        }
+        assert_eq!(failed, vec![IoType::Write, IoType::Flush]);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use futures::channel::mpsc::UnboundedReceiver;
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_event,
+        nexus_lookup,
+        ChildState,
+        NexusEvent,
+        OnlineVerify,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "online_verify_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+async fn write(fill: u8) {
+    let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+    let mut buf = h.dma_malloc(4096).unwrap();
+    buf.fill(fill);
+    h.write_at(0, &buf).await.unwrap();
+}
+
+/// the rebuild_skipped flags of the online verified events received
+fn verified(events: &mut UnboundedReceiver<NexusEvent>) -> Vec<bool> {
+    let mut skipped = Vec::new();
+    while let Ok(Some(event)) = events.try_next() {
+        if let NexusEvent::OnlineVerified {
+            child,
+            rebuild_skipped,
+            ..
+        } = event
+        {
+            assert_eq!(child, CHILD_2);
+            skipped.push(rebuild_skipped);
+        }
+    }
+    skipped
+}
+
+#[tokio::test]
+async fn online_verify() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().online_verify, None);
+        nexus.set_online_verify(Some(OnlineVerify {
+            samples: 4,
+            segment_size: 4096,
+        }));
+        write(0xaa).await;
+        let mut events = nexus_event::subscribe();
+
+        // the child missed no writes while it was offline, so it is reopened
+        // right away
+        nexus.offline_child(CHILD_2).await.unwrap();
+        nexus.online_child(CHILD_2).await.unwrap();
+        assert_eq!(nexus.children[1].state(), ChildState::Open);
+        assert!(nexus.get_rebuild_state(CHILD_2).await.is_err());
+        assert_eq!(verified(&mut events), vec![true]);
+
+        // the nexus is written while the child is offline, so it is rebuilt
+        nexus.offline_child(CHILD_2).await.unwrap();
+        write(0xbb).await;
+        nexus.online_child(CHILD_2).await.unwrap();
+        assert_ne!(nexus.children[1].state(), ChildState::Open);
+        assert_eq!(verified(&mut events), vec![false]);
+    })
+    .await;
+
+    let mut rebuilt = false;
+    for _ in 0 .. 100 {
+        rebuilt = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.children[1].state() == ChildState::Open
+            })
+            .await;
+        if rebuilt {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(rebuilt);
+
+    ms.spawn(async {
+        nexus_lookup(NEXUS_NAME).unwrap().destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,50 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_event, nexus_lookup, NexusEvent},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "partial_submit_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn no_partial_submit_on_healthy_children() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let mut events = nexus_event::subscribe();
+
+        // writes submitted to all children are not reported
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xff);
+        for i in 0 .. 8 {
+            h.write_at(i * 4096, &buf).await.unwrap();
+        }
+        h.flush().await.unwrap();
+        assert_eq!(nexus.metrics().partial_submits, 0);
+
+        while let Ok(Some(event)) = events.try_next() {
+            assert!(
+                !matches!(event, NexusEvent::PartialSubmit { .. }),
+                "unexpected event {:?}",
+                event
+            );
+        }
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,58 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, PausePolicy},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "pause_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn io_during_pause() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xff);
+
+        // by default IO is held while paused and submitted on resume
+        assert_eq!(nexus.policy().pause, PausePolicy::default());
+        nexus.pause().await.unwrap();
+        let mut write = Box::pin(h.write_at(0, &buf));
+        assert!(futures::poll!(&mut write).is_pending());
+        nexus.resume().await.unwrap();
+        write.await.unwrap();
+
+        // IO beyond the queue depth is rejected
+        nexus.set_pause_policy(PausePolicy::Queue(0));
+        nexus.pause().await.unwrap();
+        assert!(h.write_at(0, &buf).await.is_err());
+        nexus.resume().await.unwrap();
+
+        // when rejecting, IO fails with a retriable status while paused and
+        // succeeds once resumed
+        nexus.set_pause_policy(PausePolicy::Reject);
+        nexus.pause().await.unwrap();
+        assert!(h.write_at(0, &buf).await.is_err());
+        assert!(h.read_at(0, &mut buf).await.is_err());
+        nexus.resume().await.unwrap();
+        h.write_at(0, &buf).await.unwrap();
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
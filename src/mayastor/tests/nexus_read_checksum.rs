@@ -0,0 +1,63 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, read_checksum, READ_CHECKSUM_MD_SIZE},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "read_checksum_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn read_returns_checksums() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert!(!nexus.read_checksum());
+        nexus.set_read_checksum(true).unwrap();
+        assert!(nexus.read_checksum());
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.as_mut_slice()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, b)| *b = (i / 512) as u8);
+        h.write_at(4096, &buf).await.unwrap();
+
+        // the metadata of every block holds the checksum of its data
+        let mut data = h.dma_malloc(4096).unwrap();
+        let mut md = h.dma_malloc(8 * READ_CHECKSUM_MD_SIZE as u64).unwrap();
+        h.read_with_md_at(4096, &mut data, &mut md).await.unwrap();
+        assert_eq!(data.as_slice(), buf.as_slice());
+        for (i, (block, md)) in data
+            .as_slice()
+            .chunks(512)
+            .zip(md.as_slice().chunks(READ_CHECKSUM_MD_SIZE as usize))
+            .enumerate()
+        {
+            assert_eq!(md, &read_checksum(8 + i as u64, block)[..]);
+        }
+
+        // a read without a metadata buffer is served as usual
+        h.read_at(4096, &mut data).await.unwrap();
+        assert_eq!(data.as_slice(), buf.as_slice());
+
+        drop(h);
+        nexus.set_read_checksum(false).unwrap();
+        assert!(!nexus.read_checksum());
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,84 @@
+use std::{sync::Arc, time::Duration};
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState, ReadVerifier, Reason},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "read_corruption_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+/// expects every block to be filled with 0xaa
+struct PatternVerifier;
+
+impl ReadVerifier for PatternVerifier {
+    fn verify(&self, _child: &str, _offset: u64, data: &[&[u8]]) -> bool {
+        data.iter().all(|d| d.iter().all(|b| *b == 0xaa))
+    }
+}
+
+#[tokio::test]
+async fn read_corruption_faults_child() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus.set_read_verifier(Some(Arc::new(PatternVerifier)));
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // corrupt the data on the second child behind the back of the nexus
+        let offset = nexus.data_ent_offset * 512;
+        let child = BdevHandle::open("m1", true, false).unwrap();
+        let mut bad = child.dma_malloc(4096).unwrap();
+        bad.fill(0x55);
+        child.write_at(offset, &bad).await.unwrap();
+        drop(child);
+
+        // reads are spread over the children, the corrupted data is never
+        // returned
+        for _ in 0 .. 2 {
+            buf.fill(0);
+            h.read_at(0, &mut buf).await.unwrap();
+            assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        }
+    })
+    .await;
+
+    let mut faulted = false;
+    for _ in 0 .. 100 {
+        faulted = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.children[1].state()
+                    == ChildState::Faulted(Reason::DataCorruption)
+            })
+            .await;
+        if faulted {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+    }
+    assert!(faulted);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.children[0].state(), ChildState::Open);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,74 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_READ,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "read_failover_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/read_failover_disk1.img";
+static DISKNAME2: &str = "/tmp/read_failover_disk2.img";
+static ERROR_DEVICE: &str = "read_failover_error_device";
+static EE_ERROR_DEVICE: &str = "EE_read_failover_error_device";
+
+#[tokio::test]
+async fn read_failover() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE, DISKNAME1);
+        let children = vec![
+            format!("bdev:///{}", EE_ERROR_DEVICE),
+            format!("aio://{}?blk_size=512", DISKNAME2),
+        ];
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // reads are spread round robin, so one of them fails on the error
+        // device and is served by the other child
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_READ,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        for _ in 0 .. 2 {
+            buf.fill(0);
+            h.read_at(0, &mut buf).await.unwrap();
+            assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        }
+
+        // the read did not fail on all children, and the failure is still
+        // accounted on the child it failed on
+        let metrics = nexus.metrics();
+        assert_eq!(metrics.failed_over_reads, 1);
+        assert_eq!(metrics.all_failed, 0);
+        assert_eq!(metrics.failed, 0);
+        assert_eq!(nexus.children[0].read_errors(), 1);
+        assert_eq!(nexus.children[1].read_errors(), 0);
+        assert!(nexus.children.iter().all(|c| c.state() == ChildState::Open));
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
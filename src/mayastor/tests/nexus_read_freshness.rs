@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ReadFreshness},
+    core::{Bdev, BdevHandle, IoStatus, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "read_freshness_nexus";
+static NEXUS_SIZE: u64 = 120 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=128";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=128";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=128";
+
+async fn reads(name: &str) -> u64 {
+    Bdev::lookup_by_name(name)
+        .unwrap()
+        .stats()
+        .await
+        .unwrap()
+        .num_read_ops
+}
+
+#[tokio::test]
+async fn read_freshness() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus.add_child(CHILD_3, true).await.unwrap();
+        nexus.start_rebuild(CHILD_3).await.unwrap();
+    })
+    .await;
+
+    // keep the child being rebuilt for the duration of the test
+    let mut paused = false;
+    for _ in 0 .. 100 {
+        paused = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.pause_rebuild(CHILD_3).await.is_ok()
+            })
+            .await;
+        if paused {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(1)).await;
+    }
+    assert!(paused);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let reader = nexus.open_reader(ReadFreshness::InSync).unwrap();
+        let any = nexus.open_reader(ReadFreshness::Any).unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // the reads are served by the children in sync, never by the child
+        // being rebuilt
+        let before = reads("m2").await;
+        for _ in 0 .. 4 {
+            buf.fill(0);
+            assert_eq!(
+                reader.read_at(0, &mut buf).await.unwrap(),
+                IoStatus::Success
+            );
+            assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        }
+        assert_eq!(reads("m2").await, before);
+
+        // the children serving reads no longer receive writes, which leaves
+        // no child in sync
+        nexus
+            .set_child_io_flags(CHILD_1, true, false)
+            .await
+            .unwrap();
+        nexus
+            .set_child_io_flags(CHILD_2, true, false)
+            .await
+            .unwrap();
+
+        reader
+            .read_at(0, &mut buf)
+            .await
+            .expect_err("read from a child which is not in sync");
+        assert_eq!(any.read_at(0, &mut buf).await.unwrap(), IoStatus::Success);
+
+        // regular reads are not affected by the reader
+        h.read_at(0, &mut buf).await.unwrap();
+
+        drop(reader);
+        drop(any);
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,60 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, Error, Reason},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "read_from_child_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn read_from_child() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(8192).unwrap();
+        buf.fill(0xaa);
+        h.write_at(4096, &buf).await.unwrap();
+        drop(h);
+
+        // both children of the mirror hold the written data
+        let first = nexus.read_from_child(CHILD_1, 4096, 8192).await.unwrap();
+        let second = nexus.read_from_child(CHILD_2, 4096, 8192).await.unwrap();
+        assert_eq!(first.len(), 8192);
+        assert_eq!(first.as_slice(), second.as_slice());
+        assert!(first.as_slice().iter().all(|b| *b == 0xaa));
+
+        // the region before it was never written
+        let first = nexus.read_from_child(CHILD_1, 0, 4096).await.unwrap();
+        let second = nexus.read_from_child(CHILD_2, 0, 4096).await.unwrap();
+        assert_eq!(first.as_slice(), second.as_slice());
+        assert!(first.as_slice().iter().all(|b| *b != 0xaa));
+
+        // only open children of the nexus can be read
+        assert!(matches!(
+            nexus.read_from_child("malloc:///m2", 0, 4096).await,
+            Err(Error::ChildNotFound { .. })
+        ));
+        nexus.fault_child(CHILD_2, Reason::Rpc).await.unwrap();
+        assert!(matches!(
+            nexus.read_from_child(CHILD_2, 0, 4096).await,
+            Err(Error::ChildNotOpen { .. })
+        ));
+        assert!(nexus.read_from_child(CHILD_1, 0, 4096).await.is_ok());
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
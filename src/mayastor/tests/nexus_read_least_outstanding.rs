@@ -0,0 +1,146 @@
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use once_cell::sync::Lazy;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ReadPolicy},
+    core::{Bdev, BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::MayastorTest;
+
+static NEXUS_NAME: &str = "least_outstanding_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+/// number of reads and of writes submitted at once by the benchmark
+const DEPTH: u64 = 32;
+
+async fn reads(name: &str) -> u64 {
+    Bdev::lookup_by_name(name)
+        .unwrap()
+        .stats()
+        .await
+        .unwrap()
+        .num_read_ops
+}
+
+async fn create_nexus() {
+    nexus_create(
+        NEXUS_NAME,
+        NEXUS_SIZE,
+        None,
+        &[CHILD_1.to_string(), CHILD_2.to_string()],
+    )
+    .await
+    .unwrap();
+}
+
+fn setup() -> &'static MayastorTest<'static> {
+    static MAYASTOR: Lazy<MayastorTest<'static>> =
+        Lazy::new(|| MayastorTest::new(MayastorCliArgs::default()));
+    &MAYASTOR
+}
+
+#[tokio::test]
+async fn read_least_outstanding() {
+    let ms = setup();
+    ms.spawn(async {
+        create_nexus().await;
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().read, ReadPolicy::RoundRobin);
+        nexus.set_read_policy(ReadPolicy::LeastOutstanding);
+
+        // without outstanding IO the readers are rotated between
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        let before = (reads("m0").await, reads("m1").await);
+        for _ in 0 .. 10 {
+            h.read_at(0, &mut buf).await.unwrap();
+        }
+        assert_eq!(reads("m0").await - before.0, 5);
+        assert_eq!(reads("m1").await - before.1, 5);
+
+        // mixed reads and writes all complete, and leave no child IO behind
+        let mut wbuf = h.dma_malloc(4096).unwrap();
+        wbuf.fill(0xa5);
+        let mut rbufs = (0 .. DEPTH)
+            .map(|_| h.dma_malloc(4096).unwrap())
+            .collect::<Vec<_>>();
+        let writes =
+            join_all((0 .. DEPTH).map(|i| h.write_at(i * 4096, &wbuf)));
+        let reads = join_all(
+            rbufs
+                .iter_mut()
+                .enumerate()
+                .map(|(i, buf)| h.read_at((DEPTH + i as u64) * 4096, buf)),
+        );
+        let (writes, reads) = futures::join!(writes, reads);
+        writes
+            .into_iter()
+            .for_each(|r| assert_eq!(r.unwrap(), 4096));
+        reads.into_iter().for_each(|r| assert_eq!(r.unwrap(), 4096));
+
+        for dump in nexus.dump_channels().await {
+            for child in dump.readers.iter().chain(dump.writers.iter()) {
+                assert_eq!(child.outstanding, 0);
+            }
+        }
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
+
+/// p99 of the latency of reads submitted along with as many writes
+async fn read_p99(h: &BdevHandle) -> Duration {
+    let wbuf = h.dma_malloc(4096).unwrap();
+    let mut rbufs = (0 .. DEPTH)
+        .map(|_| h.dma_malloc(4096).unwrap())
+        .collect::<Vec<_>>();
+    let mut latencies = Vec::new();
+    for _ in 0 .. 100 {
+        let writes =
+            join_all((0 .. DEPTH).map(|i| h.write_at(i * 4096, &wbuf)));
+        let reads = join_all(rbufs.iter_mut().enumerate().map(|(i, buf)| {
+            let start = Instant::now();
+            async move {
+                h.read_at((DEPTH + i as u64) * 4096, buf).await.unwrap();
+                start.elapsed()
+            }
+        }));
+        let (_, reads) = futures::join!(writes, reads);
+        latencies.extend(reads);
+    }
+    latencies.sort();
+    latencies[latencies.len() * 99 / 100]
+}
+
+/// compares the read tail latency of the read policies under a mixed read
+/// and write load, run with `--ignored` to benchmark
+#[tokio::test]
+#[ignore]
+async fn read_least_outstanding_benchmark() {
+    let ms = setup();
+    ms.spawn(async {
+        create_nexus().await;
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+
+        let round_robin = read_p99(&h).await;
+        nexus.set_read_policy(ReadPolicy::LeastOutstanding);
+        let least_outstanding = read_p99(&h).await;
+        println!(
+            "read p99 round robin: {:?} least outstanding: {:?}",
+            round_robin, least_outstanding
+        );
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
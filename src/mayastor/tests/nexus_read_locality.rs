@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_lookup,
+        ChildLocality,
+        ChildState,
+        ReadPolicy,
+        Reason,
+    },
+    core::{Bdev, BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "read_locality_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+async fn reads(name: &str) -> u64 {
+    Bdev::lookup_by_name(name)
+        .unwrap()
+        .stats()
+        .await
+        .unwrap()
+        .num_read_ops
+}
+
+#[tokio::test]
+async fn read_locality() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus
+            .add_child_with_locality(CHILD_3, false, ChildLocality::Remote)
+            .await
+            .unwrap();
+        assert_eq!(
+            nexus.child_lookup("m2").unwrap().locality(),
+            ChildLocality::Remote
+        );
+        assert_eq!(
+            nexus.child_lookup("m0").unwrap().locality(),
+            ChildLocality::Local
+        );
+    })
+    .await;
+
+    let mut rebuilt = false;
+    for _ in 0 .. 100 {
+        rebuilt = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.child_lookup("m2").unwrap().state() == ChildState::Open
+            })
+            .await;
+        if rebuilt {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(rebuilt);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus.set_read_policy(ReadPolicy::PreferLocal);
+
+        let h = BdevHandle::open(NEXUS_NAME, false, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+
+        // reads are spread over the local children only
+        let (m0, m1, m2) =
+            (reads("m0").await, reads("m1").await, reads("m2").await);
+        for _ in 0 .. 10 {
+            h.read_at(0, &mut buf).await.unwrap();
+        }
+        assert_eq!(reads("m0").await, m0 + 5);
+        assert_eq!(reads("m1").await, m1 + 5);
+        assert_eq!(reads("m2").await, m2);
+
+        // the remote child does not serve reads while a local one is healthy
+        nexus.fault_child(CHILD_1, Reason::Rpc).await.unwrap();
+        let (m1, m2) = (reads("m1").await, reads("m2").await);
+        for _ in 0 .. 10 {
+            h.read_at(0, &mut buf).await.unwrap();
+        }
+        assert_eq!(reads("m1").await, m1 + 10);
+        assert_eq!(reads("m2").await, m2);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
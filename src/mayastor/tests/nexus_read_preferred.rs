@@ -0,0 +1,91 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ReadPolicy},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "read_preferred_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+const READS: u64 = 30;
+
+/// issue a batch of reads and return the number served by every child
+async fn reads() -> Vec<u64> {
+    let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+    let before = nexus.child_io_stats().await;
+    let h = BdevHandle::open(NEXUS_NAME, false, false).unwrap();
+    let mut buf = h.dma_malloc(4096).unwrap();
+    for i in 0 .. READS {
+        h.read_at(i * 4096, &mut buf).await.unwrap();
+    }
+    drop(h);
+    let after = nexus.child_io_stats().await;
+    before
+        .iter()
+        .zip(after.iter())
+        .map(|(b, a)| a.reads - b.reads)
+        .collect()
+}
+
+#[tokio::test]
+async fn read_preferred() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[
+                CHILD_1.to_string(),
+                CHILD_2.to_string(),
+                CHILD_3.to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // the reads are rotated between all children by default
+        assert_eq!(reads().await, vec![READS / 3; 3]);
+
+        // with the preferred policy but no preferred child, the reads are
+        // still rotated between all children
+        nexus.set_read_policy(ReadPolicy::Preferred);
+        assert_eq!(reads().await, vec![READS / 3; 3]);
+
+        // the preferred child serves all reads
+        assert!(nexus.set_preferred_reader(Some("other")).await.is_err());
+        nexus.set_preferred_reader(Some(CHILD_2)).await.unwrap();
+        assert_eq!(nexus.policy().preferred_reader, Some(CHILD_2.to_string()));
+        assert_eq!(reads().await, vec![0, READS, 0]);
+
+        // a preferred child which serves no reads leaves them to the others
+        nexus
+            .set_child_read_weight(CHILD_2, 0, false)
+            .await
+            .unwrap();
+        let served = reads().await;
+        assert_eq!(served[1], 0);
+        assert_eq!(served[0] + served[2], READS);
+        nexus
+            .set_child_read_weight(CHILD_2, 100, false)
+            .await
+            .unwrap();
+
+        // the preferred child is kept when the channels are reconfigured
+        nexus.offline_child(CHILD_3).await.unwrap();
+        assert_eq!(reads().await, vec![0, READS, 0]);
+
+        // once the preference is cleared, the reads are rotated again
+        nexus.set_preferred_reader(None).await.unwrap();
+        nexus.set_read_policy(ReadPolicy::RoundRobin);
+        assert_eq!(reads().await, vec![READS / 2, READS / 2, 0]);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,81 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_READ,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "read_repair_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/read_repair_disk1.img";
+static DISKNAME2: &str = "/tmp/read_repair_disk2.img";
+static ERROR_DEVICE1: &str = "read_repair_error_device1";
+static ERROR_DEVICE2: &str = "read_repair_error_device2";
+static EE_ERROR_DEVICE1: &str = "EE_read_repair_error_device1";
+static EE_ERROR_DEVICE2: &str = "EE_read_repair_error_device2";
+
+#[tokio::test]
+async fn read_repair_from_other_child() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE1, DISKNAME1);
+        create_error_bdev(ERROR_DEVICE2, DISKNAME2);
+        let children = vec![
+            format!("bdev:///{}", EE_ERROR_DEVICE1),
+            format!("bdev:///{}", EE_ERROR_DEVICE2),
+        ];
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(16 * 1024).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // the read fails on one child and then on the other, which it is
+        // repaired from. On the child with the second error it fails the
+        // first segment of the repair when that child is tried first, which
+        // must then be read from the other child; otherwise it is left to
+        // fail a later read, which the other child then serves.
+        inject_error(
+            EE_ERROR_DEVICE1,
+            SPDK_BDEV_IO_TYPE_READ,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        inject_error(
+            EE_ERROR_DEVICE2,
+            SPDK_BDEV_IO_TYPE_READ,
+            VBDEV_IO_FAILURE,
+            2,
+        );
+
+        for _ in 0 .. 2 {
+            buf.fill(0);
+            h.read_at(0, &mut buf).await.unwrap();
+            assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        }
+
+        // a media error on a read does not take the child out
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert!(nexus.children.iter().all(|c| c.state() == ChildState::Open));
+        assert_eq!(nexus.metrics().all_failed, 1);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
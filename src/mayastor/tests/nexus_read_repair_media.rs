@@ -0,0 +1,112 @@
+#![cfg(feature = "fault-injection")]
+
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_lookup,
+        replay_failure_trace,
+        FailureTrace,
+        FailureTraceEntry,
+    },
+    core::{BdevHandle, IoStatus, IoType, MayastorCliArgs, NvmeStatusCode},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_READ,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "read_repair_media_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/read_repair_media_disk1.img";
+static DISKNAME2: &str = "/tmp/read_repair_media_disk2.img";
+static ERROR_DEVICE: &str = "read_repair_media_error_device";
+static EE_ERROR_DEVICE: &str = "EE_read_repair_media_error_device";
+static EE_CHILD: &str = "bdev:///EE_read_repair_media_error_device";
+
+/// the status of a read which failed with an unrecovered read error
+const UNRECOVERED_READ_ERROR: NvmeStatusCode = NvmeStatusCode {
+    sct: 0x02,
+    sc: 0x81,
+};
+
+#[tokio::test]
+async fn read_repair_media_error() {
+    common::truncate_file(DISKNAME1, 16 * 1024);
+    common::truncate_file(DISKNAME2, 16 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE, DISKNAME1);
+        let child2 = format!("aio://{}?blk_size=512", DISKNAME2);
+        let children = vec![EE_CHILD.to_string(), child2.clone()];
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+
+        // the first child holds stale data, and is the only one read from
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+        nexus
+            .set_child_io_flags(EE_CHILD, true, false)
+            .await
+            .unwrap();
+        buf.fill(0xbb);
+        h.write_at(0, &buf).await.unwrap();
+        nexus
+            .set_child_io_flags(EE_CHILD, true, true)
+            .await
+            .unwrap();
+        nexus
+            .set_child_io_flags(&child2, false, true)
+            .await
+            .unwrap();
+
+        // the read fails on the first child with a media error, as does the
+        // read of the repair, which is therefore served by the other child
+        // and rewrites the blocks of the first child
+        replay_failure_trace(&FailureTrace {
+            entries: vec![FailureTraceEntry {
+                at: Duration::default(),
+                child: EE_CHILD.to_string(),
+                io_type: IoType::Read,
+                status: IoStatus::NvmeError,
+                nvme_status: UNRECOVERED_READ_ERROR,
+                latency: Duration::default(),
+            }],
+        });
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_READ,
+            VBDEV_IO_FAILURE,
+            2,
+        );
+        assert!(!UNRECOVERED_READ_ERROR.do_not_retry());
+
+        buf.fill(0);
+        h.read_at(0, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xbb));
+        assert_eq!(nexus.metrics().all_failed, 1);
+
+        // the repaired blocks are served by the first child
+        buf.fill(0);
+        h.read_at(0, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xbb));
+        assert_eq!(nexus.metrics().all_failed, 1);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_event, nexus_lookup, ChildState, NexusEvent},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "read_sampling_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn read_sampling_repairs_divergent_child() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[
+                CHILD_1.to_string(),
+                CHILD_2.to_string(),
+                CHILD_3.to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().read_sample_interval, 0);
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+
+        // reads are not sampled by default
+        for _ in 0 .. 3 {
+            h.read_at(0, &mut buf).await.unwrap();
+        }
+        assert_eq!(nexus.metrics().sampled_reads, 0);
+
+        // corrupt the data behind the back of the nexus on one child
+        let ch = BdevHandle::open("m2", true, false).unwrap();
+        let mut bad = ch.dma_malloc(4096).unwrap();
+        bad.fill(0x55);
+        ch.write_at(nexus.data_ent_offset * 512, &bad)
+            .await
+            .unwrap();
+        drop(ch);
+
+        // every read is sampled, the reads are spread over all children such
+        // that one is served by the corrupted child, and all return the data
+        // of the majority
+        nexus.set_read_sample_interval(1);
+        let mut events = nexus_event::subscribe();
+        for _ in 0 .. 3 {
+            h.read_at(0, &mut buf).await.unwrap();
+            assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+        }
+        let metrics = nexus.metrics();
+        assert_eq!(metrics.sampled_reads, 3);
+        assert_eq!(metrics.sampled_mismatches, 1);
+
+        let mut mismatch = None;
+        while let Ok(Some(event)) = events.try_next() {
+            if let NexusEvent::ReadMismatch {
+                children,
+                repaired,
+                ..
+            } = event
+            {
+                mismatch = Some((children, repaired));
+            }
+        }
+        assert_eq!(mismatch, Some((vec!["m2".to_string()], true)));
+        drop(h);
+    })
+    .await;
+
+    // the divergent child is retired to be rebuilt
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.children[2].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
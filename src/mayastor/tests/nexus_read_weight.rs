@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_lookup,
+        ReadWeight,
+        TracePoint,
+        READ_WEIGHT_DEFAULT,
+    },
+    core::{BdevHandle, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "read_weight_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn read_weight() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[
+                CHILD_1.to_string(),
+                CHILD_2.to_string(),
+                CHILD_3.to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert!(nexus
+            .read_weights()
+            .iter()
+            .all(|w| w.weight == READ_WEIGHT_DEFAULT && !w.pinned));
+        assert!(nexus.set_child_read_weight("nope", 0, false).await.is_err());
+
+        // the second child serves no reads, the first one is pinned
+        nexus
+            .set_child_read_weight(CHILD_2, 0, false)
+            .await
+            .unwrap();
+        nexus
+            .set_child_read_weight(CHILD_1, 50, true)
+            .await
+            .unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        let mut events = nexus.start_io_trace(Duration::from_secs(60)).unwrap();
+        h.write_at(0, &buf).await.unwrap();
+        for _ in 0 .. 30 {
+            h.read_at(0, &mut buf).await.unwrap();
+        }
+        assert!(nexus.stop_io_trace());
+
+        let mut writes = Vec::new();
+        let mut reads = Vec::new();
+        while let Ok(Some(event)) = events.try_next() {
+            if let TracePoint::Dispatch {
+                child,
+            } = event.point
+            {
+                match event.io_type {
+                    IoType::Write => writes.push(child),
+                    IoType::Read => reads.push(child),
+                    _ => {}
+                }
+            }
+        }
+
+        // writes continue to all children, reads avoid the second one and
+        // are spread over the others by their weight
+        writes.sort();
+        assert_eq!(writes, vec![CHILD_1, CHILD_2, CHILD_3]);
+        assert_eq!(reads.len(), 30);
+        assert!(!reads.iter().any(|c| c == CHILD_2));
+        assert_eq!(reads.iter().filter(|c| *c == CHILD_1).count(), 10);
+        assert_eq!(reads.iter().filter(|c| *c == CHILD_3).count(), 20);
+        drop(h);
+
+        // a reconfiguration resets the weights which are not pinned
+        nexus.offline_child(CHILD_3).await.unwrap();
+        assert_eq!(
+            nexus.read_weights(),
+            vec![
+                ReadWeight {
+                    child: CHILD_1.to_string(),
+                    weight: 50,
+                    pinned: true,
+                },
+                ReadWeight {
+                    child: CHILD_2.to_string(),
+                    weight: READ_WEIGHT_DEFAULT,
+                    pinned: false,
+                },
+                ReadWeight {
+                    child: CHILD_3.to_string(),
+                    weight: READ_WEIGHT_DEFAULT,
+                    pinned: false,
+                },
+            ]
+        );
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,74 @@
+use futures::channel::oneshot;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{BdevHandle, MayastorCliArgs, Reactors},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "rebalance_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn rebalance_channels() {
+    let args = MayastorCliArgs {
+        reactor_mask: "0x3".into(),
+        ..Default::default()
+    };
+    let ms = common::MayastorTest::new(args);
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // hold a channel on both cores
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let (opened_s, opened_r) = oneshot::channel();
+        let (done_s, done_r) = oneshot::channel::<()>();
+        Reactors::get_by_core(1).unwrap().send_future(async move {
+            let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+            opened_s.send(()).unwrap();
+            done_r.await.unwrap();
+            drop(h);
+        });
+        opened_r.await.unwrap();
+        assert_eq!(nexus.dump_channels().await.len(), 2);
+
+        // consistent channels are left as they are
+        assert_eq!(nexus.rebalance().await, 0);
+
+        // the channels no longer read from an open child
+        nexus
+            .set_child_io_flags(CHILD_2, false, true)
+            .await
+            .unwrap();
+        for dump in nexus.dump_channels().await {
+            assert_eq!(dump.readers.len(), 1);
+            assert_eq!(dump.writers.len(), 2);
+        }
+
+        // rebalancing restores the view of the children on every core
+        let epoch = nexus.epoch();
+        assert_eq!(nexus.rebalance().await, 2);
+        assert!(nexus.epoch() > epoch);
+        for dump in nexus.dump_channels().await {
+            assert_eq!(dump.readers.len(), 2);
+            assert_eq!(dump.writers.len(), 2);
+        }
+        assert_eq!(nexus.rebalance().await, 0);
+
+        done_s.send(()).unwrap();
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "rebuild_completion_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn rebuild_completion() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    let (completion, epoch) = ms
+        .spawn(async {
+            nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+                .await
+                .unwrap();
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+            // the future is obtained before the rebuild is started
+            let completion = nexus.rebuild_completion(CHILD_2);
+            let epoch = nexus.epoch();
+            nexus.add_child(CHILD_2, false).await.unwrap();
+            (completion, epoch)
+        })
+        .await;
+
+    // the future resolves without polling the state of the child
+    let completion = tokio::time::timeout(Duration::from_secs(10), completion)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(completion.child, CHILD_2);
+    assert!(completion.success);
+    assert!(completion.epoch > epoch);
+
+    ms.spawn(async move {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(completion.bytes, nexus.size());
+        assert_eq!(nexus.child_lookup("m1").unwrap().state(), ChildState::Open);
+        assert!(completion.epoch <= nexus.epoch());
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
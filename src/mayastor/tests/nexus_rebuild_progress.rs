@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState, RebuildProgress},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "rebuild_progress_nexus";
+static NEXUS_SIZE: u64 = 120 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=128";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=128";
+
+async fn progress(ms: &common::MayastorTest<'_>) -> Option<RebuildProgress> {
+    ms.spawn(async {
+        nexus_lookup(NEXUS_NAME).unwrap().rebuild_progress(CHILD_2)
+    })
+    .await
+}
+
+#[tokio::test]
+async fn rebuild_progress() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+            .await
+            .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert!(nexus.rebuild_progress(CHILD_2).is_none());
+        nexus.add_child(CHILD_2, true).await.unwrap();
+        nexus.start_rebuild(CHILD_2).await.unwrap();
+    })
+    .await;
+
+    // pause the rebuild part way through
+    let mut paused = false;
+    for _ in 0 .. 100 {
+        paused = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.pause_rebuild(CHILD_2).await.is_ok()
+            })
+            .await;
+        if paused {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(1)).await;
+    }
+    assert!(paused);
+
+    let mut samples = Vec::new();
+    for _ in 0 .. 10 {
+        samples.push(progress(&ms).await.unwrap());
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+    }
+    let partial = samples.last().unwrap().clone();
+    assert_eq!(partial.child, CHILD_2);
+    assert_eq!(partial.blocks_total, NEXUS_SIZE / 512);
+    assert!(partial.percent < 100);
+
+    // the progress is reported in the status of the nexus as well
+    ms.spawn(async move {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let child = &nexus.status_snapshot().await.children[1];
+        assert_eq!(child.rebuild_progress, Some(partial.percent));
+        nexus.resume_rebuild(CHILD_2).await.unwrap();
+    })
+    .await;
+
+    // sample the progress until the rebuild is done
+    let mut done = false;
+    for _ in 0 .. 1000 {
+        match progress(&ms).await {
+            Some(p) => samples.push(p),
+            None => {
+                done = true;
+                break;
+            }
+        }
+        tokio::time::delay_for(Duration::from_millis(1)).await;
+    }
+    assert!(done);
+
+    assert!(samples.windows(2).all(|w| {
+        w[0].blocks_copied <= w[1].blocks_copied && w[0].percent <= w[1].percent
+    }));
+    assert!(samples
+        .iter()
+        .all(|p| p.blocks_copied <= p.blocks_total && p.percent <= 100));
+    let last = samples.last().unwrap();
+    assert!(last.blocks_copied > 0);
+    assert!(last.remaining.is_some());
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.child_lookup("m1").unwrap().state(), ChildState::Open);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
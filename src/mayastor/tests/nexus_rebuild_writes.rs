@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState},
+    core::{Bdev, BdevHandle, MayastorCliArgs},
+    rebuild::{ClientOperations, RebuildJob},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "rebuild_writes_nexus";
+static NEXUS_SIZE: u64 = 120 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=128";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=128";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=128";
+
+async fn dst_writes() -> u64 {
+    Bdev::lookup_by_name("m2")
+        .unwrap()
+        .stats()
+        .await
+        .unwrap()
+        .num_write_ops
+}
+
+#[tokio::test]
+async fn rebuild_writes_below_cursor() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus.add_child(CHILD_3, true).await.unwrap();
+        nexus.start_rebuild(CHILD_3).await.unwrap();
+    })
+    .await;
+
+    // the job can only be paused once it is running
+    let mut paused = false;
+    for _ in 0 .. 100 {
+        paused = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.pause_rebuild(CHILD_3).await.is_ok()
+            })
+            .await;
+        if paused {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(1)).await;
+    }
+    assert!(paused);
+
+    // wait for the copy tasks in flight to drain
+    paused = false;
+    for _ in 0 .. 100 {
+        paused = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.get_rebuild_state(CHILD_3).await.unwrap().state
+                    == "paused"
+            })
+            .await;
+        if paused {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+    }
+    assert!(paused);
+
+    ms.spawn(async {
+        let stats = RebuildJob::lookup(CHILD_3).unwrap().as_client().stats();
+        assert!(stats.blocks_recovered < stats.blocks_total);
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(512).unwrap();
+
+        // the start of the nexus has been rebuilt already
+        buf.fill(0xaa);
+        let before = dst_writes().await;
+        h.write_at(0, &buf).await.unwrap();
+        assert_eq!(dst_writes().await, before + 1);
+
+        // the end of the nexus is yet to be rebuilt, so the write is left to
+        // the rebuild
+        buf.fill(0xbb);
+        let before = dst_writes().await;
+        h.write_at(NEXUS_SIZE - 512, &buf).await.unwrap();
+        assert_eq!(dst_writes().await, before);
+
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus.resume_rebuild(CHILD_3).await.unwrap();
+    })
+    .await;
+
+    let mut rebuilt = false;
+    for _ in 0 .. 100 {
+        rebuilt = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.child_lookup("m2").unwrap().state() == ChildState::Open
+            })
+            .await;
+        if rebuilt {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(rebuilt);
+
+    // the rebuild has copied the skipped write to the child
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let offset = nexus.data_ent_offset * 512 + NEXUS_SIZE - 512;
+
+        let h = BdevHandle::open("m2", false, false).unwrap();
+        let mut buf = h.dma_malloc(512).unwrap();
+        h.read_at(offset, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xbb));
+        drop(h);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
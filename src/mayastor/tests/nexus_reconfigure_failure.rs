@@ -0,0 +1,176 @@
+#![cfg(feature = "fault-injection")]
+
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        inject_reconfigure_failure,
+        nexus_create,
+        nexus_event,
+        nexus_lookup,
+        ChildState,
+        NexusEvent,
+        NexusStatus,
+        Reason,
+    },
+    core::{Bdev, BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_WRITE,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "reconfigure_failure_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/reconfigure_failure_disk1.img";
+static DISKNAME2: &str = "/tmp/reconfigure_failure_disk2.img";
+static DISKNAME3: &str = "/tmp/reconfigure_failure_disk3.img";
+static ERROR_DEVICE: &str = "reconfigure_failure_error_device";
+static EE_ERROR_DEVICE: &str = "EE_reconfigure_failure_error_device";
+
+#[tokio::test]
+async fn retire_with_failed_reconfigure() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+    common::truncate_file(DISKNAME3, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    let mut events = ms
+        .spawn(async {
+            create_error_bdev(ERROR_DEVICE, DISKNAME1);
+            let children = vec![
+                format!("bdev:///{}", EE_ERROR_DEVICE),
+                format!("aio://{}?blk_size=512", DISKNAME2),
+                format!("aio://{}?blk_size=512", DISKNAME3),
+            ];
+            nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+                .await
+                .unwrap();
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+            nexus.set_reconfigure_retries(1);
+
+            // the child fails a write, and the reconfiguration for its retire
+            // fails along with its retry
+            inject_reconfigure_failure(NEXUS_NAME, 2);
+            inject_error(
+                EE_ERROR_DEVICE,
+                SPDK_BDEV_IO_TYPE_WRITE,
+                VBDEV_IO_FAILURE,
+                1,
+            );
+            let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+            let mut buf = h.dma_malloc(4096).unwrap();
+            buf.fill(0xaa);
+            let events = nexus_event::subscribe();
+            h.write_at(0, &buf).await.unwrap();
+            events
+        })
+        .await;
+
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.metrics().retires_in_flight == 0
+                    && nexus.children[0].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async move {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // the child is kept faulted but not destroyed, as the channels may
+        // still use it, and the nexus needs attention
+        assert_eq!(
+            nexus.children[0].state(),
+            ChildState::Faulted(Reason::IoError)
+        );
+        assert!(Bdev::lookup_by_name(EE_ERROR_DEVICE).is_some());
+        assert_eq!(nexus.status(), NexusStatus::Degraded);
+        assert!(nexus.needs_attention());
+        assert_eq!(nexus.metrics().reconfigure_failures, 2);
+        let faulted = format!("bdev:///{}", EE_ERROR_DEVICE);
+        assert_eq!(nexus.stalled_retires(), vec![faulted.clone()]);
+        let mut failed = 0;
+        let mut stalled = 0;
+        while let Ok(Some(event)) = events.try_next() {
+            if event
+                == (NexusEvent::ReconfigureFailed {
+                    nexus: NEXUS_NAME.to_string(),
+                    event: "ChildFault".to_string(),
+                    status: -5,
+                })
+            {
+                failed += 1;
+            }
+            if event
+                == (NexusEvent::RetireStalled {
+                    nexus: NEXUS_NAME.to_string(),
+                    child: faulted.clone(),
+                })
+            {
+                stalled += 1;
+            }
+        }
+        assert_eq!(failed, 1);
+        assert_eq!(stalled, 1);
+
+        // IO is still served
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        h.write_at(0, &buf).await.unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+
+        // rebalancing the channels drops the faulted child from them and
+        // clears the mark
+        nexus.rebalance().await;
+        assert!(!nexus.needs_attention());
+        assert_eq!(nexus.stalled_retires(), vec![faulted.clone()]);
+        for dump in nexus.dump_channels().await {
+            assert_eq!(dump.writers.len(), 2);
+            assert!(dump.writers.iter().all(|c| c.bdev != EE_ERROR_DEVICE));
+        }
+
+        // a failure within the retries is recovered from
+        inject_reconfigure_failure(NEXUS_NAME, 1);
+        let child = format!("aio://{}?blk_size=512", DISKNAME3);
+        nexus.fault_child(&child, Reason::Rpc).await.unwrap();
+        assert!(!nexus.needs_attention());
+        assert_eq!(nexus.metrics().reconfigure_failures, 3);
+        for dump in nexus.dump_channels().await {
+            assert_eq!(dump.writers.len(), 1);
+        }
+        while let Ok(Some(event)) = events.try_next() {
+            assert!(!matches!(event, NexusEvent::ReconfigureFailed { .. }));
+        }
+
+        h.write_at(0, &buf).await.unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+        drop(h);
+
+        // removing the faulted child ends its stalled retire
+        nexus.remove_child(&faulted).await.unwrap();
+        assert!(nexus.stalled_retires().is_empty());
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[
+        DISKNAME1.to_string(),
+        DISKNAME2.to_string(),
+        DISKNAME3.to_string(),
+    ]);
+}
@@ -0,0 +1,181 @@
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState, FaultPolicy},
+    core::{BdevHandle, MayastorCliArgs, Reactors},
+};
+
+pub mod common;
+use common::{
+    error_bdev::{
+        create_error_bdev,
+        inject_error,
+        SPDK_BDEV_IO_TYPE_WRITE,
+        VBDEV_IO_FAILURE,
+    },
+    MayastorTest,
+};
+
+static NEXUS_NAME: &str = "retire_core_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+static DISKNAME1: &str = "/tmp/retire_core_disk1.img";
+static DISKNAME2: &str = "/tmp/retire_core_disk2.img";
+static ERROR_DEVICE: &str = "retire_core_error_device";
+
+/// number of writes whose latency is measured while a child is retired
+const WRITES: u32 = 2000;
+
+fn setup() -> &'static MayastorTest<'static> {
+    static MAYASTOR: Lazy<MayastorTest<'static>> = Lazy::new(|| {
+        MayastorTest::new(MayastorCliArgs {
+            reactor_mask: "0x3".into(),
+            ..Default::default()
+        })
+    });
+    &MAYASTOR
+}
+
+/// create a nexus whose first child fails the next write, on an error device
+/// over the first disk
+async fn create_nexus(name: &str, disks: &[String], error_device: &str) {
+    create_error_bdev(error_device, &disks[0]);
+    let children = vec![
+        format!("bdev:///EE_{}", error_device),
+        format!("aio://{}?blk_size=512", disks[1]),
+    ];
+    nexus_create(name, NEXUS_SIZE, None, &children)
+        .await
+        .unwrap();
+    let nexus = nexus_lookup(name).unwrap();
+    nexus.set_fault_policy(FaultPolicy {
+        read_errors: 1,
+        write_errors: 1,
+    });
+    inject_error(
+        &format!("EE_{}", error_device),
+        SPDK_BDEV_IO_TYPE_WRITE,
+        VBDEV_IO_FAILURE,
+        1,
+    );
+}
+
+/// wait until the first child of the nexus has been retired
+async fn wait_retired(ms: &MayastorTest<'static>, name: &'static str) {
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async move {
+                let nexus = nexus_lookup(name).unwrap();
+                nexus.metrics().retires_in_flight == 0
+                    && nexus.children[0].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+}
+
+#[tokio::test]
+async fn retire_core() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+    let disks = [DISKNAME1.to_string(), DISKNAME2.to_string()];
+
+    let ms = setup();
+    ms.spawn(async move {
+        create_nexus(NEXUS_NAME, &disks, ERROR_DEVICE).await;
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // children are retired on the master reactor by default
+        assert_eq!(nexus.policy().retire_core, None);
+        assert_eq!(nexus.retire_reactor().core(), Reactors::master().core());
+
+        // a core without a reactor falls back to the master reactor
+        nexus.set_retire_core(Some(7));
+        assert_eq!(nexus.retire_reactor().core(), Reactors::master().core());
+
+        // the child which failed the write is retired on the configured core
+        nexus.set_retire_core(Some(1));
+        assert_eq!(nexus.retire_reactor().core(), 1);
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+    })
+    .await;
+
+    wait_retired(ms, NEXUS_NAME).await;
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
+
+/// mean and maximum latency of `WRITES` writes to the nexus on the master
+/// reactor, the first of which fails on a child and retires it
+async fn write_latency(h: &BdevHandle) -> (Duration, Duration) {
+    let mut buf = h.dma_malloc(4096).unwrap();
+    buf.fill(0xaa);
+    let mut total = Duration::default();
+    let mut max = Duration::default();
+    for i in 0 .. WRITES {
+        let start = Instant::now();
+        h.write_at(u64::from(i % 1024) * 4096, &buf).await.unwrap();
+        let elapsed = start.elapsed();
+        total += elapsed;
+        max = max.max(elapsed);
+    }
+    (total / WRITES, max)
+}
+
+/// compares the latency of writes on the master reactor while a child is
+/// retired inline on the master reactor and on a dedicated core, run with
+/// `--ignored` to benchmark
+#[tokio::test]
+#[ignore]
+async fn retire_core_benchmark() {
+    let ms = setup();
+    for (i, core) in [None, Some(1)].iter().cloned().enumerate() {
+        let name: &'static str = Box::leak(
+            format!("retire_core_benchmark_nexus{}", i).into_boxed_str(),
+        );
+        let disks = [
+            format!("/tmp/retire_core_benchmark{}_disk1.img", i),
+            format!("/tmp/retire_core_benchmark{}_disk2.img", i),
+        ];
+        disks
+            .iter()
+            .for_each(|d| common::truncate_file(d, 64 * 1024));
+
+        let files = disks.clone();
+        ms.spawn(async move {
+            let error_device = format!("retire_core_benchmark{}", i);
+            create_nexus(name, &files, &error_device).await;
+            let nexus = nexus_lookup(name).unwrap();
+            nexus.set_retire_core(core);
+            let h = BdevHandle::open(name, true, false).unwrap();
+            let (mean, max) = write_latency(&h).await;
+            println!(
+                "retire core {:?}: write latency mean {:?} max {:?}",
+                core, mean, max
+            );
+        })
+        .await;
+
+        wait_retired(ms, name).await;
+        ms.spawn(async move {
+            nexus_lookup(name).unwrap().destroy().await.unwrap();
+        })
+        .await;
+        common::delete_file(&disks);
+    }
+}
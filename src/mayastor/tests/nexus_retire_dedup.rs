@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_WRITE,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "retire_dedup_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/retire_dedup_disk1.img";
+static DISKNAME2: &str = "/tmp/retire_dedup_disk2.img";
+static ERROR_DEVICE: &str = "retire_dedup_error_device";
+static EE_ERROR_DEVICE: &str = "EE_retire_dedup_error_device";
+
+#[tokio::test]
+async fn retire_dedup() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE, DISKNAME1);
+        let children = vec![
+            format!("bdev:///{}", EE_ERROR_DEVICE),
+            format!("aio://{}?blk_size=512", DISKNAME2),
+        ];
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_WRITE,
+            VBDEV_IO_FAILURE,
+            100,
+        );
+
+        // the writes fail on the same child before it has been retired
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let buf = h.dma_malloc(4096).unwrap();
+        let writes = (0 .. 4).map(|i| h.write_at(i * 4096, &buf));
+        for result in futures::future::join_all(writes).await {
+            result.unwrap();
+        }
+    })
+    .await;
+
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.metrics().retires_in_flight == 0
+                    && nexus.children[0].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        // only the first failed write dispatched a retire of the child
+        assert!(nexus.metrics().retires_deduplicated >= 1);
+        assert!(nexus.metrics().retires_deduplicated <= 3);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
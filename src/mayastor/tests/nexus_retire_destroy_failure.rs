@@ -0,0 +1,109 @@
+#![cfg(feature = "fault-injection")]
+
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        inject_destroy_failure,
+        nexus_create,
+        nexus_lookup,
+        ChildState,
+        NexusStatus,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_WRITE,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "retire_destroy_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/retire_destroy_disk1.img";
+static DISKNAME2: &str = "/tmp/retire_destroy_disk2.img";
+static ERROR_DEVICE: &str = "retire_destroy_error_device";
+static EE_ERROR_DEVICE: &str = "EE_retire_destroy_error_device";
+
+#[tokio::test]
+async fn retire_with_failed_destroy() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        create_error_bdev(ERROR_DEVICE, DISKNAME1);
+        let children = vec![
+            format!("bdev:///{}", EE_ERROR_DEVICE),
+            format!("aio://{}?blk_size=512", DISKNAME2),
+        ];
+        nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+            .await
+            .unwrap();
+
+        // the child fails a write, and then fails to be destroyed when it is
+        // retired for it
+        inject_destroy_failure(&children[0]);
+        inject_error(
+            EE_ERROR_DEVICE,
+            SPDK_BDEV_IO_TYPE_WRITE,
+            VBDEV_IO_FAILURE,
+            1,
+        );
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+    })
+    .await;
+
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.metrics().retires_in_flight == 0
+                    && nexus.children[0].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // the child is kept rather than half destroyed, and no core resumed
+        // IO with it
+        assert_eq!(nexus.children[0].state(), ChildState::DestroyFailed);
+        assert_eq!(nexus.status(), NexusStatus::Degraded);
+        for dump in nexus.dump_channels().await {
+            assert_eq!(dump.readers.len(), 1);
+            assert_eq!(dump.writers.len(), 1);
+            assert!(dump
+                .readers
+                .iter()
+                .chain(dump.writers.iter())
+                .all(|c| c.bdev != EE_ERROR_DEVICE));
+        }
+
+        // IO is served by the remaining child
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        h.write_at(0, &buf).await.unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+        drop(h);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
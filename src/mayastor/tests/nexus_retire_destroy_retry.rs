@@ -0,0 +1,117 @@
+#![cfg(feature = "fault-injection")]
+
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{
+        inject_destroy_failures,
+        nexus_create,
+        nexus_event,
+        nexus_lookup,
+        ChildState,
+        DestroyRetry,
+        NexusEvent,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+use common::error_bdev::{
+    create_error_bdev,
+    inject_error,
+    SPDK_BDEV_IO_TYPE_WRITE,
+    VBDEV_IO_FAILURE,
+};
+
+static NEXUS_NAME: &str = "retire_destroy_retry_nexus";
+static NEXUS_SIZE: u64 = 60 * 1024 * 1024;
+
+static DISKNAME1: &str = "/tmp/retire_destroy_retry_disk1.img";
+static DISKNAME2: &str = "/tmp/retire_destroy_retry_disk2.img";
+static ERROR_DEVICE: &str = "retire_destroy_retry_error_device";
+static EE_ERROR_DEVICE: &str = "EE_retire_destroy_retry_error_device";
+
+#[tokio::test]
+async fn retire_destroy_retry() {
+    common::truncate_file(DISKNAME1, 64 * 1024);
+    common::truncate_file(DISKNAME2, 64 * 1024);
+
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    let mut events = ms
+        .spawn(async {
+            create_error_bdev(ERROR_DEVICE, DISKNAME1);
+            let children = vec![
+                format!("bdev:///{}", EE_ERROR_DEVICE),
+                format!("aio://{}?blk_size=512", DISKNAME2),
+            ];
+            nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &children)
+                .await
+                .unwrap();
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+            assert_eq!(nexus.policy().destroy_retry.retries, 0);
+            nexus.set_destroy_retry(DestroyRetry {
+                retries: 2,
+                backoff: Duration::from_millis(10),
+            });
+            let events = nexus_event::subscribe();
+
+            // the child fails a write, and then every attempt to destroy it
+            // when it is retired for it
+            inject_destroy_failures(&children[0], 3);
+            inject_error(
+                EE_ERROR_DEVICE,
+                SPDK_BDEV_IO_TYPE_WRITE,
+                VBDEV_IO_FAILURE,
+                1,
+            );
+            let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+            let mut buf = h.dma_malloc(4096).unwrap();
+            buf.fill(0xaa);
+            h.write_at(0, &buf).await.unwrap();
+            events
+        })
+        .await;
+
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.metrics().retires_in_flight == 0
+                    && nexus.children[0].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async move {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // the destroy was retried before the child was given up on
+        assert_eq!(nexus.children[0].state(), ChildState::DestroyFailed);
+        assert_eq!(nexus.metrics().child_destroy_retries, 2);
+
+        let mut destroy_failed = false;
+        while let Ok(Some(event)) = events.try_next() {
+            if let NexusEvent::ChildStateChanged {
+                child,
+                state: ChildState::DestroyFailed,
+                ..
+            } = event
+            {
+                assert_eq!(child, format!("bdev:///{}", EE_ERROR_DEVICE));
+                destroy_failed = true;
+            }
+        }
+        assert!(destroy_failed);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+
+    common::delete_file(&[DISKNAME1.to_string(), DISKNAME2.to_string()]);
+}
@@ -0,0 +1,61 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState, SelfTestFailure},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "self_test_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_self_test() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+        let metadata = nexus.children[0].create_metadata().await.unwrap();
+
+        let report = nexus.self_test().await;
+        assert!(report.passed());
+        assert_eq!(report.children.len(), 2);
+        assert_eq!(report.children[0].child, CHILD_1);
+        assert_eq!(report.children[1].child, CHILD_2);
+
+        // the test does not touch the data of the nexus
+        buf.fill(0);
+        h.read_at(0, &mut buf).await.unwrap();
+        assert!(buf.as_slice().iter().all(|b| *b == 0xaa));
+
+        // the metadata of the children is left intact
+        let probed = nexus.children[0].get_metadata().await.unwrap();
+        assert_eq!(probed.index, metadata.index);
+
+        nexus.offline_child(CHILD_2).await.unwrap();
+        let report = nexus.self_test().await;
+        assert!(!report.passed());
+        assert!(report.children[0].passed());
+        assert_eq!(
+            report.children[1].failure,
+            Some(SelfTestFailure::NotOpen(ChildState::Closed))
+        );
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,59 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "snapshot_lease_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn snapshot_lease() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // only a single lease can be held at a time
+        let lease = nexus.snapshot_lease().await;
+        assert!(nexus.try_snapshot_lease().is_none());
+        drop(lease);
+        assert!(nexus.try_snapshot_lease().is_some());
+
+        // the snapshots wait for the lease held here, and run one after the
+        // other once it is released
+        let lease = nexus.snapshot_lease().await;
+        let n = &*nexus;
+        let started = std::cell::Cell::new(0);
+        futures::join!(
+            async {
+                started.set(started.get() + 1);
+                let _ = n.create_snapshot().await;
+            },
+            async {
+                started.set(started.get() + 1);
+                let _ = n.create_snapshot().await;
+            },
+            async {
+                // both snapshots have started and are waiting for the lease
+                assert_eq!(started.get(), 2);
+                assert!(n.try_snapshot_lease().is_none());
+                drop(lease);
+            }
+        );
+        assert!(nexus.try_snapshot_lease().is_some());
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
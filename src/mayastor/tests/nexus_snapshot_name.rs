@@ -0,0 +1,75 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, SnapshotClock},
+    core::MayastorCliArgs,
+    lvs::Lvol,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "snapshot_name_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+const T: u64 = 1_600_000_000;
+
+/// a clock which only moves when told to
+struct ManualClock(AtomicU64);
+
+impl SnapshotClock for ManualClock {
+    fn now(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[tokio::test]
+async fn snapshot_name() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let clock = Arc::new(ManualClock(AtomicU64::new(T)));
+        nexus.set_snapshot_clock(clock.clone());
+        assert_eq!(nexus.next_snapshot_time(), T);
+
+        // two snapshots within the same second are taken at distinct times,
+        // whether or not they succeed on the children
+        let mut names = Vec::new();
+        for _ in 0 .. 2 {
+            let time = nexus.next_snapshot_time();
+            names.push(Lvol::format_snapshot_name(NEXUS_NAME, time));
+            let _ = nexus.create_snapshot().await;
+        }
+        assert_eq!(
+            names,
+            vec![
+                Lvol::format_snapshot_name(NEXUS_NAME, T),
+                Lvol::format_snapshot_name(NEXUS_NAME, T + 1),
+            ]
+        );
+        assert_eq!(nexus.next_snapshot_time(), T + 2);
+
+        // a clock which went back does not reuse the times either
+        clock.0.store(T - 10, Ordering::Relaxed);
+        assert_eq!(nexus.next_snapshot_time(), T + 2);
+
+        // the clock is followed again once it passed the last snapshot
+        clock.0.store(T + 10, Ordering::Relaxed);
+        assert_eq!(nexus.next_snapshot_time(), T + 10);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
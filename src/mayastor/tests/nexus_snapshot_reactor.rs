@@ -0,0 +1,49 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{MayastorCliArgs, Reactors},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "snapshot_reactor_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn snapshot_reactor() {
+    let args = MayastorCliArgs {
+        reactor_mask: "0x3".into(),
+        ..Default::default()
+    };
+    let ms = common::MayastorTest::new(args);
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // snapshots run on the master reactor by default
+        assert_eq!(nexus.policy().snapshot_core, None);
+        assert_eq!(nexus.snapshot_reactor().core(), Reactors::master().core());
+
+        // a core without a reactor falls back to the master reactor
+        nexus.set_snapshot_core(Some(7));
+        assert_eq!(nexus.snapshot_reactor().core(), Reactors::master().core());
+
+        // the snapshot is dispatched to the configured reactor and its outcome
+        // is returned to the caller, which holds no lease afterwards
+        nexus.set_snapshot_core(Some(1));
+        assert_eq!(nexus.snapshot_reactor().core(), 1);
+        let _ = nexus.create_snapshot().await;
+        assert!(nexus.try_snapshot_lease().is_some());
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
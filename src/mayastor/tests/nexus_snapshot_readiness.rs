@@ -0,0 +1,69 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState, Reason, SnapshotBlocker},
+    core::MayastorCliArgs,
+    lvs::Lvs,
+};
+use rpc::mayastor::CreatePoolRequest;
+
+pub mod common;
+
+static NEXUS_NAME: &str = "snapshot_readiness_nexus";
+static NEXUS_SIZE: u64 = 16 * 1024 * 1024;
+static POOL_NAME: &str = "snapshot_readiness_pool";
+static UUID: &str = "00000000-76b6-4fcf-864d-1027d4038757";
+static CHILD_2: &str = "malloc:///m0?blk_size=512&size_mb=32";
+
+#[tokio::test]
+async fn snapshot_readiness() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        Lvs::create_or_import(CreatePoolRequest {
+            name: POOL_NAME.to_string(),
+            disks: vec!["malloc:///disk0?size_mb=64".into()],
+        })
+        .await
+        .unwrap();
+        let pool = Lvs::lookup(POOL_NAME).unwrap();
+        pool.create_lvol(UUID, 32 * 1024 * 1024, true)
+            .await
+            .unwrap();
+
+        let child_1 = format!("loopback:///{}", UUID);
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[child_1.clone(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // the local replica can be snapshot, the malloc device cannot
+        let readiness = nexus.can_snapshot();
+        assert!(readiness.nexus_handle);
+        assert!(!readiness.is_ready());
+        assert_eq!(readiness.children.len(), 2);
+        assert_eq!(readiness.children[0].child, child_1);
+        assert!(readiness.children[0].is_ready());
+        assert_eq!(readiness.children[1].child, CHILD_2);
+        assert_eq!(
+            readiness.children[1].blocker,
+            Some(SnapshotBlocker::Unsupported)
+        );
+
+        nexus.fault_child(CHILD_2, Reason::Rpc).await.unwrap();
+        let readiness = nexus.can_snapshot();
+        assert_eq!(
+            readiness.children[1].blocker,
+            Some(SnapshotBlocker::NotOpen(ChildState::Faulted(Reason::Rpc)))
+        );
+
+        // the dry-run has not created a snapshot
+        assert_eq!(pool.lvols().unwrap().count(), 1);
+
+        nexus.destroy().await.unwrap();
+        pool.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,132 @@
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_lookup,
+        ChildState,
+        NexusStatusSnapshot,
+        Readiness,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "status_snapshot_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+/// the figures of the snapshot which are derived from each other agree
+fn assert_consistent(s: &NexusStatusSnapshot) {
+    let open = s
+        .children
+        .iter()
+        .filter(|c| c.state == ChildState::Open)
+        .count();
+    let rebuilding = s
+        .children
+        .iter()
+        .filter(|c| c.rebuild_progress.is_some())
+        .count();
+    assert_eq!(s.health.open, open);
+    assert_eq!(s.health.rebuilding, rebuilding);
+    assert_eq!(
+        s.health.open
+            + s.health.faulted
+            + s.health.rebuilding
+            + s.health.missing
+            + s.health.other,
+        s.children.len()
+    );
+    if open == 0 {
+        assert_eq!(s.readiness, Readiness::NotReady);
+    }
+    assert!(s
+        .children
+        .iter()
+        .all(|c| c.rebuild_progress.map_or(true, |p| p <= 100)));
+    assert_eq!(
+        s.latency.samples,
+        s.children.iter().map(|c| c.latency.samples).sum::<u64>()
+    );
+    assert!(
+        s.metrics.completed + s.metrics.failed
+            <= s.metrics.reads + s.metrics.writes
+    );
+}
+
+#[tokio::test]
+async fn status_snapshot() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let s = nexus.status_snapshot().await;
+        assert_consistent(&s);
+        assert_eq!(s.nexus, NEXUS_NAME);
+        assert_eq!(s.readiness, Readiness::Ready);
+        assert_eq!(s.health.open, 2);
+        assert_eq!(s.latency.samples, 0);
+        assert_eq!(s.metrics.snapshots, 0);
+
+        // snapshots are taken while writes and reads are in flight, and while
+        // a child is added and rebuilt
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let writes = async {
+            let mut buf = h.dma_malloc(4096).unwrap();
+            buf.fill(0xaa);
+            for i in 0 .. 64 {
+                h.write_at(i * 4096, &buf).await.unwrap();
+            }
+        };
+        let reads = async {
+            let mut buf = h.dma_malloc(4096).unwrap();
+            let mut snapshots = Vec::new();
+            for i in 0 .. 64 {
+                h.read_at(i * 4096, &mut buf).await.unwrap();
+                snapshots.push(
+                    nexus_lookup(NEXUS_NAME).unwrap().status_snapshot().await,
+                );
+            }
+            snapshots
+        };
+        let add = async {
+            nexus_lookup(NEXUS_NAME)
+                .unwrap()
+                .add_child(CHILD_3, false)
+                .await
+                .unwrap();
+            nexus_lookup(NEXUS_NAME).unwrap().status_snapshot().await
+        };
+        let (_, snapshots, added) = futures::join!(writes, reads, add);
+        snapshots.iter().for_each(assert_consistent);
+        assert_consistent(&added);
+        assert_eq!(added.children.len(), 3);
+
+        // the counters only grow from one snapshot to the next
+        for pair in snapshots.windows(2) {
+            assert!(pair[0].metrics.reads <= pair[1].metrics.reads);
+            assert!(pair[0].metrics.writes <= pair[1].metrics.writes);
+            assert!(pair[0].time <= pair[1].time);
+        }
+
+        let last = nexus.status_snapshot().await;
+        assert_consistent(&last);
+        assert_eq!(last.metrics.reads, 64);
+        assert_eq!(last.metrics.writes, 64);
+        assert!(last.latency.samples >= 128);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,46 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "sync_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn nexus_sync() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(64 * 1024).unwrap();
+        buf.fill(0xaa);
+
+        // the sync runs while a write is in flight
+        let (write, synced) = futures::join!(h.write_at(0, &buf), nexus.sync());
+        write.unwrap();
+        let mut synced = synced.unwrap();
+        synced.sort();
+        assert_eq!(synced, vec![CHILD_1.to_string(), CHILD_2.to_string()]);
+
+        // only open children are synced
+        nexus.offline_child(CHILD_2).await.unwrap();
+        assert_eq!(nexus.sync().await.unwrap(), vec![CHILD_1.to_string()]);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
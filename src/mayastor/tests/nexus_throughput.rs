@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "throughput_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn throughput() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.metrics().throughput.write_bytes_per_sec, 0);
+
+        // the writes of a window of over a second are measured
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let buf = h.dma_malloc(64 * 1024).unwrap();
+        let started = Instant::now();
+        let mut written = 0;
+        while started.elapsed() < Duration::from_millis(1100) {
+            h.write_at(written % NEXUS_SIZE, &buf).await.unwrap();
+            written += buf.len();
+        }
+        let throughput = nexus.metrics().throughput;
+        assert!(throughput.write_bytes_per_sec > 0);
+        assert!(throughput.write_bytes_per_sec <= written);
+        assert_eq!(throughput.read_bytes_per_sec, 0);
+        assert_eq!(throughput.background_bytes_per_sec, 0);
+    })
+    .await;
+
+    // the throughput drops to zero once the nexus has been idle for a whole
+    // window
+    tokio::time::delay_for(Duration::from_millis(2200)).await;
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.metrics().throughput.write_bytes_per_sec, 0);
+
+        // reads are measured separately from writes
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(64 * 1024).unwrap();
+        let started = Instant::now();
+        let mut offset = 0;
+        while started.elapsed() < Duration::from_millis(1100) {
+            h.read_at(offset % NEXUS_SIZE, &mut buf).await.unwrap();
+            offset += buf.len();
+        }
+        let throughput = nexus.metrics().throughput;
+        assert!(throughput.read_bytes_per_sec > 0);
+        assert_eq!(throughput.write_bytes_per_sec, 0);
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
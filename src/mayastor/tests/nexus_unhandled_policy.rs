@@ -0,0 +1,58 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, UnhandledAction, UnhandledPolicy},
+    core::{Bdev, IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "unhandled_policy_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn unhandled_policy() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let bdev = Bdev::lookup_by_name(NEXUS_NAME).unwrap();
+
+        // by default IO the nexus does not handle is not supported
+        assert_eq!(nexus.policy().unhandled, UnhandledPolicy::default());
+        assert_eq!(
+            nexus.policy().unhandled.action(IoType::Compare),
+            UnhandledAction::Fail
+        );
+        assert!(!bdev.io_type_supported(IoType::Compare));
+
+        // actions which are not implemented for the IO type are refused
+        assert!(nexus
+            .set_unhandled_policy(UnhandledPolicy::new(vec![(
+                IoType::NvmeIo,
+                UnhandledAction::Broadcast
+            )]))
+            .is_err());
+        assert_eq!(nexus.policy().unhandled, UnhandledPolicy::default());
+
+        // an emulated compare is supported regardless of the children
+        nexus
+            .set_unhandled_policy(UnhandledPolicy::new(vec![(
+                IoType::Compare,
+                UnhandledAction::Emulate,
+            )]))
+            .unwrap();
+        assert!(bdev.io_type_supported(IoType::Compare));
+        assert!(nexus.metrics().unhandled.is_empty());
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
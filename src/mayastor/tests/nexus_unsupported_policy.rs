@@ -0,0 +1,58 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, UnsupportedAction, UnsupportedPolicy},
+    core::{IoType, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "unsupported_policy_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[test]
+fn unsupported_policy_actions() {
+    let policy = UnsupportedPolicy {
+        write_zeroes: UnsupportedAction::Emulate,
+        unmap: UnsupportedAction::Ignore,
+        flush: UnsupportedAction::Fail,
+    };
+    assert_eq!(
+        policy.action(IoType::WriteZeros),
+        UnsupportedAction::Emulate
+    );
+    assert_eq!(policy.action(IoType::Unmap), UnsupportedAction::Ignore);
+    assert_eq!(policy.action(IoType::Flush), UnsupportedAction::Fail);
+
+    // data carrying IO is never ignored
+    assert_eq!(policy.action(IoType::Write), UnsupportedAction::Fail);
+    assert_eq!(policy.action(IoType::Read), UnsupportedAction::Fail);
+}
+
+#[tokio::test]
+async fn set_unsupported_policy() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().unsupported, UnsupportedPolicy::default());
+
+        let policy = UnsupportedPolicy {
+            write_zeroes: UnsupportedAction::Emulate,
+            unmap: UnsupportedAction::Emulate,
+            flush: UnsupportedAction::Ignore,
+        };
+        nexus.set_unsupported_policy(policy.clone());
+        assert_eq!(nexus.policy().unsupported, policy);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
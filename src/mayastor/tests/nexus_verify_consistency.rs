@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, DivergentRange, VerifyOptions},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "verify_consistency_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn verify_consistency() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(64 * 1024).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+        drop(h);
+
+        let opts = VerifyOptions {
+            length: Some(64 * 1024),
+            segment_size: 4096,
+            ..Default::default()
+        };
+        let report = nexus.verify_consistency(opts.clone()).await.unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(report.end, 64 * 1024);
+        assert_eq!(report.children.len(), 2);
+
+        // corrupt a block of the second child behind the back of the nexus
+        let child = BdevHandle::open("m1", true, false).unwrap();
+        let mut bad = child.dma_malloc(512).unwrap();
+        bad.fill(0x55);
+        child
+            .write_at(nexus.data_ent_offset * 512 + 8192, &bad)
+            .await
+            .unwrap();
+        drop(child);
+
+        let report = nexus.verify_consistency(opts.clone()).await.unwrap();
+        assert_eq!(report.divergent_bytes, 4096);
+        assert_eq!(
+            report.ranges,
+            vec![DivergentRange {
+                offset: 8192,
+                length: 4096,
+                children: vec![CHILD_2.to_string()],
+            }]
+        );
+
+        // the check can be resumed where it stopped, and throttled
+        let report = nexus
+            .verify_consistency(VerifyOptions {
+                length: Some(8192),
+                segment_delay: Some(Duration::from_millis(1)),
+                ..opts.clone()
+            })
+            .await
+            .unwrap();
+        assert!(report.is_consistent());
+        let report = nexus
+            .verify_consistency(VerifyOptions {
+                offset: report.end,
+                length: Some(8192),
+                ..opts
+            })
+            .await
+            .unwrap();
+        assert_eq!(report.start, 8192);
+        assert_eq!(report.divergent_bytes, 4096);
+
+        assert_eq!(nexus.metrics().divergent_bytes, 8192);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,124 @@
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_event,
+        nexus_lookup,
+        NexusEvent,
+        VerifyOptions,
+    },
+    core::{BdevHandle, MayastorCliArgs},
+    lvs::{Lvol, Lvs},
+};
+use rpc::mayastor::CreatePoolRequest;
+
+pub mod common;
+
+static NEXUS_NAME: &str = "verify_snapshot_nexus";
+static NEXUS_SIZE: u64 = 8 * 1024 * 1024;
+static POOL_NAME: &str = "verify_snapshot_pool";
+static UUID1: &str = "00000000-76b6-4fcf-864d-1027d4038761";
+static UUID2: &str = "00000000-76b6-4fcf-864d-1027d4038762";
+
+/// snapshot the replica as a snapshot of the nexus taken at time t would
+async fn snapshot(pool: &Lvs, uuid: &str, t: u64) {
+    let lvol = pool.lvols().unwrap().find(|l| l.name() == uuid).unwrap();
+    lvol.snapshot(&Lvol::format_snapshot_name(uuid, t))
+        .await
+        .unwrap();
+}
+
+async fn write(fill: u8) {
+    let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+    let mut buf = h.dma_malloc(4096).unwrap();
+    buf.fill(fill);
+    h.write_at(8192, &buf).await.unwrap();
+}
+
+#[tokio::test]
+async fn verify_snapshot() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        Lvs::create_or_import(CreatePoolRequest {
+            name: POOL_NAME.to_string(),
+            disks: vec!["malloc:///disk0?size_mb=64".into()],
+        })
+        .await
+        .unwrap();
+        let pool = Lvs::lookup(POOL_NAME).unwrap();
+        for uuid in &[UUID1, UUID2] {
+            pool.create_lvol(uuid, 12 * 1024 * 1024, false)
+                .await
+                .unwrap();
+        }
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[
+                format!("loopback:///{}", UUID1),
+                format!("loopback:///{}", UUID2),
+            ],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let mut events = nexus_event::subscribe();
+
+        // a snapshot which has not been taken is rejected
+        let name = |t| Lvol::format_snapshot_name(NEXUS_NAME, t);
+        assert!(nexus
+            .verify_snapshot_consistency(&name(1), VerifyOptions::default())
+            .await
+            .is_err());
+        assert!(nexus
+            .verify_snapshot_consistency("other", VerifyOptions::default())
+            .await
+            .is_err());
+
+        // the snapshots of an idle nexus agree
+        write(0xaa).await;
+        snapshot(&pool, UUID1, 1).await;
+        snapshot(&pool, UUID2, 1).await;
+        let report = nexus
+            .verify_snapshot_consistency(&name(1), VerifyOptions::default())
+            .await
+            .unwrap();
+        assert!(report.is_consistent());
+        assert!(report.skipped.is_empty());
+        assert_eq!(report.children.len(), 2);
+        assert!(report.children.iter().all(|c| c.agrees()));
+        assert_eq!(
+            report.children[0].snapshot,
+            Lvol::format_snapshot_name(UUID1, 1)
+        );
+        assert_eq!(report.report.end, NEXUS_SIZE);
+        assert!(events.try_next().is_err());
+
+        // the nexus is written between the snapshots of its children
+        snapshot(&pool, UUID1, 2).await;
+        write(0xbb).await;
+        snapshot(&pool, UUID2, 2).await;
+        let report = nexus
+            .verify_snapshot_consistency(&name(2), VerifyOptions::default())
+            .await
+            .unwrap();
+        assert!(!report.is_consistent());
+        assert_eq!(report.report.divergent_bytes, 64 * 1024);
+        let first = report.first_divergence().unwrap();
+        assert_eq!(first.offset, 0);
+        assert_eq!(first.children, vec![format!("loopback:///{}", UUID2)]);
+        assert!(report.children[0].agrees());
+        assert_eq!(report.children[1].divergent_bytes, 64 * 1024);
+        assert!(matches!(
+            events.try_next().unwrap().unwrap(),
+            NexusEvent::SnapshotDivergent {
+                divergent_bytes,
+                ..
+            } if divergent_bytes == 64 * 1024
+        ));
+
+        nexus.destroy().await.unwrap();
+        pool.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, HealthLevel, Reason},
+    core::MayastorCliArgs,
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "wait_for_health_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn wait_for_health() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    let healthy = ms
+        .spawn(async {
+            nexus_create(NEXUS_NAME, NEXUS_SIZE, None, &[CHILD_1.to_string()])
+                .await
+                .unwrap();
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+            // a nexus with a single open child is healthy already
+            let health = nexus
+                .wait_for_health(HealthLevel::Healthy, Duration::from_secs(1))
+                .await
+                .unwrap();
+            assert_eq!(health.open, 1);
+
+            // the added child is rebuilt before the nexus is healthy again
+            let healthy = nexus
+                .wait_for_health(HealthLevel::Healthy, Duration::from_secs(10));
+            nexus.add_child(CHILD_2, false).await.unwrap();
+            assert_ne!(nexus.health().level, HealthLevel::Healthy);
+            healthy
+        })
+        .await;
+
+    let started = Instant::now();
+    let health = healthy.await.unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(health.level, HealthLevel::Healthy);
+    assert_eq!(health.open, 2);
+    assert_eq!(health.rebuilding, 0);
+
+    // the health the nexus was last seen with is returned on timeout
+    let started = Instant::now();
+    let unhealthy = ms
+        .spawn(async {
+            let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+            nexus.fault_child(CHILD_2, Reason::Rpc).await.unwrap();
+            nexus.wait_for_health(
+                HealthLevel::Healthy,
+                Duration::from_millis(200),
+            )
+        })
+        .await;
+
+    let health = unhealthy.await.unwrap_err();
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    assert_eq!(health.level, HealthLevel::Critical);
+    assert_eq!(health.faulted, 1);
+
+    ms.spawn(async {
+        nexus_lookup(NEXUS_NAME).unwrap().destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,65 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "warm_channels_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn warm_channels() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        // every thread has a channel with both children
+        let warmed = nexus.warm_channels().await.unwrap();
+        assert!(!warmed.is_empty());
+        assert!(warmed
+            .iter()
+            .all(|w| w.readers == 2 && w.writers == 2 && !w.refreshed));
+        let created = nexus.metrics().channels_created;
+        assert!(created >= warmed.len() as u64);
+
+        // the first IO finds the channel of its thread in place
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(0, &buf).await.unwrap();
+        h.read_at(0, &mut buf).await.unwrap();
+        drop(h);
+        assert_eq!(nexus.metrics().channels_created, created);
+
+        // the warm channels are kept across a reconfiguration
+        nexus.offline_child(CHILD_2).await.unwrap();
+        let dumps = nexus.dump_channels().await;
+        assert_eq!(dumps.len(), warmed.len());
+        assert!(dumps
+            .iter()
+            .all(|d| d.readers.len() == 1 && d.writers.len() == 1));
+        assert_eq!(nexus.metrics().channels_created, created);
+
+        // warming again validates the channels as they are
+        let warmed = nexus.warm_channels().await.unwrap();
+        assert!(warmed
+            .iter()
+            .all(|w| w.readers == 1 && w.writers == 1 && !w.refreshed));
+
+        nexus.cool_channels().await;
+        assert!(nexus.dump_channels().await.is_empty());
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,184 @@
+#![cfg(feature = "fault-injection")]
+
+use std::time::{Duration, Instant};
+
+use mayastor::{
+    bdev::{
+        nexus_create,
+        nexus_lookup,
+        replay_failure_trace,
+        ChildState,
+        FailureTrace,
+        FailureTraceEntry,
+        Reason,
+        WriteAck,
+    },
+    core::{
+        Bdev,
+        BdevHandle,
+        IoStatus,
+        IoType,
+        MayastorCliArgs,
+        NvmeStatusCode,
+    },
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "write_ack_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+const LAG: Duration = Duration::from_millis(500);
+
+/// make the next write to the third child complete with the given status
+/// after the lag
+fn lag_third_child(status: IoStatus) {
+    replay_failure_trace(&FailureTrace {
+        entries: vec![FailureTraceEntry {
+            at: Duration::default(),
+            child: CHILD_3.to_string(),
+            io_type: IoType::Write,
+            status,
+            nvme_status: NvmeStatusCode::default(),
+            latency: LAG,
+        }],
+    });
+}
+
+/// write to the nexus and read the data back, returning the time the write
+/// took
+async fn write_read(fill: u8) -> Duration {
+    let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+    let mut buf = h.dma_malloc(4096).unwrap();
+    buf.fill(fill);
+    let start = Instant::now();
+    h.write_at(0, &buf).await.unwrap();
+    let elapsed = start.elapsed();
+    buf.fill(0);
+    h.read_at(0, &mut buf).await.unwrap();
+    assert!(buf.as_slice().iter().all(|b| *b == fill));
+    elapsed
+}
+
+async fn reads(name: &str) -> u64 {
+    Bdev::lookup_by_name(name)
+        .unwrap()
+        .stats()
+        .await
+        .unwrap()
+        .num_read_ops
+}
+
+#[tokio::test]
+async fn write_ack() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[
+                CHILD_1.to_string(),
+                CHILD_2.to_string(),
+                CHILD_3.to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().write_ack, WriteAck::All);
+        assert!(nexus.set_write_ack(WriteAck::Quorum(0)).is_err());
+
+        // all children are waited for by default
+        lag_third_child(IoStatus::Success);
+        assert!(write_read(0xaa).await >= LAG);
+        assert_eq!(nexus.metrics().quorum_acks, 0);
+
+        // the write is acknowledged once two children completed it, the
+        // third one completes it in the background
+        nexus.set_write_ack(WriteAck::Quorum(2)).unwrap();
+        lag_third_child(IoStatus::Success);
+        assert!(write_read(0xbb).await < LAG);
+        assert_eq!(nexus.metrics().quorum_acks, 1);
+
+        // until the third child completed the acknowledged write, it serves
+        // no reads and a write which overlaps the acknowledged one is held
+        // back
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0x11);
+        lag_third_child(IoStatus::Success);
+        let start = Instant::now();
+        h.write_at(8192, &buf).await.unwrap();
+        assert!(start.elapsed() < LAG);
+        let before = reads("m2").await;
+        for _ in 0 .. 3 {
+            h.read_at(8192, &mut buf).await.unwrap();
+            assert!(buf.as_slice().iter().all(|b| *b == 0x11));
+        }
+        assert_eq!(reads("m2").await, before);
+        buf.fill(0x22);
+        h.write_at(8192, &buf).await.unwrap();
+        assert!(start.elapsed() >= LAG);
+
+        // a flush waits for the children which lag behind, the held write
+        // has been acknowledged by the quorum as well
+        lag_third_child(IoStatus::Success);
+        let start = Instant::now();
+        h.write_at(8192, &buf).await.unwrap();
+        assert!(start.elapsed() < LAG);
+        h.flush().await.unwrap();
+        assert!(start.elapsed() >= LAG);
+        assert_eq!(nexus.metrics().quorum_acks, 4);
+        drop(h);
+    })
+    .await;
+
+    tokio::time::delay_for(LAG * 2).await;
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.children[2].state(), ChildState::Open);
+        assert_eq!(nexus.metrics().late_write_failures, 0);
+
+        // the third child fails the write after it was acknowledged
+        lag_third_child(IoStatus::Failed);
+        assert!(write_read(0xcc).await < LAG);
+        assert_eq!(nexus.metrics().quorum_acks, 5);
+    })
+    .await;
+
+    // the child which failed the acknowledged write is retired
+    let mut retired = false;
+    for _ in 0 .. 100 {
+        retired = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.children[2].state() != ChildState::Open
+            })
+            .await;
+        if retired {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(retired);
+
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.metrics().late_write_failures, 1);
+        assert_eq!(
+            nexus.children[2].history().last_fault.map(|(r, _)| r),
+            Some(Reason::IoError)
+        );
+
+        // with no more children than the quorum, the writes wait for all
+        write_read(0xdd).await;
+        assert_eq!(nexus.metrics().quorum_acks, 5);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
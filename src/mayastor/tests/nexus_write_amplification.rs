@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, ChildState},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "write_amplification_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+static CHILD_3: &str = "malloc:///m2?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn write_amplification() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.write_stats().await.write_amplification, 0.0);
+
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let buf = h.dma_malloc(64 * 1024).unwrap();
+        h.write_at(0, &buf).await.unwrap();
+
+        // every write is mirrored to both children
+        let metrics = nexus.write_stats().await;
+        assert_eq!(metrics.logical_bytes_written, 64 * 1024);
+        assert_eq!(metrics.physical_bytes_written, 2 * 64 * 1024);
+        assert_eq!(metrics.write_amplification, 2.0);
+
+        nexus.add_child(CHILD_3, false).await.unwrap();
+    })
+    .await;
+
+    let mut rebuilt = false;
+    for _ in 0 .. 100 {
+        rebuilt = ms
+            .spawn(async {
+                let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+                nexus.child_lookup("m2").unwrap().state() == ChildState::Open
+            })
+            .await;
+        if rebuilt {
+            break;
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    assert!(rebuilt);
+
+    // the rebuild wrote the whole nexus to the new child
+    ms.spawn(async {
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let metrics = nexus.write_stats().await;
+        assert_eq!(metrics.logical_bytes_written, 64 * 1024);
+        assert!(metrics.physical_bytes_written >= 2 * 64 * 1024 + NEXUS_SIZE);
+        assert!(metrics.write_amplification > 2.0);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
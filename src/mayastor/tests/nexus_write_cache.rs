@@ -0,0 +1,88 @@
+use futures::future::join_all;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, WriteCache},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "write_cache_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+/// number of contiguous writes submitted at once
+const WRITES: u8 = 16;
+
+#[tokio::test]
+async fn write_through_cache_coalesces_writes() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().write_cache, WriteCache::Disabled);
+
+        // write-back is not supported yet
+        assert!(nexus
+            .set_write_cache(WriteCache::WriteBack {
+                max_dirty_bytes: 1 << 20,
+            })
+            .is_err());
+        assert_eq!(nexus.policy().write_cache, WriteCache::Disabled);
+
+        nexus
+            .set_write_cache(WriteCache::WriteThrough {
+                max_bytes: 1 << 20,
+            })
+            .unwrap();
+
+        // submit contiguous writes of distinct patterns at once
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let bufs = (1 ..= WRITES)
+            .map(|pattern| {
+                let mut buf = h.dma_malloc(4096).unwrap();
+                buf.fill(pattern);
+                buf
+            })
+            .collect::<Vec<_>>();
+        join_all(
+            bufs.iter()
+                .enumerate()
+                .map(|(i, buf)| h.write_at(i as u64 * 4096, buf)),
+        )
+        .await
+        .into_iter()
+        .for_each(|r| assert_eq!(r.unwrap(), 4096));
+
+        h.flush().await.unwrap();
+
+        let metrics = nexus.metrics();
+        assert_eq!(metrics.cache_dirty_bytes, 0);
+        assert!(metrics.flushes >= 1);
+
+        // every write landed where it was written, on both children
+        let offset = nexus.data_ent_offset * 512;
+        for child in &["m0", "m1"] {
+            let ch = BdevHandle::open(child, false, false).unwrap();
+            let mut buf = ch.dma_malloc(4096).unwrap();
+            for pattern in 1 ..= WRITES {
+                let at = offset + u64::from(pattern - 1) * 4096;
+                ch.read_at(at, &mut buf).await.unwrap();
+                assert!(buf.as_slice().iter().all(|b| *b == pattern));
+            }
+        }
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,73 @@
+use futures::future::join_all;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, WriteOrdering},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "write_ordering_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+/// number of overlapping writes submitted at once
+const WRITES: u8 = 16;
+
+#[tokio::test]
+async fn barrier_writes_land_in_order() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        assert_eq!(nexus.policy().write_ordering, WriteOrdering::Unordered);
+        nexus.set_write_ordering(WriteOrdering::Barrier);
+
+        // submit overlapping writes of distinct patterns at once, every write
+        // must land after the ones submitted before it
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let bufs = (1 ..= WRITES)
+            .map(|pattern| {
+                let mut buf = h.dma_malloc(4096).unwrap();
+                buf.fill(pattern);
+                buf
+            })
+            .collect::<Vec<_>>();
+        join_all(bufs.iter().map(|buf| h.write_at(0, buf)))
+            .await
+            .into_iter()
+            .for_each(|r| assert_eq!(r.unwrap(), 4096));
+
+        // a flush waits for the ordered writes as well
+        h.flush().await.unwrap();
+
+        // both children hold the data of the last write
+        let offset = nexus.data_ent_offset * 512;
+        for child in &["m0", "m1"] {
+            let ch = BdevHandle::open(child, false, false).unwrap();
+            let mut buf = ch.dma_malloc(4096).unwrap();
+            ch.read_at(offset, &mut buf).await.unwrap();
+            assert!(buf.as_slice().iter().all(|b| *b == WRITES));
+        }
+
+        // without ordering the writes still succeed
+        nexus.set_write_ordering(WriteOrdering::Unordered);
+        join_all(bufs.iter().map(|buf| h.write_at(4096, buf)))
+            .await
+            .into_iter()
+            .for_each(|r| assert_eq!(r.unwrap(), 4096));
+
+        drop(h);
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,73 @@
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, Error, VerifyOptions},
+    core::{BdevHandle, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "write_to_child_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn write_to_child() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+        let h = BdevHandle::open(NEXUS_NAME, true, false).unwrap();
+        let mut buf = h.dma_malloc(4096).unwrap();
+        buf.fill(0xaa);
+        h.write_at(4096, &buf).await.unwrap();
+        drop(h);
+        assert!(nexus
+            .verify_consistency(VerifyOptions::default())
+            .await
+            .unwrap()
+            .is_consistent());
+
+        // diverge the region on the second child behind the back of the nexus
+        let h = nexus.children[1].handle().unwrap();
+        let offset = nexus.data_ent_offset * 512;
+        let mut bad = h.dma_malloc(4096).unwrap();
+        bad.fill(0x55);
+        h.write_at(offset + 4096, &bad).await.unwrap();
+        drop(h);
+        let report = nexus
+            .verify_consistency(VerifyOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(report.divergent_bytes, 4096);
+        assert_eq!(report.ranges[0].offset, 4096);
+
+        // data which no other child holds is not written
+        bad.fill(0x33);
+        assert!(matches!(
+            nexus.write_to_child(CHILD_1, 4096, &bad).await,
+            Err(Error::RepairDiverges { .. })
+        ));
+        let first = nexus.read_from_child(CHILD_1, 4096, 4096).await.unwrap();
+        assert!(first.as_slice().iter().all(|b| *b == 0xaa));
+
+        // the region is repaired with the data of the first child
+        nexus.write_to_child(CHILD_2, 4096, &first).await.unwrap();
+        let second = nexus.read_from_child(CHILD_2, 4096, 4096).await.unwrap();
+        assert_eq!(first.as_slice(), second.as_slice());
+        assert!(nexus
+            .verify_consistency(VerifyOptions::default())
+            .await
+            .unwrap()
+            .is_consistent());
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,68 @@
+use std::cell::Cell;
+
+use mayastor::{
+    bdev::{nexus_create, nexus_lookup, Reason},
+    core::{CoreError, MayastorCliArgs},
+};
+
+pub mod common;
+
+static NEXUS_NAME: &str = "writer_set_nexus";
+static NEXUS_SIZE: u64 = 10 * 1024 * 1024;
+static CHILD_1: &str = "malloc:///m0?blk_size=512&size_mb=12";
+static CHILD_2: &str = "malloc:///m1?blk_size=512&size_mb=12";
+
+#[tokio::test]
+async fn writer_set() {
+    let ms = common::MayastorTest::new(MayastorCliArgs::default());
+    ms.spawn(async {
+        nexus_create(
+            NEXUS_NAME,
+            NEXUS_SIZE,
+            None,
+            &[CHILD_1.to_string(), CHILD_2.to_string()],
+        )
+        .await
+        .unwrap();
+        let nexus = nexus_lookup(NEXUS_NAME).unwrap();
+
+        let set = nexus.writer_set();
+        assert_eq!(set.children(), [CHILD_1, CHILD_2]);
+        assert!(set.is_current(nexus));
+
+        // a child faulted while the operation runs restarts it against the
+        // remaining children
+        let attempts = Cell::new(0);
+        let (set, _) = nexus
+            .with_writer_set(|_| {
+                let attempts = &attempts;
+                async move {
+                    attempts.set(attempts.get() + 1);
+                    if attempts.get() == 1 {
+                        nexus_lookup(NEXUS_NAME)
+                            .unwrap()
+                            .fault_child(CHILD_2, Reason::Rpc)
+                            .await
+                            .unwrap();
+                    }
+                    Ok(())
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(attempts.get(), 2);
+        assert_eq!(set.children(), [CHILD_1]);
+        assert_eq!(set.epoch(), nexus.epoch());
+
+        let results = nexus
+            .for_each_writer(|hdl| async move {
+                Ok::<_, CoreError>(hdl.get_bdev().name())
+            })
+            .await
+            .unwrap();
+        assert_eq!(results, vec![(CHILD_1.to_string(), "m0".to_string())]);
+
+        nexus.destroy().await.unwrap();
+    })
+    .await;
+}
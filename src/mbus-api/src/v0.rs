@@ -96,6 +96,8 @@ pub enum MessageIdVs {
     RemoveNexusChild,
     /// Add a child to a nexus
     AddNexusChild,
+    /// Event raised by a nexus
+    NexusEvent,
     /// Get all volumes
     GetVolumes,
     /// Create Volume,
@@ -845,6 +847,45 @@ pub struct AddNexusChild {
 }
 bus_impl_message_all!(AddNexusChild, AddNexusChild, Child, Nexus);
 
+/// Severity of an event raised by mayastor
+#[derive(
+    Serialize, Deserialize, Debug, Clone, EnumString, ToString, Eq, PartialEq,
+)]
+pub enum EventSeverity {
+    /// informational, no action is required
+    Info,
+    /// the nexus still serves IO but requires attention
+    Warning,
+    /// the nexus is unable to serve IO
+    Critical,
+}
+
+impl Default for EventSeverity {
+    fn default() -> Self {
+        Self::Info
+    }
+}
+
+/// Event raised by a nexus, e.g. when the nexus or one of its children changes
+/// state. Events are published and do not expect a reply.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NexusEvent {
+    /// id of the mayastor instance
+    pub node: NodeId,
+    /// name of the nexus
+    pub nexus: String,
+    /// severity of the event
+    pub severity: EventSeverity,
+    /// kind of the event
+    pub kind: String,
+    /// URI of the child the event relates to, if any
+    pub child: Option<ChildUri>,
+    /// reason or further detail of the event
+    pub reason: String,
+}
+bus_impl_message_all!(NexusEvent, NexusEvent, (), Nexus);
+
 /// Volumes
 ///
 /// Volume information